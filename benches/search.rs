@@ -0,0 +1,86 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use jars::cafebabe::{FieldAccessFlags, MethodAccessFlags};
+use jars::testing::{TestClass, TestJar};
+use jars::{field, filter, method, ClassPat, SearchOptions, Searcher};
+
+/// Builds an archive of synthetic classes with a handful of members each, spread over a few packages.
+fn archive(classes: usize) -> TestJar {
+    (0..classes).fold(TestJar::default(), |jar, i| {
+        let class = TestClass::new(format!("p{}/C{i}", i % 8))
+            .field(FieldAccessFlags::PRIVATE, "a", if i % 2 == 0 { "I" } else { "J" })
+            .field(
+                FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL,
+                "b",
+                "Ljava/lang/String;",
+            )
+            .method(MethodAccessFlags::PUBLIC, "<init>", "()V")
+            .method(
+                MethodAccessFlags::PUBLIC,
+                "c",
+                format!("(I{})V", "J".repeat(i % 5)),
+            )
+            .method(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+                "d",
+                "()Ljava/lang/String;",
+            );
+        jar.class(class)
+    })
+}
+
+fn patterns(count: usize) -> Vec<ClassPat> {
+    (0..count)
+        .map(|i| {
+            let pat = ClassPat::default()
+                .with(field!([private] i32))
+                .with(field!([private final] String));
+            if i % 2 == 0 {
+                pat.with(method!(public static (String) -> ()))
+            } else {
+                pat.with(method!(public () -> ()))
+                    .with(method!(public (i32) -> ()))
+                    .with(method!(public static () -> String))
+            }
+        })
+        .collect()
+}
+
+fn search(c: &mut Criterion) {
+    let mut jar = archive(2000).open().unwrap();
+
+    let mut group = c.benchmark_group("search");
+    for count in [1, 16, 128] {
+        let searcher = Searcher::new(patterns(count));
+        group.bench_function(format!("{count} patterns"), |b| {
+            b.iter(|| black_box(searcher.search(&mut jar).unwrap()))
+        });
+    }
+
+    let searcher =
+        Searcher::new(patterns(16)).with_options(SearchOptions::default().filter(filter::package("p0")));
+    group.bench_function("16 patterns, filtered", |b| {
+        b.iter(|| black_box(searcher.search(&mut jar).unwrap()))
+    });
+
+    let searcher =
+        Searcher::new(patterns(16)).with_options(SearchOptions::default().inherited_members(true));
+    group.bench_function("16 patterns, inherited members", |b| {
+        b.iter(|| black_box(searcher.search(&mut jar).unwrap()))
+    });
+    group.finish();
+}
+
+fn read(c: &mut Criterion) {
+    let bytes = archive(2000).to_bytes().unwrap();
+    c.bench_function("open and parse", |b| {
+        b.iter(|| {
+            let mut jar = jars::Jar::new(std::io::Cursor::new(bytes.as_slice())).unwrap();
+            for entry in jar.classes() {
+                black_box(entry.unwrap().parse().unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, search, read);
+criterion_main!(benches);
@@ -1,11 +1,7 @@
-use std::fs::File;
-use std::io::BufReader;
-
 use jars::{java, method, Any, ClassPat, Jar, Result};
 
 fn main() -> Result<()> {
-    let file = File::open("myjar.jar")?;
-    let mut jar = Jar::new(BufReader::new(file))?;
+    let mut jar = Jar::open("myjar.jar")?;
 
     let class_pat = ClassPat::default()
         .public()
@@ -21,9 +17,10 @@ fn main() -> Result<()> {
     // }
 
     let [entry] = jars::search_exact(&mut jar, &[class_pat])?;
-    let class = entry.parse()?;
+    // the header of a matched entry is cached, so this doesn't parse the class again
+    let header = entry.header()?;
 
-    println!("our match: {}", class.this_class);
+    println!("our match: {}", header.this_class);
 
     Ok(())
 }
@@ -0,0 +1,53 @@
+/*
+ * C API of the jars library, enabled with the `ffi` feature.
+ *
+ * Functions returning pointers return NULL on failure, the error message
+ * can then be retrieved with jars_last_error on the same thread.
+ * Panics are reported the same way, functions returning sizes return 0.
+ * Strings returned by the API are owned by the object they were retrieved
+ * from and remain valid until it's freed.
+ */
+#ifndef JARS_H
+#define JARS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct JarsJar JarsJar;
+typedef struct JarsPatterns JarsPatterns;
+typedef struct JarsMatches JarsMatches;
+
+/* Returns the last error that occurred on this thread, or NULL. */
+const char *jars_last_error(void);
+
+/* Opens an archive from a buffer, the contents are copied. */
+JarsJar *jars_jar_open(const uint8_t *bytes, size_t len);
+void jars_jar_free(JarsJar *jar);
+
+/* Compiles patterns written in the pattern text format. */
+JarsPatterns *jars_patterns_compile(const char *src);
+size_t jars_patterns_len(const JarsPatterns *pats);
+void jars_patterns_free(JarsPatterns *pats);
+
+/* Searches for the patterns, allowing for more than one match per pattern. */
+JarsMatches *jars_search(JarsJar *jar, const JarsPatterns *pats);
+size_t jars_matches_len(const JarsMatches *matches);
+size_t jars_match_pattern(const JarsMatches *matches, size_t index);
+const char *jars_match_pattern_name(const JarsMatches *matches, size_t index);
+const char *jars_match_class_name(const JarsMatches *matches, size_t index);
+const char *jars_match_entry(const JarsMatches *matches, size_t index);
+size_t jars_match_bindings_len(const JarsMatches *matches, size_t index);
+/* Returns the index of a matched method if is_method is set, of a field otherwise. */
+size_t jars_match_binding(const JarsMatches *matches, size_t index, size_t binding, bool *is_method);
+void jars_matches_free(JarsMatches *matches);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
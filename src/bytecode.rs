@@ -0,0 +1,370 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::constant_pool::{ConstantPool, ConstantPoolError, PoolConstant};
+
+/// A disassembled method body.
+#[derive(Debug, Clone)]
+pub struct Disassembly {
+    pub name: String,
+    pub descriptor: String,
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub instructions: Vec<Instruction>,
+}
+
+impl fmt::Display for Disassembly {
+    /// Writes a listing in a format similar to `javap -c`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}{}", self.name, self.descriptor)?;
+        writeln!(f, "  stack={}, locals={}", self.max_stack, self.max_locals)?;
+        for instr in &self.instructions {
+            writeln!(f, "  {instr}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A single instruction with its operands decoded and constant pool references resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    /// Offset of the instruction in the method body.
+    pub offset: usize,
+    pub opcode: u8,
+    /// Whether the instruction was prefixed by `wide`, which widens its local index and increment.
+    pub wide: bool,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /// Returns the mnemonic of the opcode, e.g. `invokevirtual`.
+    #[inline]
+    pub fn mnemonic(&self) -> &'static str {
+        MNEMONICS[self.opcode as usize]
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>5}: ", self.offset)?;
+        if self.wide {
+            write!(f, "wide ")?;
+        }
+        write!(f, "{}", self.mnemonic())?;
+        let mut comment = None;
+        for op in &self.operands {
+            match op {
+                Operand::Int(i) => write!(f, " {i}")?,
+                Operand::Local(i) => write!(f, " {i}")?,
+                Operand::Target(offset) => write!(f, " {offset}")?,
+                Operand::ArrayType(name) => write!(f, " {name}")?,
+                Operand::Constant { index, value } => {
+                    write!(f, " #{index}")?;
+                    comment = Some(value);
+                }
+                Operand::Switch { default, cases } => {
+                    write!(f, " {{")?;
+                    for (key, target) in cases {
+                        write!(f, " {key}: {target},")?;
+                    }
+                    write!(f, " default: {default} }}")?;
+                }
+            }
+        }
+        if let Some(value) = comment {
+            write!(f, " // {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An operand of an [`Instruction`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// An immediate integer, e.g. of `bipush` or `iinc`.
+    Int(i32),
+    /// An index of a local variable.
+    Local(u16),
+    /// An absolute offset of a branch target.
+    Target(usize),
+    /// A resolved constant pool entry.
+    Constant { index: u16, value: PoolConstant },
+    /// The element type of `newarray`.
+    ArrayType(&'static str),
+    /// Targets of `tableswitch` and `lookupswitch`.
+    Switch {
+        default: usize,
+        cases: Vec<(i32, usize)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BytecodeError {
+    #[error("unexpected end of bytecode at offset {0}")]
+    UnexpectedEnd(usize),
+    #[error("invalid opcode {opcode:#x} at offset {offset}")]
+    InvalidOpcode { offset: usize, opcode: u8 },
+    #[error("invalid branch target at offset {0}")]
+    InvalidTarget(usize),
+    #[error("constant pool error: {0}")]
+    ConstantPoolError(#[from] ConstantPoolError),
+}
+
+/// Decodes the instructions of a method body.
+pub(crate) fn disassemble(code: &[u8], pool: &ConstantPool) -> Result<Vec<Instruction>, BytecodeError> {
+    let mut reader = CodeReader { code, pos: 0 };
+    let mut instructions = vec![];
+    while reader.pos < code.len() {
+        instructions.push(reader.instruction(pool)?);
+    }
+    Ok(instructions)
+}
+
+struct CodeReader<'a> {
+    code: &'a [u8],
+    pos: usize,
+}
+
+impl CodeReader<'_> {
+    fn instruction(&mut self, pool: &ConstantPool) -> Result<Instruction, BytecodeError> {
+        let offset = self.pos;
+        let mut opcode = self.u8()?;
+        let wide = opcode == WIDE;
+        if wide {
+            opcode = self.u8()?;
+        }
+
+        let constant = |index: u16| -> Result<Operand, BytecodeError> {
+            let value = pool.get(index)?;
+            Ok(Operand::Constant { index, value })
+        };
+        let operands = match opcode {
+            // bipush
+            0x10 => vec![Operand::Int(self.u8()? as i8 as i32)],
+            // sipush
+            0x11 => vec![Operand::Int(self.u16()? as i16 as i32)],
+            // ldc
+            0x12 => vec![constant(self.u8()? as u16)?],
+            // ldc_w, ldc2_w, field and method instructions, new, anewarray, checkcast, instanceof
+            0x13 | 0x14 | 0xb2..=0xb8 | 0xbb | 0xbd | 0xc0 | 0xc1 => vec![constant(self.u16()?)?],
+            // loads, stores and ret
+            0x15..=0x19 | 0x36..=0x3a | 0xa9 => vec![Operand::Local(self.local(wide)?)],
+            // iinc
+            0x84 => {
+                let local = self.local(wide)?;
+                let increment = if wide {
+                    self.u16()? as i16 as i32
+                } else {
+                    self.u8()? as i8 as i32
+                };
+                vec![Operand::Local(local), Operand::Int(increment)]
+            }
+            // conditional branches, goto and jsr
+            0x99..=0xa8 | 0xc6 | 0xc7 => {
+                let rel = self.u16()? as i16 as isize;
+                vec![Operand::Target(target(offset, rel)?)]
+            }
+            // goto_w, jsr_w
+            0xc8 | 0xc9 => {
+                let rel = self.u32()? as i32 as isize;
+                vec![Operand::Target(target(offset, rel)?)]
+            }
+            // tableswitch
+            0xaa => {
+                self.align();
+                let default = target(offset, self.u32()? as i32 as isize)?;
+                let low = self.u32()? as i32;
+                let high = self.u32()? as i32;
+                let cases = (low..=high)
+                    .map(|key| Ok((key, target(offset, self.u32()? as i32 as isize)?)))
+                    .collect::<Result<_, BytecodeError>>()?;
+                vec![Operand::Switch { default, cases }]
+            }
+            // lookupswitch
+            0xab => {
+                self.align();
+                let default = target(offset, self.u32()? as i32 as isize)?;
+                let count = self.u32()?;
+                let cases = (0..count)
+                    .map(|_| {
+                        let key = self.u32()? as i32;
+                        Ok((key, target(offset, self.u32()? as i32 as isize)?))
+                    })
+                    .collect::<Result<_, BytecodeError>>()?;
+                vec![Operand::Switch { default, cases }]
+            }
+            // invokeinterface
+            0xb9 => {
+                let method = constant(self.u16()?)?;
+                let count = self.u8()?;
+                self.u8()?;
+                vec![method, Operand::Int(count as i32)]
+            }
+            // invokedynamic
+            0xba => {
+                let call_site = constant(self.u16()?)?;
+                self.u16()?;
+                vec![call_site]
+            }
+            // newarray
+            0xbc => {
+                let ty = match self.u8()? {
+                    4 => "boolean",
+                    5 => "char",
+                    6 => "float",
+                    7 => "double",
+                    8 => "byte",
+                    9 => "short",
+                    10 => "int",
+                    11 => "long",
+                    ty => return Err(BytecodeError::InvalidOpcode { offset, opcode: ty }),
+                };
+                vec![Operand::ArrayType(ty)]
+            }
+            // multianewarray
+            0xc5 => {
+                let class = constant(self.u16()?)?;
+                vec![class, Operand::Int(self.u8()? as i32)]
+            }
+            WIDE.. => return Err(BytecodeError::InvalidOpcode { offset, opcode }),
+            _ => vec![],
+        };
+        if wide && !matches!(opcode, 0x15..=0x19 | 0x36..=0x3a | 0xa9 | 0x84) {
+            return Err(BytecodeError::InvalidOpcode { offset, opcode });
+        }
+        Ok(Instruction {
+            offset,
+            opcode,
+            wide,
+            operands,
+        })
+    }
+
+    fn local(&mut self, wide: bool) -> Result<u16, BytecodeError> {
+        if wide {
+            self.u16()
+        } else {
+            self.u8().map(u16::from)
+        }
+    }
+
+    /// Skips the padding before the operands of switch instructions.
+    fn align(&mut self) {
+        self.pos = (self.pos + 3) & !3;
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BytecodeError> {
+        let bytes = self
+            .code
+            .get(self.pos..self.pos + N)
+            .ok_or(BytecodeError::UnexpectedEnd(self.pos))?;
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, BytecodeError> {
+        self.take::<1>().map(|[b]| b)
+    }
+
+    fn u16(&mut self) -> Result<u16, BytecodeError> {
+        self.take().map(u16::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, BytecodeError> {
+        self.take().map(u32::from_be_bytes)
+    }
+}
+
+fn target(offset: usize, rel: isize) -> Result<usize, BytecodeError> {
+    offset
+        .checked_add_signed(rel)
+        .ok_or(BytecodeError::InvalidTarget(offset))
+}
+
+const WIDE: u8 = 0xc4;
+
+// `wide` and opcodes above `jsr_w` are never exposed through `Instruction`
+#[rustfmt::skip]
+const MNEMONICS: [&str; 256] = {
+    let mut table = ["<invalid>"; 256];
+    let known = [
+        "nop", "aconst_null", "iconst_m1", "iconst_0", "iconst_1", "iconst_2", "iconst_3", "iconst_4",
+        "iconst_5", "lconst_0", "lconst_1", "fconst_0", "fconst_1", "fconst_2", "dconst_0", "dconst_1",
+        "bipush", "sipush", "ldc", "ldc_w", "ldc2_w", "iload", "lload", "fload",
+        "dload", "aload", "iload_0", "iload_1", "iload_2", "iload_3", "lload_0", "lload_1",
+        "lload_2", "lload_3", "fload_0", "fload_1", "fload_2", "fload_3", "dload_0", "dload_1",
+        "dload_2", "dload_3", "aload_0", "aload_1", "aload_2", "aload_3", "iaload", "laload",
+        "faload", "daload", "aaload", "baload", "caload", "saload", "istore", "lstore",
+        "fstore", "dstore", "astore", "istore_0", "istore_1", "istore_2", "istore_3", "lstore_0",
+        "lstore_1", "lstore_2", "lstore_3", "fstore_0", "fstore_1", "fstore_2", "fstore_3", "dstore_0",
+        "dstore_1", "dstore_2", "dstore_3", "astore_0", "astore_1", "astore_2", "astore_3", "iastore",
+        "lastore", "fastore", "dastore", "aastore", "bastore", "castore", "sastore", "pop",
+        "pop2", "dup", "dup_x1", "dup_x2", "dup2", "dup2_x1", "dup2_x2", "swap",
+        "iadd", "ladd", "fadd", "dadd", "isub", "lsub", "fsub", "dsub",
+        "imul", "lmul", "fmul", "dmul", "idiv", "ldiv", "fdiv", "ddiv",
+        "irem", "lrem", "frem", "drem", "ineg", "lneg", "fneg", "dneg",
+        "ishl", "lshl", "ishr", "lshr", "iushr", "lushr", "iand", "land",
+        "ior", "lor", "ixor", "lxor", "iinc", "i2l", "i2f", "i2d",
+        "l2i", "l2f", "l2d", "f2i", "f2l", "f2d", "d2i", "d2l",
+        "d2f", "i2b", "i2c", "i2s", "lcmp", "fcmpl", "fcmpg", "dcmpl",
+        "dcmpg", "ifeq", "ifne", "iflt", "ifge", "ifgt", "ifle", "if_icmpeq",
+        "if_icmpne", "if_icmplt", "if_icmpge", "if_icmpgt", "if_icmple", "if_acmpeq", "if_acmpne", "goto",
+        "jsr", "ret", "tableswitch", "lookupswitch", "ireturn", "lreturn", "freturn", "dreturn",
+        "areturn", "return", "getstatic", "putstatic", "getfield", "putfield", "invokevirtual", "invokespecial",
+        "invokestatic", "invokeinterface", "invokedynamic", "new", "newarray", "anewarray", "arraylength", "athrow",
+        "checkcast", "instanceof", "monitorenter", "monitorexit", "wide", "multianewarray", "ifnull", "ifnonnull",
+        "goto_w", "jsr_w",
+    ];
+    let mut i = 0;
+    while i < known.len() {
+        table[i] = known[i];
+        i += 1;
+    }
+    table
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn disassemble_instructions() {
+        let mut class = ClassBuilder::new("Foo");
+        let println = class.pool().method_ref("java/io/PrintStream", "println", "(I)V");
+        let bytes = class.to_bytes();
+        let pool = ConstantPool::parse(&bytes).unwrap();
+
+        let [a, b] = println.to_be_bytes();
+        #[rustfmt::skip]
+        let code = [
+            0x10, 0xff,                                     // bipush -1
+            0xc4, 0x84, 0x01, 0x00, 0x01, 0x00,             // wide iinc 256 256
+            0x00,                                           // nop (padding follows)
+            0xaa, 0, 0,                                     // tableswitch
+            0, 0, 0, 22,                                    // default
+            0, 0, 0, 1, 0, 0, 0, 1,                         // low, high
+            0, 0, 0, 19,
+            0xb6, a, b,                                     // invokevirtual
+            0xa7, 0xff, 0xe1,                               // goto 0
+        ];
+        let instructions = disassemble(&code, &pool).unwrap();
+        assert_eq!(instructions.len(), 6);
+        assert_eq!(instructions[0].operands, [Operand::Int(-1)]);
+        assert_eq!(instructions[1].to_string(), "    2: wide iinc 256 256");
+        assert_eq!(instructions[3].operands, [Operand::Switch {
+            default: 31,
+            cases: vec![(1, 28)]
+        }]);
+        assert_eq!(
+            instructions[4].to_string(),
+            format!("   28: invokevirtual #{println} // Method java/io/PrintStream.println:(I)V")
+        );
+        assert_eq!(instructions[5].operands, [Operand::Target(0)]);
+        assert_eq!(instructions[5].mnemonic(), "goto");
+        assert_eq!(MNEMONICS[0xc9], "jsr_w");
+
+        assert_eq!(disassemble(&[0x10], &pool), Err(BytecodeError::UnexpectedEnd(1)));
+    }
+}
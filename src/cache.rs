@@ -0,0 +1,157 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::descriptor::{Descriptor, MethodDescriptor};
+
+thread_local! {
+    static CACHE: RefCell<Option<DescriptorCache>> = const { RefCell::new(None) };
+}
+
+/// Parsed descriptors of the members checked during a search, keyed by their strings.
+///
+/// Archives use a small set of distinct descriptors, so most of them are parsed once per search.
+/// The parsed descriptors own their class names, which are freed along with the cache.
+#[derive(Default)]
+struct DescriptorCache {
+    methods: HashMap<Box<str>, Option<Rc<OwnedMethodDescriptor>>>,
+    fields: HashMap<Box<str>, Option<Rc<OwnedDescriptor>>>,
+}
+
+/// A parsed method descriptor owning its class names, see [`OwnedDescriptor`].
+#[derive(Debug)]
+pub(crate) struct OwnedMethodDescriptor {
+    pub return_type: Option<OwnedDescriptor>,
+    pub param_types: Vec<OwnedDescriptor>,
+}
+
+/// A parsed type descriptor owning its class names, unlike a [`Descriptor`] borrowing them from a class.
+#[derive(Debug, PartialEq)]
+pub(crate) enum OwnedDescriptor {
+    /// A primitive type.
+    Primitive(Descriptor<'static>),
+    Array(Box<Self>),
+    Object(Box<str>),
+}
+
+impl OwnedDescriptor {
+    fn new(desc: Descriptor<'_>) -> Self {
+        match desc {
+            Descriptor::Array(elem) => Self::Array(Box::new(Self::new(*elem))),
+            Descriptor::Object(name) => Self::Object(name.into()),
+            Descriptor::Boolean => Self::Primitive(Descriptor::Boolean),
+            Descriptor::Byte => Self::Primitive(Descriptor::Byte),
+            Descriptor::Short => Self::Primitive(Descriptor::Short),
+            Descriptor::Integer => Self::Primitive(Descriptor::Integer),
+            Descriptor::Long => Self::Primitive(Descriptor::Long),
+            Descriptor::Float => Self::Primitive(Descriptor::Float),
+            Descriptor::Double => Self::Primitive(Descriptor::Double),
+            Descriptor::Char => Self::Primitive(Descriptor::Char),
+        }
+    }
+
+    /// Returns the descriptor borrowing the class names.
+    pub(crate) fn get(&self) -> Descriptor<'_> {
+        match self {
+            Self::Primitive(desc) => desc.clone(),
+            Self::Array(elem) => Descriptor::Array(Box::new(elem.get())),
+            Self::Object(name) => Descriptor::Object(name),
+        }
+    }
+}
+
+/// Caches descriptors parsed on the current thread until it's dropped, see [`scope`].
+pub(crate) struct CacheScope {
+    owner: bool,
+}
+
+impl Drop for CacheScope {
+    fn drop(&mut self) {
+        if self.owner {
+            CACHE.with(|cache| cache.borrow_mut().take());
+        }
+    }
+}
+
+/// Starts caching the descriptors parsed on the current thread, nested scopes share the outermost cache.
+pub(crate) fn scope() -> CacheScope {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let owner = cache.is_none();
+        cache.get_or_insert_with(DescriptorCache::default);
+        CacheScope { owner }
+    })
+}
+
+/// Returns a parsed method descriptor, or `None` if no cache is active.
+///
+/// The inner option is `None` for invalid descriptors.
+pub(crate) fn method_descriptor(str: &str) -> Option<Option<Rc<OwnedMethodDescriptor>>> {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let methods = &mut cache.as_mut()?.methods;
+        if let Some(parsed) = methods.get(str) {
+            return Some(parsed.clone());
+        }
+        let parsed = MethodDescriptor::parse(str).ok().map(|desc| {
+            Rc::new(OwnedMethodDescriptor {
+                return_type: desc.return_type.map(OwnedDescriptor::new),
+                param_types: desc.param_types.into_iter().map(OwnedDescriptor::new).collect(),
+            })
+        });
+        methods.insert(str.into(), parsed.clone());
+        Some(parsed)
+    })
+}
+
+/// Returns a parsed field descriptor, or `None` if no cache is active.
+///
+/// The inner option is `None` for invalid descriptors.
+pub(crate) fn field_descriptor(str: &str) -> Option<Option<Rc<OwnedDescriptor>>> {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let fields = &mut cache.as_mut()?.fields;
+        if let Some(parsed) = fields.get(str) {
+            return Some(parsed.clone());
+        }
+        let parsed = Descriptor::parse(str)
+            .ok()
+            .map(|desc| Rc::new(OwnedDescriptor::new(desc)));
+        fields.insert(str.into(), parsed.clone());
+        Some(parsed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_descriptors_in_scope() {
+        assert!(method_descriptor("(I)V").is_none());
+
+        let outer = scope();
+        let parsed = method_descriptor("([Ljava/lang/String;J)I").unwrap().unwrap();
+        let params: Vec<_> = parsed.param_types.iter().map(OwnedDescriptor::get).collect();
+        assert_eq!(params, [
+            Descriptor::Array(Box::new(Descriptor::Object("java/lang/String"))),
+            Descriptor::Long
+        ]);
+        {
+            let _inner = scope();
+            let again = method_descriptor("([Ljava/lang/String;J)I").unwrap().unwrap();
+            assert!(Rc::ptr_eq(&parsed, &again));
+        }
+        assert_eq!(
+            field_descriptor("Lfoo;")
+                .unwrap()
+                .as_deref()
+                .map(OwnedDescriptor::get),
+            Some(Descriptor::Object("foo"))
+        );
+        assert_eq!(field_descriptor("L").unwrap(), None);
+
+        drop(outer);
+        assert!(field_descriptor("Lfoo;").is_none());
+    }
+}
@@ -0,0 +1,378 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use crate::bytecode::{BytecodeError, Disassembly, Instruction, Operand};
+
+/// Rounds of refinement of the shape labels compared by [`MethodCfg::similarity`].
+const SHAPE_ITERATIONS: usize = 3;
+
+/// A maximal sequence of instructions executed in order, entered at its first instruction only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Indices of the instructions of the block in the disassembly of the method.
+    pub instructions: Range<usize>,
+    /// Offset of the first instruction of the block in the method body.
+    pub offset: usize,
+    /// Indices of the blocks control can flow to from this block, the block that follows it first.
+    pub successors: Vec<usize>,
+}
+
+/// The control-flow graph of a method, with its basic blocks and their dominators.
+///
+/// The first block is the entry of the method. Exception handlers aren't part of the graph,
+/// so the code only reachable through them forms blocks without predecessors.
+///
+/// # Examples
+/// ```no_run
+/// let mut jar = jars::Jar::open("app.jar")?;
+/// let entry = jar.class_by_name("com/example/Foo")?.unwrap();
+/// if let Some(cfg) = entry.cfg(0)? {
+///     println!("{} blocks, {} loops", cfg.blocks.len(), cfg.back_edges().count());
+/// }
+/// # Ok::<(), jars::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodCfg {
+    pub blocks: Vec<BasicBlock>,
+    dominators: Vec<Option<usize>>,
+}
+
+impl MethodCfg {
+    /// Builds the graph of a method body from its instructions.
+    pub fn new(instructions: &[Instruction]) -> Result<Self, BytecodeError> {
+        let index_of = |offset: usize| {
+            instructions
+                .binary_search_by_key(&offset, |ins| ins.offset)
+                .map_err(|_| BytecodeError::InvalidTarget(offset))
+        };
+
+        let mut leaders = vec![false; instructions.len()];
+        if let Some(first) = leaders.first_mut() {
+            *first = true;
+        }
+        for (i, ins) in instructions.iter().enumerate() {
+            for target in targets(ins) {
+                leaders[index_of(target)?] = true;
+            }
+            if ends_block(ins.opcode) || !targets(ins).is_empty() {
+                if let Some(next) = leaders.get_mut(i + 1) {
+                    *next = true;
+                }
+            }
+        }
+
+        let starts: Vec<usize> = (0..instructions.len()).filter(|&i| leaders[i]).collect();
+        let block_of = |index: usize| starts.partition_point(|&start| start <= index) - 1;
+        let mut blocks = Vec::with_capacity(starts.len());
+        for (block, &start) in starts.iter().enumerate() {
+            let end = starts.get(block + 1).copied().unwrap_or(instructions.len());
+            let last = &instructions[end - 1];
+            let mut successors = vec![];
+            if !ends_block(last.opcode) && end < instructions.len() {
+                successors.push(block + 1);
+            }
+            for target in targets(last) {
+                let target = block_of(index_of(target)?);
+                if !successors.contains(&target) {
+                    successors.push(target);
+                }
+            }
+            blocks.push(BasicBlock {
+                instructions: start..end,
+                offset: instructions[start].offset,
+                successors,
+            });
+        }
+
+        let dominators = dominators(&blocks);
+        Ok(Self { blocks, dominators })
+    }
+
+    /// Returns the index of the block starting at `offset`, if there is one.
+    #[inline]
+    pub fn block_at(&self, offset: usize) -> Option<usize> {
+        self.blocks
+            .binary_search_by_key(&offset, |block| block.offset)
+            .ok()
+    }
+
+    /// Returns the indices of the blocks control can flow to `block` from.
+    pub fn predecessors(&self, block: usize) -> Vec<usize> {
+        (0..self.blocks.len())
+            .filter(|&i| self.blocks[i].successors.contains(&block))
+            .collect()
+    }
+
+    /// Returns the edges of the graph as pairs of block indices.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, block)| block.successors.iter().map(move |&j| (i, j)))
+    }
+
+    /// Returns the immediate dominator of a block, which is `None` for the entry and unreachable blocks.
+    #[inline]
+    pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+        self.dominators.get(block).copied().flatten()
+    }
+
+    /// Returns whether every path from the entry to block `b` goes through block `a`.
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        let mut block = Some(b);
+        while let Some(current) = block {
+            if current == a {
+                return true;
+            }
+            block = self.immediate_dominator(current);
+        }
+        false
+    }
+
+    /// Returns whether a block can be reached from the entry without going through exception handlers.
+    #[inline]
+    pub fn is_reachable(&self, block: usize) -> bool {
+        block == 0 || self.immediate_dominator(block).is_some()
+    }
+
+    /// Returns the edges going to a block that dominates their source, each of them closes a loop.
+    pub fn back_edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.edges().filter(|&(from, to)| self.dominates(to, from))
+    }
+
+    /// Returns the sorted Weisfeiler-Lehman labels of the blocks after each of `iterations` rounds of refinement.
+    ///
+    /// Blocks start labeled by their number of predecessors and successors, each round relabels a block
+    /// by its label and the labels of its neighbours. Labels only depend on the shape of the graph,
+    /// not on the instructions of the blocks or their order in the method.
+    pub fn shape_labels(&self, iterations: usize) -> Vec<u64> {
+        let predecessors: Vec<_> = (0..self.blocks.len()).map(|i| self.predecessors(i)).collect();
+        let mut labels: Vec<u64> = self
+            .blocks
+            .iter()
+            .zip(&predecessors)
+            .map(|(block, predecessors)| hash(&(predecessors.len(), block.successors.len())))
+            .collect();
+        let mut all = labels.clone();
+        for _ in 0..iterations {
+            labels = self
+                .blocks
+                .iter()
+                .zip(&predecessors)
+                .zip(&labels)
+                .map(|((block, predecessors), label)| {
+                    let mut successors: Vec<_> = block.successors.iter().map(|&i| labels[i]).collect();
+                    let mut predecessors: Vec<_> = predecessors.iter().map(|&i| labels[i]).collect();
+                    successors.sort_unstable();
+                    predecessors.sort_unstable();
+                    hash(&(label, successors, predecessors))
+                })
+                .collect();
+            all.extend(&labels);
+        }
+        all.sort_unstable();
+        all
+    }
+
+    /// Returns a score between 0 and 1 of how similar the shapes of two graphs are,
+    /// 1 meaning that they're indistinguishable.
+    ///
+    /// The score is the Jaccard index of the [shape labels](MethodCfg::shape_labels) of the graphs.
+    /// Unlike opcode sequences, it's unaffected by renaming, reordering of blocks and substitution of instructions,
+    /// so it survives most flow obfuscation that doesn't add branches.
+    /// It's how `fingerprint::match_versions` tells apart classes with the same signatures.
+    pub fn similarity(&self, other: &Self) -> f64 {
+        let (a, b) = (
+            self.shape_labels(SHAPE_ITERATIONS),
+            other.shape_labels(SHAPE_ITERATIONS),
+        );
+        if a.is_empty() && b.is_empty() {
+            return 1.;
+        }
+        // both label lists are sorted, so their multiset intersection is a merge
+        let (mut i, mut j, mut common) = (0, 0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    common += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        common as f64 / (a.len() + b.len() - common) as f64
+    }
+}
+
+impl Disassembly {
+    /// Builds the [`MethodCfg`] of the method.
+    #[inline]
+    pub fn cfg(&self) -> Result<MethodCfg, BytecodeError> {
+        MethodCfg::new(&self.instructions)
+    }
+}
+
+fn hash(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the branch targets of an instruction.
+fn targets(ins: &Instruction) -> Vec<usize> {
+    match ins.operands.first() {
+        Some(Operand::Target(target)) => vec![*target],
+        Some(Operand::Switch { default, cases }) => {
+            let mut targets = vec![*default];
+            for (_, target) in cases {
+                if !targets.contains(target) {
+                    targets.push(*target);
+                }
+            }
+            targets
+        }
+        _ => vec![],
+    }
+}
+
+/// Returns whether control never continues with the next instruction,
+/// i.e. for `goto`, switches, returns, `athrow` and `ret`.
+fn ends_block(opcode: u8) -> bool {
+    matches!(opcode, 0xa7 | 0xc8 | 0xa9 | 0xaa | 0xab | 0xac..=0xb1 | 0xbf)
+}
+
+/// Computes the immediate dominators of the blocks with the algorithm of Cooper, Harvey and Kennedy.
+fn dominators(blocks: &[BasicBlock]) -> Vec<Option<usize>> {
+    let mut dominators = vec![None; blocks.len()];
+    if blocks.is_empty() {
+        return dominators;
+    }
+
+    // reverse postorder of the reachable blocks
+    let mut order = vec![];
+    let mut visited = vec![false; blocks.len()];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some((block, next)) = stack.last_mut() {
+        if let Some(&successor) = blocks[*block].successors.get(*next) {
+            *next += 1;
+            if !visited[successor] {
+                visited[successor] = true;
+                stack.push((successor, 0));
+            }
+        } else {
+            order.push(*block);
+            stack.pop();
+        }
+    }
+    order.reverse();
+    let mut rank = vec![usize::MAX; blocks.len()];
+    for (i, &block) in order.iter().enumerate() {
+        rank[block] = i;
+    }
+
+    let mut predecessors = vec![vec![]; blocks.len()];
+    for (i, block) in blocks.iter().enumerate() {
+        for &successor in &block.successors {
+            predecessors[successor].push(i);
+        }
+    }
+
+    let intersect = |dominators: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while rank[a] > rank[b] {
+                a = dominators[a].unwrap_or(0);
+            }
+            while rank[b] > rank[a] {
+                b = dominators[b].unwrap_or(0);
+            }
+        }
+        a
+    };
+    dominators[0] = Some(0);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in &order[1..] {
+            let mut dominator = None;
+            for &predecessor in &predecessors[block] {
+                if dominators[predecessor].is_none() {
+                    continue;
+                }
+                dominator = Some(match dominator {
+                    Some(dominator) => intersect(&dominators, predecessor, dominator),
+                    None => predecessor,
+                });
+            }
+            if dominator != dominators[block] {
+                dominators[block] = dominator;
+                changed = true;
+            }
+        }
+    }
+    dominators[0] = None;
+    dominators
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::disassemble;
+    use crate::constant_pool::ConstantPool;
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn build_loop_cfg() {
+        let bytes = ClassBuilder::new("a").to_bytes();
+        let pool = ConstantPool::parse(&bytes).unwrap();
+        #[rustfmt::skip]
+        let code = [
+            0x03, 0x3b,          // 0: iconst_0, istore_0
+            0x1a, 0x10, 10,      // 2: iload_0, bipush 10
+            0xa2, 0, 9,          // 5: if_icmpge 14
+            0x84, 0, 1,          // 8: iinc 0 1
+            0xa7, 0xff, 0xf7,    // 11: goto 2
+            0xb1,                // 14: return
+            0x57, 0xb1,          // 15: pop, return
+        ];
+        let cfg = MethodCfg::new(&disassemble(&code, &pool).unwrap()).unwrap();
+
+        let offsets: Vec<_> = cfg.blocks.iter().map(|block| block.offset).collect();
+        assert_eq!(offsets, [0, 2, 8, 14, 15]);
+        assert_eq!(cfg.edges().collect::<Vec<_>>(), [(0, 1), (1, 2), (1, 3), (2, 1)]);
+        assert_eq!(cfg.predecessors(1), [0, 2]);
+        assert_eq!(cfg.block_at(8), Some(2));
+
+        assert_eq!(cfg.immediate_dominator(2), Some(1));
+        assert_eq!(cfg.immediate_dominator(3), Some(1));
+        assert!(cfg.dominates(0, 3));
+        assert!(!cfg.dominates(2, 3));
+        assert!(!cfg.is_reachable(4));
+        assert_eq!(cfg.back_edges().collect::<Vec<_>>(), [(2, 1)]);
+    }
+
+    #[test]
+    fn compare_cfg_shapes() {
+        let bytes = ClassBuilder::new("a").to_bytes();
+        let pool = ConstantPool::parse(&bytes).unwrap();
+        let cfg = |code: &[u8]| MethodCfg::new(&disassemble(code, &pool).unwrap()).unwrap();
+        // for (int i = 0; i < 10; i++);
+        let count_up = cfg(&[
+            0x03, 0x3b, 0x1a, 0x10, 10, 0xa2, 0, 9, 0x84, 0, 1, 0xa7, 0xff, 0xf7, 0xb1,
+        ]);
+        // for (int i = 5; i > 0; i -= 2) with different instructions
+        let count_down = cfg(&[
+            0x08, 0x3c, 0x1b, 0x9e, 0, 9, 0x84, 1, 0xfe, 0xa7, 0xff, 0xf9, 0xb1,
+        ]);
+        // return
+        let straight = cfg(&[0xb1]);
+
+        assert_eq!(count_up.similarity(&count_down), 1.);
+        assert!(count_up.similarity(&straight) < 0.2);
+        assert_eq!(straight.similarity(&straight), 1.);
+    }
+}
@@ -0,0 +1,125 @@
+use std::io::{Read, Seek};
+
+use crate::jar::Jar;
+use crate::mods::{manifest_attributes, MANIFEST};
+use crate::result::Result;
+
+const INDEX: &str = "META-INF/INDEX.LIST";
+const SPRING_BOOT_INDEXES: [&str; 2] = ["BOOT-INF/classpath.idx", "WEB-INF/classpath.idx"];
+
+/// The contents of `META-INF/INDEX.LIST`, listing the packages of the archive
+/// and of the archives on its class path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JarIndex {
+    /// The indexed archives in the order they're listed, starting with the archive itself.
+    pub jars: Vec<IndexedJar>,
+}
+
+impl JarIndex {
+    /// Parses an index, ignoring its header.
+    pub fn parse(text: &str) -> Self {
+        let mut jars = vec![];
+        // sections are separated by blank lines, the first one is the header
+        for section in text.replace("\r\n", "\n").split("\n\n").skip(1) {
+            let mut lines = section.lines().map(str::trim).filter(|line| !line.is_empty());
+            let Some(name) = lines.next() else {
+                continue;
+            };
+            jars.push(IndexedJar {
+                name: name.to_owned(),
+                packages: lines.map(|line| line.trim_end_matches('/').to_owned()).collect(),
+            });
+        }
+        Self { jars }
+    }
+
+    /// Returns the archives listing the package of a class, identified by its internal name.
+    pub fn jars_for<'a>(&'a self, class: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let package = class.rsplit_once('/').map_or("", |(package, _)| package);
+        self.jars
+            .iter()
+            .filter(move |jar| jar.packages.iter().any(|pkg| pkg == package))
+            .map(|jar| jar.name.as_str())
+    }
+}
+
+/// An archive listed by a [`JarIndex`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedJar {
+    /// Path of the archive, relative to the indexed archive.
+    pub name: String,
+    /// Internal names of the packages of the archive, e.g. `com/example`.
+    pub packages: Vec<String>,
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Reads the class path of the archive, the paths of other archives its classes depend on.
+    ///
+    /// The class path is declared by the `Class-Path` attribute of the manifest, relative to the archive,
+    /// and by the `classpath.idx` files of Spring Boot archives, relative to the root of the archive.
+    pub fn class_path(&mut self) -> Result<Vec<String>> {
+        let mut paths = vec![];
+        for entry in self.resources_where(is_class_path) {
+            let entry = entry?;
+            let text = String::from_utf8_lossy(entry.bytes());
+            if entry.name() == MANIFEST {
+                for attr in manifest_attributes(&text, "Class-Path") {
+                    paths.extend(attr.split_ascii_whitespace().map(str::to_owned));
+                }
+            } else {
+                // each line is a quoted path in a YAML list, e.g. `- "BOOT-INF/lib/foo.jar"`
+                let lines = text.lines().filter_map(|line| line.trim().strip_prefix('-'));
+                paths.extend(lines.map(|line| line.trim().trim_matches('"').to_owned()));
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Reads the `META-INF/INDEX.LIST` file of the archive, if it has one.
+    pub fn index(&mut self) -> Result<Option<JarIndex>> {
+        let Some(entry) = self.resources_where(|name| name == INDEX.as_bytes()).next() else {
+            return Ok(None);
+        };
+        Ok(Some(JarIndex::parse(&String::from_utf8_lossy(entry?.bytes()))))
+    }
+}
+
+fn is_class_path(name: &[u8]) -> bool {
+    name == MANIFEST.as_bytes() || SPRING_BOOT_INDEXES.iter().any(|index| name == index.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn read_class_path_and_index() {
+        let index =
+            "JarIndex-Version: 1.0\n\napp.jar\ncom/example\n\nlib/util.jar\ncom/example/util\norg/util/\n";
+        let mut jar = TestJar::default()
+            .class(TestClass::new("com/example/Main"))
+            .entry(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\r\nClass-Path: lib/util.jar\r\n  lib/other.jar\r\n",
+            )
+            .entry("BOOT-INF/classpath.idx", "- \"BOOT-INF/lib/spring.jar\"\n")
+            .entry("META-INF/INDEX.LIST", index)
+            .open()
+            .unwrap();
+
+        assert_eq!(jar.class_path().unwrap(), [
+            "lib/util.jar",
+            "lib/other.jar",
+            "BOOT-INF/lib/spring.jar"
+        ]);
+        let index = jar.index().unwrap().unwrap();
+        assert_eq!(index.jars.len(), 2);
+        assert_eq!(index.jars[1].packages, ["com/example/util", "org/util"]);
+        assert_eq!(index.jars_for("org/util/Strings").collect::<Vec<_>>(), [
+            "lib/util.jar"
+        ]);
+        assert_eq!(index.jars_for("com/example/Main").collect::<Vec<_>>(), [
+            "app.jar"
+        ]);
+    }
+}
@@ -0,0 +1,146 @@
+use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+use crate::bytecode::{BytecodeError, Operand};
+use crate::constant_pool::{ConstantPool, MemberRef, PoolConstant};
+use crate::emu::{Emulator, Value};
+use crate::model::{ClassModel, ConstantValue};
+
+/// Descriptor of the stubs decrypting string constants, see [`StringDecryptor`](crate::StringDecryptor).
+const STUB_DESCRIPTOR: &str = "(Ljava/lang/String;)Ljava/lang/String;";
+
+/// Emulates the static initializer of a class and returns the values assigned to its static fields,
+/// indexed like the fields of the class.
+///
+/// `code` holds the bodies of the methods of the class, calls to its static methods are emulated too.
+/// Fields assigned values that can't be computed, or assigned after the emulation stopped, have no value.
+/// A string passed to a static stub taking and returning a string is replaced by the result of `decrypt`,
+/// if there is one.
+pub(crate) fn static_values<C: ClassModel + ?Sized>(
+    class: &C,
+    code: &[Option<&[u8]>],
+    pool: &ConstantPool<'_>,
+    decrypt: impl Fn(&MemberRef, &str) -> Option<String>,
+) -> Result<Vec<Option<ConstantValue>>, BytecodeError> {
+    let mut values = vec![None; class.field_count()];
+    let body = |i: usize| code.get(i).copied().flatten();
+    let clinit = (0..class.method_count()).find(|&i| class.method(i).name == "<clinit>");
+    let Some(clinit) = clinit.and_then(body) else {
+        return Ok(values);
+    };
+
+    let intercept = |stub: &MemberRef, args: &[Value]| match args {
+        [Value::String(value)] if stub.descriptor == STUB_DESCRIPTOR => {
+            decrypt(stub, value).map(Value::String)
+        }
+        _ => None,
+    };
+    let mut emulator = Emulator::new(class.this_class(), pool).intercept(&intercept);
+    for i in 0..class.method_count() {
+        let method = class.method(i);
+        if let Some(body) = body(i).filter(|_| method.access_flags.contains(MethodAccessFlags::STATIC)) {
+            emulator = emulator.method(method.name, method.descriptor, body);
+        }
+    }
+    emulator.run(clinit, &[])?;
+
+    for (i, value) in values.iter_mut().enumerate() {
+        let field = class.field(i);
+        if field.access_flags.contains(FieldAccessFlags::STATIC) {
+            *value = emulator
+                .static_value(field.name, field.descriptor)
+                .and_then(Value::to_constant);
+        }
+    }
+    Ok(values)
+}
+
+/// Returns the value pushed by a constant load instruction.
+pub(crate) fn constant(opcode: u8, operands: &[Operand]) -> Option<ConstantValue> {
+    let value = match (opcode, operands) {
+        // iconst_m1 to iconst_5
+        (0x02..=0x08, _) => ConstantValue::Integer(opcode as i32 - 3),
+        (0x09..=0x0a, _) => ConstantValue::Long(opcode as i64 - 0x09),
+        (0x0b..=0x0d, _) => ConstantValue::Float((opcode - 0x0b) as f32),
+        (0x0e..=0x0f, _) => ConstantValue::Double((opcode - 0x0e) as f64),
+        // bipush and sipush
+        (0x10..=0x11, [Operand::Int(i)]) => ConstantValue::Integer(*i),
+        // ldc, ldc_w and ldc2_w
+        (0x12..=0x14, [Operand::Constant { value, .. }]) => match value {
+            PoolConstant::Integer(i) => ConstantValue::Integer(*i),
+            PoolConstant::Float(f) => ConstantValue::Float(*f),
+            PoolConstant::Long(l) => ConstantValue::Long(*l),
+            PoolConstant::Double(d) => ConstantValue::Double(*d),
+            PoolConstant::String(str) => ConstantValue::String(str.clone()),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::MethodAccessFlags;
+
+    use super::*;
+    use crate::testing::TestJar;
+    use crate::writer::{ClassBuilder, Code, FieldBuilder, MethodBuilder};
+    use crate::{ClassPat, FieldPat, MethodPat, Searcher};
+
+    const PUTSTATIC: u8 = 0xb3;
+
+    #[test]
+    fn extract_static_values() {
+        let mut class = ClassBuilder::new("a");
+        let version = class.pool().string("1.2.3");
+        let version_field = class.pool().field_ref("a", "VERSION", "Ljava/lang/String;");
+        let protocol_field = class.pool().field_ref("a", "PROTOCOL", "I");
+        let flags = FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC;
+        let clinit = MethodBuilder::new(MethodAccessFlags::STATIC, "<clinit>", "()V").code(Code {
+            max_stack: 1,
+            max_locals: 0,
+            // ldc_w, putstatic, bipush 47, putstatic, return
+            bytecode: [
+                &[0x13][..],
+                &version.to_be_bytes(),
+                &[PUTSTATIC],
+                &version_field.to_be_bytes(),
+                &[0x10, 47, PUTSTATIC],
+                &protocol_field.to_be_bytes(),
+                &[0xb1],
+            ]
+            .concat(),
+        });
+        let bytes = class
+            .with_field(FieldBuilder::new(
+                flags | FieldAccessFlags::FINAL,
+                "VERSION",
+                "Ljava/lang/String;",
+            ))
+            .with_field(FieldBuilder::new(flags, "PROTOCOL", "I"))
+            .with_field(FieldBuilder::new(flags, "MAX", "J").constant(ConstantValue::Long(8)))
+            .with_method(clinit)
+            .to_bytes();
+        let mut jar = TestJar::default().entry("a.class", bytes).open().unwrap();
+
+        let entry = jar.classes().next().unwrap().unwrap();
+        assert_eq!(entry.field_values().unwrap(), vec![
+            Some(ConstantValue::String("1.2.3".to_owned())),
+            Some(ConstantValue::Integer(47)),
+            Some(ConstantValue::Long(8)),
+        ]);
+
+        let pat = |version: &str| {
+            ClassPat::default()
+                .with(FieldPat::default().value(version))
+                .with(FieldPat::default().value(47))
+                .with(FieldPat::default().value(8i64))
+                .with(MethodPat::default().name("<clinit>"))
+        };
+        let matches = Searcher::new([pat("1.2.2"), pat("1.2.3")])
+            .search_many(&mut jar)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pattern, 1);
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Read, Seek};
+
+use crate::jar::Jar;
+use crate::name::ClassName;
+use crate::result::Result;
+
+/// Strings shared by more classes than this are too common to relate them, e.g. an empty string.
+const MAX_STRING_CLASSES: usize = 4;
+const MAX_ITERATIONS: usize = 32;
+
+/// Classes of an archive grouped into inferred packages by [`Jar::cluster_classes`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Clustering {
+    /// Internal names of the classes of each cluster, sorted by name.
+    pub clusters: Vec<Vec<ClassName>>,
+    /// Relations between the clusters, sorted by the indices of the clusters.
+    pub edges: Vec<ClusterEdge>,
+}
+
+impl Clustering {
+    /// Returns the index of the cluster containing a class.
+    pub fn cluster_of(&self, class: &str) -> Option<usize> {
+        self.clusters
+            .iter()
+            .position(|cluster| cluster.binary_search_by(|name| name.as_str().cmp(class)).is_ok())
+    }
+}
+
+/// An undirected edge of the cluster graph of a [`Clustering`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClusterEdge {
+    pub from: usize,
+    pub to: usize,
+    /// Sum of the weights of the relations between the classes of both clusters.
+    pub weight: usize,
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Groups the classes of the archive into clusters that likely belonged to the same package
+    /// before obfuscation.
+    ///
+    /// Classes are related by referring to each other and by sharing string literals that few other classes use.
+    /// Clusters are formed by propagating labels along the strongest relations,
+    /// which is deterministic for the same archive.
+    pub fn cluster_classes(&mut self) -> Result<Clustering> {
+        let mut classes = BTreeMap::new();
+        for entry in self.classes() {
+            let entry = entry?;
+            let pool = entry.constant_pool()?;
+            let refs: BTreeSet<String> = pool
+                .class_names()
+                .map(|name| element_class(&name).to_owned())
+                .collect();
+            let strings: BTreeSet<String> = pool.strings().map(|str| str.into_owned()).collect();
+            classes.insert(entry.header()?.this_class.clone(), (refs, strings));
+        }
+        let names: Vec<ClassName> = classes.keys().cloned().collect();
+        let index: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+
+        let mut weights: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        let mut users: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, (refs, strings)) in classes.values().enumerate() {
+            for j in refs.iter().filter_map(|name| index.get(name.as_str())) {
+                if i != *j {
+                    *weights.entry((i.min(*j), i.max(*j))).or_default() += 1;
+                }
+            }
+            for str in strings {
+                users.entry(str).or_default().push(i);
+            }
+        }
+        for classes in users
+            .values()
+            .filter(|classes| classes.len() <= MAX_STRING_CLASSES)
+        {
+            for (k, &i) in classes.iter().enumerate() {
+                for &j in &classes[k + 1..] {
+                    *weights.entry((i, j)).or_default() += 1;
+                }
+            }
+        }
+
+        let mut neighbours = vec![vec![]; names.len()];
+        for (&(i, j), &weight) in &weights {
+            neighbours[i].push((j, weight));
+            neighbours[j].push((i, weight));
+        }
+        let labels = propagate_labels(&neighbours);
+
+        let mut clusters: BTreeMap<usize, Vec<ClassName>> = BTreeMap::new();
+        for (i, label) in labels.iter().enumerate() {
+            clusters.entry(*label).or_default().push(names[i].clone());
+        }
+        let cluster_index: HashMap<usize, usize> = clusters
+            .keys()
+            .enumerate()
+            .map(|(i, label)| (*label, i))
+            .collect();
+
+        let mut edges: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for (&(i, j), &weight) in &weights {
+            let (a, b) = (cluster_index[&labels[i]], cluster_index[&labels[j]]);
+            if a != b {
+                *edges.entry((a.min(b), a.max(b))).or_default() += weight;
+            }
+        }
+        Ok(Clustering {
+            clusters: clusters.into_values().collect(),
+            edges: edges
+                .into_iter()
+                .map(|((from, to), weight)| ClusterEdge { from, to, weight })
+                .collect(),
+        })
+    }
+}
+
+/// Assigns each node the label with the highest total weight among its neighbours until no label changes.
+fn propagate_labels(neighbours: &[Vec<(usize, usize)>]) -> Vec<usize> {
+    let mut labels: Vec<usize> = (0..neighbours.len()).collect();
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (i, edges) in neighbours.iter().enumerate() {
+            let mut tally: BTreeMap<usize, usize> = BTreeMap::new();
+            for &(j, weight) in edges {
+                *tally.entry(labels[j]).or_default() += weight;
+            }
+            let current = tally.get(&labels[i]).copied().unwrap_or_default();
+            // ties are resolved in favour of the smallest label, which keeps the result deterministic
+            let best = tally.iter().max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)));
+            if let Some((&label, &weight)) = best {
+                if weight > current && label != labels[i] {
+                    labels[i] = label;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    labels
+}
+
+/// Returns the element class of an array class, e.g. `a` for `[[La;`.
+fn element_class(name: &str) -> &str {
+    let element = name.trim_start_matches('[');
+    if element.len() == name.len() {
+        return name;
+    }
+    element
+        .strip_prefix('L')
+        .and_then(|element| element.strip_suffix(';'))
+        .unwrap_or(element)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestJar;
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn cluster_related_classes() {
+        let class = |name: &str, refs: &[&str], strings: &[&str]| {
+            let mut class = ClassBuilder::new(name);
+            for name in refs {
+                class.pool().class(name);
+            }
+            for str in strings {
+                class.pool().string(str);
+            }
+            class.to_bytes()
+        };
+        let mut jar = TestJar::default()
+            .entry("a.class", class("a", &["b"], &["render"]))
+            .entry("b.class", class("b", &["[Lc;"], &[]))
+            .entry("c.class", class("c", &[], &["render"]))
+            .entry("d.class", class("d", &["e"], &["net"]))
+            .entry("e.class", class("e", &["a"], &["net"]))
+            .open()
+            .unwrap();
+
+        let clustering = jar.cluster_classes().unwrap();
+        assert_eq!(clustering.clusters, vec![vec!["a", "b", "c"], vec!["d", "e"]]);
+        assert_eq!(clustering.edges, vec![ClusterEdge {
+            from: 0,
+            to: 1,
+            weight: 1
+        }]);
+        assert_eq!(clustering.cluster_of("e"), Some(1));
+    }
+}
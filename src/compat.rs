@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Read, Seek};
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+
+use crate::jar::Jar;
+use crate::model::ClassModel;
+use crate::pat::Visibility;
+use crate::result::Result;
+
+/// A change between two versions of an archive that breaks code compiled against the older one,
+/// reported by [`compare_api`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Incompatibility {
+    /// Internal name of the class.
+    pub class: String,
+    /// The member of the class that changed, [`None`] for changes of the class itself.
+    pub member: Option<ApiMember>,
+    pub change: ApiChange,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.member {
+            Some(member) => write!(f, "{}.{}: {}", self.class, member, self.change),
+            None => write!(f, "{}: {}", self.class, self.change),
+        }
+    }
+}
+
+/// A field or a method of a class, identified by its name and descriptor.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ApiMember {
+    pub name: String,
+    pub descriptor: String,
+}
+
+impl fmt::Display for ApiMember {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name, self.descriptor)
+    }
+}
+
+/// The kind of an [`Incompatibility`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ApiChange {
+    /// The class or member was removed.
+    Removed,
+    /// The member was removed, but a member with the same name and the provided descriptor exists.
+    DescriptorChanged(String),
+    /// The class or member is less visible than before.
+    Narrowed,
+    /// The class or member became final, so it can no longer be extended, overridden or assigned.
+    MadeFinal,
+    /// The class or method became abstract.
+    MadeAbstract,
+    /// The class became an interface or the other way around.
+    KindChanged,
+    /// The member became static or stopped being static.
+    StaticChanged,
+    /// The class no longer directly extends or implements the provided type.
+    SupertypeRemoved(String),
+}
+
+impl fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Removed => write!(f, "removed"),
+            Self::DescriptorChanged(desc) => write!(f, "descriptor changed to {desc}"),
+            Self::Narrowed => write!(f, "visibility narrowed"),
+            Self::MadeFinal => write!(f, "made final"),
+            Self::MadeAbstract => write!(f, "made abstract"),
+            Self::KindChanged => write!(f, "changed between a class and an interface"),
+            Self::StaticChanged => write!(f, "static modifier changed"),
+            Self::SupertypeRemoved(name) => write!(f, "no longer extends {name}"),
+        }
+    }
+}
+
+/// Compares the public API of two versions of an archive and reports the binary-incompatible changes.
+///
+/// Public classes and their public and protected members make up the API,
+/// additions are compatible and aren't reported. Incompatibilities are sorted by class and member.
+///
+/// # Examples
+/// ```no_run
+/// let mut old = jars::Jar::open("lib-1.0.jar").unwrap();
+/// let mut new = jars::Jar::open("lib-2.0.jar").unwrap();
+/// for incompatibility in jars::compare_api(&mut old, &mut new).unwrap() {
+///     println!("{incompatibility}");
+/// }
+/// ```
+pub fn compare_api<R1: Read + Seek, R2: Read + Seek>(
+    old: &mut Jar<R1>,
+    new: &mut Jar<R2>,
+) -> Result<Vec<Incompatibility>> {
+    let old = read_api(old, false)?;
+    let new = read_api(new, true)?;
+    let mut res = vec![];
+    for (name, old) in &old {
+        let mut report = |member: Option<&ApiMember>, change| {
+            res.push(Incompatibility {
+                class: name.clone(),
+                member: member.cloned(),
+                change,
+            });
+        };
+        let Some(new) = new
+            .get(name)
+            .filter(|new| new.flags.contains(ClassAccessFlags::PUBLIC))
+        else {
+            let exists = new.contains_key(name);
+            report(
+                None,
+                if exists {
+                    ApiChange::Narrowed
+                } else {
+                    ApiChange::Removed
+                },
+            );
+            continue;
+        };
+        if old.flags.contains(ClassAccessFlags::INTERFACE)
+            != new.flags.contains(ClassAccessFlags::INTERFACE)
+        {
+            report(None, ApiChange::KindChanged);
+        } else {
+            let modifiers = [
+                (ClassAccessFlags::FINAL, ApiChange::MadeFinal),
+                (ClassAccessFlags::ABSTRACT, ApiChange::MadeAbstract),
+            ];
+            for (flag, change) in modifiers {
+                if !old.flags.contains(flag) && new.flags.contains(flag) {
+                    report(None, change);
+                }
+            }
+        }
+        for supertype in &old.supertypes {
+            if !new.supertypes.contains(supertype) {
+                report(None, ApiChange::SupertypeRemoved(supertype.clone()));
+            }
+        }
+
+        for (member, old_member) in &old.members {
+            let Some(new_member) = new.members.get(member) else {
+                let renamed = new
+                    .members
+                    .keys()
+                    .find(|other| other.name == member.name && old_member.is_method == other.is_method());
+                match renamed {
+                    Some(other) => report(
+                        Some(member),
+                        ApiChange::DescriptorChanged(other.descriptor.clone()),
+                    ),
+                    None => report(Some(member), ApiChange::Removed),
+                }
+                continue;
+            };
+            if new_member.visibility < old_member.visibility {
+                report(Some(member), ApiChange::Narrowed);
+            }
+            if new_member.is_static != old_member.is_static {
+                report(Some(member), ApiChange::StaticChanged);
+            }
+            if new_member.is_final && !old_member.is_final {
+                report(Some(member), ApiChange::MadeFinal);
+            }
+            if new_member.is_abstract && !old_member.is_abstract {
+                report(Some(member), ApiChange::MadeAbstract);
+            }
+        }
+    }
+    res.sort();
+    Ok(res)
+}
+
+#[derive(Debug)]
+struct ApiClass {
+    flags: ClassAccessFlags,
+    supertypes: Vec<String>,
+    members: BTreeMap<ApiMember, MemberInfo>,
+}
+
+#[derive(Debug)]
+struct MemberInfo {
+    visibility: Visibility,
+    is_method: bool,
+    is_static: bool,
+    is_final: bool,
+    is_abstract: bool,
+}
+
+impl ApiMember {
+    fn is_method(&self) -> bool {
+        self.descriptor.starts_with('(')
+    }
+}
+
+/// Reads the public classes of an archive along with their public and protected members,
+/// or all classes and members if `all` is set, so that narrowed ones can be told apart from removed ones.
+fn read_api<R: Read + Seek>(jar: &mut Jar<R>, all: bool) -> Result<BTreeMap<String, ApiClass>> {
+    let mut classes = BTreeMap::new();
+    for entry in jar.classes() {
+        let entry = entry?;
+        let class = entry.parse_without_bytecode()?;
+        if !all && !class.access_flags().contains(ClassAccessFlags::PUBLIC) {
+            continue;
+        }
+
+        let mut members = BTreeMap::new();
+        for i in 0..class.method_count() {
+            let method = class.method(i);
+            let flags = method.access_flags;
+            let info = MemberInfo {
+                visibility: Visibility::from_bits(flags.bits()),
+                is_method: true,
+                is_static: flags.contains(MethodAccessFlags::STATIC),
+                is_final: flags.contains(MethodAccessFlags::FINAL),
+                is_abstract: flags.contains(MethodAccessFlags::ABSTRACT),
+            };
+            insert_member(&mut members, method.name, method.descriptor, info, all);
+        }
+        for i in 0..class.field_count() {
+            let field = class.field(i);
+            let flags = field.access_flags;
+            let info = MemberInfo {
+                visibility: Visibility::from_bits(flags.bits()),
+                is_method: false,
+                is_static: flags.contains(FieldAccessFlags::STATIC),
+                is_final: flags.contains(FieldAccessFlags::FINAL),
+                is_abstract: false,
+            };
+            insert_member(&mut members, field.name, field.descriptor, info, all);
+        }
+
+        let supertypes = class
+            .super_class()
+            .into_iter()
+            .chain((0..class.interface_count()).map(|i| class.interface(i)))
+            .map(ToOwned::to_owned)
+            .collect();
+        classes.insert(class.this_class().to_owned(), ApiClass {
+            flags: class.access_flags(),
+            supertypes,
+            members,
+        });
+    }
+    Ok(classes)
+}
+
+fn insert_member(
+    members: &mut BTreeMap<ApiMember, MemberInfo>,
+    name: &str,
+    descriptor: &str,
+    info: MemberInfo,
+    all: bool,
+) {
+    // protected members are part of the API of classes that can be extended
+    if all || info.visibility >= Visibility::Protected {
+        let member = ApiMember {
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+        };
+        members.insert(member, info);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn report_incompatibilities() {
+        let mut old = TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .implements("b")
+                    .method(MethodAccessFlags::PUBLIC, "c", "()V")
+                    .method(MethodAccessFlags::PUBLIC, "d", "(I)V")
+                    .method(MethodAccessFlags::PROTECTED, "e", "()V")
+                    .field(FieldAccessFlags::PUBLIC, "f", "I"),
+            )
+            .class(TestClass::new("g"))
+            .open()
+            .unwrap();
+        let mut new = TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL)
+                    .method(MethodAccessFlags::PUBLIC, "d", "(J)V")
+                    .method(MethodAccessFlags::PRIVATE, "e", "()V")
+                    .method(MethodAccessFlags::PUBLIC, "h", "()V")
+                    .field(FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC, "f", "I"),
+            )
+            .open()
+            .unwrap();
+
+        let member = |name: &str, descriptor: &str| {
+            Some(ApiMember {
+                name: name.to_owned(),
+                descriptor: descriptor.to_owned(),
+            })
+        };
+        let changes: Vec<_> = compare_api(&mut old, &mut new)
+            .unwrap()
+            .into_iter()
+            .map(|inc| (inc.class, inc.member, inc.change))
+            .collect();
+        assert_eq!(changes, [
+            ("a".to_owned(), None, ApiChange::MadeFinal),
+            ("a".to_owned(), None, ApiChange::SupertypeRemoved("b".to_owned())),
+            ("a".to_owned(), member("c", "()V"), ApiChange::Removed),
+            (
+                "a".to_owned(),
+                member("d", "(I)V"),
+                ApiChange::DescriptorChanged("(J)V".to_owned())
+            ),
+            ("a".to_owned(), member("e", "()V"), ApiChange::Narrowed),
+            ("a".to_owned(), member("f", "I"), ApiChange::StaticChanged),
+            ("g".to_owned(), None, ApiChange::Removed),
+        ]);
+    }
+}
@@ -0,0 +1,422 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The constant pool of a class file, read directly from its bytes.
+///
+/// Reading the pool is much cheaper than parsing the whole class, entries are only resolved when queried.
+#[derive(Debug)]
+pub struct ConstantPool<'a> {
+    // index 0 is unused, the second slot of longs and doubles is `Entry::Unusable`
+    entries: Vec<Entry<'a>>,
+    // offset of the first byte after the pool in the class file
+    end: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Entry<'a> {
+    Unusable,
+    Utf8(&'a [u8]),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(u16),
+    String(u16),
+    FieldRef(u16, u16),
+    MethodRef(u16, u16),
+    InterfaceMethodRef(u16, u16),
+    NameAndType(u16, u16),
+    MethodHandle(u8, u16),
+    MethodType(u16),
+    Dynamic(u16, u16),
+    InvokeDynamic(u16, u16),
+    Module(u16),
+    Package(u16),
+}
+
+impl<'a> ConstantPool<'a> {
+    /// Reads the constant pool of a class file.
+    pub fn parse(class: &'a [u8]) -> Result<Self, ConstantPoolError> {
+        let mut reader = Reader::new(class, 8);
+        let count = reader.u16()?;
+        let mut entries = Vec::with_capacity(count as usize);
+        entries.push(Entry::Unusable);
+        while entries.len() < count as usize {
+            let tag = reader.u8()?;
+            let entry = match tag {
+                1 => {
+                    let len = reader.u16()?;
+                    Entry::Utf8(reader.take(len as usize)?)
+                }
+                3 => Entry::Integer(reader.u32()? as i32),
+                4 => Entry::Float(f32::from_bits(reader.u32()?)),
+                5 => Entry::Long(reader.u64()? as i64),
+                6 => Entry::Double(f64::from_bits(reader.u64()?)),
+                7 => Entry::Class(reader.u16()?),
+                8 => Entry::String(reader.u16()?),
+                9 => Entry::FieldRef(reader.u16()?, reader.u16()?),
+                10 => Entry::MethodRef(reader.u16()?, reader.u16()?),
+                11 => Entry::InterfaceMethodRef(reader.u16()?, reader.u16()?),
+                12 => Entry::NameAndType(reader.u16()?, reader.u16()?),
+                15 => Entry::MethodHandle(reader.u8()?, reader.u16()?),
+                16 => Entry::MethodType(reader.u16()?),
+                17 => Entry::Dynamic(reader.u16()?, reader.u16()?),
+                18 => Entry::InvokeDynamic(reader.u16()?, reader.u16()?),
+                19 => Entry::Module(reader.u16()?),
+                20 => Entry::Package(reader.u16()?),
+                _ => return Err(ConstantPoolError::InvalidTag(tag)),
+            };
+            entries.push(entry);
+            if matches!(entry, Entry::Long(_) | Entry::Double(_)) {
+                entries.push(Entry::Unusable);
+            }
+        }
+        Ok(Self {
+            entries,
+            end: reader.pos,
+        })
+    }
+
+    /// Returns the offset of the access flags of the class, which follow the pool.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the number of slots of the pool, including the unused slot 0
+    /// and the second slots of longs and doubles.
+    #[inline]
+    pub fn count(&self) -> u16 {
+        self.entries.len() as u16
+    }
+
+    /// Resolves all entries of the pool along with their indices.
+    ///
+    /// Entries with invalid references are skipped, they're reported by [`get`](Self::get).
+    pub fn entries(&self) -> impl Iterator<Item = (u16, PoolConstant)> + '_ {
+        (1..self.count()).filter_map(|i| Some((i, self.get(i).ok()?)))
+    }
+
+    /// Returns the names of the classes referenced by the pool,
+    /// array classes are named by their descriptors, e.g. `[Ljava/lang/String;`.
+    pub fn class_names(&self) -> impl Iterator<Item = Cow<'a, str>> + '_ {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Class(name) => self.utf8(*name).ok(),
+            _ => None,
+        })
+    }
+
+    /// Returns the string literals of the pool.
+    pub fn strings(&self) -> impl Iterator<Item = Cow<'a, str>> + '_ {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::String(str) => self.utf8(*str).ok(),
+            _ => None,
+        })
+    }
+
+    /// Returns the fields referenced by the pool.
+    pub fn field_refs(&self) -> impl Iterator<Item = MemberRef> + '_ {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::FieldRef(class, nat) => self.member_ref(*class, *nat).ok(),
+            _ => None,
+        })
+    }
+
+    /// Returns the methods referenced by the pool, including interface methods.
+    pub fn method_refs(&self) -> impl Iterator<Item = MemberRef> + '_ {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::MethodRef(class, nat) | Entry::InterfaceMethodRef(class, nat) => {
+                self.member_ref(*class, *nat).ok()
+            }
+            _ => None,
+        })
+    }
+
+    /// Resolves the entry at `index` along with all entries it refers to.
+    pub fn get(&self, index: u16) -> Result<PoolConstant, ConstantPoolError> {
+        let res = match self.entry(index)? {
+            Entry::Utf8(bytes) => PoolConstant::Utf8(decode_utf8(bytes).into_owned()),
+            Entry::Integer(i) => PoolConstant::Integer(i),
+            Entry::Float(f) => PoolConstant::Float(f),
+            Entry::Long(l) => PoolConstant::Long(l),
+            Entry::Double(d) => PoolConstant::Double(d),
+            Entry::Class(name) => PoolConstant::Class(self.utf8(name)?.into_owned()),
+            Entry::String(str) => PoolConstant::String(self.utf8(str)?.into_owned()),
+            Entry::FieldRef(class, nat) => PoolConstant::Field(self.member_ref(class, nat)?),
+            Entry::MethodRef(class, nat) => PoolConstant::Method(self.member_ref(class, nat)?),
+            Entry::InterfaceMethodRef(class, nat) => {
+                PoolConstant::InterfaceMethod(self.member_ref(class, nat)?)
+            }
+            Entry::NameAndType(name, desc) => PoolConstant::NameAndType {
+                name: self.utf8(name)?.into_owned(),
+                descriptor: self.utf8(desc)?.into_owned(),
+            },
+            Entry::MethodHandle(kind, reference) => PoolConstant::MethodHandle {
+                kind,
+                reference: match self.entry(reference)? {
+                    Entry::FieldRef(class, nat)
+                    | Entry::MethodRef(class, nat)
+                    | Entry::InterfaceMethodRef(class, nat) => self.member_ref(class, nat)?,
+                    _ => return Err(ConstantPoolError::InvalidIndex(reference)),
+                },
+            },
+            Entry::MethodType(desc) => PoolConstant::MethodType(self.utf8(desc)?.into_owned()),
+            Entry::Dynamic(bootstrap, nat) => PoolConstant::Dynamic(self.dynamic_ref(bootstrap, nat)?),
+            Entry::InvokeDynamic(bootstrap, nat) => {
+                PoolConstant::InvokeDynamic(self.dynamic_ref(bootstrap, nat)?)
+            }
+            Entry::Module(name) => PoolConstant::Module(self.utf8(name)?.into_owned()),
+            Entry::Package(name) => PoolConstant::Package(self.utf8(name)?.into_owned()),
+            Entry::Unusable => return Err(ConstantPoolError::InvalidIndex(index)),
+        };
+        Ok(res)
+    }
+
+    fn entry(&self, index: u16) -> Result<Entry<'a>, ConstantPoolError> {
+        self.entries
+            .get(index as usize)
+            .copied()
+            .ok_or(ConstantPoolError::InvalidIndex(index))
+    }
+
+    pub(crate) fn utf8(&self, index: u16) -> Result<Cow<'a, str>, ConstantPoolError> {
+        match self.entry(index)? {
+            Entry::Utf8(bytes) => Ok(decode_utf8(bytes)),
+            _ => Err(ConstantPoolError::InvalidIndex(index)),
+        }
+    }
+
+    fn name_and_type(&self, index: u16) -> Result<(Cow<'a, str>, Cow<'a, str>), ConstantPoolError> {
+        match self.entry(index)? {
+            Entry::NameAndType(name, desc) => Ok((self.utf8(name)?, self.utf8(desc)?)),
+            _ => Err(ConstantPoolError::InvalidIndex(index)),
+        }
+    }
+
+    fn member_ref(&self, class: u16, name_and_type: u16) -> Result<MemberRef, ConstantPoolError> {
+        let class = match self.entry(class)? {
+            Entry::Class(name) => self.utf8(name)?,
+            _ => return Err(ConstantPoolError::InvalidIndex(class)),
+        };
+        let (name, descriptor) = self.name_and_type(name_and_type)?;
+        Ok(MemberRef {
+            class: class.into_owned(),
+            name: name.into_owned(),
+            descriptor: descriptor.into_owned(),
+        })
+    }
+
+    fn dynamic_ref(
+        &self,
+        bootstrap_method: u16,
+        name_and_type: u16,
+    ) -> Result<DynamicRef, ConstantPoolError> {
+        let (name, descriptor) = self.name_and_type(name_and_type)?;
+        Ok(DynamicRef {
+            bootstrap_method,
+            name: name.into_owned(),
+            descriptor: descriptor.into_owned(),
+        })
+    }
+}
+
+/// A resolved entry of a constant pool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolConstant {
+    Utf8(String),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(String),
+    String(String),
+    Field(MemberRef),
+    Method(MemberRef),
+    InterfaceMethod(MemberRef),
+    NameAndType { name: String, descriptor: String },
+    MethodHandle { kind: u8, reference: MemberRef },
+    MethodType(String),
+    Dynamic(DynamicRef),
+    InvokeDynamic(DynamicRef),
+    Module(String),
+    Package(String),
+}
+
+impl fmt::Display for PoolConstant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utf8(str) => write!(f, "Utf8 {str}"),
+            Self::Integer(i) => write!(f, "int {i}"),
+            Self::Float(v) => write!(f, "float {v}f"),
+            Self::Long(l) => write!(f, "long {l}l"),
+            Self::Double(d) => write!(f, "double {d}d"),
+            Self::Class(name) => write!(f, "class {name}"),
+            Self::String(str) => write!(f, "String {str:?}"),
+            Self::Field(member) => write!(f, "Field {member}"),
+            Self::Method(member) => write!(f, "Method {member}"),
+            Self::InterfaceMethod(member) => write!(f, "InterfaceMethod {member}"),
+            Self::NameAndType { name, descriptor } => write!(f, "NameAndType {name}:{descriptor}"),
+            Self::MethodHandle { kind, reference } => write!(f, "MethodHandle {kind}:{reference}"),
+            Self::MethodType(desc) => write!(f, "MethodType {desc}"),
+            Self::Dynamic(dynamic) => write!(f, "Dynamic {dynamic}"),
+            Self::InvokeDynamic(dynamic) => write!(f, "InvokeDynamic {dynamic}"),
+            Self::Module(name) => write!(f, "Module {name}"),
+            Self::Package(name) => write!(f, "Package {name}"),
+        }
+    }
+}
+
+/// A reference to a field or a method of a class.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemberRef {
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+impl fmt::Display for MemberRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}:{}", self.class, self.name, self.descriptor)
+    }
+}
+
+/// A dynamically computed constant or call site.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DynamicRef {
+    /// Index into the `BootstrapMethods` attribute of the class.
+    pub bootstrap_method: u16,
+    pub name: String,
+    pub descriptor: String,
+}
+
+impl fmt::Display for DynamicRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}:{}:{}", self.bootstrap_method, self.name, self.descriptor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstantPoolError {
+    UnexpectedEof,
+    InvalidTag(u8),
+    InvalidIndex(u16),
+}
+
+impl fmt::Display for ConstantPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of the constant pool"),
+            Self::InvalidTag(tag) => write!(f, "invalid constant pool tag {tag}"),
+            Self::InvalidIndex(index) => write!(f, "invalid constant pool index {index}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConstantPoolError {}
+
+/// Decodes the modified UTF-8 used by class files.
+///
+/// It only differs from UTF-8 in the encoding of the null character and supplementary characters,
+/// so valid UTF-8 is borrowed and only other strings are decoded to UTF-16 first.
+fn decode_utf8(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(str) = core::str::from_utf8(bytes) {
+        return Cow::Borrowed(str);
+    }
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i] as u16;
+        let next = |n: usize| bytes.get(i + n).map_or(0, |&b| b as u16 & 0x3F);
+        if b & 0x80 == 0 {
+            units.push(b);
+            i += 1;
+        } else if b & 0xE0 == 0xC0 {
+            units.push((b & 0x1F) << 6 | next(1));
+            i += 2;
+        } else {
+            units.push((b & 0x0F) << 12 | next(1) << 6 | next(2));
+            i += 3;
+        }
+    }
+    Cow::Owned(String::from_utf16_lossy(&units))
+}
+
+/// A big-endian reader of class file structures.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    pub(crate) fn new(bytes: &'a [u8], pos: usize) -> Self {
+        Self { bytes, pos }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], ConstantPoolError> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(ConstantPoolError::UnexpectedEof)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, ConstantPoolError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, ConstantPoolError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, ConstantPoolError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ConstantPoolError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn decode_modified_utf8() {
+        assert_eq!(decode_utf8(b"Foo"), "Foo");
+        assert_eq!(decode_utf8(b"a\xC0\x80b"), "a\0b");
+        // U+1F600 encoded as a surrogate pair
+        assert_eq!(decode_utf8(b"\xED\xA0\xBD\xED\xB8\x80"), "\u{1F600}");
+    }
+
+    #[test]
+    fn query_pool() {
+        let mut class = ClassBuilder::new("a");
+        class.pool().string("hello");
+        class.pool().field_ref("b", "c", "I");
+        class
+            .pool()
+            .interface_method_ref("java/lang/Runnable", "run", "()V");
+        let bytes = class.to_bytes();
+
+        let pool = ConstantPool::parse(&bytes).unwrap();
+        let classes: Vec<_> = pool.class_names().collect();
+        assert!(classes.contains(&"a".into()));
+        assert!(classes.contains(&"java/lang/Runnable".into()));
+        assert_eq!(pool.strings().collect::<Vec<_>>(), vec!["hello"]);
+        let field = MemberRef {
+            class: "b".to_owned(),
+            name: "c".to_owned(),
+            descriptor: "I".to_owned(),
+        };
+        assert_eq!(pool.field_refs().collect::<Vec<_>>(), vec![field]);
+        assert_eq!(pool.method_refs().next().unwrap().name, "run");
+        assert_eq!(pool.entries().count(), pool.count() as usize - 1);
+    }
+}
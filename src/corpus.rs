@@ -0,0 +1,176 @@
+//! Searching every archive under a directory tree.
+//!
+//! # Examples
+//! ```no_run
+//! use jars::{corpus, ClassPat, FieldPat};
+//!
+//! let pat = ClassPat::default().with(FieldPat::default().value("${jndi:"));
+//! let report = corpus::scan("/opt/apps", [pat])?;
+//! for (path, mat) in report.matches() {
+//!     println!("{}: {}", path.display(), mat.entry.name());
+//! }
+//! for (path, err) in report.failures() {
+//!     eprintln!("{}: {err}", path.display());
+//! }
+//! # Ok::<(), jars::Error>(())
+//! ```
+
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fs, thread};
+
+use crate::jar::Jar;
+use crate::pat::ClassPat;
+use crate::result::{Error, Result};
+use crate::search::{Match, SearchReport, Searcher};
+
+/// Extensions of the files treated as archives, compared case-insensitively.
+pub const EXTENSIONS: [&str; 3] = ["jar", "war", "ear"];
+
+/// Searches for the patterns in every archive under a directory, see [`scan_with`].
+pub fn scan(dir: impl AsRef<Path>, pats: impl IntoIterator<Item = ClassPat>) -> Result<CorpusReport> {
+    scan_with(dir, &Searcher::new(pats))
+}
+
+/// Searches every archive under a directory with a [`Searcher`], using a thread per available core.
+///
+/// Archives are found by their [`EXTENSIONS`], symbolic links to directories aren't followed.
+/// An archive that fails to open or to be searched is reported as an [`Artifact`] with an error
+/// without affecting the others, only failures to read the directory tree abort the scan.
+pub fn scan_with(dir: impl AsRef<Path>, searcher: &Searcher) -> Result<CorpusReport> {
+    let paths = discover(dir.as_ref())?;
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(paths.len());
+    let next = AtomicUsize::new(0);
+    let mut searched: Vec<(usize, Artifact)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut searched = vec![];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(index) else {
+                            break searched;
+                        };
+                        let report = Jar::open(path).and_then(|mut jar| searcher.search(&mut jar));
+                        searched.push((index, Artifact {
+                            path: path.clone(),
+                            report,
+                        }));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("corpus scan worker panicked"))
+            .collect()
+    });
+    searched.sort_unstable_by_key(|(index, _)| *index);
+    Ok(CorpusReport {
+        artifacts: searched.into_iter().map(|(_, artifact)| artifact).collect(),
+    })
+}
+
+/// Returns the paths of the archives under a directory, sorted.
+pub fn discover(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else if is_archive(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort_unstable();
+    Ok(paths)
+}
+
+fn is_archive(path: &Path) -> bool {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    EXTENSIONS.iter().any(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// The outcome of a [`scan`], with an artifact for each archive found.
+#[derive(Debug, Default)]
+pub struct CorpusReport {
+    /// The archives, ordered by path.
+    pub artifacts: Vec<Artifact>,
+}
+
+impl CorpusReport {
+    /// Returns the matches of all archives along with their paths.
+    pub fn matches(&self) -> impl Iterator<Item = (&Path, &Match)> + '_ {
+        self.artifacts.iter().flat_map(|artifact| {
+            let matches = artifact.report.as_ref().map_or(&[][..], |report| &report.matches);
+            matches.iter().map(|mat| (artifact.path.as_path(), mat))
+        })
+    }
+
+    /// Returns the errors of the archives that couldn't be searched along with their paths.
+    pub fn failures(&self) -> impl Iterator<Item = (&Path, &Error)> + '_ {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| Some((artifact.path.as_path(), artifact.report.as_ref().err()?)))
+    }
+}
+
+/// An archive found by a [`scan`].
+#[derive(Debug)]
+pub struct Artifact {
+    pub path: PathBuf,
+    /// The report of the search, or the error that stopped it.
+    pub report: Result<SearchReport>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::MethodAccessFlags;
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::MethodPat;
+
+    #[test]
+    fn scan_directory_tree() {
+        let dir = std::env::temp_dir().join(format!("jars-corpus-{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib/nested")).unwrap();
+        let jar = |name: &str| {
+            TestJar::default()
+                .class(TestClass::new(name).method(MethodAccessFlags::PUBLIC, "x", "()V"))
+                .to_bytes()
+                .unwrap()
+        };
+        fs::write(dir.join("app.jar"), jar("a")).unwrap();
+        fs::write(dir.join("lib/nested/dep.JAR"), jar("b")).unwrap();
+        fs::write(dir.join("lib/broken.war"), b"not an archive").unwrap();
+        fs::write(dir.join("lib/readme.txt"), b"").unwrap();
+
+        let report = scan(&dir, [ClassPat::default().with(MethodPat::default().name("x"))]);
+        fs::remove_dir_all(&dir).unwrap();
+        let report = report.unwrap();
+
+        let paths: Vec<_> = report
+            .artifacts
+            .iter()
+            .map(|artifact| artifact.path.strip_prefix(&dir).unwrap())
+            .collect();
+        assert_eq!(paths, [
+            Path::new("app.jar"),
+            Path::new("lib/broken.war"),
+            Path::new("lib/nested/dep.JAR")
+        ]);
+        let classes: Vec<_> = report.matches().map(|(_, mat)| mat.entry.name()).collect();
+        assert_eq!(classes, ["a.class", "b.class"]);
+        let failures: Vec<_> = report.failures().map(|(path, _)| path).collect();
+        assert_eq!(failures, [dir.join("lib/broken.war")]);
+    }
+}
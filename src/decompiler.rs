@@ -0,0 +1,138 @@
+#[cfg(feature = "decompiler")]
+use std::ffi::OsString;
+#[cfg(feature = "decompiler")]
+use std::process::Command;
+
+#[cfg(feature = "decompiler")]
+use crate::result::Error;
+use crate::result::Result;
+
+/// A decompiler turning class files back into Java source.
+///
+/// This can be implemented to hand matches over to any external decompiler,
+/// [`JarEntry::decompile`](crate::JarEntry::decompile) and [`Match::decompile`](crate::Match::decompile)
+/// accept any implementation.
+pub trait Decompiler {
+    /// Decompiles a class file, `name` is the internal name of the class.
+    fn decompile(&self, name: &str, class: &[u8]) -> Result<String>;
+}
+
+impl<D: Decompiler + ?Sized> Decompiler for &D {
+    #[inline]
+    fn decompile(&self, name: &str, class: &[u8]) -> Result<String> {
+        (**self).decompile(name, class)
+    }
+}
+
+/// A [`Decompiler`] running an external program which prints the source to its standard output.
+///
+/// The class is written to a temporary file and its path is passed as the last argument.
+///
+/// # Examples
+/// ```no_run
+/// use jars::ProcessDecompiler;
+///
+/// let cfr = ProcessDecompiler::jar("cfr.jar").arg("--silent").arg("true");
+/// ```
+#[cfg(feature = "decompiler")]
+#[derive(Debug, Clone)]
+pub struct ProcessDecompiler {
+    program: OsString,
+    args: Vec<OsString>,
+}
+
+#[cfg(feature = "decompiler")]
+impl ProcessDecompiler {
+    pub fn new(program: impl Into<OsString>) -> Self {
+        Self {
+            program: program.into(),
+            args: vec![],
+        }
+    }
+
+    /// Creates a decompiler running an executable JAR with `java -jar`, e.g. CFR.
+    pub fn jar(path: impl Into<OsString>) -> Self {
+        Self::new("java").arg("-jar").arg(path)
+    }
+
+    /// Adds an argument passed to the program before the path of the class.
+    #[inline]
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+}
+
+#[cfg(feature = "decompiler")]
+impl Decompiler for ProcessDecompiler {
+    fn decompile(&self, name: &str, class: &[u8]) -> Result<String> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = std::env::temp_dir().join(format!(
+            "jars-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)?;
+        let simple_name = name.rsplit('/').next().unwrap_or(name);
+        let path = dir.join(format!("{simple_name}.class"));
+
+        let output = std::fs::write(&path, class)
+            .and_then(|()| Command::new(&self.program).args(&self.args).arg(&path).output());
+        std::fs::remove_dir_all(&dir).ok();
+        let output = output?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::DecompilerError(format!(
+                "{}: {}",
+                output.status,
+                stderr.trim()
+            )));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| Error::DecompilerError("output is not valid UTF-8".to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    struct Echo;
+
+    impl Decompiler for Echo {
+        fn decompile(&self, name: &str, class: &[u8]) -> Result<String> {
+            Ok(format!("class {name} ({} bytes)", class.len()))
+        }
+    }
+
+    #[test]
+    fn decompile_entry() {
+        let class = TestClass::new("com/example/Foo");
+        let mut jar = TestJar::default().class(class.clone()).open().unwrap();
+        let entry = jar.classes().next().unwrap().unwrap();
+
+        let expected = format!("class com/example/Foo ({} bytes)", class.to_bytes().len());
+        assert_eq!(entry.decompile(&Echo).unwrap(), expected);
+    }
+
+    #[cfg(all(feature = "decompiler", unix))]
+    #[test]
+    fn decompile_with_process() {
+        let decompiler = ProcessDecompiler::new("sh").arg("-c").arg("basename \"$0\"");
+        assert_eq!(
+            decompiler.decompile("com/example/Foo", &[]).unwrap(),
+            "Foo.class\n"
+        );
+
+        let failing = ProcessDecompiler::new("sh").arg("-c").arg("exit 1");
+        assert!(matches!(
+            failing.decompile("Foo", &[]),
+            Err(Error::DecompilerError(_))
+        ));
+    }
+}
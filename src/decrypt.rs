@@ -0,0 +1,163 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::bytecode::Instruction;
+use crate::clinit::constant;
+use crate::constant_pool::MemberRef;
+use crate::model::ConstantValue;
+
+const IXOR: u8 = 0x82;
+
+/// A string constant passed to a decryption stub, see [`StringDecryptor`].
+#[derive(Debug, Clone, Copy)]
+pub struct EncryptedString<'a> {
+    /// Internal name of the class the string is used by.
+    pub class: &'a str,
+    /// The string as it's stored in the class.
+    pub value: &'a str,
+    /// The static method called with the string to decrypt it.
+    pub stub: &'a MemberRef,
+    /// Instructions of the stub, if it's declared by the class the string is used by.
+    pub stub_code: Option<&'a [Instruction]>,
+}
+
+/// Recovers the strings that obfuscators replace with calls to decryption stubs.
+///
+/// Stubs are static methods taking and returning a string. When their result is assigned to a static field
+/// in the static initializer, the decrypted string becomes the value of the field, which is matched by
+/// [`FieldPat::value`](crate::FieldPat::value) once the decryptor is set with
+/// [`SearchOptions::string_decryptor`](crate::SearchOptions::string_decryptor).
+///
+/// Closures taking an [`EncryptedString`] are decryptors, [`XorDecryptor`] emulates simple stubs.
+pub trait StringDecryptor {
+    /// Returns the plaintext of a string, or `None` if it can't be decrypted.
+    fn decrypt(&self, string: &EncryptedString<'_>) -> Option<String>;
+}
+
+impl<F: Fn(&EncryptedString<'_>) -> Option<String>> StringDecryptor for F {
+    #[inline]
+    fn decrypt(&self, string: &EncryptedString<'_>) -> Option<String> {
+        self(string)
+    }
+}
+
+/// A decryptor emulating stubs that XOR every character of a string with a constant key.
+///
+/// The key is read from the instructions of the stub, so only stubs declared by the class using the string
+/// and XOR-ing a single constant are decrypted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XorDecryptor;
+
+impl StringDecryptor for XorDecryptor {
+    fn decrypt(&self, string: &EncryptedString<'_>) -> Option<String> {
+        let key = xor_key(string.stub_code?)?;
+        char::decode_utf16(string.value.encode_utf16().map(|unit| unit ^ key))
+            .collect::<Result<_, _>>()
+            .ok()
+    }
+}
+
+/// Returns the only constant XOR-ed in the code of a stub, truncated to a character.
+fn xor_key(code: &[Instruction]) -> Option<u16> {
+    let mut keys = code.windows(2).filter_map(|window| match window {
+        [load, xor] if xor.opcode == IXOR => match constant(load.opcode, &load.operands)? {
+            ConstantValue::Integer(key) => Some(key as u16),
+            _ => None,
+        },
+        _ => None,
+    });
+    let key = keys.next()?;
+    keys.all(|other| other == key).then_some(key)
+}
+
+/// A decryptor shared between copies of [`SearchOptions`](crate::SearchOptions).
+#[derive(Clone)]
+pub(crate) struct SharedDecryptor(pub(crate) Arc<dyn StringDecryptor + Send + Sync>);
+
+impl fmt::Debug for SharedDecryptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedDecryptor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::testing::TestJar;
+    use crate::writer::{ClassBuilder, Code, FieldBuilder, MethodBuilder};
+    use crate::{ClassPat, FieldPat, MethodPat, SearchOptions, Searcher, TypePat};
+
+    #[test]
+    fn decrypt_xor_strings() {
+        let encrypted: String = "secret".chars().map(|c| (c as u8 ^ 42) as char).collect();
+        let mut class = ClassBuilder::new("a");
+        let string = class.pool().string(&encrypted);
+        let stub = class
+            .pool()
+            .method_ref("a", "z", "(Ljava/lang/String;)Ljava/lang/String;");
+        let field = class.pool().field_ref("a", "KEY", "Ljava/lang/String;");
+        let clinit = MethodBuilder::new(MethodAccessFlags::STATIC, "<clinit>", "()V").code(Code {
+            max_stack: 1,
+            max_locals: 0,
+            // ldc_w, invokestatic z, putstatic KEY, return
+            bytecode: [
+                &[0x13][..],
+                &string.to_be_bytes(),
+                &[0xb8],
+                &stub.to_be_bytes(),
+                &[0xb3],
+                &field.to_be_bytes(),
+                &[0xb1],
+            ]
+            .concat(),
+        });
+        let decrypt = MethodBuilder::new(
+            MethodAccessFlags::PRIVATE | MethodAccessFlags::STATIC,
+            "z",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+        )
+        .code(Code {
+            max_stack: 2,
+            max_locals: 1,
+            // iload_0, bipush 42, ixor, i2c, areturn
+            bytecode: vec![0x1a, 0x10, 42, IXOR, 0x92, 0xb0],
+        });
+        let bytes = class
+            .with_field(FieldBuilder::new(
+                FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+                "KEY",
+                "Ljava/lang/String;",
+            ))
+            .with_method(clinit)
+            .with_method(decrypt)
+            .to_bytes();
+        let mut jar = TestJar::default().entry("a.class", bytes).open().unwrap();
+
+        let entry = jar.classes().next().unwrap().unwrap();
+        assert_eq!(entry.field_values().unwrap(), vec![None]);
+        assert_eq!(entry.decrypted_field_values(&XorDecryptor).unwrap(), vec![Some(
+            ConstantValue::String("secret".to_owned())
+        )]);
+        let custom = |string: &EncryptedString<'_>| Some(string.value.to_uppercase());
+        assert_eq!(entry.decrypted_field_values(&custom).unwrap(), vec![Some(
+            ConstantValue::String(encrypted.to_uppercase())
+        )]);
+
+        let pat = ClassPat::default()
+            .with(FieldPat::default().value("secret"))
+            .with(MethodPat::default().name("<clinit>"))
+            .with(
+                MethodPat::default()
+                    .name("z")
+                    .param(TypePat::Any)
+                    .returns(TypePat::Any),
+            );
+        let searcher = Searcher::new([pat]);
+        assert!(searcher.search_many(&mut jar).unwrap().is_empty());
+        let options = SearchOptions::default().string_decryptor(XorDecryptor);
+        let report = searcher.with_options(options).search(&mut jar).unwrap();
+        assert_eq!(report.matches.len(), 1);
+    }
+}
@@ -1,4 +1,8 @@
-use thiserror::Error;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
 
 /// A [Java type descriptor](https://docs.oracle.com/javase/specs/jvms/se18/html/jvms-4.html#jvms-4.3.2).
 #[derive(Debug, Clone, PartialEq)]
@@ -48,6 +52,23 @@ impl<'a> Descriptor<'a> {
     }
 }
 
+impl fmt::Display for Descriptor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Boolean => f.write_str("Z"),
+            Self::Byte => f.write_str("B"),
+            Self::Short => f.write_str("S"),
+            Self::Integer => f.write_str("I"),
+            Self::Long => f.write_str("J"),
+            Self::Float => f.write_str("F"),
+            Self::Double => f.write_str("D"),
+            Self::Char => f.write_str("C"),
+            Self::Array(elem) => write!(f, "[{elem}"),
+            Self::Object(name) => write!(f, "L{name};"),
+        }
+    }
+}
+
 /// A [Java type signature](https://docs.oracle.com/javase/specs/jvms/se18/html/jvms-4.html#jvms-4.7.9.1).
 #[derive(Debug, Clone, PartialEq)]
 pub enum Signature<'a> {
@@ -97,33 +118,85 @@ impl<'a> MethodDescriptor<'a> {
 
     /// Attempts to parse a method descriptor, possibly borrowing from the input.
     pub fn parse(str: &'a str) -> Result<Self, DescriptorError> {
-        let mut rem = str
+        let mut params = Self::param_types(str)?;
+        let param_types = params.by_ref().collect::<Result<_, _>>()?;
+        let return_type = params.return_type()?;
+        Ok(Self::new(return_type, param_types))
+    }
+
+    /// Returns an iterator that lazily parses the parameter types of a method descriptor.
+    ///
+    /// This allows rejecting a descriptor on the first mismatched parameter without parsing the rest.
+    pub fn param_types(str: &'a str) -> Result<ParamTypes<'a>, DescriptorError> {
+        let rem = str
             .strip_prefix('(')
             .ok_or(DescriptorError::MismatchedChar('('))?;
-        let mut params = vec![];
-        while rem.as_bytes().first() != Some(&b')') {
-            params.push(Descriptor::consume(&mut rem)?);
+        Ok(ParamTypes { rem, failed: false })
+    }
+}
+
+/// A lazy iterator over the parameter types of a [`MethodDescriptor`].
+///
+/// The iterator ends after the first error, since the rest of the descriptor can't be parsed.
+#[derive(Debug, Clone)]
+pub struct ParamTypes<'a> {
+    rem: &'a str,
+    failed: bool,
+}
+
+impl<'a> ParamTypes<'a> {
+    /// Skips the remaining parameters and parses the return type, [`None`] meaning void.
+    pub fn return_type(mut self) -> Result<Option<Descriptor<'a>>, DescriptorError> {
+        for param in self.by_ref() {
+            param?;
         }
-        rem = &rem[1..];
-        let return_type = if rem.as_bytes().first() == Some(&b'V') {
-            None
+        let mut rem = self
+            .rem
+            .strip_prefix(')')
+            .ok_or(DescriptorError::MismatchedChar(')'))?;
+        if rem.as_bytes().first() == Some(&b'V') {
+            Ok(None)
         } else {
-            Some(Descriptor::consume(&mut rem)?)
-        };
-        Ok(Self::new(return_type, params))
+            Ok(Some(Descriptor::consume(&mut rem)?))
+        }
+    }
+}
+
+impl<'a> Iterator for ParamTypes<'a> {
+    type Item = Result<Descriptor<'a>, DescriptorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.rem.as_bytes().first() == Some(&b')') {
+            return None;
+        }
+        let param = Descriptor::consume(&mut self.rem);
+        self.failed = param.is_err();
+        Some(param)
     }
 }
 
-#[derive(Debug, Error)]
+impl FusedIterator for ParamTypes<'_> {}
+
+#[derive(Debug)]
 pub enum DescriptorError {
-    #[error("unexpected end of input")]
     EndOfInput,
-    #[error("expected char {0}")]
     MismatchedChar(char),
-    #[error("invalid descriptor prefix character")]
     InvalidPrefix,
 }
 
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EndOfInput => write!(f, "unexpected end of input"),
+            Self::MismatchedChar(char) => write!(f, "expected char {char}"),
+            Self::InvalidPrefix => write!(f, "invalid descriptor prefix character"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DescriptorError {}
+
 #[cfg(test)]
 mod tests {
     use std::vec;
@@ -146,6 +219,34 @@ mod tests {
         ])
     }
 
+    #[test]
+    fn stop_param_types_at_errors() {
+        let params: Vec<_> = MethodDescriptor::param_types("(IJ)V").unwrap().collect();
+        assert!(matches!(params[..], [
+            Ok(Descriptor::Integer),
+            Ok(Descriptor::Long)
+        ]));
+
+        for desc in ["(I", "(IQ)V", "(ILjava/lang/String"] {
+            let mut params = MethodDescriptor::param_types(desc).unwrap();
+            assert!(matches!(params.next(), Some(Ok(_))), "{desc}");
+            assert!(matches!(params.next(), Some(Err(_))), "{desc}");
+            assert!(params.next().is_none(), "{desc}");
+            assert!(params.next().is_none(), "{desc}");
+        }
+        assert!(MethodDescriptor::param_types("(I")
+            .unwrap()
+            .return_type()
+            .is_err());
+    }
+
+    #[test]
+    fn display_descriptors() {
+        for str in ["I", "[[J", "Ljava/lang/String;", "[Ljava/util/List;"] {
+            assert_eq!(Descriptor::parse(str).unwrap().to_string(), str);
+        }
+    }
+
     #[test]
     fn parse_signatures() {
         let desc = Signature::parse("Ljava/util/Map<Ljava/lang/Integer;Ljava/lang/Boolean;>;").unwrap();
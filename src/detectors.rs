@@ -0,0 +1,319 @@
+//! Detection of known classes, e.g. vulnerable ones, with bundles of detectors stored as text.
+//!
+//! A detector is declared with its name, followed by the classes that identify it,
+//! the patterns matching them when they can be renamed and the sources of the version of the library:
+//! ```text
+//! # CVE-2021-44228
+//! detector log4shell
+//!     class org.apache.logging.log4j.core.lookup.JndiLookup
+//!     pattern public class JndiLookup
+//!         method public (*, String) -> String
+//!     version maven org.apache.logging.log4j:log4j-core
+//!     version manifest Implementation-Version
+//!     version field org.apache.logging.log4j.core.util.Constants VERSION
+//! ```
+//! Patterns are written in the [pattern format](crate::parse_patterns), with the `pattern` keyword
+//! in front of the class declaration. Versions are read from the first source that has one.
+//!
+//! # Examples
+//! ```no_run
+//! use jars::detectors::{self, DetectorBundle};
+//!
+//! let bundle = DetectorBundle::parse(&std::fs::read_to_string("detectors.txt")?)?;
+//! let mut jar = jars::Jar::open("app.jar")?;
+//! for detection in detectors::run(&mut jar, &bundle)? {
+//!     println!("{} {:?}: {:?}", detection.detector, detection.version, detection.classes);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{Read, Seek};
+
+use crate::dsl::{parse_line, PatternError};
+use crate::jar::Jar;
+use crate::model::ConstantValue;
+use crate::mods::manifest_attributes;
+use crate::name::{to_internal, ClassName};
+use crate::pat::ClassPat;
+use crate::result::Result;
+use crate::search::Searcher;
+
+/// Detectors parsed from text, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct DetectorBundle {
+    pub detectors: Vec<Detector>,
+}
+
+impl DetectorBundle {
+    /// Parses a bundle, blank lines and comments starting with `#` are ignored.
+    pub fn parse(text: &str) -> Result<Self, PatternError> {
+        let mut detectors: Vec<Detector> = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            let error = |message: &str| PatternError::new(i + 1, message);
+            if keyword.is_empty() {
+                continue;
+            } else if keyword == "detector" {
+                if rest.is_empty() {
+                    return Err(error("expected the name of the detector"));
+                }
+                detectors.push(Detector::new(rest));
+                continue;
+            }
+            let detector = detectors
+                .last_mut()
+                .ok_or_else(|| error("expected a detector declaration"))?;
+            match keyword {
+                "class" if !rest.is_empty() => detector.classes.push(to_internal(rest).into()),
+                "pattern" => parse_line(rest, &mut detector.patterns).map_err(|message| error(&message))?,
+                "method" | "field" if !detector.patterns.is_empty() => {
+                    parse_line(line, &mut detector.patterns).map_err(|message| error(&message))?;
+                }
+                "version" => {
+                    let source = VersionSource::parse(rest).ok_or_else(|| {
+                        error("expected `maven GROUP:ARTIFACT`, `manifest ATTRIBUTE` or `field CLASS NAME`")
+                    })?;
+                    detector.versions.push(source);
+                }
+                _ => return Err(error(&format!("unexpected `{line}`"))),
+            }
+        }
+        Ok(Self { detectors })
+    }
+}
+
+/// Identifies a library by its classes, see [`DetectorBundle`].
+#[derive(Debug, Clone)]
+pub struct Detector {
+    pub name: String,
+    /// Internal names of the classes of the library.
+    pub classes: Vec<ClassName>,
+    /// Patterns matching the classes of the library, named after their classes.
+    pub patterns: Vec<(String, ClassPat)>,
+    /// Where to read the version of the library from, in order of preference.
+    pub versions: Vec<VersionSource>,
+}
+
+impl Detector {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            classes: vec![],
+            patterns: vec![],
+            versions: vec![],
+        }
+    }
+}
+
+/// A source of the version of a detected library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSource {
+    /// The version of a Maven artifact declared in `META-INF/maven`.
+    Maven { group_id: String, artifact_id: String },
+    /// An attribute of the manifest, e.g. `Implementation-Version`.
+    Manifest(String),
+    /// The constant value of a static field.
+    Field { class: ClassName, name: String },
+}
+
+impl VersionSource {
+    fn parse(src: &str) -> Option<Self> {
+        let mut tokens = src.split_whitespace();
+        let source = match tokens.next()? {
+            "maven" => {
+                let (group_id, artifact_id) = tokens.next()?.split_once(':')?;
+                Self::Maven {
+                    group_id: group_id.to_owned(),
+                    artifact_id: artifact_id.to_owned(),
+                }
+            }
+            "manifest" => Self::Manifest(tokens.next()?.to_owned()),
+            "field" => Self::Field {
+                class: to_internal(tokens.next()?).into(),
+                name: tokens.next()?.to_owned(),
+            },
+            _ => return None,
+        };
+        tokens.next().is_none().then_some(source)
+    }
+
+    fn read<R: Read + Seek>(&self, jar: &mut Jar<R>) -> Result<Option<String>> {
+        match self {
+            Self::Maven {
+                group_id,
+                artifact_id,
+            } => Ok(jar
+                .maven_artifacts()?
+                .into_iter()
+                .find(|artifact| &artifact.group_id == group_id && &artifact.artifact_id == artifact_id)
+                .map(|artifact| artifact.version)),
+            Self::Manifest(name) => Ok(jar
+                .manifest()?
+                .and_then(|text| manifest_attributes(&text, name).pop())),
+            Self::Field { class, name } => {
+                let Some(entry) = jar.class_by_name(class)? else {
+                    return Ok(None);
+                };
+                let index = entry
+                    .parse_without_bytecode()?
+                    .fields
+                    .iter()
+                    .position(|field| field.name == *name);
+                let Some(index) = index else {
+                    return Ok(None);
+                };
+                Ok(entry.field_values()?.swap_remove(index).map(|value| match value {
+                    ConstantValue::Integer(i) => i.to_string(),
+                    ConstantValue::Float(f) => f.to_string(),
+                    ConstantValue::Long(l) => l.to_string(),
+                    ConstantValue::Double(d) => d.to_string(),
+                    ConstantValue::String(str) => str,
+                }))
+            }
+        }
+    }
+}
+
+/// A detector that found classes in an archive, see [`run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    /// Name of the detector.
+    pub detector: String,
+    /// Internal names of the classes found by their names or patterns, sorted.
+    pub classes: Vec<ClassName>,
+    /// The version of the library, if any of the sources of the detector had one.
+    pub version: Option<String>,
+}
+
+/// Runs the detectors of a bundle against an archive, returning the ones that found any of their classes.
+///
+/// Detections are in the order of the detectors in the bundle.
+pub fn run<R: Read + Seek>(jar: &mut Jar<R>, bundle: &DetectorBundle) -> Result<Vec<Detection>> {
+    let owners: Vec<usize> = bundle
+        .detectors
+        .iter()
+        .enumerate()
+        .flat_map(|(i, detector)| detector.patterns.iter().map(move |_| i))
+        .collect();
+    let patterns = bundle
+        .detectors
+        .iter()
+        .flat_map(|detector| detector.patterns.iter().map(|(_, pat)| pat.clone()));
+    let matches = Searcher::new(patterns).search_many(jar)?;
+
+    let mut found: Vec<Vec<ClassName>> = vec![vec![]; bundle.detectors.len()];
+    for mat in &matches {
+        found[owners[mat.pattern]].push(mat.entry.header()?.this_class.clone());
+    }
+    for (detector, classes) in bundle.detectors.iter().zip(&mut found) {
+        for class in &detector.classes {
+            if jar.class_by_name(class)?.is_some() {
+                classes.push(class.clone());
+            }
+        }
+    }
+
+    let mut detections = vec![];
+    for (detector, mut classes) in bundle.detectors.iter().zip(found) {
+        if classes.is_empty() {
+            continue;
+        }
+        classes.sort();
+        classes.dedup();
+        let mut version = None;
+        for source in &detector.versions {
+            version = source.read(jar)?;
+            if version.is_some() {
+                break;
+            }
+        }
+        detections.push(Detection {
+            detector: detector.name.clone(),
+            classes,
+            version,
+        });
+    }
+    Ok(detections)
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::writer::{ClassBuilder, FieldBuilder};
+
+    #[test]
+    fn detect_vulnerable_classes() {
+        let bundle = DetectorBundle::parse(
+            "
+            # CVE-2021-44228
+            detector log4shell
+                class org.apache.logging.log4j.core.lookup.JndiLookup
+                version maven org.apache.logging.log4j:log4j-core
+                version manifest Implementation-Version
+
+            detector shaded-lookup
+                pattern public class JndiLookup
+                    method public (*, String) -> String
+                version field shaded/Constants VERSION
+
+            detector absent
+                class com.example.Missing
+            ",
+        )
+        .unwrap();
+        assert_eq!(bundle.detectors.len(), 3);
+
+        let lookup = |name: &str| {
+            TestClass::new(name).method(
+                MethodAccessFlags::PUBLIC,
+                "lookup",
+                "(Ljava/lang/Object;Ljava/lang/String;)Ljava/lang/String;",
+            )
+        };
+        let constants = ClassBuilder::new("shaded/Constants")
+            .with_field(
+                FieldBuilder::new(
+                    FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+                    "VERSION",
+                    "Ljava/lang/String;",
+                )
+                .constant(ConstantValue::String("2.14.1".to_owned())),
+            )
+            .to_bytes();
+        let mut jar = TestJar::default()
+            .class(lookup("org/apache/logging/log4j/core/lookup/JndiLookup"))
+            .class(lookup("shaded/a"))
+            .entry("shaded/Constants.class", constants)
+            .entry(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\nImplementation-Version: 2.14.0\n",
+            )
+            .open()
+            .unwrap();
+
+        let detections = run(&mut jar, &bundle).unwrap();
+        assert_eq!(detections, [
+            Detection {
+                detector: "log4shell".to_owned(),
+                classes: vec!["org/apache/logging/log4j/core/lookup/JndiLookup".into()],
+                version: Some("2.14.0".to_owned()),
+            },
+            Detection {
+                detector: "shaded-lookup".to_owned(),
+                classes: vec![
+                    "org/apache/logging/log4j/core/lookup/JndiLookup".into(),
+                    "shaded/a".into()
+                ],
+                version: Some("2.14.1".to_owned()),
+            }
+        ]);
+
+        let err = DetectorBundle::parse("detector a\n    method public () -> ()").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
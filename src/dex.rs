@@ -0,0 +1,502 @@
+//! Reader for Android DEX files and APK archives.
+//!
+//! DEX classes implement [`ClassModel`], so that [`ClassPat`] can be used to search them.
+//! Keep in mind that DEX stores static fields before instance fields and direct methods
+//! (constructors, static and private methods) before virtual methods,
+//! which affects patterns that rely on the order of members.
+//! Patterns written against class files should use [`MatchStrategy::Set`](crate::MatchStrategy::Set)
+//! or [`MatchStrategy::BestEffort`](crate::MatchStrategy::BestEffort) rather than the positional strategy
+//! to match DEX classes.
+
+use std::borrow::Cow;
+use std::io::{Read, Seek};
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+use thiserror::Error;
+
+use crate::jar::{Jar, Limits};
+use crate::matching::MemberBinding;
+use crate::model::{ClassModel, Constant, MemberView};
+use crate::pat::ClassPat;
+use crate::result::Result;
+use crate::search::Searcher;
+
+const NO_INDEX: u32 = 0xffff_ffff;
+
+/// A class loaded from a DEX file.
+#[derive(Debug, Clone)]
+pub struct DexClass {
+    pub access_flags: u32,
+    pub name: String,
+    pub super_class: Option<String>,
+    pub interfaces: Vec<String>,
+    pub fields: Vec<DexMember>,
+    pub methods: Vec<DexMember>,
+}
+
+impl ClassModel for DexClass {
+    /// Returns the access flags of the class, flags specific to DEX are dropped.
+    #[inline]
+    fn access_flags(&self) -> ClassAccessFlags {
+        ClassAccessFlags::from_bits_truncate(self.access_flags as u16)
+    }
+
+    #[inline]
+    fn this_class(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    fn super_class(&self) -> Option<&str> {
+        self.super_class.as_deref()
+    }
+
+    #[inline]
+    fn interface_count(&self) -> usize {
+        self.interfaces.len()
+    }
+
+    #[inline]
+    fn interface(&self, index: usize) -> &str {
+        &self.interfaces[index]
+    }
+
+    #[inline]
+    fn method_count(&self) -> usize {
+        self.methods.len()
+    }
+
+    #[inline]
+    fn method(&self, index: usize) -> MemberView<'_, MethodAccessFlags> {
+        let method = &self.methods[index];
+        MemberView {
+            access_flags: MethodAccessFlags::from_bits_truncate(method.access_flags as u16),
+            name: &method.name,
+            descriptor: &method.descriptor,
+        }
+    }
+
+    #[inline]
+    fn field_count(&self) -> usize {
+        self.fields.len()
+    }
+
+    #[inline]
+    fn field(&self, index: usize) -> MemberView<'_, FieldAccessFlags> {
+        let field = &self.fields[index];
+        MemberView {
+            access_flags: FieldAccessFlags::from_bits_truncate(field.access_flags as u16),
+            name: &field.name,
+            descriptor: &field.descriptor,
+        }
+    }
+
+    /// Static values of DEX classes are not read, this always returns [`None`].
+    #[inline]
+    fn field_constant(&self, _index: usize) -> Option<Constant<'_>> {
+        None
+    }
+}
+
+/// A field or a method of a [`DexClass`].
+#[derive(Debug, Clone)]
+pub struct DexMember {
+    pub access_flags: u32,
+    pub name: String,
+    pub descriptor: String,
+}
+
+/// A match of a [`ClassPat`] in a set of [`DexClass`]es.
+#[derive(Debug)]
+pub struct DexMatch<'a> {
+    pub class: &'a DexClass,
+    pub pattern: usize,
+    pub bindings: Vec<MemberBinding>,
+}
+
+/// Searches for the provided patterns in a set of DEX classes.
+///
+/// This function allows for more than one match per pattern.
+/// Members are in the DEX order, see the [module documentation](self).
+pub fn search_many<'a>(classes: &'a [DexClass], pats: &[ClassPat]) -> Vec<DexMatch<'a>> {
+    search_with(classes, &Searcher::new(pats.iter().cloned()))
+}
+
+/// Searches for the patterns of a [`Searcher`] in a set of DEX classes.
+///
+/// Patterns are checked from the highest priority and rejected early based on member counts,
+/// like in archives, but the [`SearchOptions`](crate::SearchOptions) of the searcher are not used.
+pub fn search_with<'a>(classes: &'a [DexClass], searcher: &Searcher) -> Vec<DexMatch<'a>> {
+    let order = searcher.priority_order();
+    classes
+        .iter()
+        .filter_map(|class| {
+            let ((pattern, (bindings, _)), _) = searcher.check_model(class, &order)?;
+            Some(DexMatch {
+                class,
+                pattern,
+                bindings,
+            })
+        })
+        .collect()
+}
+
+/// Reads all classes from the `classes*.dex` files of an APK archive.
+#[inline]
+pub fn read_apk<R: Read + Seek>(source: R) -> Result<Vec<DexClass>> {
+    read_apk_with_limits(source, Limits::default())
+}
+
+/// Reads all classes from the `classes*.dex` files of an APK archive within the provided [`Limits`].
+///
+/// This should be preferred over [`read_apk`] for archives coming from untrusted sources.
+pub fn read_apk_with_limits<R: Read + Seek>(source: R, limits: Limits) -> Result<Vec<DexClass>> {
+    let mut jar = Jar::with_limits(source, limits)?;
+    let mut files = jar.resources_where(is_dex_name).collect::<Result<Vec<_>>>()?;
+    // classes.dex comes first, followed by classes2.dex, classes3.dex etc.
+    files.sort_by(|a, b| (a.raw_name().len(), a.raw_name()).cmp(&(b.raw_name().len(), b.raw_name())));
+
+    let mut classes = vec![];
+    for file in files {
+        classes.extend(parse_dex(file.bytes())?);
+    }
+    Ok(classes)
+}
+
+fn is_dex_name(name: &[u8]) -> bool {
+    name.starts_with(b"classes") && name.ends_with(b".dex") && !name.contains(&b'/')
+}
+
+/// Parses the classes defined in a DEX file.
+pub fn parse_dex(bytes: &[u8]) -> Result<Vec<DexClass>, DexError> {
+    DexReader::new(bytes)?.classes()
+}
+
+struct DexReader<'a> {
+    bytes: &'a [u8],
+    string_ids: u32,
+    string_ids_off: u32,
+    type_ids: u32,
+    type_ids_off: u32,
+    proto_ids: u32,
+    proto_ids_off: u32,
+    field_ids: u32,
+    field_ids_off: u32,
+    method_ids: u32,
+    method_ids_off: u32,
+    class_defs: u32,
+    class_defs_off: u32,
+}
+
+impl<'a> DexReader<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, DexError> {
+        if bytes.get(..4) != Some(b"dex\n") {
+            return Err(DexError::InvalidMagic);
+        }
+        let u32_at = |offset| read_u32(bytes, offset);
+        Ok(Self {
+            bytes,
+            string_ids: u32_at(56)?,
+            string_ids_off: u32_at(60)?,
+            type_ids: u32_at(64)?,
+            type_ids_off: u32_at(68)?,
+            proto_ids: u32_at(72)?,
+            proto_ids_off: u32_at(76)?,
+            field_ids: u32_at(80)?,
+            field_ids_off: u32_at(84)?,
+            method_ids: u32_at(88)?,
+            method_ids_off: u32_at(92)?,
+            class_defs: u32_at(96)?,
+            class_defs_off: u32_at(100)?,
+        })
+    }
+
+    fn classes(&self) -> Result<Vec<DexClass>, DexError> {
+        (0..self.class_defs)
+            .map(|i| self.class(self.class_defs_off as usize + i as usize * 32))
+            .collect()
+    }
+
+    fn class(&self, offset: usize) -> Result<DexClass, DexError> {
+        let name = internal_name(self.type_name(read_u32(self.bytes, offset)?)?)?;
+        let access_flags = read_u32(self.bytes, offset + 4)?;
+        let super_class = match read_u32(self.bytes, offset + 8)? {
+            NO_INDEX => None,
+            idx => Some(internal_name(self.type_name(idx)?)?),
+        };
+        let interfaces = self
+            .type_list(read_u32(self.bytes, offset + 12)?)?
+            .into_iter()
+            .map(internal_name)
+            .collect::<Result<_, _>>()?;
+
+        let mut fields = vec![];
+        let mut methods = vec![];
+        let class_data_off = read_u32(self.bytes, offset + 24)?;
+        if class_data_off != 0 {
+            let mut pos = class_data_off as usize;
+            let static_fields = read_uleb128(self.bytes, &mut pos)?;
+            let instance_fields = read_uleb128(self.bytes, &mut pos)?;
+            let direct_methods = read_uleb128(self.bytes, &mut pos)?;
+            let virtual_methods = read_uleb128(self.bytes, &mut pos)?;
+
+            for count in [static_fields, instance_fields] {
+                let mut idx = 0;
+                for _ in 0..count {
+                    idx = read_uleb128(self.bytes, &mut pos)?.wrapping_add(idx);
+                    let access_flags = read_uleb128(self.bytes, &mut pos)?;
+                    fields.push(self.field(idx, access_flags)?);
+                }
+            }
+            for count in [direct_methods, virtual_methods] {
+                let mut idx = 0;
+                for _ in 0..count {
+                    idx = read_uleb128(self.bytes, &mut pos)?.wrapping_add(idx);
+                    let access_flags = read_uleb128(self.bytes, &mut pos)?;
+                    let _code_off = read_uleb128(self.bytes, &mut pos)?;
+                    methods.push(self.method(idx, access_flags)?);
+                }
+            }
+        }
+
+        Ok(DexClass {
+            access_flags,
+            name,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+        })
+    }
+
+    fn field(&self, idx: u32, access_flags: u32) -> Result<DexMember, DexError> {
+        let offset = self.item_offset(self.field_ids_off, self.field_ids, idx, 8)?;
+        let typ = read_u16(self.bytes, offset + 2)?;
+        let name = read_u32(self.bytes, offset + 4)?;
+        Ok(DexMember {
+            access_flags,
+            name: self.string(name)?.into_owned(),
+            descriptor: self.type_name(typ.into())?.into_owned(),
+        })
+    }
+
+    fn method(&self, idx: u32, access_flags: u32) -> Result<DexMember, DexError> {
+        let offset = self.item_offset(self.method_ids_off, self.method_ids, idx, 8)?;
+        let proto = read_u16(self.bytes, offset + 2)?;
+        let name = read_u32(self.bytes, offset + 4)?;
+
+        let proto_offset = self.item_offset(self.proto_ids_off, self.proto_ids, proto.into(), 12)?;
+        let return_type = self.type_name(read_u32(self.bytes, proto_offset + 4)?)?;
+        let params = self.type_list(read_u32(self.bytes, proto_offset + 8)?)?;
+        let mut descriptor = String::from("(");
+        for param in params {
+            descriptor.push_str(&param);
+        }
+        descriptor.push(')');
+        descriptor.push_str(&return_type);
+
+        Ok(DexMember {
+            access_flags,
+            name: self.string(name)?.into_owned(),
+            descriptor,
+        })
+    }
+
+    fn type_list(&self, offset: u32) -> Result<Vec<Cow<'a, str>>, DexError> {
+        if offset == 0 {
+            return Ok(vec![]);
+        }
+        let offset = offset as usize;
+        let size = read_u32(self.bytes, offset)?;
+        (0..size as usize)
+            .map(|i| self.type_name(read_u16(self.bytes, offset + 4 + i * 2)?.into()))
+            .collect()
+    }
+
+    fn type_name(&self, idx: u32) -> Result<Cow<'a, str>, DexError> {
+        let offset = self.item_offset(self.type_ids_off, self.type_ids, idx, 4)?;
+        self.string(read_u32(self.bytes, offset)?)
+    }
+
+    fn string(&self, idx: u32) -> Result<Cow<'a, str>, DexError> {
+        let offset = self.item_offset(self.string_ids_off, self.string_ids, idx, 4)?;
+        let mut pos = read_u32(self.bytes, offset)? as usize;
+        let _utf16_len = read_uleb128(self.bytes, &mut pos)?;
+        let data = self.bytes.get(pos..).ok_or(DexError::UnexpectedEof)?;
+        let len = data.iter().position(|&b| b == 0).ok_or(DexError::UnexpectedEof)?;
+        Ok(String::from_utf8_lossy(&data[..len]))
+    }
+
+    fn item_offset(&self, base: u32, count: u32, idx: u32, size: usize) -> Result<usize, DexError> {
+        if idx >= count {
+            return Err(DexError::InvalidIndex(idx));
+        }
+        Ok(base as usize + idx as usize * size)
+    }
+}
+
+fn internal_name(descriptor: Cow<str>) -> Result<String, DexError> {
+    descriptor
+        .strip_prefix('L')
+        .and_then(|str| str.strip_suffix(';'))
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| DexError::InvalidClassType(descriptor.into_owned()))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, DexError> {
+    let bytes = bytes.get(offset..offset + 2).ok_or(DexError::UnexpectedEof)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, DexError> {
+    let bytes = bytes.get(offset..offset + 4).ok_or(DexError::UnexpectedEof)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_uleb128(bytes: &[u8], pos: &mut usize) -> Result<u32, DexError> {
+    let mut result = 0;
+    for i in 0..5 {
+        let byte = *bytes.get(*pos).ok_or(DexError::UnexpectedEof)?;
+        *pos += 1;
+        result |= u32::from(byte & 0x7f) << (i * 7);
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Error)]
+pub enum DexError {
+    #[error("invalid DEX magic")]
+    InvalidMagic,
+    #[error("unexpected end of DEX file")]
+    UnexpectedEof,
+    #[error("invalid DEX index {0}")]
+    InvalidIndex(u32),
+    #[error("invalid class type {0}")]
+    InvalidClassType(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::pat::{FieldPat, MatchStrategy, MethodPat, TypePat};
+    use crate::testing::TestJar;
+
+    /// Builds a DEX file defining `a` with the fields `x` and `count`, and the method `run`.
+    /// The class definitions come last, so a count that's too large runs past the end of the file.
+    fn dex_file(class_defs: u32) -> Vec<u8> {
+        fn put(dex: &mut [u8], offset: usize, value: u32) {
+            dex[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        let strings = ["La;", "Ljava/lang/Object;", "I", "V", "x", "count", "run"];
+        let mut dex = vec![0; 0x70];
+        dex[..8].copy_from_slice(b"dex\n035\0");
+
+        let string_ids = dex.len();
+        dex.resize(string_ids + strings.len() * 4, 0);
+        let type_ids = dex.len();
+        for string in 0..4 {
+            dex.extend(u32::to_le_bytes(string));
+        }
+        let proto_ids = dex.len();
+        dex.extend([3, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        let field_ids = dex.len();
+        dex.extend([0, 0, 2, 0, 4, 0, 0, 0, 0, 0, 2, 0, 5, 0, 0, 0]);
+        let method_ids = dex.len();
+        dex.extend([0, 0, 0, 0, 6, 0, 0, 0]);
+
+        let type_list = dex.len() as u32;
+        put(&mut dex, proto_ids + 8, type_list);
+        dex.extend([1, 0, 0, 0, 2, 0, 0, 0]);
+        let class_data = dex.len();
+        // one static field, one instance field, no direct methods and one virtual method,
+        // with access flags of the method spanning three bytes
+        dex.extend([1, 1, 0, 1, 0, 0x08, 1, 0x02, 0, 0x81, 0x80, 0x04, 0]);
+        for (i, string) in strings.iter().enumerate() {
+            let string_data = dex.len() as u32;
+            put(&mut dex, string_ids + i * 4, string_data);
+            dex.push(string.len() as u8);
+            dex.extend(string.as_bytes());
+            dex.push(0);
+        }
+
+        let class_defs_off = dex.len();
+        dex.extend([0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        dex.extend([0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+        dex.extend(u32::to_le_bytes(class_data as u32));
+        dex.extend([0, 0, 0, 0]);
+
+        let sections = [
+            (strings.len(), string_ids),
+            (4, type_ids),
+            (1, proto_ids),
+            (2, field_ids),
+            (1, method_ids),
+            (class_defs as usize, class_defs_off),
+        ];
+        for (i, (count, offset)) in sections.into_iter().enumerate() {
+            put(&mut dex, 56 + i * 8, count as u32);
+            put(&mut dex, 60 + i * 8, offset as u32);
+        }
+        dex
+    }
+
+    fn members(members: &[DexMember]) -> Vec<(&str, &str, u32)> {
+        members
+            .iter()
+            .map(|member| (&*member.name, &*member.descriptor, member.access_flags))
+            .collect()
+    }
+
+    #[test]
+    fn parse_dex_files() {
+        let classes = parse_dex(&dex_file(1)).unwrap();
+        let [class] = &classes[..] else {
+            panic!("expected one class, got {classes:?}")
+        };
+        assert_eq!(class.name, "a");
+        assert_eq!(class.access_flags, 1);
+        assert_eq!(class.super_class.as_deref(), Some("java/lang/Object"));
+        assert_eq!(members(&class.fields), [("x", "I", 0x08), ("count", "I", 0x02)]);
+        assert_eq!(members(&class.methods), [("run", "(I)V", 0x10001)]);
+
+        assert!(matches!(parse_dex(&dex_file(2)), Err(DexError::UnexpectedEof)));
+        assert!(matches!(parse_dex(b"dey\n035\0"), Err(DexError::InvalidMagic)));
+    }
+
+    #[test]
+    fn search_dex_classes() {
+        let classes = parse_dex(&dex_file(1)).unwrap();
+        let pats = [
+            ClassPat::default().strategy(MatchStrategy::BestEffort),
+            ClassPat::default()
+                .with(FieldPat::default().name("count"))
+                .with(FieldPat::default().name("x"))
+                .with(MethodPat::default().name("run").param(TypePat::Any))
+                .strategy(MatchStrategy::Set)
+                .priority(1),
+        ];
+        let matches = search_many(&classes, &pats);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pattern, 1);
+        assert_eq!(matches[0].class.name, "a");
+    }
+
+    #[test]
+    fn read_apks_within_limits() {
+        let apk = TestJar::default()
+            .entry("classes2.dex", dex_file(1))
+            .entry("classes.dex", dex_file(1))
+            .entry("assets/classes3.dex", dex_file(2))
+            .to_bytes()
+            .unwrap();
+        assert_eq!(read_apk(Cursor::new(&apk)).unwrap().len(), 2);
+
+        let limits = Limits::default().max_entry_size(64);
+        assert!(read_apk_with_limits(Cursor::new(&apk), limits).is_err());
+    }
+}
@@ -0,0 +1,520 @@
+//! A text format for class patterns, mirroring the [`method!`](crate::method) and [`field!`](crate::field) macros.
+//!
+//! Each pattern starts with a class declaration, followed by its members in the order they are defined in:
+//! ```text
+//! # comments start with a hash
+//! public final class com.example.Singleton
+//!     field private static *
+//!     method private <init>() -> void
+//!     method public static (String, int[]) -> java.util.List
+//!
+//! exact public interface Listener extends Object implements java.util.EventListener
+//!     method public abstract (*) -> ()
+//!     method public default () -> boolean
+//! ```
+//! Classes can also be required to provide services declared in `META-INF/services`,
+//! e.g. `class Driver provides java.sql.Driver`, see [`ClassPat::provides_service`],
+//! and to be declared as entry points by mod metadata, e.g. `class Mod entrypoint main`.
+//! The declaration can end with the [`MatchStrategy`] binding the members,
+//! one of `members positional`, `members subsequence`, `members set` or `members best_effort`.
+//! Member names are optional, when present they are required to match.
+//! The `package` modifier requires a package-private class or member.
+//! The `default` modifier requires a method to have an inheritable implementation, see [`MethodPat::default_impl`].
+//!
+//! Methods can be followed by constraints on their `invokedynamic` call sites, see [`CallSitePat`]:
+//! ```text
+//! method public () -> () lambda Runnable lambda concat indy 3
+//! ```
+//! `lambda [TYPE]` requires a lambda, optionally implementing the provided interface,
+//! `concat` requires a string concatenation and `indy COUNT` requires an exact number of call sites.
+//! Types are written as in Java, with `*` matching any type and `()` being the same as `void`.
+//! A class identified by another pattern can be referred to by its name prefixed with `@`, see [`TypePat::Ref`].
+//! Any type can be captured under a name prefixed with `$`, see [`TypePat::Capture`].
+//! Simple names of common `java.lang` classes such as `String` are resolved to the `java.lang` package.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt;
+
+use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+use crate::descriptor::Descriptor;
+use crate::name::to_internal;
+use crate::pat::{
+    CallSitePat, ClassPat, FieldPat, MatchStrategy, MemberPat, MethodPat, TypePat, Visibility
+};
+
+/// Parses a set of named patterns from text.
+///
+/// # Examples
+/// ```
+/// let pats = jars::parse_patterns("public class Foo\n  method public static (String) -> int").unwrap();
+/// assert_eq!(pats[0].0, "Foo");
+/// ```
+pub fn parse_patterns(src: &str) -> Result<Vec<(String, ClassPat)>, PatternError> {
+    let mut pats = vec![];
+    for (i, line) in src.lines().enumerate() {
+        let line = line.split_once('#').map_or(line, |(line, _)| line);
+        parse_line(line, &mut pats).map_err(|message| PatternError::new(i + 1, message))?;
+    }
+    Ok(pats)
+}
+
+pub(crate) fn parse_line(line: &str, pats: &mut Vec<(String, ClassPat)>) -> Result<(), String> {
+    let mut tokens = Tokens::new(tokenize(line));
+    match tokens.peek() {
+        None => return Ok(()),
+        Some(keyword @ ("method" | "field")) => {
+            tokens.next();
+            let (_, pat) = pats.last_mut().ok_or("member declared before a class")?;
+            let member = if keyword == "method" {
+                MemberPat::Method(parse_method(&mut tokens)?)
+            } else {
+                MemberPat::Field(parse_field(&mut tokens)?)
+            };
+            pat.members.push(member);
+        }
+        Some(_) => pats.push(parse_class(&mut tokens)?),
+    }
+    tokens.end()
+}
+
+fn parse_class(tokens: &mut Tokens) -> Result<(String, ClassPat), String> {
+    let mut pat = ClassPat::default();
+    loop {
+        pat = match tokens.expect_name()? {
+            "public" => pat.public(),
+            "package" => pat.package_private(),
+            "final" => pat.final_(),
+            "abstract" => pat.abstract_(),
+            "exact" => pat.exact_flags(),
+            "class" => break,
+            "interface" => {
+                pat.flags |= ClassPat::interface().flags;
+                break;
+            }
+            other => return Err(format!("unexpected `{other}`, expected a class modifier")),
+        }
+    }
+    let name = tokens.expect_name()?.to_owned();
+    if tokens.eat("extends") {
+        pat = pat.with_base(parse_type(tokens)?);
+    }
+    if tokens.eat("implements") {
+        loop {
+            pat = pat.with_impl(parse_type(tokens)?);
+            if !tokens.eat(",") {
+                break;
+            }
+        }
+    }
+    if tokens.eat("provides") {
+        loop {
+            pat = pat.provides_service(tokens.expect_name()?);
+            if !tokens.eat(",") {
+                break;
+            }
+        }
+    }
+    if tokens.eat("entrypoint") {
+        pat = pat.entry_point(tokens.expect_name()?);
+    }
+    if tokens.eat("members") {
+        let strategy = match tokens.expect_name()? {
+            "positional" => MatchStrategy::Positional,
+            "subsequence" => MatchStrategy::Subsequence,
+            "set" => MatchStrategy::Set,
+            "best_effort" => MatchStrategy::BestEffort,
+            other => return Err(format!("unexpected `{other}`, expected a match strategy")),
+        };
+        pat = pat.strategy(strategy);
+    }
+    Ok((name, pat))
+}
+
+fn parse_method(tokens: &mut Tokens) -> Result<MethodPat, String> {
+    let mut pat = MethodPat::default();
+    while let Some(token) = tokens.peek() {
+        if token == "default" {
+            pat = pat.default_impl();
+        } else if token == "package" {
+            pat = pat.visibility(Visibility::Package);
+        } else if let Some(flag) = method_flag(token) {
+            pat = pat.flags(flag);
+        } else {
+            break;
+        }
+        tokens.next();
+    }
+    if tokens.peek() != Some("(") {
+        pat = pat.name(tokens.expect_name()?);
+    }
+    tokens.expect("(")?;
+    if !tokens.eat(")") {
+        loop {
+            pat = pat.param(parse_type(tokens)?);
+            if tokens.eat(")") {
+                break;
+            }
+            tokens.expect(",")?;
+        }
+    }
+    tokens.expect("->")?;
+    let ret = if tokens.eat("(") {
+        tokens.expect(")")?;
+        TypePat::Void
+    } else {
+        parse_type(tokens)?
+    };
+    pat = pat.returns(ret);
+
+    loop {
+        pat = match tokens.peek() {
+            Some("lambda") => {
+                tokens.next();
+                let mut site = CallSitePat::lambda();
+                if tokens
+                    .peek()
+                    .is_some_and(|token| !CALL_SITE_KEYWORDS.contains(&token))
+                {
+                    site = site.returns(parse_type(tokens)?);
+                }
+                pat.call_site(site)
+            }
+            Some("concat") => {
+                tokens.next();
+                pat.call_site(CallSitePat::string_concat())
+            }
+            Some("indy") => {
+                tokens.next();
+                let count = tokens.expect_name()?;
+                let count = count
+                    .parse()
+                    .map_err(|_| format!("invalid call site count `{count}`"))?;
+                pat.call_site_count(count)
+            }
+            _ => break,
+        }
+    }
+    Ok(pat)
+}
+
+const CALL_SITE_KEYWORDS: [&str; 3] = ["lambda", "concat", "indy"];
+
+fn parse_field(tokens: &mut Tokens) -> Result<FieldPat, String> {
+    let mut pat = FieldPat::default();
+    while let Some(token) = tokens.peek() {
+        if token == "package" {
+            pat = pat.visibility(Visibility::Package);
+        } else if let Some(flag) = field_flag(token) {
+            pat = pat.flags(flag);
+        } else {
+            break;
+        }
+        tokens.next();
+    }
+    pat = pat.of_type(parse_type(tokens)?);
+    if let Some(name) = tokens.peek() {
+        tokens.next();
+        pat = pat.name(name);
+    }
+    Ok(pat)
+}
+
+fn parse_type(tokens: &mut Tokens) -> Result<TypePat, String> {
+    parse_type_name(tokens.expect_name()?)
+}
+
+/// Parses a single type written as in the text format, e.g. `java.util.List[]`.
+pub(crate) fn parse_type_name(str: &str) -> Result<TypePat, String> {
+    let base = str.trim_end_matches("[]");
+    let dims = (str.len() - base.len()) / 2;
+    if let Some(name) = base.strip_prefix('@') {
+        if dims > 0 {
+            return Err(format!("`{str}` cannot be an array"));
+        }
+        return Ok(TypePat::Ref(name.to_owned()));
+    }
+    if let Some(name) = base.strip_prefix('$') {
+        if dims > 0 {
+            return Err(format!("`{str}` cannot be an array"));
+        }
+        return Ok(TypePat::Capture(name.to_owned()));
+    }
+    let desc = match base {
+        "*" | "Any" if dims == 0 => return Ok(TypePat::Any),
+        "void" if dims == 0 => return Ok(TypePat::Void),
+        "*" | "Any" | "void" => return Err(format!("`{str}` cannot be an array")),
+        "boolean" => Descriptor::Boolean,
+        "byte" => Descriptor::Byte,
+        "short" => Descriptor::Short,
+        "int" => Descriptor::Integer,
+        "long" => Descriptor::Long,
+        "float" => Descriptor::Float,
+        "double" => Descriptor::Double,
+        "char" => Descriptor::Char,
+        name if JAVA_LANG.contains(&name) => return Ok(exact(dims, &format!("java/lang/{name}"))),
+        name => return Ok(exact(dims, &to_internal(name))),
+    };
+    let desc = (0..dims).fold(desc, |desc, _| Descriptor::Array(desc.into()));
+    Ok(TypePat::Match(desc))
+}
+
+/// Returns the pattern of a class type or an array of it, which owns the class name.
+fn exact(dims: usize, name: &str) -> TypePat {
+    TypePat::Exact(format!("{}L{name};", "[".repeat(dims)))
+}
+
+pub(crate) const JAVA_LANG: &[&str] = &[
+    "Boolean",
+    "Byte",
+    "Character",
+    "CharSequence",
+    "Class",
+    "Double",
+    "Enum",
+    "Exception",
+    "Float",
+    "Integer",
+    "Iterable",
+    "Long",
+    "Number",
+    "Object",
+    "Runnable",
+    "RuntimeException",
+    "Short",
+    "String",
+    "Thread",
+    "Throwable",
+];
+
+pub(crate) fn method_flag(str: &str) -> Option<MethodAccessFlags> {
+    let flag = match str {
+        "public" => MethodAccessFlags::PUBLIC,
+        "private" => MethodAccessFlags::PRIVATE,
+        "protected" => MethodAccessFlags::PROTECTED,
+        "static" => MethodAccessFlags::STATIC,
+        "final" => MethodAccessFlags::FINAL,
+        "synchronized" => MethodAccessFlags::SYNCHRONIZED,
+        "bridge" => MethodAccessFlags::BRIDGE,
+        "varargs" => MethodAccessFlags::VARARGS,
+        "native" => MethodAccessFlags::NATIVE,
+        "abstract" => MethodAccessFlags::ABSTRACT,
+        "strict" | "strictfp" => MethodAccessFlags::STRICT,
+        "synthetic" => MethodAccessFlags::SYNTHETIC,
+        _ => return None,
+    };
+    Some(flag)
+}
+
+pub(crate) fn field_flag(str: &str) -> Option<FieldAccessFlags> {
+    let flag = match str {
+        "public" => FieldAccessFlags::PUBLIC,
+        "private" => FieldAccessFlags::PRIVATE,
+        "protected" => FieldAccessFlags::PROTECTED,
+        "static" => FieldAccessFlags::STATIC,
+        "final" => FieldAccessFlags::FINAL,
+        "volatile" => FieldAccessFlags::VOLATILE,
+        "transient" => FieldAccessFlags::TRANSIENT,
+        "synthetic" => FieldAccessFlags::SYNTHETIC,
+        "enum" => FieldAccessFlags::ENUM,
+        _ => return None,
+    };
+    Some(flag)
+}
+
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut rem = line.trim_start();
+    while !rem.is_empty() {
+        let len = if rem.starts_with("->") {
+            2
+        } else if rem.starts_with(['(', ')', ',']) {
+            1
+        } else {
+            rem.char_indices()
+                .find(|&(i, c)| c.is_whitespace() || "(),".contains(c) || rem[i..].starts_with("->"))
+                .map_or(rem.len(), |(i, _)| i)
+        };
+        tokens.push(&rem[..len]);
+        rem = rem[len..].trim_start();
+    }
+    tokens
+}
+
+struct Tokens<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(tokens: Vec<&'a str>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let tok = self.peek();
+        self.pos += 1;
+        tok
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let matches = self.peek() == Some(expected);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next() {
+            Some(tok) if tok == expected => Ok(()),
+            Some(tok) => Err(format!("unexpected `{tok}`, expected `{expected}`")),
+            None => Err(format!("unexpected end of line, expected `{expected}`")),
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<&'a str, String> {
+        match self.next() {
+            Some(tok @ ("(" | ")" | "," | "->")) => Err(format!("unexpected `{tok}`, expected a name")),
+            Some(tok) => Ok(tok),
+            None => Err("unexpected end of line, expected a name".to_owned()),
+        }
+    }
+
+    fn end(&self) -> Result<(), String> {
+        match self.peek() {
+            Some(tok) => Err(format!("unexpected `{tok}`, expected end of line")),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatternError {}
+
+impl PatternError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::ClassAccessFlags;
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn parse_and_search() {
+        let src = "
+            # the first pattern
+            public class First implements Runnable
+                field private static final long   # a serial number
+                method <init>() -> ()
+                method public static run(java.lang.String[], *) -> int
+
+            class Second extends com.example.Base members set
+        ";
+        let pats = parse_patterns(src).unwrap();
+        assert_eq!(pats.len(), 2);
+        assert_eq!(pats[1].0, "Second");
+        assert_eq!(
+            pats[1].1.base.type_pat().and_then(TypePat::class_name),
+            Some("com/example/Base")
+        );
+        assert_eq!(pats[1].1.strategy, MatchStrategy::Set);
+
+        let class = TestClass::new("a")
+            .implements("java/lang/Runnable")
+            .field(
+                FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+                "b",
+                "J",
+            )
+            .method(MethodAccessFlags::PUBLIC, "<init>", "()V")
+            .method(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+                "run",
+                "([Ljava/lang/String;Ljava/lang/Object;)I",
+            );
+        let mut jar = TestJar::default().class(class).open().unwrap();
+        let [entry] = crate::search_exact(&mut jar, &[pats[0].1.clone()]).unwrap();
+        assert_eq!(entry.name(), "a.class");
+    }
+
+    #[test]
+    fn match_default_methods() {
+        let src = "
+            interface Listener
+                method public abstract () -> ()
+                method public default () -> ()
+                method public () -> ()
+        ";
+        let pats = parse_patterns(src).unwrap();
+
+        let public = MethodAccessFlags::PUBLIC;
+        let listener = |name: &str, second| {
+            TestClass::new(name)
+                .flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT)
+                .method(public | MethodAccessFlags::ABSTRACT, "b", "()V")
+                .method(second, "c", "()V")
+                .method(public | MethodAccessFlags::STATIC, "d", "()V")
+        };
+        let mut jar = TestJar::default()
+            .class(listener("a", public))
+            .class(listener("e", public | MethodAccessFlags::STATIC))
+            .open()
+            .unwrap();
+        let [entry] = crate::search_exact(&mut jar, &[pats[0].1.clone()]).unwrap();
+        assert_eq!(entry.name(), "a.class");
+    }
+
+    #[test]
+    fn parse_owned_class_types() {
+        assert_eq!(
+            parse_type_name("String"),
+            Ok(TypePat::Exact("Ljava/lang/String;".into()))
+        );
+        let typ = parse_type_name("com.example.Foo[][]").unwrap();
+        assert_eq!(typ, TypePat::Exact("[[Lcom/example/Foo;".into()));
+        assert_eq!(
+            parse_type_name("com.example.Foo").unwrap().class_name(),
+            Some("com/example/Foo")
+        );
+    }
+
+    #[test]
+    fn report_errors() {
+        let err = parse_patterns("method () -> ()").unwrap_err();
+        assert_eq!(err, PatternError::new(1, "member declared before a class"));
+
+        let err = parse_patterns("class A\n\n  method public (int -> ()").unwrap_err();
+        assert_eq!(err, PatternError::new(3, "unexpected `->`, expected `,`"));
+
+        let err = parse_patterns("class A\n  field void[]").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
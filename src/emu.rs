@@ -0,0 +1,852 @@
+//! A small interpreter folding the values computed by the code of a class, e.g. by static initializers
+//! and string decryption stubs.
+//!
+//! The [`Emulator`] only tracks primitives, strings and arrays. Values coming from elsewhere, like fields of
+//! other classes or calls it can't follow, are [`Value::Unknown`] and the computations depending on them are unknown
+//! too. Emulation stops at branches it can't decide and at instructions that would throw.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::bytecode::{self, BytecodeError, Instruction, Operand};
+use crate::clinit::constant;
+use crate::constant_pool::{ConstantPool, MemberRef, PoolConstant};
+use crate::descriptor::{Descriptor, MethodDescriptor};
+use crate::model::ConstantValue;
+
+const STRING: &str = "java/lang/String";
+/// Longest array allocated by the emulator, longer ones are unknown.
+const MAX_ARRAY_LEN: i32 = 1 << 16;
+/// Deepest chain of emulated calls, deeper calls are unknown.
+const MAX_DEPTH: usize = 8;
+
+/// A function providing the results of calls, see [`Emulator::intercept`].
+type Intercept<'a> = &'a dyn Fn(&MemberRef, &[Value]) -> Option<Value>;
+
+/// A value computed by the [`Emulator`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// An `int`, or a `boolean`, `byte`, `char` or `short`.
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Array(Array),
+    Null,
+    /// An object under construction, identified by the offset of the `new` instruction that created it.
+    Uninitialized(usize),
+    /// A value the emulator can't compute.
+    Unknown,
+}
+
+impl Value {
+    /// Returns the value as a constant, if it's a primitive or a string.
+    pub fn to_constant(&self) -> Option<ConstantValue> {
+        match self {
+            Self::Int(i) => Some(ConstantValue::Integer(*i)),
+            Self::Long(l) => Some(ConstantValue::Long(*l)),
+            Self::Float(f) => Some(ConstantValue::Float(*f)),
+            Self::Double(d) => Some(ConstantValue::Double(*d)),
+            Self::String(str) => Some(ConstantValue::String(str.clone())),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the value takes two slots of the operand stack, or `None` if it's unknown.
+    fn is_wide(&self) -> Option<bool> {
+        match self {
+            Self::Long(_) | Self::Double(_) => Some(true),
+            Self::Unknown => None,
+            _ => Some(false),
+        }
+    }
+}
+
+impl From<ConstantValue> for Value {
+    fn from(value: ConstantValue) -> Self {
+        match value {
+            ConstantValue::Integer(i) => Self::Int(i),
+            ConstantValue::Long(l) => Self::Long(l),
+            ConstantValue::Float(f) => Self::Float(f),
+            ConstantValue::Double(d) => Self::Double(d),
+            ConstantValue::String(str) => Self::String(str),
+        }
+    }
+}
+
+/// An array shared by the values referring to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array(Rc<RefCell<Vec<Value>>>);
+
+impl Array {
+    #[inline]
+    fn new(elements: Vec<Value>) -> Self {
+        Self(Rc::new(RefCell::new(elements)))
+    }
+
+    /// Returns a copy of the elements of the array.
+    #[inline]
+    pub fn to_vec(&self) -> Vec<Value> {
+        self.0.borrow().clone()
+    }
+
+    /// Returns the elements of a `char` array as a string, if they're all known.
+    pub fn to_utf16_string(&self) -> Option<String> {
+        let units = self
+            .0
+            .borrow()
+            .iter()
+            .map(|element| match element {
+                Value::Int(i) => Some(*i as u16),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        String::from_utf16(&units).ok()
+    }
+}
+
+/// How the emulation of a method ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The method returned, with a value unless it's void.
+    Returned(Option<Value>),
+    /// The emulation stopped before the method returned.
+    Stopped,
+}
+
+/// An interpreter for the code of the methods of a class, see the [module documentation](self).
+///
+/// # Examples
+/// ```no_run
+/// use jars::emu::{Emulator, Outcome};
+///
+/// let mut jar = jars::Jar::open("app.jar")?;
+/// let entry = jar.class_by_name("com/example/Foo")?.unwrap();
+/// let pool = entry.constant_pool()?;
+/// let code = [0x10, 6, 0x10, 7, 0x68, 0xac]; // bipush 6, bipush 7, imul, ireturn
+/// let outcome = Emulator::new("com/example/Foo", &pool).run(&code, &[])?;
+/// assert_eq!(outcome, Outcome::Returned(Some(jars::emu::Value::Int(42))));
+/// # Ok::<(), jars::Error>(())
+/// ```
+pub struct Emulator<'a> {
+    class: &'a str,
+    pool: &'a ConstantPool<'a>,
+    methods: Vec<(&'a str, &'a str, &'a [u8])>,
+    statics: BTreeMap<(String, String), Value>,
+    intercept: Option<Intercept<'a>>,
+    fuel: usize,
+    allocation: usize,
+    depth: usize,
+}
+
+impl<'a> Emulator<'a> {
+    /// Creates an emulator for the code of `class`, which resolves constants in `pool`.
+    #[inline]
+    pub fn new(class: &'a str, pool: &'a ConstantPool<'a>) -> Self {
+        Self {
+            class,
+            pool,
+            methods: vec![],
+            statics: BTreeMap::new(),
+            intercept: None,
+            fuel: 100_000,
+            allocation: 1 << 20,
+            depth: 0,
+        }
+    }
+
+    /// Adds a static method of the class whose calls are emulated rather than unknown.
+    #[inline]
+    pub fn method(mut self, name: &'a str, descriptor: &'a str, code: &'a [u8]) -> Self {
+        self.methods.push((name, descriptor, code));
+        self
+    }
+
+    /// Sets the number of instructions executed before the emulation stops, 100 000 by default.
+    ///
+    /// The budget is shared by all the methods run by the emulator.
+    #[inline]
+    pub fn fuel(mut self, fuel: usize) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Sets the number of array elements allocated before the emulation stops, 1 048 576 by default.
+    ///
+    /// Like the fuel, the budget is shared by all the methods run by the emulator.
+    #[inline]
+    pub fn allocation(mut self, elements: usize) -> Self {
+        self.allocation = elements;
+        self
+    }
+
+    /// Sets a function consulted before calls, which provides their result when it returns a value.
+    #[inline]
+    pub fn intercept(mut self, intercept: Intercept<'a>) -> Self {
+        self.intercept = Some(intercept);
+        self
+    }
+
+    /// Returns the value of a static field of the class, as assigned by the code run so far.
+    #[inline]
+    pub fn static_value(&self, name: &str, descriptor: &str) -> Option<&Value> {
+        self.statics.get(&(name.to_owned(), descriptor.to_owned()))
+    }
+
+    /// Runs a method body with the provided arguments, a `long` or a `double` taking a single argument.
+    pub fn run(&mut self, code: &[u8], args: &[Value]) -> Result<Outcome, BytecodeError> {
+        let mut locals = vec![];
+        for arg in args {
+            locals.push(arg.clone());
+            if arg.is_wide() == Some(true) {
+                locals.push(Value::Unknown);
+            }
+        }
+        self.execute(code, locals)
+    }
+
+    fn execute(&mut self, code: &[u8], locals: Vec<Value>) -> Result<Outcome, BytecodeError> {
+        let instructions = bytecode::disassemble(code, self.pool)?;
+        let mut frame = Frame {
+            stack: vec![],
+            locals,
+        };
+        let mut pc = 0;
+        while let Some(ins) = instructions.get(pc) {
+            let Some(fuel) = self.fuel.checked_sub(1) else {
+                break;
+            };
+            self.fuel = fuel;
+            match self.step(&mut frame, ins) {
+                Some(Flow::Next) => pc += 1,
+                Some(Flow::Jump(offset)) => {
+                    pc = instructions
+                        .binary_search_by_key(&offset, |ins| ins.offset)
+                        .map_err(|_| BytecodeError::InvalidTarget(offset))?;
+                }
+                Some(Flow::Return(value)) => return Ok(Outcome::Returned(value)),
+                None => break,
+            }
+        }
+        Ok(Outcome::Stopped)
+    }
+
+    /// Executes an instruction, returning `None` if the emulation can't continue.
+    fn step(&mut self, frame: &mut Frame, ins: &Instruction) -> Option<Flow> {
+        let op = ins.opcode;
+        match (op, ins.operands.as_slice()) {
+            // nop
+            (0x00, _) => {}
+            // aconst_null
+            (0x01, _) => frame.push(Value::Null),
+            // constants, bipush, sipush and ldc
+            (0x02..=0x14, operands) => {
+                frame.push(constant(op, operands).map_or(Value::Unknown, Value::from));
+            }
+            // loads
+            (0x15..=0x19, [Operand::Local(i)]) => frame.push(frame.local(*i as usize)),
+            (0x1a..=0x2d, _) => frame.push(frame.local(((op - 0x1a) % 4) as usize)),
+            // array loads
+            (0x2e..=0x35, _) => {
+                let index = frame.pop()?;
+                let value = match (frame.pop()?, index) {
+                    (Value::Array(array), Value::Int(i)) => {
+                        array.0.borrow().get(usize::try_from(i).ok()?)?.clone()
+                    }
+                    (Value::Null, _) => return None,
+                    _ => Value::Unknown,
+                };
+                frame.push(value);
+            }
+            // stores
+            (0x36..=0x3a, [Operand::Local(i)]) => frame.store(*i as usize)?,
+            (0x3b..=0x4e, _) => frame.store(((op - 0x3b) % 4) as usize)?,
+            // array stores
+            (0x4f..=0x56, _) => {
+                let value = frame.pop()?;
+                let index = frame.pop()?;
+                match (frame.pop()?, index, value) {
+                    (Value::Array(array), Value::Int(i), value) => {
+                        let value = match (op, value) {
+                            // bastore, castore and sastore truncate
+                            (0x54, Value::Int(i)) => Value::Int(i as i8 as i32),
+                            (0x55, Value::Int(i)) => Value::Int(i as u16 as i32),
+                            (0x56, Value::Int(i)) => Value::Int(i as i16 as i32),
+                            (_, value) => value,
+                        };
+                        *array.0.borrow_mut().get_mut(usize::try_from(i).ok()?)? = value;
+                    }
+                    (Value::Unknown, _, _) => {}
+                    _ => return None,
+                }
+            }
+            // pop
+            (0x57, _) => {
+                frame.pop()?;
+            }
+            // pop2
+            (0x58, _) => {
+                if !frame.pop()?.is_wide()? {
+                    frame.pop()?;
+                }
+            }
+            // dup
+            (0x59, _) => {
+                let value = frame.pop()?;
+                frame.push(value.clone());
+                frame.push(value);
+            }
+            // dup_x1
+            (0x5a, _) => {
+                let value1 = frame.pop()?;
+                let value2 = frame.pop()?;
+                frame.push(value1.clone());
+                frame.push(value2);
+                frame.push(value1);
+            }
+            // dup2 of a single wide value
+            (0x5c, _) if frame.stack.last()?.is_wide()? => {
+                let value = frame.pop()?;
+                frame.push(value.clone());
+                frame.push(value);
+            }
+            // dup2
+            (0x5c, _) => {
+                let value1 = frame.pop()?;
+                let value2 = frame.pop()?;
+                value2.is_wide()?;
+                frame.push(value2.clone());
+                frame.push(value1.clone());
+                frame.push(value2);
+                frame.push(value1);
+            }
+            // swap
+            (0x5f, _) => {
+                let value1 = frame.pop()?;
+                let value2 = frame.pop()?;
+                frame.push(value1);
+                frame.push(value2);
+            }
+            // negation
+            (0x74..=0x77, _) => {
+                let value = match frame.pop()? {
+                    Value::Int(i) => Value::Int(i.wrapping_neg()),
+                    Value::Long(l) => Value::Long(l.wrapping_neg()),
+                    Value::Float(f) => Value::Float(-f),
+                    Value::Double(d) => Value::Double(-d),
+                    _ => Value::Unknown,
+                };
+                frame.push(value);
+            }
+            // arithmetic, shifts and bitwise operations
+            (0x60..=0x83, _) => {
+                let b = frame.pop()?;
+                let a = frame.pop()?;
+                let value = match (a, b) {
+                    (Value::Unknown, _) | (_, Value::Unknown) => Value::Unknown,
+                    (a, b) => arithmetic(op, a, b)?,
+                };
+                frame.push(value);
+            }
+            // iinc
+            (0x84, [Operand::Local(i), Operand::Int(increment)]) => {
+                let value = match frame.local(*i as usize) {
+                    Value::Int(value) => Value::Int(value.wrapping_add(*increment)),
+                    _ => Value::Unknown,
+                };
+                frame.set_local(*i as usize, value);
+            }
+            // conversions
+            (0x85..=0x93, _) => {
+                let value = frame.pop()?;
+                frame.push(convert(op, value));
+            }
+            // comparisons
+            (0x94..=0x98, _) => {
+                let b = frame.pop()?;
+                let a = frame.pop()?;
+                frame.push(compare(op, a, b));
+            }
+            // if<cond>
+            (0x99..=0x9e, [Operand::Target(target)]) => {
+                let Value::Int(value) = frame.pop()? else {
+                    return None;
+                };
+                return Some(branch(condition(op - 0x99, value, 0), *target));
+            }
+            // if_icmp<cond>
+            (0x9f..=0xa4, [Operand::Target(target)]) => {
+                let (Value::Int(b), Value::Int(a)) = (frame.pop()?, frame.pop()?) else {
+                    return None;
+                };
+                return Some(branch(condition(op - 0x9f, a, b), *target));
+            }
+            // goto and goto_w
+            (0xa7 | 0xc8, [Operand::Target(target)]) => return Some(Flow::Jump(*target)),
+            // tableswitch and lookupswitch
+            (0xaa | 0xab, [Operand::Switch { default, cases }]) => {
+                let Value::Int(key) = frame.pop()? else {
+                    return None;
+                };
+                let target = cases
+                    .iter()
+                    .find(|(case, _)| *case == key)
+                    .map_or(*default, |(_, target)| *target);
+                return Some(Flow::Jump(target));
+            }
+            // returns
+            (0xac..=0xb0, _) => return Some(Flow::Return(Some(frame.pop()?))),
+            (0xb1, _) => return Some(Flow::Return(None)),
+            // getstatic
+            (
+                0xb2,
+                [Operand::Constant {
+                    value: PoolConstant::Field(field),
+                    ..
+                }],
+            ) => {
+                let value = if field.class == self.class {
+                    self.statics
+                        .get(&(field.name.clone(), field.descriptor.clone()))
+                        .cloned()
+                } else {
+                    None
+                };
+                frame.push(value.unwrap_or(Value::Unknown));
+            }
+            // putstatic
+            (
+                0xb3,
+                [Operand::Constant {
+                    value: PoolConstant::Field(field),
+                    ..
+                }],
+            ) => {
+                let value = frame.pop()?;
+                if field.class == self.class {
+                    self.statics
+                        .insert((field.name.clone(), field.descriptor.clone()), value);
+                }
+            }
+            // getfield
+            (0xb4, _) => {
+                frame.pop()?;
+                frame.push(Value::Unknown);
+            }
+            // putfield
+            (0xb5, _) => {
+                frame.pop()?;
+                frame.pop()?;
+            }
+            // invocations
+            (
+                0xb6..=0xb9,
+                [Operand::Constant {
+                    value: PoolConstant::Method(method) | PoolConstant::InterfaceMethod(method),
+                    ..
+                }, ..],
+            ) => self.invoke(frame, op, method)?,
+            // invokedynamic
+            (
+                0xba,
+                [Operand::Constant {
+                    value: PoolConstant::InvokeDynamic(site),
+                    ..
+                }],
+            ) => {
+                let descriptor = MethodDescriptor::parse(&site.descriptor).ok()?;
+                for _ in &descriptor.param_types {
+                    frame.pop()?;
+                }
+                if descriptor.return_type.is_some() {
+                    frame.push(Value::Unknown);
+                }
+            }
+            // new
+            (0xbb, _) => frame.push(Value::Uninitialized(ins.offset)),
+            // newarray and anewarray
+            (0xbc | 0xbd, [operand]) => {
+                let value = match frame.pop()? {
+                    Value::Int(len @ 0..=MAX_ARRAY_LEN) => {
+                        self.allocate(len as usize)?;
+                        let element = match operand {
+                            Operand::ArrayType("long") => Value::Long(0),
+                            Operand::ArrayType("float") => Value::Float(0.),
+                            Operand::ArrayType("double") => Value::Double(0.),
+                            Operand::ArrayType(_) => Value::Int(0),
+                            _ => Value::Null,
+                        };
+                        Value::Array(Array::new(vec![element; len as usize]))
+                    }
+                    Value::Int(len) if len < 0 => return None,
+                    _ => Value::Unknown,
+                };
+                frame.push(value);
+            }
+            // arraylength
+            (0xbe, _) => {
+                let value = match frame.pop()? {
+                    Value::Array(array) => Value::Int(array.0.borrow().len() as i32),
+                    Value::Null => return None,
+                    _ => Value::Unknown,
+                };
+                frame.push(value);
+            }
+            // checkcast
+            (0xc0, _) => {}
+            // instanceof
+            (0xc1, _) => {
+                frame.pop()?;
+                frame.push(Value::Unknown);
+            }
+            // monitorenter and monitorexit
+            (0xc2 | 0xc3, _) => {
+                frame.pop()?;
+            }
+            // multianewarray
+            (0xc5, [_, Operand::Int(dimensions)]) => {
+                for _ in 0..*dimensions {
+                    frame.pop()?;
+                }
+                frame.push(Value::Unknown);
+            }
+            // ifnull and ifnonnull
+            (0xc6 | 0xc7, [Operand::Target(target)]) => {
+                let is_null = match frame.pop()? {
+                    Value::Null => true,
+                    Value::String(_) | Value::Array(_) => false,
+                    _ => return None,
+                };
+                return Some(branch(is_null == (op == 0xc6), *target));
+            }
+            // if_acmp<cond>, jsr, ret, athrow and the dup forms that depend on unknown sizes
+            _ => return None,
+        }
+        Some(Flow::Next)
+    }
+
+    fn invoke(&mut self, frame: &mut Frame, op: u8, method: &MemberRef) -> Option<()> {
+        let descriptor = MethodDescriptor::parse(&method.descriptor).ok()?;
+        let mut args = vec![];
+        for _ in &descriptor.param_types {
+            args.push(frame.pop()?);
+        }
+        args.reverse();
+        // invokestatic has no receiver
+        let receiver = if op == 0xb8 { None } else { Some(frame.pop()?) };
+
+        let result = self
+            .intercept
+            .and_then(|intercept| intercept(method, &args))
+            .or_else(|| {
+                let result = string_method(method, receiver.as_ref(), &args)?;
+                if let Value::Array(array) = &result {
+                    let len = array.0.borrow().len();
+                    self.allocate(len)?;
+                }
+                Some(result)
+            })
+            .or_else(|| {
+                (op == 0xb8 && method.class == self.class)
+                    .then(|| self.call(method, &descriptor, args))
+                    .flatten()
+            });
+
+        if let (Some(Value::Uninitialized(id)), true) = (&receiver, method.name == "<init>") {
+            // the constructed object replaces its copies, strings are the only objects constructed
+            let object = match result {
+                Some(value @ Value::String(_)) => value,
+                _ => Value::Unknown,
+            };
+            frame.replace(&Value::Uninitialized(*id), &object);
+        } else if descriptor.return_type.is_some() {
+            frame.push(result.unwrap_or(Value::Unknown));
+        }
+        Some(())
+    }
+
+    /// Takes the elements of a new array out of the allocation budget, stopping the emulation once it runs out.
+    fn allocate(&mut self, elements: usize) -> Option<()> {
+        let Some(allocation) = self.allocation.checked_sub(elements) else {
+            self.fuel = 0;
+            return None;
+        };
+        self.allocation = allocation;
+        Some(())
+    }
+
+    /// Emulates a static method of the class, returning `None` if it doesn't return a value.
+    fn call(
+        &mut self,
+        method: &MemberRef,
+        descriptor: &MethodDescriptor<'_>,
+        args: Vec<Value>,
+    ) -> Option<Value> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        let &(_, _, code) = self
+            .methods
+            .iter()
+            .find(|(name, desc, _)| *name == method.name && *desc == method.descriptor)?;
+        let mut locals = vec![];
+        for (arg, typ) in args.into_iter().zip(&descriptor.param_types) {
+            locals.push(arg);
+            if matches!(typ, Descriptor::Long | Descriptor::Double) {
+                locals.push(Value::Unknown);
+            }
+        }
+        self.depth += 1;
+        let outcome = self.execute(code, locals);
+        self.depth -= 1;
+        match outcome.ok()? {
+            Outcome::Returned(value) => value,
+            Outcome::Stopped => None,
+        }
+    }
+}
+
+struct Frame {
+    stack: Vec<Value>,
+    locals: Vec<Value>,
+}
+
+impl Frame {
+    #[inline]
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<Value> {
+        self.stack.pop()
+    }
+
+    #[inline]
+    fn local(&self, index: usize) -> Value {
+        self.locals.get(index).cloned().unwrap_or(Value::Unknown)
+    }
+
+    fn set_local(&mut self, index: usize, value: Value) {
+        if self.locals.len() <= index {
+            self.locals.resize(index + 1, Value::Unknown);
+        }
+        self.locals[index] = value;
+    }
+
+    fn store(&mut self, index: usize) -> Option<()> {
+        let value = self.pop()?;
+        if value.is_wide() == Some(true) {
+            self.set_local(index + 1, Value::Unknown);
+        }
+        self.set_local(index, value);
+        Some(())
+    }
+
+    fn replace(&mut self, old: &Value, new: &Value) {
+        for value in self.stack.iter_mut().chain(&mut self.locals) {
+            if value == old {
+                *value = new.clone();
+            }
+        }
+    }
+}
+
+enum Flow {
+    Next,
+    Jump(usize),
+    Return(Option<Value>),
+}
+
+#[inline]
+fn branch(taken: bool, target: usize) -> Flow {
+    if taken {
+        Flow::Jump(target)
+    } else {
+        Flow::Next
+    }
+}
+
+/// Evaluates the condition of a branch, in the order of `ifeq` to `ifle`.
+fn condition(cond: u8, a: i32, b: i32) -> bool {
+    match cond {
+        0 => a == b,
+        1 => a != b,
+        2 => a < b,
+        3 => a >= b,
+        4 => a > b,
+        _ => a <= b,
+    }
+}
+
+/// Evaluates a binary operation, returning `None` if it throws.
+fn arithmetic(op: u8, a: Value, b: Value) -> Option<Value> {
+    use Value::{Double as D, Float as F, Int as I, Long as L};
+
+    let value = match (op, a, b) {
+        (0x60, I(a), I(b)) => I(a.wrapping_add(b)),
+        (0x61, L(a), L(b)) => L(a.wrapping_add(b)),
+        (0x62, F(a), F(b)) => F(a + b),
+        (0x63, D(a), D(b)) => D(a + b),
+        (0x64, I(a), I(b)) => I(a.wrapping_sub(b)),
+        (0x65, L(a), L(b)) => L(a.wrapping_sub(b)),
+        (0x66, F(a), F(b)) => F(a - b),
+        (0x67, D(a), D(b)) => D(a - b),
+        (0x68, I(a), I(b)) => I(a.wrapping_mul(b)),
+        (0x69, L(a), L(b)) => L(a.wrapping_mul(b)),
+        (0x6a, F(a), F(b)) => F(a * b),
+        (0x6b, D(a), D(b)) => D(a * b),
+        (0x6c, I(_), I(0)) | (0x6d, L(_), L(0)) | (0x70, I(_), I(0)) | (0x71, L(_), L(0)) => return None,
+        (0x6c, I(a), I(b)) => I(a.wrapping_div(b)),
+        (0x6d, L(a), L(b)) => L(a.wrapping_div(b)),
+        (0x6e, F(a), F(b)) => F(a / b),
+        (0x6f, D(a), D(b)) => D(a / b),
+        (0x70, I(a), I(b)) => I(a.wrapping_rem(b)),
+        (0x71, L(a), L(b)) => L(a.wrapping_rem(b)),
+        (0x72, F(a), F(b)) => F(a % b),
+        (0x73, D(a), D(b)) => D(a % b),
+        (0x78, I(a), I(b)) => I(a.wrapping_shl(b as u32)),
+        (0x79, L(a), I(b)) => L(a.wrapping_shl(b as u32)),
+        (0x7a, I(a), I(b)) => I(a.wrapping_shr(b as u32)),
+        (0x7b, L(a), I(b)) => L(a.wrapping_shr(b as u32)),
+        (0x7c, I(a), I(b)) => I((a as u32).wrapping_shr(b as u32) as i32),
+        (0x7d, L(a), I(b)) => L((a as u64).wrapping_shr(b as u32) as i64),
+        (0x7e, I(a), I(b)) => I(a & b),
+        (0x7f, L(a), L(b)) => L(a & b),
+        (0x80, I(a), I(b)) => I(a | b),
+        (0x81, L(a), L(b)) => L(a | b),
+        (0x82, I(a), I(b)) => I(a ^ b),
+        (0x83, L(a), L(b)) => L(a ^ b),
+        _ => Value::Unknown,
+    };
+    Some(value)
+}
+
+/// Evaluates a conversion between primitive types.
+fn convert(op: u8, value: Value) -> Value {
+    use Value::{Double as D, Float as F, Int as I, Long as L};
+
+    // float to integer casts saturate and map NaN to zero like in Java
+    match (op, value) {
+        (0x85, I(i)) => L(i as i64),
+        (0x86, I(i)) => F(i as f32),
+        (0x87, I(i)) => D(i as f64),
+        (0x88, L(l)) => I(l as i32),
+        (0x89, L(l)) => F(l as f32),
+        (0x8a, L(l)) => D(l as f64),
+        (0x8b, F(f)) => I(f as i32),
+        (0x8c, F(f)) => L(f as i64),
+        (0x8d, F(f)) => D(f as f64),
+        (0x8e, D(d)) => I(d as i32),
+        (0x8f, D(d)) => L(d as i64),
+        (0x90, D(d)) => F(d as f32),
+        (0x91, I(i)) => I(i as i8 as i32),
+        (0x92, I(i)) => I(i as u16 as i32),
+        (0x93, I(i)) => I(i as i16 as i32),
+        _ => Value::Unknown,
+    }
+}
+
+/// Evaluates `lcmp`, `fcmpl`, `fcmpg`, `dcmpl` or `dcmpg`.
+fn compare(op: u8, a: Value, b: Value) -> Value {
+    let ordering = match (a, b) {
+        (Value::Long(a), Value::Long(b)) => Some(a.cmp(&b)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(&b),
+        (Value::Double(a), Value::Double(b)) => a.partial_cmp(&b),
+        _ => return Value::Unknown,
+    };
+    // comparisons with NaN yield -1 for the `l` variants and 1 for the `g` variants
+    Value::Int(
+        ordering.map_or(if op == 0x96 || op == 0x98 { 1 } else { -1 }, |ordering| {
+            ordering as i32
+        }),
+    )
+}
+
+/// Evaluates the methods of `String` used to build and take apart strings.
+fn string_method(method: &MemberRef, receiver: Option<&Value>, args: &[Value]) -> Option<Value> {
+    if method.class != STRING {
+        return None;
+    }
+    let chars = |array: &Array| array.to_utf16_string().map(Value::String);
+    match (method.name.as_str(), method.descriptor.as_str(), receiver, args) {
+        ("<init>", "([C)V", _, [Value::Array(array)]) => chars(array),
+        ("valueOf" | "copyValueOf", "([C)Ljava/lang/String;", None, [Value::Array(array)]) => chars(array),
+        ("toCharArray", "()[C", Some(Value::String(str)), []) => {
+            let units = str.encode_utf16().map(|unit| Value::Int(unit as i32)).collect();
+            Some(Value::Array(Array::new(units)))
+        }
+        ("intern", "()Ljava/lang/String;", Some(value @ Value::String(_)), []) => Some(value.clone()),
+        ("length", "()I", Some(Value::String(str)), []) => {
+            Some(Value::Int(str.encode_utf16().count() as i32))
+        }
+        ("charAt", "(I)C", Some(Value::String(str)), [Value::Int(i)]) => {
+            let unit = str.encode_utf16().nth(usize::try_from(*i).ok()?)?;
+            Some(Value::Int(unit as i32))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn emulate_decryption_loop() {
+        // static String z(String s) {
+        //     char[] chars = s.toCharArray();
+        //     for (int i = 0; i < chars.length; i++) chars[i] ^= 42;
+        //     return new String(chars);
+        // }
+        let mut class = ClassBuilder::new("a");
+        let to_char_array = class.pool().method_ref(STRING, "toCharArray", "()[C");
+        let string = class.pool().class(STRING);
+        let init = class.pool().method_ref(STRING, "<init>", "([C)V");
+        let bytes = class.to_bytes();
+        let pool = ConstantPool::parse(&bytes).unwrap();
+        let [a, b] = to_char_array.to_be_bytes();
+        let [c, d] = string.to_be_bytes();
+        let [e, f] = init.to_be_bytes();
+        #[rustfmt::skip]
+        let code = [
+            0x2a, 0xb6, a, b, 0x4b,       // 0: aload_0, invokevirtual toCharArray, astore_0
+            0x03, 0x3c,                   // 5: iconst_0, istore_1
+            0x1b, 0x2a, 0xbe, 0xa2, 0, 18, // 7: iload_1, aload_0, arraylength, if_icmpge 28
+            0x2a, 0x1b, 0x5c, 0x34,       // 13: aload_0, iload_1, dup2, caload
+            0x10, 42, 0x82, 0x92, 0x55,   // 17: bipush 42, ixor, i2c, castore
+            0x84, 1, 1,                   // 22: iinc 1 1
+            0xa7, 0xff, 0xee,             // 25: goto 7
+            0xbb, c, d, 0x59, 0x2a,       // 28: new String, dup, aload_0
+            0xb7, e, f, 0xb0,             // 33: invokespecial <init>, areturn
+        ];
+        let encrypted: String = "secret".chars().map(|c| (c as u8 ^ 42) as char).collect();
+        let outcome = Emulator::new("a", &pool)
+            .run(&code, &[Value::String(encrypted)])
+            .unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Returned(Some(Value::String("secret".to_owned())))
+        );
+    }
+
+    #[test]
+    fn stop_past_allocation_budget() {
+        let bytes = ClassBuilder::new("a").to_bytes();
+        let pool = ConstantPool::parse(&bytes).unwrap();
+        // bipush 1, bipush 16, ishl, newarray int, pop
+        let alloc = [0x10, 1, 0x10, 16, 0x78, 0xbc, 10, 0x57];
+        // iconst_1, ireturn
+        let code = [&alloc[..], &alloc, &alloc, &[0x04, 0xac]].concat();
+        let run = |elements| {
+            Emulator::new("a", &pool)
+                .allocation(elements)
+                .run(&code, &[])
+                .unwrap()
+        };
+        assert_eq!(run(3 << 16), Outcome::Returned(Some(Value::Int(1))));
+        assert_eq!(run(2 << 16), Outcome::Stopped);
+    }
+}
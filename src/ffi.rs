@@ -0,0 +1,333 @@
+//! A C API for embedding the matcher, declared in `include/jars.h`.
+//!
+//! Functions that can fail return a null pointer and store an error message,
+//! which can be retrieved with [`jars_last_error`] on the same thread.
+//! Panics are caught before they reach the caller and reported the same way,
+//! with functions returning sizes or indices returning zero.
+//! Strings returned by the API are owned by the object they are retrieved from.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::io::Cursor;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::dsl::parse_patterns;
+use crate::jar::Jar;
+use crate::matching::MemberBinding;
+use crate::result::Result;
+use crate::search::Searcher;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An archive read from memory.
+pub struct JarsJar(Jar<Cursor<Vec<u8>>>);
+
+/// A compiled set of named patterns.
+pub struct JarsPatterns {
+    searcher: Searcher,
+    names: Vec<CString>,
+}
+
+/// Matches returned by a search.
+pub struct JarsMatches(Vec<FfiMatch>);
+
+struct FfiMatch {
+    pattern: usize,
+    pattern_name: CString,
+    class_name: CString,
+    entry: CString,
+    bindings: Vec<MemberBinding>,
+}
+
+fn handle<A>(res: Result<A>) -> Option<A> {
+    match res {
+        Ok(val) => Some(val),
+        Err(err) => {
+            set_error(err.to_string());
+            None
+        }
+    }
+}
+
+/// Runs the body of an entry point, returning `default` and storing an error if it panics,
+/// since unwinding into the caller would abort it.
+fn catch<A>(default: A, f: impl FnOnce() -> A) -> A {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        set_error(format!("panic: {message}"));
+        default
+    })
+}
+
+fn set_error(message: String) {
+    let message = CString::new(message).unwrap_or_else(|_| c"invalid error message".to_owned());
+    LAST_ERROR.with(|err| *err.borrow_mut() = Some(message));
+}
+
+unsafe fn get_match<'a>(matches: *const JarsMatches, index: usize) -> &'a FfiMatch {
+    &(&(*matches).0)[index]
+}
+
+fn c_string(str: &str) -> CString {
+    // names read from class files cannot contain interior nulls, but be defensive
+    CString::new(str).unwrap_or_else(|_| CString::new(str.replace('\0', "")).unwrap())
+}
+
+/// Returns the message of the last error that occurred on this thread, or null if there was none.
+///
+/// The message is valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn jars_last_error() -> *const c_char {
+    catch(ptr::null(), || {
+        LAST_ERROR.with(|err| err.borrow().as_ref().map_or(ptr::null(), |err| err.as_ptr()))
+    })
+}
+
+/// Opens an archive from a buffer, the contents are copied.
+///
+/// # Safety
+/// `bytes` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn jars_jar_open(bytes: *const u8, len: usize) -> *mut JarsJar {
+    catch(ptr::null_mut(), || {
+        if bytes.is_null() {
+            set_error("null archive buffer".to_owned());
+            return ptr::null_mut();
+        }
+        let bytes = std::slice::from_raw_parts(bytes, len).to_vec();
+        handle(Jar::from_bytes(bytes)).map_or(ptr::null_mut(), |jar| Box::into_raw(Box::new(JarsJar(jar))))
+    })
+}
+
+/// Frees an archive.
+///
+/// # Safety
+/// `jar` must be null or a pointer returned by [`jars_jar_open`] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jars_jar_free(jar: *mut JarsJar) {
+    catch((), || {
+        if !jar.is_null() {
+            drop(Box::from_raw(jar));
+        }
+    })
+}
+
+/// Compiles patterns written in the [text format](crate::parse_patterns).
+///
+/// # Safety
+/// `src` must be a valid null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn jars_patterns_compile(src: *const c_char) -> *mut JarsPatterns {
+    catch(ptr::null_mut(), || {
+        if src.is_null() {
+            set_error("null pattern source".to_owned());
+            return ptr::null_mut();
+        }
+        let Ok(src) = CStr::from_ptr(src).to_str() else {
+            set_error("pattern source is not valid UTF-8".to_owned());
+            return ptr::null_mut();
+        };
+        let Some(pats) = handle(parse_patterns(src).map_err(Into::into)) else {
+            return ptr::null_mut();
+        };
+        let names = pats.iter().map(|(name, _)| c_string(name)).collect();
+        let searcher = Searcher::new(pats.into_iter().map(|(_, pat)| pat));
+        Box::into_raw(Box::new(JarsPatterns { searcher, names }))
+    })
+}
+
+/// Returns the number of patterns in a set.
+///
+/// # Safety
+/// `pats` must be a valid pointer returned by [`jars_patterns_compile`].
+#[no_mangle]
+pub unsafe extern "C" fn jars_patterns_len(pats: *const JarsPatterns) -> usize {
+    catch(0, || (*pats).names.len())
+}
+
+/// Frees a set of patterns.
+///
+/// # Safety
+/// `pats` must be null or a pointer returned by [`jars_patterns_compile`] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jars_patterns_free(pats: *mut JarsPatterns) {
+    catch((), || {
+        if !pats.is_null() {
+            drop(Box::from_raw(pats));
+        }
+    })
+}
+
+/// Searches for the patterns in an archive, allowing for more than one match per pattern.
+///
+/// # Safety
+/// `jar` and `pats` must be valid pointers returned by [`jars_jar_open`] and [`jars_patterns_compile`].
+#[no_mangle]
+pub unsafe extern "C" fn jars_search(jar: *mut JarsJar, pats: *const JarsPatterns) -> *mut JarsMatches {
+    catch(ptr::null_mut(), || {
+        let pats = &*pats;
+        let res = pats.searcher.search_many(&mut (*jar).0).and_then(|matches| {
+            matches
+                .into_iter()
+                .map(|mat| {
+                    Ok(FfiMatch {
+                        pattern: mat.pattern,
+                        pattern_name: pats.names[mat.pattern].clone(),
+                        class_name: c_string(&mat.entry.header()?.this_class),
+                        entry: c_string(mat.entry.name()),
+                        bindings: mat.bindings,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        });
+        handle(res).map_or(ptr::null_mut(), |matches| {
+            Box::into_raw(Box::new(JarsMatches(matches)))
+        })
+    })
+}
+
+/// Returns the number of matches.
+///
+/// # Safety
+/// `matches` must be a valid pointer returned by [`jars_search`].
+#[no_mangle]
+pub unsafe extern "C" fn jars_matches_len(matches: *const JarsMatches) -> usize {
+    catch(0, || (*matches).0.len())
+}
+
+/// Returns the index of the pattern of the match at `index`.
+///
+/// # Safety
+/// `matches` must be a valid pointer returned by [`jars_search`] and `index` must be in bounds.
+#[no_mangle]
+pub unsafe extern "C" fn jars_match_pattern(matches: *const JarsMatches, index: usize) -> usize {
+    catch(0, || get_match(matches, index).pattern)
+}
+
+/// Returns the name of the pattern of the match at `index`.
+///
+/// # Safety
+/// `matches` must be a valid pointer returned by [`jars_search`] and `index` must be in bounds.
+#[no_mangle]
+pub unsafe extern "C" fn jars_match_pattern_name(
+    matches: *const JarsMatches,
+    index: usize,
+) -> *const c_char {
+    catch(ptr::null(), || get_match(matches, index).pattern_name.as_ptr())
+}
+
+/// Returns the internal name of the class matched at `index`.
+///
+/// # Safety
+/// `matches` must be a valid pointer returned by [`jars_search`] and `index` must be in bounds.
+#[no_mangle]
+pub unsafe extern "C" fn jars_match_class_name(matches: *const JarsMatches, index: usize) -> *const c_char {
+    catch(ptr::null(), || get_match(matches, index).class_name.as_ptr())
+}
+
+/// Returns the path of the archive entry matched at `index`.
+///
+/// # Safety
+/// `matches` must be a valid pointer returned by [`jars_search`] and `index` must be in bounds.
+#[no_mangle]
+pub unsafe extern "C" fn jars_match_entry(matches: *const JarsMatches, index: usize) -> *const c_char {
+    catch(ptr::null(), || get_match(matches, index).entry.as_ptr())
+}
+
+/// Returns the number of member bindings of the match at `index`.
+///
+/// # Safety
+/// `matches` must be a valid pointer returned by [`jars_search`] and `index` must be in bounds.
+#[no_mangle]
+pub unsafe extern "C" fn jars_match_bindings_len(matches: *const JarsMatches, index: usize) -> usize {
+    catch(0, || get_match(matches, index).bindings.len())
+}
+
+/// Returns the member matched by a member pattern, as an index into the methods of the class
+/// if `is_method` is set to true and an index into its fields otherwise.
+///
+/// # Safety
+/// `matches` must be a valid pointer returned by [`jars_search`], `index` and `binding` must be in bounds
+/// and `is_method` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn jars_match_binding(
+    matches: *const JarsMatches,
+    index: usize,
+    binding: usize,
+    is_method: *mut bool,
+) -> usize {
+    catch(0, || {
+        let (method, member) = match get_match(matches, index).bindings[binding] {
+            MemberBinding::Method(i) => (true, i),
+            MemberBinding::Field(i) => (false, i),
+        };
+        *is_method = method;
+        member
+    })
+}
+
+/// Frees matches.
+///
+/// # Safety
+/// `matches` must be null or a pointer returned by [`jars_search`] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jars_matches_free(matches: *mut JarsMatches) {
+    catch((), || {
+        if !matches.is_null() {
+            drop(Box::from_raw(matches));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn search_through_ffi() {
+        let class = TestClass::new("a").method(cafebabe::MethodAccessFlags::PUBLIC, "run", "()V");
+        let bytes = TestJar::default().class(class).to_bytes().unwrap();
+
+        unsafe {
+            let jar = jars_jar_open(bytes.as_ptr(), bytes.len());
+            let pats = jars_patterns_compile(c"class Runner\n  method public () -> ()".as_ptr());
+            assert_eq!(jars_patterns_len(pats), 1);
+
+            let matches = jars_search(jar, pats);
+            assert_eq!(jars_matches_len(matches), 1);
+            assert_eq!(CStr::from_ptr(jars_match_class_name(matches, 0)), c"a");
+            assert_eq!(CStr::from_ptr(jars_match_pattern_name(matches, 0)), c"Runner");
+            assert_eq!(CStr::from_ptr(jars_match_entry(matches, 0)), c"a.class");
+            let mut is_method = false;
+            assert_eq!(jars_match_binding(matches, 0, 0, &mut is_method), 0);
+            assert!(is_method);
+
+            jars_matches_free(matches);
+            jars_patterns_free(pats);
+            jars_jar_free(jar);
+
+            assert!(jars_patterns_compile(c"method () -> ()".as_ptr()).is_null());
+            let err = CStr::from_ptr(jars_last_error()).to_str().unwrap();
+            assert_eq!(err, "pattern error: line 1: member declared before a class");
+        }
+    }
+
+    #[test]
+    fn catch_panics() {
+        let matches = JarsMatches(vec![]);
+        unsafe {
+            assert_eq!(jars_match_bindings_len(&matches, 0), 0);
+            assert!(jars_match_entry(&matches, 0).is_null());
+            let err = CStr::from_ptr(jars_last_error()).to_str().unwrap();
+            assert!(err.starts_with("panic: index out of bounds"));
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! Predicates selecting the classes of an archive before they're parsed.
+//!
+//! A [`ClassFilter`] is first checked with the name and the size of an entry, before the entry is read.
+//! Filters that also depend on the access flags of the class are checked again once the entry is read,
+//! with the flags taken from the class bytes without parsing the class.
+//!
+//! # Examples
+//! ```no_run
+//! use jars::filter::{self, ClassFilter};
+//!
+//! let mut jar = jars::Jar::open("app.jar").unwrap();
+//! let filter = filter::package("net/minecraft").and(filter::min_size(2048));
+//! for entry in jar.classes_filtered(filter) {
+//!     println!("{}", entry.unwrap().name());
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use cafebabe::ClassAccessFlags;
+
+use crate::constant_pool::ConstantPool;
+use crate::name::{package_prefix, ClassName};
+
+/// Information about a class available to a [`ClassFilter`].
+#[derive(Debug, Clone, Copy)]
+pub struct ClassInfo<'a> {
+    /// Path of the entry in the archive, e.g. `com/example/Main.class`.
+    pub name: &'a str,
+    /// Decompressed size of the entry in bytes, as declared by the archive.
+    pub size: u64,
+    /// Access flags of the class, [`None`] until the entry has been read.
+    pub flags: Option<ClassAccessFlags>,
+}
+
+/// A predicate on the classes of an archive, see the [module documentation](self).
+pub trait ClassFilter {
+    /// Returns whether the class is accepted,
+    /// or [`None`] if it cannot be decided without [`ClassInfo::flags`].
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool>;
+
+    /// Accepts classes accepted by both filters.
+    #[inline]
+    fn and<F: ClassFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Accepts classes accepted by either filter.
+    #[inline]
+    fn or<F: ClassFilter>(self, other: F) -> Or<Self, F>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Accepts classes rejected by the filter.
+    #[inline]
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+impl<F: ClassFilter + ?Sized> ClassFilter for &F {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        (**self).accepts(class)
+    }
+}
+
+impl<F: ClassFilter + ?Sized> ClassFilter for Box<F> {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        (**self).accepts(class)
+    }
+}
+
+impl<F: ClassFilter> ClassFilter for Option<F> {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        self.as_ref().map_or(Some(true), |filter| filter.accepts(class))
+    }
+}
+
+/// Accepts classes in a package or in any of its subpackages, e.g. `com/example`.
+pub fn package(name: impl Into<String>) -> Package {
+    Package(package_prefix(&name.into()))
+}
+
+/// Accepts classes with paths satisfying the predicate.
+#[inline]
+pub fn name<F: Fn(&str) -> bool>(predicate: F) -> Name<F> {
+    Name(predicate)
+}
+
+/// Accepts classes of at least the provided size in bytes.
+#[inline]
+pub fn min_size(size: u64) -> Size {
+    Size(size..=u64::MAX)
+}
+
+/// Accepts classes of at most the provided size in bytes.
+#[inline]
+pub fn max_size(size: u64) -> Size {
+    Size(0..=size)
+}
+
+/// Accepts classes with all of the provided access flags.
+#[inline]
+pub fn flags(flags: ClassAccessFlags) -> Flags {
+    Flags(flags)
+}
+
+/// Accepts classes with the provided internal names, e.g. ones identified by a previous search.
+///
+/// Names are compared with the paths of the entries without their extension,
+/// negate the filter with [`ClassFilter::not`] to skip the classes instead.
+pub fn classes(names: impl IntoIterator<Item = impl Into<ClassName>>) -> Classes {
+    Classes(names.into_iter().map(Into::into).collect())
+}
+
+/// Accepts classes with a closure, which returns [`None`] when it needs [`ClassInfo::flags`] to decide.
+#[inline]
+pub fn custom<F: Fn(&ClassInfo<'_>) -> Option<bool>>(predicate: F) -> Custom<F> {
+    Custom(predicate)
+}
+
+#[derive(Debug, Clone)]
+pub struct Package(String);
+
+impl ClassFilter for Package {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        Some(class.name.starts_with(&self.0))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Name<F>(F);
+
+impl<F: Fn(&str) -> bool> ClassFilter for Name<F> {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        Some((self.0)(class.name))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Size(std::ops::RangeInclusive<u64>);
+
+impl ClassFilter for Size {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        Some(self.0.contains(&class.size))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Classes(HashSet<ClassName>);
+
+impl Classes {
+    /// Adds a class to the set.
+    #[inline]
+    pub fn insert(&mut self, name: impl Into<ClassName>) {
+        self.0.insert(name.into());
+    }
+}
+
+impl ClassFilter for Classes {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        let name = class.name.rsplit_once('.').map_or(class.name, |(name, _)| name);
+        Some(self.0.contains(name))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Flags(ClassAccessFlags);
+
+impl ClassFilter for Flags {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        class.flags.map(|flags| flags.contains(self.0))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Custom<F>(F);
+
+impl<F: Fn(&ClassInfo<'_>) -> Option<bool>> ClassFilter for Custom<F> {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        (self.0)(class)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct And<A, B>(A, B);
+
+impl<A: ClassFilter, B: ClassFilter> ClassFilter for And<A, B> {
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        match (self.0.accepts(class), self.1.accepts(class)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<A: ClassFilter, B: ClassFilter> ClassFilter for Or<A, B> {
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        match (self.0.accepts(class), self.1.accepts(class)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Not<A>(A);
+
+impl<A: ClassFilter> ClassFilter for Not<A> {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        self.0.accepts(class).map(|accepted| !accepted)
+    }
+}
+
+/// A filter shared between copies of [`SearchOptions`](crate::SearchOptions).
+#[derive(Clone)]
+pub(crate) struct SharedFilter(pub(crate) Arc<dyn ClassFilter + Send + Sync>);
+
+impl ClassFilter for SharedFilter {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        self.0.accepts(class)
+    }
+}
+
+impl fmt::Debug for SharedFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedFilter")
+    }
+}
+
+/// Reads the access flags of a class, which follow its constant pool.
+pub(crate) fn read_flags(class: &[u8]) -> Option<ClassAccessFlags> {
+    let end = ConstantPool::parse(class).ok()?.end();
+    let bytes = class.get(end..end + 2)?;
+    Some(ClassAccessFlags::from_bits_truncate(u16::from_be_bytes([
+        bytes[0], bytes[1],
+    ])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn filter_classes() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a/b"))
+            .class(TestClass::new("a/c").flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL))
+            .class(TestClass::new("d").flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL))
+            .open()
+            .unwrap();
+        let mut names = |filter: Box<dyn ClassFilter>| {
+            jar.classes_filtered(filter)
+                .map(|entry| entry.unwrap().name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let is_final = || flags(ClassAccessFlags::FINAL);
+
+        assert_eq!(names(Box::new(package("a"))), ["a/b.class", "a/c.class"]);
+        assert_eq!(names(Box::new(package("a").and(is_final()))), ["a/c.class"]);
+        assert_eq!(names(Box::new(package("a").or(is_final()))), [
+            "a/b.class",
+            "a/c.class",
+            "d.class"
+        ]);
+        assert_eq!(names(Box::new(is_final().not())), ["a/b.class"]);
+        assert_eq!(names(Box::new(custom(|class| Some(class.name.len() == 7)))), [
+            "d.class"
+        ]);
+    }
+}
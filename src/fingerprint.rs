@@ -0,0 +1,515 @@
+//! Identification of known libraries bundled in an archive by structural fingerprints of their classes.
+//!
+//! A [`Fingerprint`] hashes the shape of a class, its access flags and the signatures of its members,
+//! with the names of the classes outside of the JDK erased, so it survives relocation by shading tools.
+//! A [`FingerprintDb`] is built from the archives of known libraries, stored as text
+//! and matched against other archives to find the libraries they bundle.
+//! Fingerprints also pair the classes of two versions of an archive, see [`match_versions`].
+//!
+//! # Examples
+//! ```no_run
+//! use jars::fingerprint::{self, FingerprintDb};
+//! use jars::{ClassPat, SearchOptions, Searcher};
+//!
+//! let mut db = FingerprintDb::default();
+//! db.add_library("guava:31.1", &mut jars::Jar::open("guava-31.1-jre.jar").unwrap()).unwrap();
+//!
+//! let mut jar = jars::Jar::open("app.jar").unwrap();
+//! let libraries = db.identify(&mut jar).unwrap();
+//! for library in &libraries {
+//!     println!("{}: {} classes", library.library, library.entries.len());
+//! }
+//! let options = SearchOptions::default().filter(fingerprint::excluding(&libraries));
+//! let matches = Searcher::new([ClassPat::default()])
+//!     .with_options(options)
+//!     .search_many(&mut jar)
+//!     .unwrap();
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{Read, Seek};
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::cfg::MethodCfg;
+use crate::constant_pool::ConstantPool;
+use crate::filter::{ClassFilter, ClassInfo};
+use crate::jar::{Jar, JarEntry};
+use crate::model::ClassModel;
+use crate::result::Result;
+use crate::{bytecode, indy};
+
+/// A structural hash of a class that doesn't depend on the packages of the classes it refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub u64);
+
+impl Fingerprint {
+    /// Computes the fingerprint of a class.
+    ///
+    /// Member names are ignored along with the names of the classes outside of the JDK,
+    /// the members are hashed in sorted order.
+    pub fn of(class: &impl ClassModel) -> Self {
+        let mut methods: Vec<_> = (0..class.method_count()).map(|i| method_key(class, i)).collect();
+        let mut fields: Vec<_> = (0..class.field_count())
+            .map(|i| {
+                let field = class.field(i);
+                (field.access_flags.bits(), erase_names(field.descriptor))
+            })
+            .collect();
+        methods.sort();
+        fields.sort();
+
+        let mut hasher = Fnv::default();
+        hasher.write(&class.access_flags().bits().to_be_bytes());
+        hasher.write(erase_name(class.super_class().unwrap_or_default()).as_bytes());
+        hasher.write(&(class.interface_count() as u32).to_be_bytes());
+        for (flags, descriptor) in methods.iter().chain(&fields) {
+            hasher.write(&flags.to_be_bytes());
+            hasher.write(descriptor.as_bytes());
+        }
+        Self(hasher.0)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = std::num::ParseIntError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(Self)
+    }
+}
+
+impl JarEntry {
+    /// Computes the [`Fingerprint`] of this class.
+    pub fn fingerprint(&self) -> Result<Fingerprint> {
+        Ok(Fingerprint::of(&self.parse_without_bytecode()?))
+    }
+}
+
+/// Fingerprints of the classes of known libraries.
+///
+/// The database is stored as text, with a fingerprint followed by the name of a library on each line,
+/// see [`FingerprintDb::parse`].
+#[derive(Debug, Default, Clone)]
+pub struct FingerprintDb {
+    libraries: BTreeMap<String, HashSet<Fingerprint>>,
+    classes: HashMap<Fingerprint, Vec<String>>,
+}
+
+impl FingerprintDb {
+    /// Parses a database, blank lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> Result<Self, FingerprintDbError> {
+        let mut db = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fingerprint = line
+                .split_once(' ')
+                .and_then(|(fingerprint, library)| Some((fingerprint.parse().ok()?, library.trim())))
+                .filter(|(_, library)| !library.is_empty());
+            let Some((fingerprint, library)) = fingerprint else {
+                return Err(FingerprintDbError { line: i + 1 });
+            };
+            db.insert(library, fingerprint);
+        }
+        Ok(db)
+    }
+
+    /// Adds the fingerprint of a class of a library.
+    pub fn insert(&mut self, library: &str, fingerprint: Fingerprint) {
+        if self
+            .libraries
+            .entry(library.to_owned())
+            .or_default()
+            .insert(fingerprint)
+        {
+            self.classes
+                .entry(fingerprint)
+                .or_default()
+                .push(library.to_owned());
+        }
+    }
+
+    /// Adds the fingerprints of all classes of a library, skipping the ones that cannot be parsed.
+    pub fn add_library<R: Read + Seek>(&mut self, library: &str, jar: &mut Jar<R>) -> Result<()> {
+        for entry in jar.classes() {
+            if let Ok(fingerprint) = entry?.fingerprint() {
+                self.insert(library, fingerprint);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the libraries in the database.
+    #[inline]
+    pub fn libraries(&self) -> impl Iterator<Item = &str> {
+        self.libraries.keys().map(String::as_str)
+    }
+
+    /// Finds the known libraries bundled in an archive, sorted by the number of matching classes.
+    ///
+    /// Classes whose fingerprints belong to many libraries are attributed to all of them.
+    pub fn identify<R: Read + Seek>(&self, jar: &mut Jar<R>) -> Result<Vec<LibraryMatch>> {
+        let mut found: BTreeMap<&str, (Vec<String>, HashSet<Fingerprint>)> = BTreeMap::new();
+        for entry in jar.classes() {
+            let entry = entry?;
+            let Ok(fingerprint) = entry.fingerprint() else {
+                continue;
+            };
+            for library in self.classes.get(&fingerprint).into_iter().flatten() {
+                let (entries, fingerprints) = found.entry(library).or_default();
+                entries.push(entry.name().to_owned());
+                fingerprints.insert(fingerprint);
+            }
+        }
+        let mut res: Vec<_> = found
+            .into_iter()
+            .map(|(library, (entries, fingerprints))| LibraryMatch {
+                library: library.to_owned(),
+                entries,
+                coverage: fingerprints.len() as f32 / self.libraries[library].len() as f32,
+            })
+            .collect();
+        res.sort_by_key(|library| Reverse(library.entries.len()));
+        Ok(res)
+    }
+}
+
+impl fmt::Display for FingerprintDb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (library, fingerprints) in &self.libraries {
+            let mut fingerprints: Vec<_> = fingerprints.iter().collect();
+            fingerprints.sort();
+            for fingerprint in fingerprints {
+                writeln!(f, "{fingerprint} {library}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid fingerprint on line {line}")]
+pub struct FingerprintDbError {
+    pub line: usize,
+}
+
+/// A known library found in an archive by [`FingerprintDb::identify`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryMatch {
+    pub library: String,
+    /// Paths of the entries of the archive matching the classes of the library.
+    pub entries: Vec<String>,
+    /// The fraction of the classes of the library found in the archive, between 0 and 1.
+    pub coverage: f32,
+}
+
+/// Rejects the entries of the libraries found in an archive, to skip them in searches.
+pub fn excluding(libraries: &[LibraryMatch]) -> Excluding {
+    let entries = libraries
+        .iter()
+        .flat_map(|library| library.entries.iter().cloned())
+        .collect();
+    Excluding(entries)
+}
+
+#[derive(Debug, Clone)]
+pub struct Excluding(HashSet<String>);
+
+impl ClassFilter for Excluding {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        Some(!self.0.contains(class.name))
+    }
+}
+
+/// A class of an archive paired with a class of another version of the archive by [`match_versions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassPair {
+    /// Path of the entry in the old archive.
+    pub old: String,
+    /// Path of the entry in the new archive.
+    pub new: String,
+    /// The mean [`MethodCfg::similarity`] of the methods of the classes, between 0 and 1.
+    pub similarity: f64,
+}
+
+/// Pairs the classes of two versions of an archive, e.g. to carry the names identified
+/// in one release of an obfuscated application over to the next one.
+///
+/// Classes are only paired with classes sharing their [`Fingerprint`]. When several classes share one,
+/// the pairs whose methods have the most similar control flow graphs are picked first,
+/// which tells apart classes with the same signatures even when their bodies are flow obfuscated.
+/// Classes that cannot be parsed are skipped, the pairs are sorted by the old path.
+pub fn match_versions<R1: Read + Seek, R2: Read + Seek>(
+    old: &mut Jar<R1>,
+    new: &mut Jar<R2>,
+) -> Result<Vec<ClassPair>> {
+    let old = ClassShape::index(old)?;
+    let mut new = ClassShape::index(new)?;
+    let mut res = vec![];
+    for (fingerprint, old) in old {
+        let Some(new) = new.remove(&fingerprint) else {
+            continue;
+        };
+        let mut candidates: Vec<_> = old
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| new.iter().enumerate().map(move |(j, b)| (i, j, a.similarity(b))))
+            .collect();
+        candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+        let (mut old_paired, mut new_paired) = (vec![false; old.len()], vec![false; new.len()]);
+        for (i, j, similarity) in candidates {
+            if old_paired[i] || new_paired[j] {
+                continue;
+            }
+            old_paired[i] = true;
+            new_paired[j] = true;
+            res.push(ClassPair {
+                old: old[i].name.clone(),
+                new: new[j].name.clone(),
+                similarity,
+            });
+        }
+    }
+    res.sort_by(|a, b| a.old.cmp(&b.old));
+    Ok(res)
+}
+
+/// The control flow graphs of the methods of a class, in the order in which they're fingerprinted.
+struct ClassShape {
+    name: String,
+    methods: Vec<Option<MethodCfg>>,
+}
+
+impl ClassShape {
+    /// Groups the classes of an archive by their fingerprints, in the order of the archive.
+    fn index<R: Read + Seek>(jar: &mut Jar<R>) -> Result<HashMap<Fingerprint, Vec<Self>>> {
+        let mut res: HashMap<_, Vec<_>> = HashMap::new();
+        for entry in jar.classes() {
+            if let Some((fingerprint, shape)) = Self::of(&entry?) {
+                res.entry(fingerprint).or_default().push(shape);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Methods without a body or that cannot be disassembled have no graph.
+    fn of(entry: &JarEntry) -> Option<(Fingerprint, Self)> {
+        let class = entry.parse_without_bytecode().ok()?;
+        let pool = ConstantPool::parse(entry.bytes()).ok();
+        let mut order: Vec<_> = (0..class.method_count()).collect();
+        order.sort_by_cached_key(|&i| method_key(&class, i));
+        let methods = order
+            .into_iter()
+            .map(|i| {
+                let code = indy::code(&class.methods[i].attributes)?;
+                let instructions = bytecode::disassemble(code.code, pool.as_ref()?).ok()?;
+                MethodCfg::new(&instructions).ok()
+            })
+            .collect();
+        let shape = Self {
+            name: entry.name().to_owned(),
+            methods,
+        };
+        Some((Fingerprint::of(&class), shape))
+    }
+
+    /// Returns the mean similarity of the graphs of the methods, or 1 if neither class has any.
+    fn similarity(&self, other: &Self) -> f64 {
+        let scores: Vec<_> = self
+            .methods
+            .iter()
+            .zip(&other.methods)
+            .filter_map(|(a, b)| Some(a.as_ref()?.similarity(b.as_ref()?)))
+            .collect();
+        if scores.is_empty() {
+            return 1.;
+        }
+        scores.iter().sum::<f64>() / scores.len() as f64
+    }
+}
+
+/// Returns the access flags and the descriptor with erased names of a method, which it's fingerprinted by.
+fn method_key(class: &impl ClassModel, index: usize) -> (u16, String) {
+    let method = class.method(index);
+    (method.access_flags.bits(), erase_names(method.descriptor))
+}
+
+/// Replaces the names of the classes outside of the JDK in a descriptor with an empty name.
+fn erase_names(descriptor: &str) -> String {
+    let mut res = String::with_capacity(descriptor.len());
+    let mut rest = descriptor;
+    while let Some(start) = rest.find('L') {
+        let Some(end) = rest[start..].find(';') else {
+            break;
+        };
+        res.push_str(&rest[..=start]);
+        res.push_str(erase_name(&rest[start + 1..start + end]));
+        rest = &rest[start + end..];
+    }
+    res.push_str(rest);
+    res
+}
+
+#[inline]
+fn erase_name(name: &str) -> &str {
+    if name.starts_with("java/") {
+        name
+    } else {
+        ""
+    }
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hashers of the standard library is stable between releases.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    #[inline]
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+        // separates consecutive writes
+        self.0 = (self.0 ^ 0xff).wrapping_mul(0x0100_0000_01b3);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::writer::{ClassBuilder, Code, MethodBuilder};
+
+    #[test]
+    fn identify_shaded_library() {
+        let cache = |package: &str| {
+            TestClass::new(format!("{package}/Cache"))
+                .method(
+                    MethodAccessFlags::PUBLIC,
+                    "get",
+                    format!("(Ljava/lang/Object;)L{package}/Entry;"),
+                )
+                .field(FieldAccessFlags::PRIVATE, "size", "I")
+        };
+        let entry = |package: &str| {
+            TestClass::new(format!("{package}/Entry")).method(
+                MethodAccessFlags::PUBLIC,
+                "key",
+                "()Ljava/lang/Object;",
+            )
+        };
+        let mut library = TestJar::default()
+            .class(cache("com/google/common"))
+            .class(entry("com/google/common"))
+            .open()
+            .unwrap();
+        let mut db = FingerprintDb::default();
+        db.add_library("guava", &mut library).unwrap();
+        let db = FingerprintDb::parse(&db.to_string()).unwrap();
+
+        let mut jar = TestJar::default()
+            .class(cache("app/shaded/guava"))
+            .class(TestClass::new("app/Main"))
+            .open()
+            .unwrap();
+        let libraries = db.identify(&mut jar).unwrap();
+        assert_eq!(libraries, [LibraryMatch {
+            library: "guava".to_owned(),
+            entries: vec!["app/shaded/guava/Cache.class".to_owned()],
+            coverage: 0.5,
+        }]);
+
+        let entries: Vec<_> = jar
+            .classes_filtered(excluding(&libraries))
+            .map(|entry| entry.unwrap().name().to_owned())
+            .collect();
+        assert_eq!(entries, ["app/Main.class"]);
+        assert_eq!(FingerprintDb::parse("abc").unwrap_err(), FingerprintDbError {
+            line: 1
+        });
+    }
+
+    #[test]
+    fn match_classes_across_versions() {
+        // for (int i = 0; i < 10; i++);
+        let count_up = [
+            0x03, 0x3b, 0x1a, 0x10, 10, 0xa2, 0, 9, 0x84, 0, 1, 0xa7, 0xff, 0xf7, 0xb1,
+        ];
+        // for (int i = 5; i > 0; i -= 2);
+        let count_down = [
+            0x08, 0x3b, 0x1a, 0x9e, 0, 9, 0x84, 0, 0xfe, 0xa7, 0xff, 0xf9, 0xb1,
+        ];
+        let straight = [0xb1];
+        let class = |name: &str, bytecode: &[u8]| {
+            let method = MethodBuilder::new(MethodAccessFlags::PUBLIC, "run", "()V").code(Code {
+                max_stack: 2,
+                max_locals: 1,
+                bytecode: bytecode.to_vec(),
+            });
+            let class = ClassBuilder::new(name).with_method(method).to_bytes();
+            (format!("{name}.class"), class)
+        };
+        let jar = |classes: [(String, Vec<u8>); 3]| {
+            classes
+                .into_iter()
+                .fold(TestJar::default(), |jar, (name, class)| jar.entry(name, class))
+                .open()
+                .unwrap()
+        };
+        let mut old = jar([
+            class("a", &count_up),
+            class("b", &straight),
+            (
+                "c.class".to_owned(),
+                TestClass::new("c")
+                    .field(FieldAccessFlags::PRIVATE, "x", "I")
+                    .to_bytes(),
+            ),
+        ]);
+        let mut new = jar([
+            class("x", &straight),
+            class("y", &count_down),
+            (
+                "z.class".to_owned(),
+                TestClass::new("z")
+                    .field(FieldAccessFlags::PUBLIC, "x", "I")
+                    .to_bytes(),
+            ),
+        ]);
+
+        let pairs = match_versions(&mut old, &mut new).unwrap();
+        assert_eq!(pairs, [
+            ClassPair {
+                old: "a.class".to_owned(),
+                new: "y.class".to_owned(),
+                similarity: 1.,
+            },
+            ClassPair {
+                old: "b.class".to_owned(),
+                new: "x.class".to_owned(),
+                similarity: 1.,
+            }
+        ]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::io::{Read, Seek};
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::constant_pool::PoolConstant;
+use crate::jar::Jar;
+use crate::result::{EntryPath, Result};
+
+/// Extensions of resources searched by [`Jar::grep`].
+const TEXT_EXTENSIONS: [&str; 11] = [
+    "properties",
+    "json",
+    "yml",
+    "yaml",
+    "toml",
+    "xml",
+    "txt",
+    "cfg",
+    "conf",
+    "lang",
+    "mf",
+];
+
+/// An occurrence of a regular expression found by [`Jar::grep`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub path: EntryPath,
+    pub location: GrepLocation,
+    /// The matched text.
+    pub text: String,
+}
+
+/// The position of a [`GrepMatch`] in its entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrepLocation {
+    /// A string literal of a class, identified by its constant pool index,
+    /// along with the byte range of the match within the string.
+    Constant { index: u16, range: Range<usize> },
+    /// A line of a text resource, starting from 1, along with the byte range of the match within the line.
+    Line { line: usize, range: Range<usize> },
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Searches for a regular expression in the string literals of all classes
+    /// and in text resources such as properties, JSON and YAML files.
+    ///
+    /// Matches are returned in the order of entries, with classes first.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut jar = jars::Jar::open("app.jar").unwrap();
+    /// for mat in jar.grep(&regex::Regex::new(r"\d+\.\d+\.\d+").unwrap()).unwrap() {
+    ///     println!("{}: {}", mat.path, mat.text);
+    /// }
+    /// ```
+    pub fn grep(&mut self, regex: &Regex) -> Result<Vec<GrepMatch>> {
+        let mut matches = vec![];
+        for entry in self.classes() {
+            let entry = entry?;
+            for (index, constant) in entry.constant_pool()?.entries() {
+                let PoolConstant::String(str) = constant else {
+                    continue;
+                };
+                for mat in regex.find_iter(&str) {
+                    matches.push(GrepMatch {
+                        path: entry.path(),
+                        location: GrepLocation::Constant {
+                            index,
+                            range: mat.range(),
+                        },
+                        text: mat.as_str().to_owned(),
+                    });
+                }
+            }
+        }
+
+        for entry in self.resources() {
+            let entry = entry?;
+            if !is_text(entry.name()) {
+                continue;
+            }
+            let text = String::from_utf8_lossy(entry.bytes());
+            for (i, line) in text.lines().enumerate() {
+                for mat in regex.find_iter(line) {
+                    matches.push(GrepMatch {
+                        path: entry.path(),
+                        location: GrepLocation::Line {
+                            line: i + 1,
+                            range: mat.range(),
+                        },
+                        text: mat.as_str().to_owned(),
+                    });
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
+
+fn is_text(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| TEXT_EXTENSIONS.iter().any(|text| ext.eq_ignore_ascii_case(text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestJar;
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn grep_classes_and_resources() {
+        let mut class = ClassBuilder::new("a");
+        let index = class.pool().string("protocol v47");
+        let mut jar = TestJar::default()
+            .entry("a.class", class.to_bytes())
+            .entry("config.yml", "name: test\nprotocol: 47\n")
+            .entry("icon.png", "v47")
+            .open()
+            .unwrap();
+
+        let matches = jar.grep(&Regex::new(r"\d+").unwrap()).unwrap();
+        let locations: Vec<_> = matches
+            .iter()
+            .map(|mat| (&*mat.path.entry, mat.location.clone(), &*mat.text))
+            .collect();
+        assert_eq!(locations, vec![
+            ("a.class", GrepLocation::Constant { index, range: 10..12 }, "47"),
+            (
+                "config.yml",
+                GrepLocation::Line {
+                    line: 2,
+                    range: 10..12
+                },
+                "47"
+            ),
+        ]);
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::name::ClassName;
+use crate::result::Result;
+use crate::search::Match;
+
+/// A constraint between the matches of a group of patterns, see [`Searcher::group`](crate::Searcher::group).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupConstraint {
+    /// No class can satisfy more than one pattern of the group,
+    /// even if it's only matched by the one with the highest priority.
+    Distinct,
+    /// Every class matched by a pattern of the group is in the same package.
+    SamePackage,
+    /// Every class matched by a pattern of the group is nested in the same top-level class, or is that class.
+    SameOuterClass,
+}
+
+/// A group of patterns declared with [`Searcher::group`](crate::Searcher::group).
+#[derive(Debug, Clone)]
+pub(crate) struct PatternGroup {
+    pub patterns: Vec<usize>,
+    pub constraint: GroupConstraint,
+}
+
+impl PatternGroup {
+    /// Returns the violation of the constraint by the matches, if there's one.
+    fn check(&self, index: usize, matches: &[Match]) -> Result<Option<Violation>> {
+        let mut classes = BTreeSet::new();
+        let mut keys = BTreeSet::new();
+        for mat in matches {
+            let class = &mat.entry.header()?.this_class;
+            match self.constraint {
+                GroupConstraint::Distinct => {
+                    let satisfied = mat
+                        .satisfied
+                        .iter()
+                        .filter(|pattern| self.patterns.contains(pattern))
+                        .count();
+                    if satisfied > 1 {
+                        classes.insert(class.clone());
+                    }
+                }
+                GroupConstraint::SamePackage | GroupConstraint::SameOuterClass
+                    if self.patterns.contains(&mat.pattern) =>
+                {
+                    let key = match self.constraint {
+                        GroupConstraint::SamePackage => ClassName::new(class.package()),
+                        _ => class.top_level(),
+                    };
+                    keys.insert(key);
+                    classes.insert(class.clone());
+                }
+                _ => {}
+            }
+        }
+        let violated = match self.constraint {
+            GroupConstraint::Distinct => !classes.is_empty(),
+            GroupConstraint::SamePackage | GroupConstraint::SameOuterClass => keys.len() > 1,
+        };
+        Ok(violated.then(|| Violation {
+            group: index,
+            constraint: self.constraint,
+            classes: classes.into_iter().collect(),
+        }))
+    }
+}
+
+/// Checks the matches of a search against the constraints of the groups.
+pub(crate) fn check_groups(groups: &[PatternGroup], matches: &[Match]) -> Result<Vec<Violation>> {
+    let mut violations = vec![];
+    for (i, group) in groups.iter().enumerate() {
+        violations.extend(group.check(i, matches)?);
+    }
+    Ok(violations)
+}
+
+/// A group of patterns whose matches don't satisfy its [`GroupConstraint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Index of the group, in the order the groups were declared.
+    pub group: usize,
+    pub constraint: GroupConstraint,
+    /// Internal names of the classes involved, sorted: the ones satisfying more than one pattern
+    /// for [`GroupConstraint::Distinct`], or all classes matched by the group otherwise.
+    pub classes: Vec<ClassName>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let constraint = match self.constraint {
+            GroupConstraint::Distinct => "classes satisfying more than one pattern",
+            GroupConstraint::SamePackage => "classes in different packages",
+            GroupConstraint::SameOuterClass => "classes in different outer classes",
+        };
+        write!(f, "group {} matched {constraint}:", self.group)?;
+        for class in &self.classes {
+            write!(f, " {class}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::result::Error;
+    use crate::testing::{TestClass, TestJar};
+    use crate::{ClassPat, FieldPat, MethodPat, Searcher};
+
+    #[test]
+    fn enforce_group_constraints() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("app/a").method(MethodAccessFlags::PUBLIC, "run", "()V"))
+            .class(TestClass::new("lib/b").field(FieldAccessFlags::PRIVATE, "x", "I"))
+            .open()
+            .unwrap();
+        let pats = [
+            ClassPat::default().with(MethodPat::default()),
+            ClassPat::default().with(FieldPat::default()),
+            ClassPat::default().with(MethodPat::default().name("run")),
+        ];
+        let searcher = Searcher::new(pats)
+            .group([0, 1], GroupConstraint::SameOuterClass)
+            .group([0, 2], GroupConstraint::Distinct);
+        let violations = searcher.search(&mut jar).unwrap().violations;
+        assert_eq!(violations, [
+            Violation {
+                group: 0,
+                constraint: GroupConstraint::SameOuterClass,
+                classes: vec!["app/a".into(), "lib/b".into()],
+            },
+            Violation {
+                group: 1,
+                constraint: GroupConstraint::Distinct,
+                classes: vec!["app/a".into()],
+            }
+        ]);
+        assert!(matches!(
+            searcher.search_exact(&mut jar),
+            Err(Error::GroupViolated(0))
+        ));
+
+        let searcher = Searcher::new([ClassPat::default().with(FieldPat::default())])
+            .group([0], GroupConstraint::SamePackage);
+        assert!(searcher.search(&mut jar).unwrap().violations.is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Read, Seek};
+
+use cafebabe::{ClassAccessFlags, MethodAccessFlags};
+
+use crate::jar::{Jar, JarEntry};
+use crate::name::ClassName;
+use crate::result::Result;
+
+/// A source of classes looked up by name, e.g. an archive on the classpath.
+pub trait ClassSource {
+    /// Looks up a class by its internal name, e.g. `com/example/Foo`.
+    fn find_class(&mut self, name: &str) -> Result<Option<JarEntry>>;
+}
+
+impl<R: Read + Seek> ClassSource for Jar<R> {
+    #[inline]
+    fn find_class(&mut self, name: &str) -> Result<Option<JarEntry>> {
+        self.class_by_name(name)
+    }
+}
+
+/// A class along with all of its supertypes that could be found, see [`Jar::resolve_hierarchy`].
+#[derive(Debug)]
+pub struct Hierarchy {
+    /// The class followed by its supertypes in breadth-first order, superclasses before interfaces.
+    pub classes: Vec<JarEntry>,
+    /// Internal names of the supertypes that weren't found, e.g. classes of the JDK.
+    pub missing: Vec<ClassName>,
+}
+
+impl Hierarchy {
+    /// Resolves the hierarchy of a class from a list of sources searched in order,
+    /// e.g. an archive followed by its classpath.
+    ///
+    /// Returns [`None`] if the class itself isn't found.
+    pub fn resolve(name: &str, sources: &mut [&mut dyn ClassSource]) -> Result<Option<Self>> {
+        let mut res = Self {
+            classes: vec![],
+            missing: vec![],
+        };
+        let name = ClassName::new(name);
+        let mut seen = HashSet::from([name.clone()]);
+        let mut pending = VecDeque::from([name]);
+        while let Some(name) = pending.pop_front() {
+            let Some(entry) = find_class(sources, &name)? else {
+                if res.classes.is_empty() {
+                    return Ok(None);
+                }
+                res.missing.push(name);
+                continue;
+            };
+            let header = entry.header()?;
+            for supertype in header.super_class.iter().chain(&header.interfaces) {
+                if seen.insert(supertype.clone()) {
+                    pending.push_back(supertype.clone());
+                }
+            }
+            res.classes.push(entry);
+        }
+        Ok(Some(res))
+    }
+
+    /// Returns the class the hierarchy was resolved for.
+    #[inline]
+    pub fn class(&self) -> &JarEntry {
+        &self.classes[0]
+    }
+
+    /// Returns the supertypes of the class that were found.
+    #[inline]
+    pub fn supertypes(&self) -> &[JarEntry] {
+        &self.classes[1..]
+    }
+
+    /// Returns the class or supertype with the provided internal name.
+    pub fn get(&self, name: &str) -> Option<&JarEntry> {
+        self.classes
+            .iter()
+            .find(|entry| entry.header().is_ok_and(|header| header.this_class == name))
+    }
+
+    /// Returns the chain of superclasses that were found, starting with the class itself
+    /// and stopping at the first superclass that's missing.
+    pub fn superclasses(&self) -> Vec<&JarEntry> {
+        let mut res = vec![self.class()];
+        while let Some(base) = res[res.len() - 1]
+            .header()
+            .ok()
+            .and_then(|header| self.get(header.super_class.as_deref()?))
+        {
+            res.push(base);
+        }
+        res
+    }
+}
+
+fn find_class(sources: &mut [&mut dyn ClassSource], name: &str) -> Result<Option<JarEntry>> {
+    for source in sources {
+        if let Some(entry) = source.find_class(name)? {
+            return Ok(Some(entry));
+        }
+    }
+    Ok(None)
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Resolves a class of the archive along with all of its supertypes in the archive.
+    ///
+    /// Returns [`None`] if the class isn't found, see [`Hierarchy::resolve`] for including a classpath.
+    #[inline]
+    pub fn resolve_hierarchy(&mut self, name: &str) -> Result<Option<Hierarchy>> {
+        Hierarchy::resolve(name, &mut [self])
+    }
+
+    /// Finds the classes of the archive implementing an interface or extending a class, directly or not.
+    ///
+    /// Interfaces extending the interface aren't included, but their implementations are.
+    /// The classes are sorted by name.
+    pub fn implementations_of(&mut self, name: &str) -> Result<Vec<JarEntry>> {
+        let subtypes = self.subtypes_of(name)?;
+        let mut res = vec![];
+        for entry in subtypes {
+            if !entry.header()?.access_flags.contains(ClassAccessFlags::INTERFACE) {
+                res.push(entry);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Finds the methods of the archive overriding or implementing a method of a class or interface.
+    ///
+    /// Overrides are declared by subtypes with the same name and descriptor, static and private methods
+    /// are skipped. The overrides are sorted by the names of their classes.
+    pub fn overrides_of(&mut self, class: &str, name: &str, descriptor: &str) -> Result<Vec<Override>> {
+        let mut res = vec![];
+        for entry in self.subtypes_of(class)? {
+            let method = entry.parse_without_bytecode()?.methods.iter().position(|method| {
+                let skipped = MethodAccessFlags::STATIC | MethodAccessFlags::PRIVATE;
+                method.name == name
+                    && method.descriptor == descriptor
+                    && !method.access_flags.intersects(skipped)
+            });
+            if let Some(method) = method {
+                res.push(Override { class: entry, method });
+            }
+        }
+        Ok(res)
+    }
+
+    /// Collects the transitive subtypes of a class in the archive, sorted by name.
+    fn subtypes_of(&mut self, name: &str) -> Result<Vec<JarEntry>> {
+        let mut entries = HashMap::new();
+        let mut subtypes: HashMap<ClassName, Vec<ClassName>> = HashMap::new();
+        for entry in self.classes() {
+            let entry = entry?;
+            let header = entry.header()?;
+            for supertype in header.super_class.iter().chain(&header.interfaces) {
+                subtypes
+                    .entry(supertype.clone())
+                    .or_default()
+                    .push(header.this_class.clone());
+            }
+            entries.insert(header.this_class.clone(), entry);
+        }
+
+        let name = ClassName::new(name);
+        let mut seen = HashSet::from([name.clone()]);
+        let mut pending = vec![name];
+        let mut res = vec![];
+        while let Some(name) = pending.pop() {
+            for subtype in subtypes.get(&name).into_iter().flatten() {
+                if seen.insert(subtype.clone()) {
+                    pending.push(subtype.clone());
+                    res.extend(entries.remove(subtype));
+                }
+            }
+        }
+        res.sort_by_cached_key(|entry| entry.header().map(|header| header.this_class.clone()).ok());
+        Ok(res)
+    }
+}
+
+/// A method overriding another one, see [`Jar::overrides_of`].
+#[derive(Debug)]
+pub struct Override {
+    pub class: JarEntry,
+    /// Index of the method in [`ClassFile::methods`](cafebabe::ClassFile::methods).
+    pub method: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn resolve_with_classpath() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a").extends("b").implements("c"))
+            .class(TestClass::new("b").implements("c"))
+            .open()
+            .unwrap();
+        let mut classpath = TestJar::default()
+            .class(TestClass::new("c").implements("d"))
+            .open()
+            .unwrap();
+
+        let hierarchy = Hierarchy::resolve("a", &mut [&mut jar, &mut classpath])
+            .unwrap()
+            .unwrap();
+        let names: Vec<_> = hierarchy
+            .classes
+            .iter()
+            .map(|entry| entry.header().unwrap().this_class.as_str())
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(hierarchy.missing, ["java/lang/Object", "d"]);
+        assert_eq!(hierarchy.superclasses().len(), 2);
+        assert!(jar.resolve_hierarchy("d").unwrap().is_none());
+    }
+
+    #[test]
+    fn find_implementations_and_overrides() {
+        let interface = ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT;
+        let mut jar = TestJar::default()
+            .class(TestClass::new("Task").flags(interface))
+            .class(TestClass::new("Job").flags(interface).implements("Task"))
+            .class(
+                TestClass::new("Base")
+                    .implements("Job")
+                    .method(MethodAccessFlags::PUBLIC, "run", "()V"),
+            )
+            .class(
+                TestClass::new("Impl")
+                    .extends("Base")
+                    .method(MethodAccessFlags::PUBLIC, "run", "()V"),
+            )
+            .class(TestClass::new("Other").method(MethodAccessFlags::PUBLIC, "run", "()V"))
+            .open()
+            .unwrap();
+
+        let names = |entries: Vec<&JarEntry>| -> Vec<ClassName> {
+            entries
+                .iter()
+                .map(|entry| entry.header().unwrap().this_class.clone())
+                .collect()
+        };
+        let implementations = jar.implementations_of("Task").unwrap();
+        assert_eq!(names(implementations.iter().collect()), ["Base", "Impl"]);
+        let overrides = jar.overrides_of("Task", "run", "()V").unwrap();
+        assert_eq!(names(overrides.iter().map(|o| &o.class).collect()), [
+            "Base", "Impl"
+        ]);
+        assert_eq!(jar.overrides_of("Base", "run", "()V").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,461 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "archive")]
+use std::cell::OnceCell;
+
+use cafebabe::attributes::{AttributeData, AttributeInfo, CodeData};
+use cafebabe::bytecode::Opcode;
+use cafebabe::constant_pool::{BootstrapArgument, LiteralConstant, MethodHandle, ReferenceKind};
+#[cfg(feature = "archive")]
+use cafebabe::{ClassAccessFlags, FieldAccessFlags};
+use cafebabe::{ClassFile, MethodAccessFlags};
+
+#[cfg(feature = "archive")]
+use crate::bytecode::{self, BytecodeError};
+#[cfg(feature = "archive")]
+use crate::clinit::static_values;
+#[cfg(feature = "archive")]
+use crate::constant_pool::ConstantPool;
+use crate::constant_pool::{MemberRef, PoolConstant};
+#[cfg(feature = "archive")]
+use crate::decrypt::{EncryptedString, StringDecryptor};
+use crate::descriptor::{Descriptor, MethodDescriptor};
+use crate::model::ClassModel;
+#[cfg(feature = "archive")]
+use crate::model::{Constant, ConstantValue, MemberView};
+
+/// An `invokedynamic` call site in the code of a method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallSite {
+    /// The bootstrap method, e.g. `java/lang/invoke/LambdaMetafactory.metafactory` for lambdas.
+    pub bootstrap: MemberRef,
+    /// Name of the call site, for lambdas it's the name of the implemented interface method.
+    pub name: String,
+    pub descriptor: String,
+    /// Static arguments passed to the bootstrap method.
+    pub arguments: Vec<PoolConstant>,
+}
+
+impl CallSite {
+    /// Returns the method implementing a lambda or referenced by a method reference,
+    /// if the call site is bootstrapped by `LambdaMetafactory`.
+    pub fn lambda_target(&self) -> Option<&MemberRef> {
+        if self.bootstrap.class != LAMBDA_METAFACTORY {
+            return None;
+        }
+        // the arguments are the erased interface method type, the implementation and the instantiated type
+        match self.arguments.get(1)? {
+            PoolConstant::MethodHandle { reference, .. } => Some(reference),
+            _ => None,
+        }
+    }
+
+    /// Returns the type produced by the call site, for lambdas it's the implemented functional interface.
+    pub fn return_type(&self) -> Option<Descriptor<'_>> {
+        MethodDescriptor::param_types(&self.descriptor)
+            .ok()?
+            .return_type()
+            .ok()?
+    }
+}
+
+pub(crate) const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+
+/// Returns the indices of the synthetic methods of a class that implement its lambdas.
+///
+/// Returns an empty list if the class doesn't provide bytecode.
+pub(crate) fn lambda_bodies<C: ClassModel + ?Sized>(class: &C) -> Vec<usize> {
+    let mut bodies = vec![];
+    for i in 0..class.method_count() {
+        for site in class.call_sites(i).unwrap_or_default() {
+            let Some(target) = site.lambda_target() else {
+                continue;
+            };
+            if target.class != class.this_class() {
+                continue;
+            }
+            let body = (0..class.method_count()).find(|&j| {
+                let method = class.method(j);
+                method.name == target.name
+                    && method.descriptor == target.descriptor
+                    && method.access_flags.contains(MethodAccessFlags::SYNTHETIC)
+            });
+            if let Some(body) = body.filter(|body| !bodies.contains(body)) {
+                bodies.push(body);
+            }
+        }
+    }
+    bodies
+}
+
+/// Returns the call sites of the method at `index` of a class, in the order they appear in its code,
+/// or an empty list if the method has no body or doesn't exist.
+///
+/// Call sites are read from the bytecode and the `BootstrapMethods` attribute parsed by cafebabe,
+/// so this returns [`None`] if the class was parsed without bytecode or refers to a missing bootstrap method.
+pub(crate) fn call_sites(class: &ClassFile<'_>, index: usize) -> Option<Vec<CallSite>> {
+    let Some(code) = class
+        .methods
+        .get(index)
+        .and_then(|method| code(&method.attributes))
+    else {
+        return Some(vec![]);
+    };
+    let bootstrap_methods = class
+        .attributes
+        .iter()
+        .find_map(|attr| match &attr.data {
+            AttributeData::BootstrapMethods(methods) => Some(&methods[..]),
+            _ => None,
+        })
+        .unwrap_or_default();
+    code.bytecode
+        .as_ref()?
+        .opcodes
+        .iter()
+        .filter_map(|(_, opcode)| match opcode {
+            Opcode::Invokedynamic(site) => Some(site),
+            _ => None,
+        })
+        .map(|site| {
+            let bootstrap = bootstrap_methods.get(site.attr_index as usize)?;
+            Some(CallSite {
+                bootstrap: member_ref(&bootstrap.method),
+                name: site.name_and_type.name.to_string(),
+                descriptor: site.name_and_type.descriptor.to_string(),
+                arguments: bootstrap.arguments.iter().map(argument).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the `Code` attribute among the attributes of a method.
+pub(crate) fn code<'a, 'b>(attributes: &'a [AttributeInfo<'b>]) -> Option<&'a CodeData<'b>> {
+    attributes.iter().find_map(|attr| match &attr.data {
+        AttributeData::Code(code) => Some(code),
+        _ => None,
+    })
+}
+
+/// Returns the part of the bytes of a class borrowed by `slice`, e.g. the body of a method parsed by cafebabe.
+#[cfg(feature = "archive")]
+fn subslice<'a>(bytes: &'a [u8], slice: &[u8]) -> Option<&'a [u8]> {
+    let start = (slice.as_ptr() as usize).checked_sub(bytes.as_ptr() as usize)?;
+    bytes.get(start..start + slice.len())
+}
+
+fn member_ref(handle: &MethodHandle<'_>) -> MemberRef {
+    MemberRef {
+        class: handle.class_name.to_string(),
+        name: handle.member_ref.name.to_string(),
+        descriptor: handle.member_ref.descriptor.to_string(),
+    }
+}
+
+fn argument(argument: &BootstrapArgument<'_>) -> PoolConstant {
+    match argument {
+        BootstrapArgument::LiteralConstant(constant) => match constant {
+            LiteralConstant::Integer(i) => PoolConstant::Integer(*i),
+            LiteralConstant::Float(f) => PoolConstant::Float(*f),
+            LiteralConstant::Long(l) => PoolConstant::Long(*l),
+            LiteralConstant::Double(d) => PoolConstant::Double(*d),
+            LiteralConstant::String(str) => PoolConstant::String(str.to_string()),
+            LiteralConstant::StringBytes(bytes) => {
+                PoolConstant::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+        },
+        BootstrapArgument::ClassInfo(name) => PoolConstant::Class(name.to_string()),
+        BootstrapArgument::MethodHandle(handle) => PoolConstant::MethodHandle {
+            kind: match handle.kind {
+                ReferenceKind::GetField => 1,
+                ReferenceKind::GetStatic => 2,
+                ReferenceKind::PutField => 3,
+                ReferenceKind::PutStatic => 4,
+                ReferenceKind::InvokeVirtual => 5,
+                ReferenceKind::InvokeStatic => 6,
+                ReferenceKind::InvokeSpecial => 7,
+                ReferenceKind::NewInvokeSpecial => 8,
+                ReferenceKind::InvokeInterface => 9,
+            },
+            reference: member_ref(handle),
+        },
+        BootstrapArgument::MethodType(descriptor) => PoolConstant::MethodType(descriptor.to_string()),
+    }
+}
+
+/// The constant pool of a class and the bodies of its methods, which are needed to emulate its static initializer.
+#[cfg(feature = "archive")]
+#[derive(Debug)]
+pub(crate) struct CodeTable<'a> {
+    pool: ConstantPool<'a>,
+    code: Vec<Option<&'a [u8]>>,
+}
+
+#[cfg(feature = "archive")]
+impl<'a> CodeTable<'a> {
+    /// Reads the constant pool from the bytes of a class, cafebabe doesn't expose it,
+    /// the bodies of the methods come from their parsed `Code` attributes.
+    pub(crate) fn new(class: &ClassFile<'_>, bytes: &'a [u8]) -> Result<Self, BytecodeError> {
+        let pool = ConstantPool::parse(bytes)?;
+        let code = class
+            .methods
+            .iter()
+            .map(|method| subslice(bytes, code(&method.attributes)?.code))
+            .collect();
+        Ok(Self { pool, code })
+    }
+
+    /// Returns the values assigned to the static fields of a class in its static initializer,
+    /// see [`static_values`].
+    pub(crate) fn static_values<C: ClassModel + ?Sized>(
+        &self,
+        class: &C,
+        decryptor: Option<&dyn StringDecryptor>,
+    ) -> Result<Vec<Option<ConstantValue>>, BytecodeError> {
+        let decrypt = |stub: &MemberRef, value: &str| {
+            let decryptor = decryptor?;
+            let stub_code = (stub.class == class.this_class())
+                .then(|| {
+                    let i = (0..class.method_count()).find(|&i| {
+                        let method = class.method(i);
+                        method.name == stub.name && method.descriptor == stub.descriptor
+                    })?;
+                    bytecode::disassemble(self.code.get(i).copied().flatten()?, &self.pool).ok()
+                })
+                .flatten();
+            decryptor.decrypt(&EncryptedString {
+                class: class.this_class(),
+                value,
+                stub,
+                stub_code: stub_code.as_deref(),
+            })
+        };
+        static_values(class, &self.code, &self.pool, decrypt)
+    }
+}
+
+/// A class model along with the bytes of the class, which provides the values assigned to static fields
+/// in the static initializer to the matcher.
+#[cfg(feature = "archive")]
+pub(crate) struct WithStaticValues<'a, C: ?Sized> {
+    class: &'a C,
+    bytes: &'a [u8],
+    table: OnceCell<Option<CodeTable<'a>>>,
+    values: OnceCell<Vec<Option<ConstantValue>>>,
+    decryptor: Option<&'a dyn StringDecryptor>,
+}
+
+#[cfg(feature = "archive")]
+impl<'a, C: ClassModel + ?Sized> WithStaticValues<'a, C> {
+    #[inline]
+    pub(crate) fn new(class: &'a C, bytes: &'a [u8]) -> Self {
+        Self {
+            class,
+            bytes,
+            table: OnceCell::new(),
+            values: OnceCell::new(),
+            decryptor: None,
+        }
+    }
+
+    /// Provides the decryptor recovering the encrypted strings assigned to static fields.
+    #[inline]
+    pub(crate) fn with_decryptor(mut self, decryptor: Option<&'a dyn StringDecryptor>) -> Self {
+        self.decryptor = decryptor;
+        self
+    }
+
+    fn table(&self) -> Option<&CodeTable<'a>> {
+        self.table
+            .get_or_init(|| {
+                let mut table = None;
+                self.class
+                    .with_class_file(&mut |class| table = CodeTable::new(class, self.bytes).ok());
+                table
+            })
+            .as_ref()
+    }
+}
+
+#[cfg(feature = "archive")]
+impl<C: ClassModel + ?Sized> ClassModel for WithStaticValues<'_, C> {
+    #[inline]
+    fn access_flags(&self) -> ClassAccessFlags {
+        self.class.access_flags()
+    }
+
+    #[inline]
+    fn this_class(&self) -> &str {
+        self.class.this_class()
+    }
+
+    #[inline]
+    fn super_class(&self) -> Option<&str> {
+        self.class.super_class()
+    }
+
+    #[inline]
+    fn interface_count(&self) -> usize {
+        self.class.interface_count()
+    }
+
+    #[inline]
+    fn interface(&self, index: usize) -> &str {
+        self.class.interface(index)
+    }
+
+    #[inline]
+    fn method_count(&self) -> usize {
+        self.class.method_count()
+    }
+
+    #[inline]
+    fn method(&self, index: usize) -> MemberView<'_, MethodAccessFlags> {
+        self.class.method(index)
+    }
+
+    #[inline]
+    fn field_count(&self) -> usize {
+        self.class.field_count()
+    }
+
+    #[inline]
+    fn field(&self, index: usize) -> MemberView<'_, FieldAccessFlags> {
+        self.class.field(index)
+    }
+
+    #[inline]
+    fn field_constant(&self, index: usize) -> Option<Constant<'_>> {
+        self.class.field_constant(index)
+    }
+
+    fn field_value(&self, index: usize) -> Option<Constant<'_>> {
+        if let Some(value) = self.class.field_constant(index) {
+            return Some(value);
+        }
+        let values = self.values.get_or_init(|| {
+            self.table()
+                .and_then(|table| table.static_values(self.class, self.decryptor).ok())
+                .unwrap_or_default()
+        });
+        values.get(index)?.as_ref().map(ConstantValue::as_constant)
+    }
+
+    #[inline]
+    fn call_sites(&self, index: usize) -> Option<Vec<CallSite>> {
+        self.class.call_sites(index)
+    }
+
+    #[inline]
+    fn with_class_file(&self, f: &mut dyn FnMut(&ClassFile<'_>)) {
+        self.class.with_class_file(f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestJar;
+    use crate::writer::{ClassBuilder, Code, MethodBuilder};
+    use crate::{parse_patterns, CallSitePat, ClassName, ClassPat, MemberBinding, MethodPat, Searcher};
+
+    #[test]
+    fn match_call_sites() {
+        let mut class = ClassBuilder::new("a");
+        let factory = class.pool().method_ref(
+            "java/lang/invoke/LambdaMetafactory",
+            "metafactory",
+            "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;\
+             Ljava/lang/invoke/MethodType;Ljava/lang/invoke/MethodHandle;Ljava/lang/invoke/MethodType;)\
+             Ljava/lang/invoke/CallSite;",
+        );
+        let handle = class.pool().method_handle(6, factory);
+        let bootstrap = class.bootstrap_method(handle, &[]);
+        let site = class
+            .pool()
+            .invoke_dynamic(bootstrap, "run", "()Ljava/lang/Runnable;");
+        let method = MethodBuilder::new(MethodAccessFlags::PUBLIC, "b", "()V").code(Code {
+            max_stack: 1,
+            max_locals: 1,
+            // invokedynamic run, pop, return
+            bytecode: [&[0xba][..], &site.to_be_bytes(), &[0, 0, 0x57, 0xb1]].concat(),
+        });
+        let bytes = class.with_method(method).to_bytes();
+        let mut jar = TestJar::default().entry("a.class", bytes).open().unwrap();
+
+        let entry = jar.classes().next().unwrap().unwrap();
+        let sites = entry.call_sites(0).unwrap();
+        assert_eq!(sites.len(), 1);
+        assert_eq!(sites[0].bootstrap.class, "java/lang/invoke/LambdaMetafactory");
+        assert_eq!(
+            sites[0].return_type(),
+            Some(Descriptor::Object("java/lang/Runnable"))
+        );
+        assert_eq!(entry.parse().unwrap().call_sites(0), Some(sites));
+        // call sites are read from the bytecode parsed by cafebabe
+        assert_eq!(entry.parse_without_bytecode().unwrap().call_sites(0), None);
+
+        let src = "
+            class Concat
+                method public () -> () concat
+            class Lambda
+                method public () -> () lambda Runnable indy 1
+        ";
+        let pats = parse_patterns(src).unwrap();
+        let searcher = Searcher::new(pats.into_iter().map(|(_, pat)| pat));
+        let matches = searcher.search_many(&mut jar).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pattern, 1);
+
+        let report = searcher.validate(&mut jar).unwrap();
+        assert_eq!(report.missing().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(report.matches[1], vec![ClassName::new("a")]);
+    }
+
+    #[test]
+    fn skip_lambda_bodies() {
+        let mut class = ClassBuilder::new("a");
+        let factory = class.pool().method_ref(LAMBDA_METAFACTORY, "metafactory", "()V");
+        let factory = class.pool().method_handle(6, factory);
+        let body = class.pool().method_ref("a", "lambda$b$0", "()V");
+        let body = class.pool().method_handle(6, body);
+        let typ = class.pool().method_type("()V");
+        let bootstrap = class.bootstrap_method(factory, &[typ, body, typ]);
+        let site = class
+            .pool()
+            .invoke_dynamic(bootstrap, "run", "()Ljava/lang/Runnable;");
+        let method = MethodBuilder::new(MethodAccessFlags::PUBLIC, "b", "()V").code(Code {
+            max_stack: 1,
+            max_locals: 1,
+            bytecode: [&[0xba][..], &site.to_be_bytes(), &[0, 0, 0x57, 0xb1]].concat(),
+        });
+        let lambda_flags =
+            MethodAccessFlags::PRIVATE | MethodAccessFlags::STATIC | MethodAccessFlags::SYNTHETIC;
+        let lambda = MethodBuilder::new(lambda_flags, "lambda$b$0", "()V").code(Code {
+            max_stack: 0,
+            max_locals: 0,
+            bytecode: vec![0xb1],
+        });
+        let bytes = class.with_method(method).with_method(lambda).to_bytes();
+        let mut jar = TestJar::default().entry("a.class", bytes).open().unwrap();
+
+        let entry = jar.classes().next().unwrap().unwrap();
+        let sites = entry.call_sites(0).unwrap();
+        assert_eq!(
+            sites[0].lambda_target().map(|target| &*target.name),
+            Some("lambda$b$0")
+        );
+
+        let lambda = CallSitePat::lambda().target(MethodPat::default().flags(MethodAccessFlags::STATIC));
+        let method = MethodPat::default()
+            .flags(MethodAccessFlags::PUBLIC)
+            .call_site(lambda);
+        let pat = ClassPat::default().with(method);
+        let pats = [pat.clone(), pat.skip_lambda_bodies()];
+        let matches = Searcher::new(pats).search_many(&mut jar).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pattern, 1);
+        assert_eq!(matches[0].bindings, vec![MemberBinding::Method(0)]);
+    }
+}
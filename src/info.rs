@@ -0,0 +1,139 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+
+use zip::DateTime;
+
+use crate::jar::Jar;
+use crate::mods::MANIFEST;
+use crate::result::Result;
+use crate::stats::CompressionStats;
+
+/// Markers left in classes by obfuscators, matched against the names and the contents of classes.
+pub(crate) const OBFUSCATOR_MARKERS: [(&str, &[u8]); 2] =
+    [("Allatori", b"ALLATORIxDEMO"), ("Zelix KlassMaster", b"ZKM")];
+
+/// A summary of the provenance of an archive, see [`Jar::info`].
+#[derive(Debug, Clone, Default)]
+pub struct JarInfo {
+    /// Main attributes of the manifest, e.g. `Created-By` or `Implementation-Version`.
+    pub manifest: BTreeMap<String, String>,
+    /// The earliest modification time of an entry.
+    pub earliest: Option<DateTime>,
+    /// The latest modification time of an entry.
+    pub latest: Option<DateTime>,
+    /// How the entries of the archive are compressed.
+    pub compression: CompressionStats,
+    /// Number of classes per class file major version, e.g. 52 for classes compiled for Java 8.
+    pub class_versions: BTreeMap<u16, usize>,
+    /// Names of the obfuscators whose signatures were found, e.g. `ProGuard`.
+    pub obfuscators: Vec<&'static str>,
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Summarizes the archive: its manifest, the timestamps and the compression of its entries,
+    /// the versions of its classes and the obfuscators they were processed with.
+    ///
+    /// Obfuscators are recognized by their signatures, watermarks left by Allatori and Zelix KlassMaster
+    /// and the short names of classes renamed by ProGuard. All classes are read, but not parsed.
+    pub fn info(&mut self) -> Result<JarInfo> {
+        let mut info = JarInfo {
+            compression: self.compression_stats()?,
+            ..Default::default()
+        };
+        self.raw_entries(|file| {
+            let time = file.last_modified();
+            let earliest = info
+                .earliest
+                .map_or(time, |earliest| cmp::min_by_key(earliest, time, key));
+            let latest = info
+                .latest
+                .map_or(time, |latest| cmp::max_by_key(latest, time, key));
+            (info.earliest, info.latest) = (Some(earliest), Some(latest));
+        })?;
+        for entry in self.resources_where(|name| name == MANIFEST.as_bytes()) {
+            info.manifest = main_attributes(&String::from_utf8_lossy(entry?.bytes()));
+        }
+
+        let mut markers = [false; OBFUSCATOR_MARKERS.len()];
+        let (mut classes, mut short_names) = (0, 0);
+        for entry in self.classes() {
+            let entry = entry?;
+            if let Some(version) = entry.bytes().get(6..8) {
+                *info
+                    .class_versions
+                    .entry(u16::from_be_bytes([version[0], version[1]]))
+                    .or_default() += 1;
+            }
+            for (found, (_, marker)) in markers.iter_mut().zip(OBFUSCATOR_MARKERS) {
+                *found |= contains(entry.name().as_bytes(), marker) || contains(entry.bytes(), marker);
+            }
+            let name = entry.name().trim_end_matches(".class");
+            let simple_name = name.rsplit_once('/').map_or(name, |(_, name)| name);
+            classes += 1;
+            short_names +=
+                usize::from(simple_name.len() <= 2 && simple_name.bytes().all(|c| c.is_ascii_lowercase()));
+        }
+        // ProGuard renames classes to the shortest names available, starting with `a`
+        if classes > 0 && short_names * 2 >= classes {
+            info.obfuscators.push("ProGuard");
+        }
+        let found = OBFUSCATOR_MARKERS.iter().zip(markers).filter(|(_, found)| *found);
+        info.obfuscators.extend(found.map(|((name, _), _)| *name));
+        Ok(info)
+    }
+}
+
+/// Returns a key ordering timestamps chronologically.
+fn key(time: &DateTime) -> (u16, u8, u8, u8, u8, u8) {
+    (
+        time.year(),
+        time.month(),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second(),
+    )
+}
+
+/// Reads the attributes of the main section of a manifest, which ends at the first blank line.
+fn main_attributes(text: &str) -> BTreeMap<String, String> {
+    // long lines are continued on the next line after a single space
+    let text = text.replace("\r\n", "\n").replace("\n ", "");
+    text.lines()
+        .take_while(|line| !line.trim().is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect()
+}
+
+pub(crate) fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn summarize_archives() {
+        let mut jar = TestJar::default()
+            .entry(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\nCreated-By: Maven\n\nName: a\nSealed: true\n",
+            )
+            .class(TestClass::new("a"))
+            .class(TestClass::new("b"))
+            .class(TestClass::new("com/example/Main").extends("ALLATORIxDEMO"))
+            .open()
+            .unwrap();
+
+        let info = jar.info().unwrap();
+        assert_eq!(info.manifest.len(), 2);
+        assert_eq!(info.manifest["Created-By"], "Maven");
+        assert!(info.earliest.is_some() && info.latest.is_some());
+        assert_eq!(info.compression.entries, 4);
+        assert_eq!(info.class_versions.into_iter().collect::<Vec<_>>(), [(52, 3)]);
+        assert_eq!(info.obfuscators, ["ProGuard", "Allatori"]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use cafebabe::{ClassAccessFlags, ClassFile, FieldAccessFlags, MethodAccessFlags, ParseOptions};
+
+use crate::indy::CallSite;
+use crate::jar::{Jar, JarEntry};
+use crate::model::{ClassModel, Constant, MemberView};
+use crate::name::ClassName;
+
+/// The classes of an archive that other classes of the archive extend, keyed by internal name.
+#[derive(Debug, Default)]
+pub(crate) struct Superclasses {
+    entries: HashMap<ClassName, JarEntry>,
+}
+
+impl Superclasses {
+    pub(crate) fn read<R: Read + Seek>(jar: &mut Jar<R>) -> Self {
+        let mut entries = HashMap::new();
+        let mut bases = HashSet::new();
+        // malformed classes are skipped here, they're reported when they're searched
+        for entry in jar.classes().flatten() {
+            let Ok(header) = entry.header() else {
+                continue;
+            };
+            bases.extend(header.super_class.clone());
+            entries.insert(header.this_class.clone(), entry);
+        }
+        entries.retain(|name, _| bases.contains(name));
+        Self { entries }
+    }
+
+    /// Parses the superclasses, the ones that cannot be parsed are treated as missing.
+    pub(crate) fn parse(&self, options: &ParseOptions) -> HashMap<&str, (ClassFile<'_>, &[u8])> {
+        self.entries
+            .iter()
+            .filter_map(|(name, entry)| {
+                let class = entry.parse_with_options(options).ok()?;
+                Some((name.as_str(), (class, entry.bytes())))
+            })
+            .collect()
+    }
+}
+
+/// Returns the superclasses of a class found in the parsed superclasses, starting with the nearest one.
+pub(crate) fn superclass_chain<'a, 'b>(
+    parsed: &'a HashMap<&str, (ClassFile<'b>, &'b [u8])>,
+    class: &ClassFile,
+) -> Vec<(&'a ClassFile<'b>, &'b [u8])> {
+    let mut chain: Vec<(&ClassFile, &[u8])> = vec![];
+    let mut base = class.super_class.as_deref();
+    // malformed archives can contain cycles
+    while let Some((class, bytes)) = base.and_then(|name| parsed.get(name)) {
+        if chain.len() == parsed.len() {
+            break;
+        }
+        chain.push((class, bytes));
+        base = class.super_class.as_deref();
+    }
+    chain
+}
+
+/// A class whose members are followed by the members it inherits from its superclasses.
+///
+/// Constructors, static initializers and private members aren't inherited.
+/// The inherited members of the nearest superclass come first.
+#[derive(Debug)]
+pub(crate) struct WithInherited<C, S> {
+    class: C,
+    supers: Vec<S>,
+    methods: Vec<(usize, usize)>,
+    fields: Vec<(usize, usize)>,
+}
+
+impl<C: ClassModel, S: ClassModel> WithInherited<C, S> {
+    pub(crate) fn new(class: C, supers: Vec<S>) -> Self {
+        let mut methods = vec![];
+        let mut fields = vec![];
+        for (i, base) in supers.iter().enumerate() {
+            methods.extend(
+                (0..base.method_count())
+                    .filter(|&j| {
+                        let method = base.method(j);
+                        !method.access_flags.contains(MethodAccessFlags::PRIVATE)
+                            && method.name != "<init>"
+                            && method.name != "<clinit>"
+                    })
+                    .map(|j| (i, j)),
+            );
+            fields.extend(
+                (0..base.field_count())
+                    .filter(|&j| !base.field(j).access_flags.contains(FieldAccessFlags::PRIVATE))
+                    .map(|j| (i, j)),
+            );
+        }
+        Self {
+            class,
+            supers,
+            methods,
+            fields,
+        }
+    }
+
+    #[inline]
+    fn inherited_method(&self, index: usize) -> Option<(&S, usize)> {
+        let (i, j) = *self.methods.get(index.checked_sub(self.class.method_count())?)?;
+        Some((&self.supers[i], j))
+    }
+
+    #[inline]
+    fn inherited_field(&self, index: usize) -> Option<(&S, usize)> {
+        let (i, j) = *self.fields.get(index.checked_sub(self.class.field_count())?)?;
+        Some((&self.supers[i], j))
+    }
+}
+
+impl<C: ClassModel, S: ClassModel> ClassModel for WithInherited<C, S> {
+    #[inline]
+    fn access_flags(&self) -> ClassAccessFlags {
+        self.class.access_flags()
+    }
+
+    #[inline]
+    fn this_class(&self) -> &str {
+        self.class.this_class()
+    }
+
+    #[inline]
+    fn super_class(&self) -> Option<&str> {
+        self.class.super_class()
+    }
+
+    #[inline]
+    fn interface_count(&self) -> usize {
+        self.class.interface_count()
+    }
+
+    #[inline]
+    fn interface(&self, index: usize) -> &str {
+        self.class.interface(index)
+    }
+
+    #[inline]
+    fn method_count(&self) -> usize {
+        self.class.method_count() + self.methods.len()
+    }
+
+    fn method(&self, index: usize) -> MemberView<'_, MethodAccessFlags> {
+        match self.inherited_method(index) {
+            Some((base, index)) => base.method(index),
+            None => self.class.method(index),
+        }
+    }
+
+    #[inline]
+    fn field_count(&self) -> usize {
+        self.class.field_count() + self.fields.len()
+    }
+
+    fn field(&self, index: usize) -> MemberView<'_, FieldAccessFlags> {
+        match self.inherited_field(index) {
+            Some((base, index)) => base.field(index),
+            None => self.class.field(index),
+        }
+    }
+
+    fn field_constant(&self, index: usize) -> Option<Constant<'_>> {
+        match self.inherited_field(index) {
+            Some((base, index)) => base.field_constant(index),
+            None => self.class.field_constant(index),
+        }
+    }
+
+    fn field_value(&self, index: usize) -> Option<Constant<'_>> {
+        match self.inherited_field(index) {
+            Some((base, index)) => base.field_value(index),
+            None => self.class.field_value(index),
+        }
+    }
+
+    fn call_sites(&self, index: usize) -> Option<Vec<CallSite>> {
+        match self.inherited_method(index) {
+            Some((base, index)) => base.call_sites(index),
+            None => self.class.call_sites(index),
+        }
+    }
+
+    #[inline]
+    fn with_class_file(&self, f: &mut dyn FnMut(&ClassFile<'_>)) {
+        self.class.with_class_file(f);
+    }
+}
@@ -1,74 +1,1290 @@
-use std::ffi::OsStr;
-use std::io::{Read, Seek};
+use std::cell::OnceCell;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::Arc;
 
-use cafebabe::{parse_class, parse_class_with_options, ClassFile, ParseOptions};
+use cafebabe::{parse_class, parse_class_with_options, ClassAccessFlags, ClassFile, ParseOptions};
+use thiserror::Error;
 use zip::read::ZipFile;
+use zip::result::ZipError;
+use zip::{CompressionMethod, ZipArchive};
 
-use crate::result::{Error, Result};
+use crate::bytecode::{self, BytecodeError, Disassembly};
+use crate::cfg::MethodCfg;
+use crate::constant_pool::ConstantPool;
+use crate::decompiler::Decompiler;
+use crate::decrypt::StringDecryptor;
+use crate::filter::{read_flags, ClassFilter, ClassInfo};
+use crate::indy::{self, CallSite, CodeTable};
+use crate::model::{ClassModel, ConstantValue};
+use crate::name::ClassName;
+use crate::result::{EntryPath, Error, Result};
+use crate::stats::CompressionStats;
+
+const DEX_MAGIC: &[u8; 4] = b"dex\n";
+const SPLIT_MAGIC: &[u8; 4] = b"PK\x07\x08";
 
 /// A JAR archive containing Java classes.
-#[derive(Debug)]
+///
+/// Archives in the zip64 format, e.g. ones larger than 4 GiB, are supported,
+/// archives split into multiple parts are rejected with [`UnsupportedFeature::MultiPart`].
+/// Encrypted archives can be read with a password, see [`Jar::with_password`].
+///
+/// Archives over cloneable readers can be cloned to read them from many threads, see [`Jar::open_shared`].
+#[derive(Debug, Clone)]
 pub struct Jar<R> {
-    zip: zip::ZipArchive<R>,
+    zip: ZipArchive<R>,
+    limits: Limits,
+    name: Option<Arc<str>>,
+    memory: Option<Arc<[u8]>>,
+    password: Option<Password>,
+    extensions: Vec<Box<[u8]>>,
+    zip64: bool,
+}
+
+impl Jar<BufReader<File>> {
+    /// Opens an archive from the file system, its path will be included in errors.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let jar = Self::new(BufReader::new(File::open(path)?))?;
+        Ok(jar.named(path.display().to_string()))
+    }
+}
+
+impl<T: AsRef<[u8]>> Jar<Cursor<T>> {
+    /// Opens an archive held in memory.
+    ///
+    /// This doesn't require file system access, which makes it suitable for targets like WebAssembly.
+    pub fn from_bytes(bytes: T) -> Result<Self> {
+        Self::new(Cursor::new(bytes))
+    }
+}
+
+impl Jar<Cursor<Arc<[u8]>>> {
+    /// Opens an archive held in shared memory.
+    ///
+    /// Stored entries are not copied when they're read, their [`JarEntry`] shares the memory of the archive instead.
+    /// Their checksums are not verified, unlike the ones of compressed entries.
+    pub fn from_shared_bytes(bytes: impl Into<Arc<[u8]>>) -> Result<Self> {
+        let bytes = bytes.into();
+        let mut jar = Self::new(Cursor::new(bytes.clone()))?;
+        jar.memory = Some(bytes);
+        Ok(jar)
+    }
 }
 
 impl<R: Read + Seek> Jar<R> {
     pub fn new(source: R) -> Result<Self> {
-        let zip = zip::ZipArchive::new(source)?;
-        Ok(Self { zip })
+        Self::with_limits(source, Limits::default())
+    }
+
+    /// Opens an archive which will be read within the provided [`Limits`].
+    ///
+    /// This should be preferred over [`Jar::new`] for archives coming from untrusted sources.
+    pub fn with_limits(mut source: R, limits: Limits) -> Result<Self> {
+        let mut magic = [0; 4];
+        let magic = source.read_exact(&mut magic).ok().map(|()| magic);
+        let end = read_directory_end(&mut source)?;
+        if end.is_some_and(|end| end.multi_part) {
+            return Err(UnsupportedFeature::MultiPart.into());
+        }
+
+        let zip = match ZipArchive::new(source) {
+            Ok(zip) => zip,
+            Err(ZipError::InvalidArchive(_)) if magic.as_ref() == Some(DEX_MAGIC) => {
+                return Err(Error::DexFile)
+            }
+            // the first part of a split archive starts with a marker instead of an entry
+            Err(ZipError::InvalidArchive(_)) if magic.as_ref() == Some(SPLIT_MAGIC) => {
+                return Err(UnsupportedFeature::MultiPart.into())
+            }
+            Err(ZipError::InvalidArchive(_)) => return Err(Error::NotAnArchive),
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(max) = limits.max_entries {
+            if zip.len() > max {
+                return Err(LimitError::EntryCount(max).into());
+            }
+        }
+        Ok(Self {
+            zip,
+            limits,
+            name: None,
+            memory: None,
+            password: None,
+            extensions: vec![],
+            zip64: end.is_some_and(|end| end.zip64),
+        })
+    }
+
+    /// Opens an encrypted archive, decrypting its entries with the provided password.
+    ///
+    /// Entries encrypted with AES require the `aes` feature, ones encrypted with ZipCrypto are always supported.
+    /// ZipCrypto can only detect most wrong passwords, the rest fail when the entries are read.
+    pub fn with_password(source: R, password: impl AsRef<[u8]>) -> Result<Self> {
+        Ok(Self::new(source)?.password(password))
+    }
+
+    /// Sets the password used to decrypt encrypted entries, see [`Jar::with_password`].
+    #[inline]
+    pub fn password(mut self, password: impl AsRef<[u8]>) -> Self {
+        self.password = Some(Password(password.as_ref().into()));
+        self
+    }
+
+    /// Reads the entries with an additional extension as classes, e.g. `.classdata` or `.class/`,
+    /// for toolchains that rename the classes they package.
+    ///
+    /// Extensions are matched ignoring case, like the standard `.class` one.
+    #[inline]
+    pub fn class_extension(mut self, extension: impl AsRef<str>) -> Self {
+        self.extensions.push(extension.as_ref().as_bytes().into());
+        self
+    }
+
+    /// Sets the name of the archive, it will be included in errors that concern its entries.
+    #[inline]
+    pub fn named(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Returns the name of the archive, if it has one.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns whether the archive is in the zip64 format,
+    /// which is required for archives larger than 4 GiB or with more than 65535 entries.
+    #[inline]
+    pub fn is_zip64(&self) -> bool {
+        self.zip64
     }
 
     /// Returns an iterator over all classes in the archive, each represented as a [`JarEntry`].
-    pub fn classes(&mut self) -> ClassIter<R> {
+    pub fn classes(&mut self) -> ClassIter<'_, R> {
         ClassIter {
             zip: &mut self.zip,
+            limits: &self.limits,
+            archive: self.name.as_ref(),
+            memory: self.memory.as_ref(),
+            password: self.password.as_ref(),
+            extensions: &self.extensions,
             index: 0,
+            total_size: 0,
         }
     }
+
+    /// Looks up a class by its internal name, e.g. `com/example/Foo`, using the central directory of the archive.
+    ///
+    /// Only classes stored under the path derived from their name can be found this way.
+    pub fn class_by_name(&mut self, name: &str) -> Result<Option<JarEntry>> {
+        let name = format!("{name}.class");
+        let file = match &self.password {
+            Some(password) => self.zip.by_name_decrypt(&name, &password.0),
+            None => self.zip.by_name(&name).map(Ok),
+        };
+        let file = match file {
+            Ok(Ok(file)) => file,
+            Ok(Err(_)) => return Err(Error::InvalidPassword),
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(err) => return Err(zip_error(err)),
+        };
+        read_entry(
+            file,
+            self.name.as_ref(),
+            self.memory.as_ref(),
+            &self.limits,
+            &mut 0,
+        )
+        .map(Some)
+    }
+
+    /// Parses every class of the archive and passes it to the callback along with the name of its entry.
+    ///
+    /// Unlike [`classes`](Self::classes) followed by [`JarEntry::parse`], the classes are read into a single buffer
+    /// reused between entries, which is why they can't outlive the callback.
+    pub fn parsed_classes(&mut self, mut f: impl FnMut(&str, ClassFile<'_>) -> Result<()>) -> Result<()> {
+        let mut buffer = vec![];
+        let mut total_size = 0;
+        for index in 0..self.zip.len() {
+            // an open entry borrows the archive, so errors are only handled once it's dropped
+            let err = match open_entry(&mut self.zip, index, self.password.as_ref()) {
+                Ok(file) if is_class(file.name_raw(), &self.extensions) => {
+                    let name = entry_name(&file);
+                    let error = |err: Error| Error::EntryError {
+                        path: EntryPath::new(self.name.clone(), name.clone()),
+                        source: err.into(),
+                    };
+                    read_into(file, &self.limits, &mut total_size, &mut buffer).map_err(error)?;
+                    let class = parse_class(&buffer).map_err(|err| error(Error::ClassError(err)))?;
+                    f(&name, class)?;
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(err) => err,
+            };
+            let Some((raw_name, err)) = entry_error(&mut self.zip, index, self.name.as_ref(), err) else {
+                break;
+            };
+            if raw_name
+                .as_deref()
+                .is_none_or(|name| is_class(name, &self.extensions))
+            {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over the classes accepted by a [`ClassFilter`], see [`filter`](crate::filter).
+    ///
+    /// Other classes are skipped without being parsed and, when the filter doesn't depend on flags, without being read.
+    #[inline]
+    pub fn classes_filtered<F: ClassFilter>(&mut self, filter: F) -> FilteredClassIter<'_, R, F> {
+        FilteredClassIter {
+            classes: self.classes(),
+            filter,
+        }
+    }
+
+    /// Returns an iterator over the entries of the archive that aren't classes or directories,
+    /// e.g. manifests and configuration files.
+    ///
+    /// Resources are represented as a [`JarEntry`] too, but they can't be parsed as classes.
+    #[inline]
+    pub fn resources(&mut self) -> ResourceIter<'_, R> {
+        self.resources_where(|name| !name.ends_with(b"/"))
+    }
+
+    /// Returns an iterator over the resources with names satisfying the predicate,
+    /// other entries are skipped without being read.
+    pub(crate) fn resources_where(&mut self, filter: fn(&[u8]) -> bool) -> ResourceIter<'_, R> {
+        ResourceIter {
+            entries: self.classes(),
+            filter,
+        }
+    }
+
+    /// Returns how the entries of the archive are compressed, without decompressing them.
+    pub fn compression_stats(&mut self) -> Result<CompressionStats> {
+        let mut stats = CompressionStats::default();
+        self.raw_entries(|file| {
+            stats.entries += 1;
+            match file.compression() {
+                CompressionMethod::Stored => stats.stored += 1,
+                CompressionMethod::Deflated => stats.deflated += 1,
+                _ => {}
+            }
+            stats.compressed_size += file.compressed_size();
+            stats.size += file.size();
+        })?;
+        Ok(stats)
+    }
+
+    /// Returns the number of entries read as classes, counted by their names without reading them.
+    pub fn class_count(&self) -> usize {
+        let extensions = &self.extensions;
+        self.zip
+            .file_names()
+            .filter(|name| is_class(name.as_bytes(), extensions))
+            .count()
+    }
+
+    /// Fails if the archive doesn't seem to contain any classes, e.g. because it's an Android package
+    /// with classes in DEX format ([`Error::AndroidPackage`]) or it only holds resources ([`Error::NoClasses`]).
+    ///
+    /// Such archives can be opened and their resources read, this only explains why searching them is pointless.
+    pub fn require_classes(&self) -> Result<()> {
+        // classes renamed to other extensions usually keep the standard one as a part of theirs
+        if self.class_count() > 0 || self.zip.file_names().any(|name| name.contains(".class")) {
+            Ok(())
+        } else if self.zip.file_names().any(|name| name == "classes.dex") {
+            Err(Error::AndroidPackage)
+        } else {
+            Err(Error::NoClasses)
+        }
+    }
+
+    /// Returns the names of the entries read as classes, without reading their contents.
+    pub(crate) fn class_entry_names(&mut self) -> Result<Vec<Box<str>>> {
+        let mut names = vec![];
+        let extensions = self.extensions.clone();
+        self.raw_entries(|file| {
+            if is_class(file.name_raw(), &extensions) {
+                names.push(entry_name(file));
+            }
+        })?;
+        Ok(names)
+    }
+
+    /// Passes the entries of the archive that aren't directories to the callback, without reading their contents.
+    pub(crate) fn raw_entries(&mut self, mut f: impl FnMut(&ZipFile)) -> Result<()> {
+        for index in 0..self.zip.len() {
+            let file = self.zip.by_index_raw(index)?;
+            if !file.is_dir() {
+                f(&file);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resource limits enforced while reading an archive.
+///
+/// Sizes are checked on the bytes actually decompressed rather than the ones declared by the archive,
+/// which can't be trusted. All limits are disabled by default.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    max_entries: Option<usize>,
+    max_entry_size: Option<u64>,
+    max_total_size: Option<u64>,
+    max_compression_ratio: Option<u64>,
+}
+
+impl Limits {
+    /// Sets the maximum number of entries in the archive.
+    #[inline]
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = Some(max);
+        self
+    }
+
+    /// Sets the maximum decompressed size of a single class in bytes.
+    #[inline]
+    pub fn max_entry_size(mut self, max: u64) -> Self {
+        self.max_entry_size = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of decompressed bytes read during a single iteration over the classes.
+    #[inline]
+    pub fn max_total_size(mut self, max: u64) -> Self {
+        self.max_total_size = Some(max);
+        self
+    }
+
+    /// Sets the maximum ratio between the decompressed and the compressed size of a class.
+    #[inline]
+    pub fn max_compression_ratio(mut self, max: u64) -> Self {
+        self.max_compression_ratio = Some(max);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum LimitError {
+    #[error("archive has more than {0} entries")]
+    EntryCount(usize),
+    #[error("entry is larger than {0} bytes")]
+    EntrySize(u64),
+    #[error("archive contents are larger than {0} bytes")]
+    TotalSize(u64),
+    #[error("entry compression ratio is higher than {0}")]
+    CompressionRatio(u64),
+}
+
+/// A feature of the zip format that archives can't be read with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum UnsupportedFeature {
+    #[error("archive is split into multiple parts")]
+    MultiPart,
+    #[error("entry is encrypted with AES, which requires the `aes` feature")]
+    AesEncryption,
+}
+
+/// The password of an encrypted archive, kept out of debug output.
+#[derive(Clone)]
+struct Password(Arc<[u8]>);
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Password(..)")
+    }
 }
 
 #[derive(Debug)]
-pub struct JarEntry(Box<[u8]>);
+pub struct JarEntry {
+    archive: Option<Arc<str>>,
+    name: Box<str>,
+    raw_name: Box<[u8]>,
+    bytes: EntryBytes,
+    compression: CompressionMethod,
+    header: OnceCell<ClassHeader>,
+}
 
 impl JarEntry {
+    #[inline]
+    fn new(
+        archive: Option<Arc<str>>,
+        name: Box<str>,
+        raw_name: Box<[u8]>,
+        bytes: EntryBytes,
+        compression: CompressionMethod,
+    ) -> Self {
+        Self {
+            archive,
+            name,
+            raw_name,
+            bytes,
+            compression,
+            header: OnceCell::new(),
+        }
+    }
+
+    /// Returns the path of this entry in the archive, with backslashes replaced by forward slashes.
+    ///
+    /// Names that are not valid UTF-8 are decoded the same way as by the zip reader.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path of this entry exactly as it's stored in the archive.
+    #[inline]
+    pub fn raw_name(&self) -> &[u8] {
+        &self.raw_name
+    }
+
+    /// Returns the name of the archive this entry comes from, if it has one.
+    #[inline]
+    pub fn archive(&self) -> Option<&str> {
+        self.archive.as_deref()
+    }
+
+    /// Returns the raw contents of this entry.
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Returns the method this entry was compressed with in the archive.
+    #[inline]
+    pub fn compression(&self) -> CompressionMethod {
+        self.compression
+    }
+
+    /// Returns whether the contents of this entry share the memory of the archive rather than being copied,
+    /// see [`Jar::from_shared_bytes`].
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.bytes, EntryBytes::Shared(..))
+    }
+
+    /// Returns the [`EntryPath`] identifying this entry in errors.
+    #[inline]
+    pub fn path(&self) -> EntryPath {
+        EntryPath::new(self.archive.clone(), self.name.clone())
+    }
+
     /// Attempts to parse this entry as a [`ClassFile`].
     #[inline]
-    pub fn parse(&self) -> Result<ClassFile> {
-        parse_class(&self.0).map_err(Error::ClassError)
+    pub fn parse(&self) -> Result<ClassFile<'_>> {
+        parse_class(self.bytes()).map_err(|err| self.error(Error::ClassError(err)))
     }
 
     /// Attempts to parse this entry as a [`ClassFile`], ignoring the bytecode of it's methods.
     #[inline]
-    pub fn parse_without_bytecode(&self) -> Result<ClassFile> {
-        parse_class_with_options(&self.0, ParseOptions::default().parse_bytecode(false))
-            .map_err(Error::ClassError)
+    pub fn parse_without_bytecode(&self) -> Result<ClassFile<'_>> {
+        self.parse_with_options(ParseOptions::default().parse_bytecode(false))
+    }
+
+    /// Attempts to parse this entry as a [`ClassFile`] using the provided [`ParseOptions`].
+    #[inline]
+    pub fn parse_with_options(&self, opts: &ParseOptions) -> Result<ClassFile<'_>> {
+        parse_class_with_options(self.bytes(), opts).map_err(|err| self.error(Error::ClassError(err)))
+    }
+
+    /// Disassembles the body of the method at `method` index of this class.
+    ///
+    /// Returns [`None`] if the method has no body, e.g. when it's abstract or native.
+    ///
+    /// # Panics
+    /// Panics if the index is out of bounds.
+    pub fn disassemble(&self, method: usize) -> Result<Option<Disassembly>> {
+        let class = self.parse_without_bytecode()?;
+        let method = &class.methods[method];
+        let Some(code) = indy::code(&method.attributes) else {
+            return Ok(None);
+        };
+
+        let instructions = ConstantPool::parse(self.bytes())
+            .map_err(BytecodeError::from)
+            .and_then(|pool| bytecode::disassemble(code.code, &pool))
+            .map_err(|err| self.error(err.into()))?;
+        Ok(Some(Disassembly {
+            name: method.name.to_string(),
+            descriptor: method.descriptor.to_string(),
+            max_stack: code.max_stack,
+            max_locals: code.max_locals,
+            instructions,
+        }))
+    }
+
+    /// Builds the control-flow graph of the method at `method` index of this class.
+    ///
+    /// Returns [`None`] if the method has no body, e.g. when it's abstract or native.
+    ///
+    /// # Panics
+    /// Panics if the index is out of bounds.
+    pub fn cfg(&self, method: usize) -> Result<Option<MethodCfg>> {
+        let Some(disassembly) = self.disassemble(method)? else {
+            return Ok(None);
+        };
+        disassembly.cfg().map(Some).map_err(|err| self.error(err.into()))
+    }
+
+    /// Returns the `invokedynamic` call sites of a method, in the order they appear in its code.
+    ///
+    /// `method` is an index into [`ClassFile::methods`](cafebabe::ClassFile::methods),
+    /// methods without a body, indices out of bounds and call sites referring to a missing bootstrap method
+    /// have no call sites.
+    pub fn call_sites(&self, method: usize) -> Result<Vec<CallSite>> {
+        Ok(indy::call_sites(&self.parse()?, method).unwrap_or_default())
+    }
+
+    /// Reads the constant pool of this class without parsing the rest of it.
+    ///
+    /// This is enough for heuristics that only look at referenced classes, members or string literals.
+    pub fn constant_pool(&self) -> Result<ConstantPool<'_>> {
+        ConstantPool::parse(self.bytes()).map_err(|err| self.error(BytecodeError::from(err).into()))
+    }
+
+    /// Returns the initial values of the fields of this class, indexed like
+    /// [`ClassFile::fields`](cafebabe::ClassFile::fields).
+    ///
+    /// Besides constant values, this includes constants assigned to static fields in the static initializer,
+    /// which is how compilers initialize fields that aren't compile-time constants, e.g. ones that aren't final.
+    pub fn field_values(&self) -> Result<Vec<Option<ConstantValue>>> {
+        self.values(None)
+    }
+
+    /// Returns the initial values of the fields of this class like [`JarEntry::field_values`],
+    /// with the strings passed to decryption stubs recovered by `decryptor`.
+    pub fn decrypted_field_values(
+        &self,
+        decryptor: &dyn StringDecryptor,
+    ) -> Result<Vec<Option<ConstantValue>>> {
+        self.values(Some(decryptor))
+    }
+
+    fn values(&self, decryptor: Option<&dyn StringDecryptor>) -> Result<Vec<Option<ConstantValue>>> {
+        let class = self.parse_without_bytecode()?;
+        let mut values = CodeTable::new(&class, self.bytes())
+            .and_then(|table| table.static_values(&class, decryptor))
+            .map_err(|err| self.error(err.into()))?;
+        for (i, value) in values.iter_mut().enumerate() {
+            if let Some(constant) = class.field_constant(i) {
+                *value = Some(constant.into_owned());
+            }
+        }
+        Ok(values)
+    }
+
+    /// Decompiles this class to Java source with the provided [`Decompiler`].
+    pub fn decompile(&self, decompiler: &impl Decompiler) -> Result<String> {
+        let name = &self.header()?.this_class;
+        decompiler
+            .decompile(name, self.bytes())
+            .map_err(|err| self.error(err))
+    }
+
+    pub(crate) fn error(&self, err: Error) -> Error {
+        Error::EntryError {
+            path: self.path(),
+            source: err.into(),
+        }
+    }
+
+    /// Returns the [`ClassHeader`] of this entry.
+    ///
+    /// The header is cached, entries returned by a search have it available without parsing the class again.
+    pub fn header(&self) -> Result<&ClassHeader> {
+        if let Some(header) = self.header.get() {
+            return Ok(header);
+        }
+        let header = ClassHeader::new(&self.parse_without_bytecode()?);
+        Ok(self.header.get_or_init(|| header))
+    }
+
+    pub(crate) fn cache_header(&self, class: &ClassFile) {
+        self.header.get_or_init(|| ClassHeader::new(class));
+    }
+}
+
+/// Contents of an entry, either read from the archive or sharing its memory.
+#[derive(Debug)]
+enum EntryBytes {
+    Owned(Box<[u8]>),
+    Shared(Arc<[u8]>, Range<usize>),
+}
+
+impl EntryBytes {
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Owned(bytes) => bytes,
+            Self::Shared(memory, range) => &memory[range.clone()],
+        }
+    }
+}
+
+/// Owned summary of the class declaration of a [`JarEntry`].
+#[derive(Debug, Clone)]
+pub struct ClassHeader {
+    pub access_flags: ClassAccessFlags,
+    pub this_class: ClassName,
+    pub super_class: Option<ClassName>,
+    pub interfaces: Vec<ClassName>,
+}
+
+impl ClassHeader {
+    fn new(class: &ClassFile) -> Self {
+        Self {
+            access_flags: class.access_flags,
+            this_class: ClassName::new(&class.this_class),
+            super_class: class.super_class.as_deref().map(ClassName::new),
+            interfaces: class.interfaces.iter().map(|name| ClassName::new(name)).collect(),
+        }
     }
 }
 
 pub struct ClassIter<'a, R> {
     zip: &'a mut zip::ZipArchive<R>,
+    limits: &'a Limits,
+    archive: Option<&'a Arc<str>>,
+    memory: Option<&'a Arc<[u8]>>,
+    password: Option<&'a Password>,
+    extensions: &'a [Box<[u8]>],
     index: usize,
+    total_size: u64,
+}
+
+impl<R: Read + Seek> ClassIter<'_, R> {
+    /// Returns the next entry with a name satisfying the predicate.
+    ///
+    /// Entries that can't be opened are returned as errors when their names satisfy the predicate,
+    /// or when their names can't be read and `unnamed` is set.
+    fn next_where(&mut self, predicate: impl Fn(&[u8]) -> bool, unnamed: bool) -> Option<Result<JarEntry>> {
+        let entry = loop {
+            let index = self.index;
+            self.index += 1;
+            let err = match open_entry(self.zip, index, self.password) {
+                Ok(entry) if predicate(entry.name_raw()) => break entry,
+                Ok(_) => continue,
+                Err(err) => err,
+            };
+            let (raw_name, err) = entry_error(self.zip, index, self.archive, err)?;
+            if raw_name.as_deref().map_or(unnamed, &predicate) {
+                return Some(Err(err));
+            }
+        };
+        Some(read_entry(
+            entry,
+            self.archive,
+            self.memory,
+            self.limits,
+            &mut self.total_size,
+        ))
+    }
+
+    fn next_filtered(&mut self, filter: &impl ClassFilter) -> Option<Result<JarEntry>> {
+        loop {
+            let entry = loop {
+                let index = self.index;
+                self.index += 1;
+                let err = match open_entry(self.zip, index, self.password) {
+                    Ok(entry) if is_class(entry.name_raw(), self.extensions) => break entry,
+                    Ok(_) => continue,
+                    Err(err) => err,
+                };
+                let (raw_name, err) = entry_error(self.zip, index, self.archive, err)?;
+                if raw_name
+                    .as_deref()
+                    .is_none_or(|name| is_class(name, self.extensions))
+                {
+                    return Some(Err(err));
+                }
+            };
+            let name = entry_name(&entry);
+            let mut info = ClassInfo {
+                name: &name,
+                size: entry.size(),
+                flags: None,
+            };
+            let accepted = filter.accepts(&info);
+            if accepted == Some(false) {
+                continue;
+            }
+            let entry = read_entry(
+                entry,
+                self.archive,
+                self.memory,
+                self.limits,
+                &mut self.total_size,
+            );
+            let Some(Ok(read)) = accepted.is_none().then_some(&entry) else {
+                return Some(entry);
+            };
+            info.flags = read_flags(read.bytes());
+            // classes without readable flags are returned so that they fail when they're parsed
+            if info.flags.is_none() || filter.accepts(&info) != Some(false) {
+                return Some(entry);
+            }
+        }
+    }
 }
 
 impl<'a, R: Read + Seek> Iterator for ClassIter<'a, R> {
     type Item = Result<JarEntry>;
 
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let entry = loop {
-            let entry = self.zip.by_index(self.index).ok()?;
-            self.index += 1;
-            let path: &Path = entry.name().as_ref();
-            if path.extension() == Some(OsStr::new("class")) {
-                break entry;
-            }
+        let extensions = self.extensions;
+        self.next_where(|name| is_class(name, extensions), true)
+    }
+}
+
+pub struct FilteredClassIter<'a, R, F> {
+    classes: ClassIter<'a, R>,
+    filter: F,
+}
+
+impl<'a, R: Read + Seek, F: ClassFilter> Iterator for FilteredClassIter<'a, R, F> {
+    type Item = Result<JarEntry>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.classes.next_filtered(&self.filter)
+    }
+}
+
+pub struct ResourceIter<'a, R> {
+    entries: ClassIter<'a, R>,
+    filter: fn(&[u8]) -> bool,
+}
+
+impl<'a, R: Read + Seek> Iterator for ResourceIter<'a, R> {
+    type Item = Result<JarEntry>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let filter = self.filter;
+        let extensions = self.entries.extensions;
+        self.entries
+            .next_where(|name| !is_class(name, extensions) && filter(name), false)
+    }
+}
+
+pub(crate) fn is_class_name(name: &[u8]) -> bool {
+    has_extension(name, b".class")
+}
+
+/// Returns whether an entry is read as a class, either by the standard extension or by an additional one.
+fn is_class(name: &[u8], extensions: &[Box<[u8]>]) -> bool {
+    is_class_name(name) || extensions.iter().any(|ext| has_extension(name, ext))
+}
+
+fn has_extension(name: &[u8], ext: &[u8]) -> bool {
+    name.len() > ext.len() && name[name.len() - ext.len()..].eq_ignore_ascii_case(ext)
+}
+
+/// Opens an entry, decrypting it if the archive has a password.
+fn open_entry<'a, R: Read + Seek>(
+    zip: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&Password>,
+) -> Result<ZipFile<'a>> {
+    match password {
+        Some(password) => zip
+            .by_index_decrypt(index, &password.0)
+            .map_err(zip_error)?
+            .map_err(|_| Error::InvalidPassword),
+        None => zip.by_index(index).map_err(zip_error),
+    }
+}
+
+/// Returns the raw name of an entry that couldn't be opened, if it can still be read, along with an error naming it.
+///
+/// Returns [`None`] past the last entry, which ends the iteration over the entries.
+fn entry_error<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    index: usize,
+    archive: Option<&Arc<str>>,
+    err: Error,
+) -> Option<(Option<Box<[u8]>>, Error)> {
+    if index >= zip.len() {
+        return None;
+    }
+    // corrupt local headers can't be read raw either
+    let Ok(file) = zip.by_index_raw(index) else {
+        return Some((None, err));
+    };
+    let err = Error::EntryError {
+        path: EntryPath::new(archive.cloned(), entry_name(&file)),
+        source: err.into(),
+    };
+    Some((Some(file.name_raw().into()), err))
+}
+
+fn zip_error(err: ZipError) -> Error {
+    match err {
+        ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => Error::PasswordRequired,
+        ZipError::UnsupportedArchive(detail) if detail.starts_with("AES") => {
+            UnsupportedFeature::AesEncryption.into()
+        }
+        err => err.into(),
+    }
+}
+
+pub(crate) fn read_entry(
+    file: ZipFile,
+    archive: Option<&Arc<str>>,
+    memory: Option<&Arc<[u8]>>,
+    limits: &Limits,
+    total_size: &mut u64,
+) -> Result<JarEntry> {
+    let raw_name: Box<[u8]> = file.name_raw().into();
+    let name = entry_name(&file);
+    let compression = file.compression();
+
+    let bytes = match memory.and_then(|memory| Some((memory, stored_range(&file, memory)?))) {
+        Some((memory, range)) => check_size(range.len() as u64, limits, total_size)
+            .map(|()| EntryBytes::Shared(memory.clone(), range)),
+        None => read_bytes(file, limits, total_size).map(EntryBytes::Owned),
+    };
+    match bytes {
+        Ok(bytes) => Ok(JarEntry::new(
+            archive.cloned(),
+            name,
+            raw_name,
+            bytes,
+            compression,
+        )),
+        Err(err) => Err(Error::EntryError {
+            path: EntryPath::new(archive.cloned(), name),
+            source: err.into(),
+        }),
+    }
+}
+
+/// The layout of an archive described by its end of central directory record.
+#[derive(Debug, Clone, Copy)]
+struct DirectoryEnd {
+    multi_part: bool,
+    zip64: bool,
+}
+
+/// Finds the end of central directory record of an archive, rewinding the source afterwards.
+///
+/// Returns [`None`] if there's no record, which the zip reader reports as an invalid archive.
+fn read_directory_end(source: &mut (impl Read + Seek)) -> io::Result<Option<DirectoryEnd>> {
+    const SIGNATURE: &[u8] = b"PK\x05\x06";
+    const LOCATOR_SIGNATURE: &[u8] = b"PK\x06\x07";
+    const RECORD_SIZE: usize = 22;
+    const LOCATOR_SIZE: usize = 20;
+    // the record ends with a comment of up to 65535 bytes
+    const MAX_TAIL: u64 = (LOCATOR_SIZE + RECORD_SIZE + u16::MAX as usize) as u64;
+
+    let len = source.seek(SeekFrom::End(0))?;
+    source.seek(SeekFrom::Start(len.saturating_sub(MAX_TAIL)))?;
+    let mut tail = vec![];
+    source.read_to_end(&mut tail)?;
+    source.rewind()?;
+
+    // only complete records are considered, the signature alone can appear in a truncated archive
+    let Some(pos) = tail
+        .windows(RECORD_SIZE)
+        .rposition(|record| record.starts_with(SIGNATURE))
+    else {
+        return Ok(None);
+    };
+    let read_u16 = |offset: usize| u16::from_le_bytes([tail[pos + offset], tail[pos + offset + 1]]);
+    let locator = pos
+        .checked_sub(LOCATOR_SIZE)
+        .map(|start| &tail[start..pos])
+        .filter(|locator| locator.starts_with(LOCATOR_SIGNATURE));
+
+    let multi_part = match locator {
+        // the locator ends with the total number of disks
+        Some(locator) => u32::from_le_bytes(locator[16..20].try_into().unwrap()) > 1,
+        None => read_u16(4) != 0 || read_u16(6) != 0,
+    };
+    Ok(Some(DirectoryEnd {
+        multi_part,
+        zip64: locator.is_some(),
+    }))
+}
+
+fn entry_name(file: &ZipFile) -> Box<str> {
+    match std::str::from_utf8(file.name_raw()) {
+        Ok(name) => name.replace('\\', "/").into(),
+        Err(_) => file.name().replace('\\', "/").into(),
+    }
+}
+
+/// Returns the range of the contents of a stored entry in the memory of its archive,
+/// or [`None`] if they have to be read through the zip reader.
+fn stored_range(file: &ZipFile, memory: &[u8]) -> Option<Range<usize>> {
+    // encrypted entries are either marked with the AES method or larger than their contents
+    if file.compression() != CompressionMethod::Stored || file.size() != file.compressed_size() {
+        return None;
+    }
+    let start = usize::try_from(file.data_start()).ok()?;
+    let end = start.checked_add(usize::try_from(file.size()).ok()?)?;
+    (end <= memory.len()).then_some(start..end)
+}
+
+fn read_bytes(file: ZipFile, limits: &Limits, total_size: &mut u64) -> Result<Box<[u8]>> {
+    let mut buffer = vec![];
+    read_into(file, limits, total_size, &mut buffer)?;
+    Ok(buffer.into_boxed_slice())
+}
+
+/// Reads an entry into a buffer, replacing its contents.
+fn read_into(file: ZipFile, limits: &Limits, total_size: &mut u64, buffer: &mut Vec<u8>) -> Result<()> {
+    let max_ratio_size = limits
+        .max_compression_ratio
+        .map(|max| (max, file.compressed_size().max(1).saturating_mul(max)));
+    if let Some((max, max_ratio_size)) = max_ratio_size {
+        // fail early on the declared size, which is checked against the bytes read below anyway
+        if file.size() > max_ratio_size {
+            return Err(LimitError::CompressionRatio(max).into());
+        }
+    }
+    let max_size = max_size(limits, *total_size).min(max_ratio_size.map_or(u64::MAX, |(_, size)| size));
+
+    // the declared size cannot be trusted, so it's not used to reserve memory and the read is capped regardless of it
+    buffer.clear();
+    file.take(max_size.saturating_add(1)).read_to_end(buffer)?;
+    if let Some((max, max_ratio_size)) = max_ratio_size {
+        if buffer.len() as u64 > max_ratio_size {
+            return Err(LimitError::CompressionRatio(max).into());
+        }
+    }
+    check_size(buffer.len() as u64, limits, total_size)
+}
+
+/// Returns the maximum size of the next entry read, given the number of bytes read before it.
+fn max_size(limits: &Limits, total_size: u64) -> u64 {
+    match (limits.max_entry_size, limits.max_total_size) {
+        (Some(entry), Some(total)) => entry.min(total.saturating_sub(total_size)),
+        (Some(entry), None) => entry,
+        (None, Some(total)) => total.saturating_sub(total_size),
+        (None, None) => u64::MAX,
+    }
+}
+
+/// Checks the size of an entry against the limits, adding it to the number of bytes read.
+fn check_size(size: u64, limits: &Limits, total_size: &mut u64) -> Result<()> {
+    if size > max_size(limits, *total_size) {
+        return match limits.max_entry_size {
+            Some(max) if size > max => Err(LimitError::EntrySize(max).into()),
+            _ => Err(LimitError::TotalSize(limits.max_total_size.unwrap_or_default()).into()),
         };
-        Some(read_class(entry))
     }
+    *total_size += size;
+    Ok(())
 }
 
-fn read_class(mut file: ZipFile) -> Result<JarEntry> {
-    let mut buffer = vec![0; file.size() as usize];
-    file.read_exact(&mut buffer)?;
-    Ok(JarEntry(buffer.into_boxed_slice()))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn detect_class_names() {
+        assert!(is_class_name(b"com/example/Foo.class"));
+        assert!(is_class_name(b"com\\example\\Foo.CLASS"));
+        assert!(is_class_name(b"\xff\xfe.class"));
+        assert!(!is_class_name(b".class"));
+        assert!(!is_class_name(b"com/example/Foo.class/"));
+        assert!(!is_class_name(b"META-INF/MANIFEST.MF"));
+    }
+
+    #[test]
+    fn enforce_limits() {
+        use crate::writer::JarWriter;
+
+        let class = |name: &str| TestClass::new(name).to_bytes();
+        let mut writer = JarWriter::new(Cursor::new(vec![]));
+        writer.write_entry("a.class", &class("a")).unwrap();
+        writer.write_entry("b.class", &class("b")).unwrap();
+        // compresses to a tiny fraction of its size
+        writer.write_entry("c.class", &[0; 0x10000]).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        let read = |limits: Limits| -> Result<Vec<JarEntry>> {
+            Jar::with_limits(Cursor::new(&bytes), limits)?.classes().collect()
+        };
+        let limit_error = |res: Result<Vec<JarEntry>>| match res {
+            Err(Error::EntryError { source, .. }) => match *source {
+                Error::LimitExceeded(err) => Some(err),
+                _ => None,
+            },
+            Err(Error::LimitExceeded(err)) => Some(err),
+            _ => None,
+        };
+
+        assert!(read(Limits::default()).is_ok());
+        assert_eq!(
+            limit_error(read(Limits::default().max_entries(2))),
+            Some(LimitError::EntryCount(2))
+        );
+        assert_eq!(
+            limit_error(read(Limits::default().max_entry_size(0x1000))),
+            Some(LimitError::EntrySize(0x1000))
+        );
+        let total = 2 * class("a").len() as u64 + 0x100;
+        assert_eq!(
+            limit_error(read(Limits::default().max_total_size(total))),
+            Some(LimitError::TotalSize(total))
+        );
+        assert_eq!(
+            limit_error(read(Limits::default().max_compression_ratio(10))),
+            Some(LimitError::CompressionRatio(10))
+        );
+
+        // the sizes declared in the headers understate the decompressed size of the last entry
+        let mut lying = bytes.clone();
+        for (signature, offset) in [(b"PK\x03\x04", 22), (b"PK\x01\x02", 24)] {
+            let pos = (0..lying.len())
+                .rev()
+                .find(|&pos| lying[pos..].starts_with(signature))
+                .unwrap();
+            lying[pos + offset..pos + offset + 4].copy_from_slice(&16u32.to_le_bytes());
+        }
+        let read = |limits: Limits| -> Result<Vec<JarEntry>> {
+            Jar::with_limits(Cursor::new(&lying), limits)?.classes().collect()
+        };
+        assert_eq!(
+            limit_error(read(Limits::default().max_compression_ratio(10))),
+            Some(LimitError::CompressionRatio(10))
+        );
+        assert_eq!(
+            limit_error(read(Limits::default().max_entry_size(0x1000))),
+            Some(LimitError::EntrySize(0x1000))
+        );
+    }
+
+    #[test]
+    fn open_archives_without_classes() {
+        let mut jar = TestJar::default()
+            .entry("META-INF/MANIFEST.MF", "Manifest-Version: 1.0\n")
+            .open()
+            .unwrap();
+        assert_eq!(jar.class_count(), 0);
+        assert_eq!(jar.resources().count(), 1);
+        assert!(matches!(jar.require_classes(), Err(Error::NoClasses)));
+        let pats = [crate::ClassPat::default()];
+        assert!(matches!(
+            crate::search_exact(&mut jar, &pats),
+            Err(Error::NoClasses)
+        ));
+
+        let apk = TestJar::default().entry("classes.dex", "dex\n").open().unwrap();
+        assert!(matches!(apk.require_classes(), Err(Error::AndroidPackage)));
+        let jar = TestJar::default().class(TestClass::new("a")).open().unwrap();
+        assert_eq!(jar.class_count(), 1);
+        assert!(jar.require_classes().is_ok());
+    }
+
+    #[test]
+    fn read_classes_with_additional_extensions() {
+        let class = TestClass::new("b").to_bytes();
+        let jar = TestJar::default()
+            .class(TestClass::new("a"))
+            .entry("b.classdata", class.clone())
+            .entry("c.class/", class)
+            .entry("d.txt", b"d".as_slice());
+        let mut jar = jar
+            .open()
+            .unwrap()
+            .class_extension(".classdata")
+            .class_extension(".CLASS/");
+
+        let names: Vec<_> = jar
+            .classes()
+            .map(|entry| entry.unwrap().name().to_owned())
+            .collect();
+        assert_eq!(names, ["a.class", "b.classdata", "c.class/"]);
+        let names: Vec<_> = jar
+            .resources()
+            .map(|entry| entry.unwrap().name().to_owned())
+            .collect();
+        assert_eq!(names, ["d.txt"]);
+    }
+
+    #[test]
+    fn find_class_by_name() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a"))
+            .class(TestClass::new("b/c").extends("a"))
+            .open()
+            .unwrap();
+        let entry = jar.class_by_name("b/c").unwrap().unwrap();
+        assert_eq!(entry.header().unwrap().super_class.as_deref(), Some("a"));
+        assert!(jar.class_by_name("b").unwrap().is_none());
+    }
+
+    #[test]
+    fn borrow_stored_entries() {
+        let class = TestClass::new("a");
+        let bytes = TestJar::default()
+            .class(class.clone())
+            .entry("b.txt", b"b".as_slice())
+            .to_bytes()
+            .unwrap();
+
+        let mut jar = Jar::from_shared_bytes(bytes.clone()).unwrap();
+        let entry = jar.classes().next().unwrap().unwrap();
+        assert_eq!(entry.compression(), CompressionMethod::Stored);
+        assert!(entry.is_borrowed());
+        assert_eq!(entry.bytes(), class.to_bytes());
+        assert_eq!(jar.compression_stats().unwrap(), CompressionStats {
+            entries: 2,
+            stored: 2,
+            deflated: 0,
+            compressed_size: class.to_bytes().len() as u64 + 1,
+            size: class.to_bytes().len() as u64 + 1,
+        });
+
+        let mut jar = Jar::from_bytes(bytes).unwrap();
+        assert!(!jar.classes().next().unwrap().unwrap().is_borrowed());
+    }
+
+    #[test]
+    fn open_zip64_archives() {
+        let jar = (0..=u16::MAX).fold(TestJar::default().class(TestClass::new("a")), |jar, i| {
+            jar.entry(format!("{i}.txt"), vec![])
+        });
+        let mut jar = jar.open().unwrap();
+        assert!(jar.is_zip64());
+        assert_eq!(jar.classes().count(), 1);
+    }
+
+    #[test]
+    fn reject_split_archives() {
+        let mut bytes = TestJar::default().class(TestClass::new("a")).to_bytes().unwrap();
+        assert!(!Jar::from_bytes(bytes.as_slice()).unwrap().is_zip64());
+
+        // the disk number of the end of central directory record, which is 22 bytes long without a comment
+        let end = bytes.len() - 22;
+        bytes[end + 4] = 1;
+        assert!(matches!(
+            Jar::from_bytes(bytes).unwrap_err(),
+            Error::Unsupported(UnsupportedFeature::MultiPart)
+        ));
+        assert!(matches!(
+            Jar::from_bytes(b"PK\x07\x08PK\x03\x04").unwrap_err(),
+            Error::Unsupported(UnsupportedFeature::MultiPart)
+        ));
+    }
+
+    #[test]
+    fn reject_truncated_directory_end() {
+        assert!(Jar::from_bytes(b"PK\x05\x06").is_err());
+        let bytes = TestJar::default().class(TestClass::new("a")).to_bytes().unwrap();
+        assert!(Jar::from_bytes(&bytes[..bytes.len() - 10]).is_err());
+    }
+
+    /// Encrypts the only entry of an archive written by [`TestJar`] with ZipCrypto.
+    fn encrypt(archive: &[u8], password: &[u8]) -> Vec<u8> {
+        fn crc32(crc: u32, byte: u8) -> u32 {
+            let poly = |c: u32, _| {
+                if c & 1 == 1 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                }
+            };
+            (0..8).fold((crc ^ u32::from(byte)) & 0xff, poly) ^ (crc >> 8)
+        }
+        fn encrypt_byte(keys: &mut [u32; 3], byte: u8) -> u8 {
+            let temp = keys[2] as u16 | 3;
+            let res = byte ^ (temp.wrapping_mul(temp ^ 1) >> 8) as u8;
+            keys[0] = crc32(keys[0], byte);
+            keys[1] = (keys[1].wrapping_add(keys[0] & 0xff))
+                .wrapping_mul(0x0808_8405)
+                .wrapping_add(1);
+            keys[2] = crc32(keys[2], (keys[1] >> 24) as u8);
+            res
+        }
+        let read_u16 = |pos: usize| u16::from_le_bytes([archive[pos], archive[pos + 1]]) as usize;
+        let read_u32 = |pos: usize| u32::from_le_bytes(archive[pos..pos + 4].try_into().unwrap());
+
+        let mut keys = [0x1234_5678, 0x2345_6789, 0x3456_7890];
+        for &byte in password {
+            encrypt_byte(&mut keys, byte);
+        }
+        let crc = read_u32(14);
+        let data_start = 30 + read_u16(26) + read_u16(28);
+        let data_end = data_start + read_u32(18) as usize;
+        let mut header = [0; 12];
+        header[11] = (crc >> 24) as u8;
+
+        let mut res = archive[..data_start].to_vec();
+        res.extend(
+            header
+                .iter()
+                .chain(&archive[data_start..data_end])
+                .map(|&byte| encrypt_byte(&mut keys, byte)),
+        );
+        res.extend_from_slice(&archive[data_end..]);
+        // set the encryption flags, grow the compressed sizes and move the central directory
+        for (flags, size, shift) in [(6, 18, 0), (data_end + 8, data_end + 20, 12)] {
+            res[flags + shift] |= 1;
+            res[size + shift..size + shift + 4].copy_from_slice(&(read_u32(size) + 12).to_le_bytes());
+        }
+        let end = res.len() - 22;
+        res[end + 16..end + 20].copy_from_slice(&(data_end as u32 + 12).to_le_bytes());
+        res
+    }
+
+    #[test]
+    fn read_encrypted_archives() {
+        let class = TestClass::new("a");
+        let bytes = encrypt(
+            &TestJar::default().class(class.clone()).to_bytes().unwrap(),
+            b"secret",
+        );
+
+        let mut jar = Jar::with_password(Cursor::new(&bytes), "secret").unwrap();
+        assert_eq!(jar.classes().next().unwrap().unwrap().bytes(), class.to_bytes());
+
+        let mut jar = Jar::new(Cursor::new(&bytes)).unwrap();
+        let err = jar.classes().next().unwrap().unwrap_err();
+        assert!(
+            matches!(err, Error::EntryError { source, .. } if matches!(*source, Error::PasswordRequired))
+        );
+
+        let mut jar = Jar::with_password(Cursor::new(&bytes), "wrong").unwrap();
+        let err = jar.classes().next().unwrap().unwrap_err();
+        assert!(
+            matches!(err, Error::EntryError { source, .. } if matches!(*source, Error::InvalidPassword))
+        );
+    }
+
+    #[test]
+    fn parse_classes_in_place() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a"))
+            .entry("b.txt", b"b".as_slice())
+            .class(TestClass::new("c/d").extends("a"))
+            .open()
+            .unwrap();
+        let mut classes = vec![];
+        jar.parsed_classes(|name, class| {
+            classes.push((name.to_owned(), class.super_class.map(|name| name.into_owned())));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(classes, [
+            ("a.class".to_owned(), Some("java/lang/Object".to_owned())),
+            ("c/d.class".to_owned(), Some("a".to_owned()))
+        ]);
+    }
 }
@@ -0,0 +1,399 @@
+//! Conversion of the output of `javap -p` into class patterns.
+//!
+//! This allows turning reference classes into patterns without writing them by hand:
+//! ```text
+//! $ javap -p -s -cp lib.jar com.example.Foo com.example.Bar > patterns.txt
+//! ```
+//! Generic types are erased, with type variables matching any type.
+//! The exact descriptors printed by `javap -s` replace the erased types when present.
+//! Member names are left out of the patterns, except for constructors and static initializers,
+//! and the classes declared in the same input are referred to with [`TypePat::Ref`],
+//! so the patterns keep matching once the classes are obfuscated.
+
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use cafebabe::MethodAccessFlags;
+
+use crate::descriptor::{Descriptor, MethodDescriptor};
+use crate::dsl::{field_flag, method_flag, parse_type_name, PatternError};
+use crate::name::to_internal;
+use crate::pat::{ClassPat, FieldPat, MemberPat, MethodPat, TypePat, Visibility};
+
+/// Parses the classes printed by `javap -p` into named patterns, the names being the qualified names of the classes.
+///
+/// Lines that don't declare classes or members, such as the bytecode printed by `javap -c`, are ignored.
+///
+/// # Examples
+/// ```
+/// let src = "
+/// public class com.example.Foo implements java.lang.Runnable {
+///   private final java.util.List<java.lang.String> names;
+///   public com.example.Foo(java.lang.String...);
+///   public void run();
+/// }
+/// ";
+/// let pats = jars::parse_javap(src).unwrap();
+/// assert_eq!(pats[0].0, "com.example.Foo");
+/// ```
+pub fn parse_javap(src: &str) -> Result<Vec<(String, ClassPat)>, PatternError> {
+    let mut parser = Parser::default();
+    for (i, line) in src.lines().enumerate() {
+        parser
+            .parse_line(line.trim())
+            .map_err(|message| PatternError::new(i + 1, message))?;
+    }
+    Ok(parser.finish())
+}
+
+#[derive(Debug, Default)]
+struct Parser {
+    pats: Vec<(String, ClassPat)>,
+    in_class: bool,
+    type_vars: Vec<String>,
+}
+
+impl Parser {
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        if let Some(desc) = line.strip_prefix("descriptor:") {
+            return self.apply_descriptor(desc.trim());
+        }
+        if line == "}" {
+            self.in_class = false;
+        } else if let Some(header) = line.strip_suffix('{').filter(|_| !self.in_class) {
+            self.parse_class(header)?;
+        } else if let Some(member) = line.strip_suffix(';').filter(|_| self.in_class) {
+            // labels such as `Code:` and the instructions following them
+            if !member
+                .split_whitespace()
+                .next()
+                .is_some_and(|token| token.ends_with(':'))
+            {
+                let member = self.parse_member(member)?;
+                self.pats.last_mut().unwrap().1.members.push(member);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_class(&mut self, header: &str) -> Result<(), String> {
+        let mut pat = ClassPat::default();
+        let mut visible = false;
+        let mut rem = header.trim();
+        loop {
+            let (token, rest) = split_word(rem);
+            rem = rest;
+            visible |= is_visibility(token);
+            pat = match token {
+                "public" => pat.public(),
+                "final" => pat.final_(),
+                "abstract" => pat.abstract_(),
+                "sealed" | "non-sealed" | "static" | "strictfp" => pat,
+                "class" => break,
+                "interface" => {
+                    pat.flags |= ClassPat::interface().flags;
+                    break;
+                }
+                "" => return Err("expected a class declaration".to_owned()),
+                other => return Err(format!("unexpected `{other}`, expected a class modifier")),
+            }
+        }
+        if !visible {
+            pat = pat.package_private();
+        }
+
+        let rem = rem.trim_start();
+        let end = rem.find(['<', ' ']).unwrap_or(rem.len());
+        let name = rem[..end].to_owned();
+        let (type_vars, rem) = type_params(&rem[end..])?;
+        self.type_vars = type_vars;
+
+        let rem = erase_generics(rem);
+        let rem = rem.split(" permits ").next().unwrap_or_default();
+        let (rem, impls) = rem.split_once("implements").unwrap_or((rem, ""));
+        let supers = rem.trim().strip_prefix("extends").unwrap_or_default();
+        let is_interface = pat.flags.contains(ClassPat::interface().flags);
+        for (i, typ) in split_list(supers).enumerate() {
+            let typ = self.parse_type(typ)?;
+            pat = if is_interface || i > 0 {
+                pat.with_impl(typ)
+            } else {
+                pat.with_base(typ)
+            };
+        }
+        for typ in split_list(impls) {
+            pat = pat.with_impl(self.parse_type(typ)?);
+        }
+        self.pats.push((name, pat));
+        self.in_class = true;
+        Ok(())
+    }
+
+    fn parse_member(&self, member: &str) -> Result<MemberPat, String> {
+        if member == "static {}" {
+            let pat = MethodPat::default()
+                .name("<clinit>")
+                .flags(MethodAccessFlags::STATIC);
+            return Ok(pat.into());
+        }
+
+        let Some((head, rest)) = member.split_once('(') else {
+            return self.parse_field(member).map(Into::into);
+        };
+        let mut pat = MethodPat::default();
+        let mut visible = false;
+        let mut rem = head.trim();
+        loop {
+            let (token, rest) = split_word(rem);
+            if token == "default" {
+                pat = pat.default_impl();
+            } else if let Some(flag) = method_flag(token) {
+                visible |= is_visibility(token);
+                pat = pat.flags(flag);
+            } else {
+                break;
+            }
+            rem = rest;
+        }
+        if !visible {
+            pat = pat.visibility(Visibility::Package);
+        }
+
+        let (method_vars, rem) = type_params(rem)?;
+        let rem = erase_generics(rem);
+        let mut words: Vec<_> = rem.split_whitespace().collect();
+        // the names of methods are left out, see the module docs
+        words.pop().ok_or("expected a method name")?;
+        match words[..] {
+            // only constructors are declared without a return type
+            [] => pat = pat.name("<init>"),
+            [ret] => pat = pat.returns(self.parse_type_with(ret, &method_vars)?),
+            _ => return Err(format!("unexpected method declaration `{member}`")),
+        }
+
+        let params = rest.split_once(')').ok_or("expected `)`")?.0;
+        let params = erase_generics(params);
+        for param in split_list(&params) {
+            let typ = match param.strip_suffix("...") {
+                Some(elem) => {
+                    pat = pat.flags(MethodAccessFlags::VARARGS);
+                    format!("{elem}[]")
+                }
+                None => param.to_owned(),
+            };
+            pat = pat.param(self.parse_type_with(&typ, &method_vars)?);
+        }
+        Ok(pat.into())
+    }
+
+    fn parse_field(&self, field: &str) -> Result<FieldPat, String> {
+        // constant values are printed with `-constants`
+        let field = field.split_once(" = ").map_or(field, |(field, _)| field);
+        let field = erase_generics(field);
+        let mut pat = FieldPat::default();
+        let mut visible = false;
+        let mut words = field.split_whitespace().peekable();
+        while let Some(token) = words.next_if(|token| field_flag(token).is_some()) {
+            visible |= is_visibility(token);
+            pat = pat.flags(field_flag(token).unwrap());
+        }
+        if !visible {
+            pat = pat.visibility(Visibility::Package);
+        }
+        let typ = words.next().ok_or("expected a field type")?;
+        Ok(pat.of_type(self.parse_type(typ)?))
+    }
+
+    /// Replaces the types of the last member with the types of its descriptor.
+    fn apply_descriptor(&mut self, desc: &str) -> Result<(), String> {
+        let invalid = || format!("invalid descriptor `{desc}`");
+        let member = self
+            .pats
+            .last_mut()
+            .and_then(|(_, pat)| pat.members.last_mut())
+            .ok_or("descriptor declared before a member")?;
+        match member {
+            MemberPat::Method(method) => {
+                let desc = MethodDescriptor::parse(desc).map_err(|_| invalid())?;
+                let exact = |desc: Descriptor<'_>| TypePat::Exact(desc.to_string());
+                method.param_types = desc.param_types.into_iter().map(exact).collect();
+                method.ret_type = desc.return_type.map_or(TypePat::Void, exact);
+            }
+            MemberPat::Field(field) => {
+                Descriptor::parse(desc).map_err(|_| invalid())?;
+                field.field_type = TypePat::Exact(desc.to_owned());
+            }
+            MemberPat::Custom(_) => {}
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn parse_type(&self, typ: &str) -> Result<TypePat, String> {
+        self.parse_type_with(typ, &[])
+    }
+
+    fn parse_type_with(&self, typ: &str, method_vars: &[String]) -> Result<TypePat, String> {
+        let base = typ.trim_end_matches("[]");
+        if self.type_vars.iter().chain(method_vars).any(|var| var == base) {
+            return Ok(TypePat::Any);
+        }
+        // `@` and `$` prefixes have a special meaning in the text format
+        if base.starts_with(['@', '$']) || base.is_empty() {
+            return Err(format!("invalid type `{typ}`"));
+        }
+        parse_type_name(typ)
+    }
+
+    fn finish(mut self) -> Vec<(String, ClassPat)> {
+        refer_to_declared(&mut self.pats);
+        self.pats
+    }
+}
+
+/// Replaces the types of the classes named by patterns with references to the patterns, see [`TypePat::Ref`].
+pub(crate) fn refer_to_declared(pats: &mut [(String, ClassPat)]) {
+    let names: BTreeMap<_, _> = pats
+        .iter()
+        .map(|(name, _)| (to_internal(name), name.clone()))
+        .collect();
+    for (_, pat) in pats {
+        pat.for_each_type_mut(|typ| {
+            if let Some(name) = typ.class_name().and_then(|name| names.get(name)) {
+                *typ = TypePat::Ref(name.clone());
+            }
+        });
+    }
+}
+
+/// Members and classes without any of these modifiers are package-private.
+#[inline]
+fn is_visibility(modifier: &str) -> bool {
+    matches!(modifier, "public" | "private" | "protected")
+}
+
+fn split_word(str: &str) -> (&str, &str) {
+    let str = str.trim_start();
+    let end = str.find(char::is_whitespace).unwrap_or(str.len());
+    (&str[..end], &str[end..])
+}
+
+/// Splits a list of types separated by commas, the generics are expected to be erased.
+fn split_list(str: &str) -> impl Iterator<Item = &str> {
+    str.split(',').map(str::trim).filter(|typ| !typ.is_empty())
+}
+
+/// Parses the type parameters at the start of a declaration, e.g. `<K, V extends Comparable<V>>`,
+/// returning their names and the rest of the declaration.
+fn type_params(str: &str) -> Result<(Vec<String>, &str), String> {
+    let str = str.trim_start();
+    if !str.starts_with('<') {
+        return Ok((vec![], str));
+    }
+    let mut depth = 0;
+    let mut names = vec![];
+    let mut start = 1;
+    for (i, c) in str.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 1 => {}
+            _ => continue,
+        }
+        if c == ',' || depth == 0 {
+            let (name, _) = split_word(&str[start..i]);
+            names.push(name.to_owned());
+            start = i + 1;
+        }
+        if depth == 0 {
+            return Ok((names, &str[i + 1..]));
+        }
+    }
+    Err("unclosed type parameters".to_owned())
+}
+
+/// Removes the type arguments from a declaration, e.g. `java.util.Map<K, V>` becomes `java.util.Map`.
+fn erase_generics(str: &str) -> String {
+    let mut res = String::with_capacity(str.len());
+    let mut depth = 0usize;
+    for c in str.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            c if depth == 0 => res.push(c),
+            _ => {}
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{ClassAccessFlags, FieldAccessFlags};
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn parse_javap_output() {
+        let src = r#"
+Compiled from "Cache.java"
+public final class com.example.Cache<K, V extends java.lang.Comparable<V>> implements java.lang.Iterable<V> {
+  private final java.util.Map<K, com.example.Cache$Entry<V>> entries;
+  static final int SIZE = 16;
+  public com.example.Cache(java.lang.String...);
+  public <T> T get(K, java.util.function.Function<K, T>);
+  static {};
+}
+class com.example.Cache$Entry<V> {
+  com.example.Cache$Entry(com.example.Cache, V);
+    descriptor: (Lcom/example/Cache;Ljava/lang/Comparable;)V
+    Code:
+       0: aload_0
+       1: invokespecial #1                  // Method java/lang/Object."<init>":()V;
+}
+"#;
+        let pats = parse_javap(src).unwrap();
+        assert_eq!(pats.len(), 2);
+        assert_eq!(pats[0].0, "com.example.Cache");
+        assert_eq!(pats[1].0, "com.example.Cache$Entry");
+
+        let public = MethodAccessFlags::PUBLIC;
+        let cache = TestClass::new("a")
+            .flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL)
+            .implements("java/lang/Iterable")
+            .field(
+                FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL,
+                "b",
+                "Ljava/util/Map;",
+            )
+            .field(FieldAccessFlags::STATIC | FieldAccessFlags::FINAL, "c", "I")
+            .method(
+                public | MethodAccessFlags::VARARGS,
+                "<init>",
+                "([Ljava/lang/String;)V",
+            )
+            .method(
+                public,
+                "d",
+                "(Ljava/lang/Object;Ljava/util/function/Function;)Ljava/lang/Object;",
+            )
+            .method(MethodAccessFlags::STATIC, "<clinit>", "()V");
+        let entry = TestClass::new("e").flags(ClassAccessFlags::empty()).method(
+            MethodAccessFlags::empty(),
+            "<init>",
+            "(La;Ljava/lang/Comparable;)V",
+        );
+        let mut jar = TestJar::default().class(cache).class(entry).open().unwrap();
+        let [cache, entry] =
+            crate::search_exact(&mut jar, &[pats[0].1.clone(), pats[1].1.clone()]).unwrap();
+        assert_eq!(cache.name(), "a.class");
+        assert_eq!(entry.name(), "e.class");
+
+        let err = parse_javap("class A {\n  public void run(;\n}").unwrap_err();
+        assert_eq!(err, PatternError::new(2, "expected `)`"));
+    }
+}
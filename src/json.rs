@@ -0,0 +1,172 @@
+//! A minimal JSON reader for metadata files, which avoids depending on a serialization library.
+
+/// A parsed JSON value, objects keep the order of their keys.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a document, returning [`None`] if it's malformed.
+    pub(crate) fn parse(src: &str) -> Option<Self> {
+        let mut parser = Parser { src, pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        (parser.pos == src.len()).then_some(value)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(str) => Some(str),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of an array, or nothing for other values.
+    pub(crate) fn elements(&self) -> &[Json] {
+        match self {
+            Self::Array(elements) => elements,
+            _ => &[],
+        }
+    }
+
+    /// Returns the entries of an object, or nothing for other values.
+    pub(crate) fn entries(&self) -> &[(String, Json)] {
+        match self {
+            Self::Object(entries) => entries,
+            _ => &[],
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        let value = match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                let mut entries = vec![];
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        if !self.eat(b':') {
+                            return None;
+                        }
+                        entries.push((key, self.value()?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return None;
+                        }
+                    }
+                }
+                Json::Object(entries)
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut elements = vec![];
+                if !self.eat(b']') {
+                    loop {
+                        elements.push(self.value()?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return None;
+                        }
+                    }
+                }
+                Json::Array(elements)
+            }
+            b'"' => Json::String(self.string()?),
+            b't' => self.keyword("true", Json::Bool(true))?,
+            b'f' => self.keyword("false", Json::Bool(false))?,
+            b'n' => self.keyword("null", Json::Null)?,
+            _ => {
+                let len = self.src[self.pos..]
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(self.src.len() - self.pos);
+                let number = self.src[self.pos..self.pos + len].parse().ok()?;
+                self.pos += len;
+                Json::Number(number)
+            }
+        };
+        Some(value)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if !self.eat(b'"') {
+            return None;
+        }
+        let mut res = String::new();
+        let mut chars = self.src[self.pos..].char_indices();
+        loop {
+            let (i, char) = chars.next()?;
+            match char {
+                '"' => {
+                    self.pos += i + 1;
+                    return Some(res);
+                }
+                '\\' => match chars.next()?.1 {
+                    'n' => res.push('\n'),
+                    't' => res.push('\t'),
+                    'r' => res.push('\r'),
+                    'b' => res.push('\u{8}'),
+                    'f' => res.push('\u{c}'),
+                    'u' => {
+                        let code: String = (0..4).filter_map(|_| chars.next().map(|(_, c)| c)).collect();
+                        let code = u32::from_str_radix(&code, 16).ok()?;
+                        res.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    other => res.push(other),
+                },
+                _ => res.push(char),
+            }
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Option<Json> {
+        self.src[self.pos..].starts_with(keyword).then(|| {
+            self.pos += keyword.len();
+            value
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let matches = self.peek() == Some(byte);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Read, Seek};
+
+use crate::constant_pool::PoolConstant;
+use crate::jar::{Jar, JarEntry};
+use crate::json::Json;
+use crate::name::ClassName;
+use crate::result::Result;
+
+/// Directories that usually hold resource bundles.
+const BUNDLE_DIRS: [&str; 5] = ["lang", "i18n", "l10n", "locale", "messages"];
+
+impl<R: Read + Seek> Jar<R> {
+    /// Collects the keys of the resource bundles of the archive.
+    ///
+    /// Bundles are `.lang` files, `.json` files in a `lang` directory and `.properties` files
+    /// that either have a locale suffix like `messages_en_US.properties` or are in a directory
+    /// such as `lang` or `i18n`.
+    pub fn translation_keys(&mut self) -> Result<BTreeSet<String>> {
+        let mut keys = BTreeSet::new();
+        for entry in self.resources() {
+            let entry = entry?;
+            let text = String::from_utf8_lossy(entry.bytes());
+            match bundle_kind(entry.name()) {
+                Some(BundleKind::Properties) => keys.extend(properties_keys(&text).map(str::to_owned)),
+                Some(BundleKind::Json) => keys.extend(json_keys(&text)),
+                None => {}
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Maps the keys of the resource bundles of the archive to the names of the classes
+    /// that reference them as string literals, keys that aren't referenced are omitted.
+    ///
+    /// Translation keys are never obfuscated, which makes the classes using them good anchors for patterns.
+    pub fn translation_key_usages(&mut self) -> Result<BTreeMap<String, Vec<ClassName>>> {
+        let keys = self.translation_keys()?;
+        let mut usages: BTreeMap<String, Vec<ClassName>> = BTreeMap::new();
+        for entry in self.classes() {
+            let entry = entry?;
+            for str in string_literals(&entry)? {
+                if keys.contains(&str) {
+                    let classes = usages.entry(str).or_default();
+                    let class = &entry.header()?.this_class;
+                    if !classes.contains(class) {
+                        classes.push(class.clone());
+                    }
+                }
+            }
+        }
+        Ok(usages)
+    }
+
+    /// Returns the classes that reference a translation key as a string literal.
+    ///
+    /// The key doesn't have to be declared in a resource bundle of the archive.
+    pub fn classes_using_translation_key(&mut self, key: &str) -> Result<Vec<JarEntry>> {
+        let mut classes = vec![];
+        for entry in self.classes() {
+            let entry = entry?;
+            if string_literals(&entry)?.iter().any(|str| str == key) {
+                classes.push(entry);
+            }
+        }
+        Ok(classes)
+    }
+}
+
+fn string_literals(entry: &JarEntry) -> Result<Vec<String>> {
+    let strings = entry
+        .constant_pool()?
+        .entries()
+        .filter_map(|(_, constant)| match constant {
+            PoolConstant::String(str) => Some(str),
+            _ => None,
+        })
+        .collect();
+    Ok(strings)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BundleKind {
+    Properties,
+    Json,
+}
+
+fn bundle_kind(path: &str) -> Option<BundleKind> {
+    let (dirs, file) = path.rsplit_once('/').unwrap_or(("", path));
+    let (stem, ext) = file.rsplit_once('.')?;
+    let in_bundle_dir = dirs.split('/').any(|dir| BUNDLE_DIRS.contains(&dir));
+    match ext {
+        "lang" => Some(BundleKind::Properties),
+        "json" if dirs.split('/').any(|dir| dir == "lang") => Some(BundleKind::Json),
+        "properties" if in_bundle_dir || has_locale_suffix(stem) => Some(BundleKind::Properties),
+        _ => None,
+    }
+}
+
+/// Checks for a suffix like `_en` or `_en_US`.
+fn has_locale_suffix(stem: &str) -> bool {
+    let mut parts = stem.rsplit('_');
+    let last = parts.next().unwrap_or_default();
+    let is_lang = |str: &str| str.len() == 2 && str.bytes().all(|b| b.is_ascii_lowercase());
+    let is_country = |str: &str| str.len() == 2 && str.bytes().all(|b| b.is_ascii_uppercase());
+    match parts.next() {
+        Some(lang) if is_country(last) => is_lang(lang) && parts.next().is_some(),
+        Some(_) => is_lang(last),
+        None => false,
+    }
+}
+
+/// Returns the keys of a properties file, also used for the `key=value` lines of `.lang` files.
+fn properties_keys(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().filter_map(|line| {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with(['#', '!']) {
+            return None;
+        }
+        let end = line.find(['=', ':']).unwrap_or(line.len());
+        Some(line[..end].trim_end()).filter(|key| !key.is_empty())
+    })
+}
+
+/// Returns the keys of the top-level object of a JSON document.
+fn json_keys(text: &str) -> Vec<String> {
+    Json::parse(text)
+        .map(|json| json.entries().iter().map(|(key, _)| key.clone()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestJar;
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn map_translation_keys() {
+        let mut a = ClassBuilder::new("a");
+        a.pool().string("gui.title");
+        let mut b = ClassBuilder::new("b");
+        b.pool().string("item.sword");
+        b.pool().string("version");
+        let mut jar = TestJar::default()
+            .entry("a.class", a.to_bytes())
+            .entry("b.class", b.to_bytes())
+            .entry(
+                "assets/lang/en_us.json",
+                r#"{"gui.title": "Title", "item.sword": "Sword \"x\""}"#,
+            )
+            .entry("messages_en_US.properties", "# comment\nmenu.quit = Quit\n")
+            .entry("pom.properties", "version=1.0\n")
+            .open()
+            .unwrap();
+
+        let keys: Vec<_> = jar.translation_keys().unwrap().into_iter().collect();
+        assert_eq!(keys, vec!["gui.title", "item.sword", "menu.quit"]);
+
+        let usages = jar.translation_key_usages().unwrap();
+        assert_eq!(usages["gui.title"], vec!["a"]);
+        assert_eq!(usages["item.sword"], vec!["b"]);
+        assert_eq!(usages.len(), 2);
+
+        let classes = jar.classes_using_translation_key("item.sword").unwrap();
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].name(), "b.class");
+    }
+}
@@ -1,12 +1,199 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "archive")]
+mod bytecode;
+#[cfg(feature = "archive")]
+mod cache;
+#[cfg(feature = "archive")]
+mod cfg;
+#[cfg(feature = "archive")]
+mod classpath;
+#[cfg(feature = "archive")]
+mod clinit;
+#[cfg(feature = "analysis")]
+mod cluster;
+#[cfg(feature = "analysis")]
+mod compat;
+mod constant_pool;
+#[cfg(feature = "archive")]
+pub mod corpus;
+#[cfg(feature = "archive")]
+mod decompiler;
+#[cfg(feature = "archive")]
+mod decrypt;
 mod descriptor;
+#[cfg(feature = "archive")]
+pub mod detectors;
+#[cfg(feature = "dex")]
+pub mod dex;
+mod dsl;
+#[cfg(feature = "archive")]
+pub mod emu;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "archive")]
+pub mod filter;
+#[cfg(feature = "fingerprint")]
+pub mod fingerprint;
+#[cfg(feature = "grep")]
+mod grep;
+#[cfg(feature = "archive")]
+mod groups;
+#[cfg(feature = "analysis")]
+mod hierarchy;
+mod indy;
+#[cfg(feature = "archive")]
+mod info;
+#[cfg(feature = "archive")]
+mod inherit;
+#[cfg(feature = "archive")]
 mod jar;
+mod javap;
+#[cfg(feature = "archive")]
+mod json;
+#[cfg(feature = "analysis")]
+mod l10n;
+mod lint;
+mod matching;
+#[cfg(feature = "archive")]
+mod maven;
+mod model;
+#[cfg(feature = "archive")]
+mod mods;
+mod name;
+#[cfg(feature = "analysis")]
+mod obfuscation;
 mod pat;
+#[cfg(feature = "macros")]
+pub mod patterns;
+#[cfg(feature = "analysis")]
+mod pipeline;
+#[cfg(feature = "analysis")]
+mod propagate;
+#[cfg(feature = "archive")]
+mod provenance;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "analysis")]
+pub mod query;
+#[cfg(feature = "archive")]
+mod relocate;
+#[cfg(feature = "std")]
 mod result;
+#[cfg(feature = "archive")]
+mod salvage;
+#[cfg(feature = "archive")]
+mod sbom;
+#[cfg(feature = "archive")]
 mod search;
+#[cfg(all(feature = "serde", feature = "archive"))]
+mod ser;
+#[cfg(feature = "service")]
+pub mod service;
+#[cfg(feature = "archive")]
+mod services;
+#[cfg(feature = "archive")]
+mod session;
+#[cfg(feature = "archive")]
+mod shard;
+#[cfg(all(feature = "archive", any(unix, windows)))]
+mod shared;
+mod source;
+#[cfg(feature = "archive")]
+mod stats;
+#[cfg(feature = "analysis")]
+mod summary;
+#[cfg(all(feature = "archive", any(test, feature = "testing")))]
+pub mod testing;
+#[cfg(feature = "archive")]
+mod usages;
+#[cfg(feature = "archive")]
+mod verify;
+#[cfg(feature = "archive")]
+pub mod writer;
 
-pub use descriptor::{Descriptor, MethodDescriptor, Signature};
-pub use jar::{Jar, JarEntry};
-pub use pat::{java, Any, ClassPat, HasTypePat, MemberPat, TypePat};
-pub use result::{Error, Result};
-pub use search::{search_exact, search_many, Match};
+#[cfg(feature = "archive")]
+pub use bytecode::{BytecodeError, Disassembly, Instruction, Operand};
+#[cfg(feature = "archive")]
+pub use cfg::{BasicBlock, MethodCfg};
+#[cfg(feature = "archive")]
+pub use classpath::{IndexedJar, JarIndex};
+#[cfg(feature = "analysis")]
+pub use cluster::{ClusterEdge, Clustering};
+#[cfg(feature = "analysis")]
+pub use compat::{compare_api, ApiChange, ApiMember, Incompatibility};
+pub use constant_pool::{ConstantPool, ConstantPoolError, DynamicRef, MemberRef, PoolConstant};
+#[cfg(feature = "archive")]
+pub use decompiler::Decompiler;
+#[cfg(feature = "decompiler")]
+pub use decompiler::ProcessDecompiler;
+#[cfg(feature = "archive")]
+pub use decrypt::{EncryptedString, StringDecryptor, XorDecryptor};
+pub use descriptor::{Descriptor, MethodDescriptor, ParamTypes, Signature};
+pub use dsl::{parse_patterns, PatternError};
+#[cfg(feature = "grep")]
+pub use grep::{GrepLocation, GrepMatch};
+#[cfg(feature = "archive")]
+pub use groups::{GroupConstraint, Violation};
+#[cfg(feature = "analysis")]
+pub use hierarchy::{ClassSource, Hierarchy, Override};
+pub use indy::CallSite;
+#[cfg(feature = "archive")]
+pub use info::JarInfo;
+#[cfg(feature = "archive")]
+pub use jar::{ClassHeader, Jar, JarEntry, LimitError, Limits, UnsupportedFeature};
+pub use javap::parse_javap;
+pub use lint::{lint, Lint, LintKind};
+pub use matching::{Captures, MemberBinding};
+#[cfg(feature = "archive")]
+pub use maven::MavenArtifact;
+pub use model::{ClassModel, Constant, ConstantValue, Member, MemberView, RawAttribute};
+#[cfg(feature = "archive")]
+pub use mods::EntryPoint;
+pub use name::ClassName;
+#[cfg(feature = "analysis")]
+pub use obfuscation::{detect_obfuscator, ObfuscationSignals, Obfuscator, ObfuscatorReport};
+#[cfg(feature = "regex")]
+pub use pat::TypeRegex;
+pub use pat::{
+    java, Any, BasePat, CallSitePat, ClassPat, CustomMemberPat, FieldPat, HasTypePat, MatchStrategy, MemberPat, MemberPredicate, MethodPat, TypePat, Visibility
+};
+#[cfg(feature = "analysis")]
+pub use pipeline::{Pipeline, PipelineReport};
+#[cfg(feature = "analysis")]
+pub use propagate::{propagate, Identity, Propagation, PropagationConflict};
+#[cfg(feature = "archive")]
+pub use provenance::{Constraint, Provenance};
+#[cfg(feature = "std")]
+pub use result::{EntryPath, Error, Result};
+#[cfg(feature = "archive")]
+pub use salvage::Salvage;
+#[cfg(feature = "archive")]
+pub use sbom::{Component, Sbom};
+#[cfg(feature = "archive")]
+pub use search::{
+    search_exact, search_exact_dyn, search_many, validate_patterns, Conflict, MalformedPolicy, Match, MatchSet, SearchOptions, SearchReport, Searcher, ValidationReport
+};
+#[cfg(all(feature = "serde", feature = "archive"))]
+pub use ser::SCHEMA_VERSION;
+#[cfg(feature = "archive")]
+pub use services::Services;
+#[cfg(feature = "archive")]
+pub use session::{SavedMatch, SearchSession, SearchedArchive};
+#[cfg(feature = "archive")]
+pub use shard::Shard;
+#[cfg(all(feature = "archive", any(unix, windows)))]
+pub use shared::SharedFile;
+pub use source::parse_java;
+#[cfg(feature = "archive")]
+pub use stats::{CompressionStats, SearchStats};
+#[cfg(feature = "analysis")]
+pub use summary::ClassSummary;
+#[cfg(feature = "archive")]
+pub use usages::TypeUsage;
+#[cfg(feature = "archive")]
+pub use verify::{Mismatch, MismatchKind};
+#[cfg(feature = "macros")]
 pub use {cafebabe, paste};
@@ -0,0 +1,205 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+
+use crate::matching::NOT_DEFAULT_IMPL;
+use crate::pat::{ClassPat, FieldPat, MemberPat, MethodPat, TypePat, Visibilities};
+
+/// Analyzes a set of patterns without an archive and reports the ones that are likely
+/// to match too many classes, to break when the archive changes or to never match at all.
+pub fn lint(pats: &[ClassPat]) -> Vec<Lint> {
+    let mut lints = vec![];
+    for (i, pat) in pats.iter().enumerate() {
+        let mut report = |kind| lints.push(Lint { pattern: i, kind });
+
+        if let Some(other) = pats[..i].iter().position(|other| other == pat) {
+            report(LintKind::Duplicate(other));
+        }
+
+        let wildcard_members = pat.members.iter().all(is_wildcard);
+        let has_supertypes = pat.base.type_pat().is_some()
+            || !pat.impls.is_empty()
+            || !pat.services.is_empty()
+            || !pat.entry_points.is_empty();
+        let no_flags = pat.flags.is_empty() && pat.visibility == Visibilities::default();
+        if wildcard_members && !has_supertypes && no_flags {
+            report(LintKind::Unconstrained);
+        } else if wildcard_members && !has_supertypes && pat.members.is_empty() {
+            report(LintKind::OnlyFlags);
+        } else if wildcard_members && !pat.members.is_empty() {
+            report(LintKind::WildcardMembers);
+        }
+
+        if contradicts_class(pat.flags) {
+            report(LintKind::ContradictoryFlags(None));
+        }
+        for (j, member) in pat.members.iter().enumerate() {
+            let contradictory = match member {
+                MemberPat::Method(pat) => {
+                    contradicts_method(pat.flags)
+                        || pat.default_impl && pat.flags.intersects(NOT_DEFAULT_IMPL)
+                }
+                MemberPat::Field(pat) => contradicts_field(pat.flags),
+                MemberPat::Custom(_) => false,
+            };
+            if contradictory {
+                report(LintKind::ContradictoryFlags(Some(j)));
+            }
+            if member_name(member).is_some_and(|name| name != "<init>" && name != "<clinit>") {
+                report(LintKind::NamedMember(j));
+            }
+        }
+    }
+    lints
+}
+
+/// A problem found in a pattern by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// Index of the pattern.
+    pub pattern: usize,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pattern {}: {}", self.pattern, self.kind)
+    }
+}
+
+/// The kind of a [`Lint`], members are identified by their index in the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// The pattern is identical to the pattern at the provided index, one of them can never match.
+    Duplicate(usize),
+    /// The pattern only checks the number of members, so it matches any class of that shape.
+    Unconstrained,
+    /// The pattern only checks the flags of classes without members.
+    OnlyFlags,
+    /// All member patterns are wildcards, so only the number and kinds of members are checked.
+    WildcardMembers,
+    /// The flags required of the class, or of a member if provided, cannot occur together.
+    ContradictoryFlags(Option<usize>),
+    /// A member is matched by name, which obfuscators usually change between versions.
+    NamedMember(usize),
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate(other) => write!(f, "duplicate of pattern {other}"),
+            Self::Unconstrained => write!(f, "matches any class with the same number of members"),
+            Self::OnlyFlags => write!(f, "only constrains the class flags"),
+            Self::WildcardMembers => write!(f, "all member patterns are wildcards"),
+            Self::ContradictoryFlags(None) => write!(f, "contradictory class flags"),
+            Self::ContradictoryFlags(Some(member)) => write!(f, "contradictory flags of member {member}"),
+            Self::NamedMember(member) => write!(f, "member {member} is matched by name"),
+        }
+    }
+}
+
+fn is_wildcard(member: &MemberPat) -> bool {
+    match member {
+        MemberPat::Method(MethodPat {
+            flags,
+            visibility,
+            param_types,
+            ret_type,
+            name,
+            default_impl,
+            call_sites,
+            call_site_count,
+        }) => {
+            flags.is_empty()
+                && *visibility == Visibilities::default()
+                && name.is_none()
+                && !default_impl
+                && call_sites.is_empty()
+                && call_site_count.is_none()
+                && matches!(ret_type, TypePat::Any)
+                && param_types.iter().all(|typ| matches!(typ, TypePat::Any))
+        }
+        MemberPat::Field(FieldPat {
+            flags,
+            visibility,
+            field_type,
+            name,
+            value,
+        }) => {
+            flags.is_empty()
+                && *visibility == Visibilities::default()
+                && name.is_none()
+                && value.is_none()
+                && matches!(field_type, TypePat::Any)
+        }
+        MemberPat::Custom(_) => false,
+    }
+}
+
+fn member_name(member: &MemberPat) -> Option<&str> {
+    match member {
+        MemberPat::Method(pat) => pat.name.as_deref(),
+        MemberPat::Field(pat) => pat.name.as_deref(),
+        MemberPat::Custom(_) => None,
+    }
+}
+
+fn contradicts_class(flags: ClassAccessFlags) -> bool {
+    flags.contains(ClassAccessFlags::FINAL)
+        && flags.intersects(ClassAccessFlags::ABSTRACT | ClassAccessFlags::INTERFACE)
+}
+
+fn contradicts_method(flags: MethodAccessFlags) -> bool {
+    let visibility = MethodAccessFlags::PUBLIC | MethodAccessFlags::PRIVATE | MethodAccessFlags::PROTECTED;
+    let not_abstract = MethodAccessFlags::FINAL
+        | MethodAccessFlags::PRIVATE
+        | MethodAccessFlags::STATIC
+        | MethodAccessFlags::NATIVE
+        | MethodAccessFlags::SYNCHRONIZED
+        | MethodAccessFlags::STRICT;
+    (flags & visibility).bits().count_ones() > 1
+        || flags.contains(MethodAccessFlags::ABSTRACT) && flags.intersects(not_abstract)
+}
+
+fn contradicts_field(flags: FieldAccessFlags) -> bool {
+    let visibility = FieldAccessFlags::PUBLIC | FieldAccessFlags::PRIVATE | FieldAccessFlags::PROTECTED;
+    (flags & visibility).bits().count_ones() > 1
+        || flags.contains(FieldAccessFlags::FINAL | FieldAccessFlags::VOLATILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field, method, Any};
+
+    #[test]
+    fn report_lints() {
+        let pats = [
+            ClassPat::default().with(method!(public (String) -> ())),
+            ClassPat::default().with(method!(public (String) -> ())),
+            ClassPat::default()
+                .with(MethodPat::default().param(TypePat::Any).returns(TypePat::Any))
+                .with(field!(Any)),
+            ClassPat::default().public().final_(),
+            ClassPat::default()
+                .final_()
+                .abstract_()
+                .with(method!(public private () -> ())),
+            ClassPat::default().with(MethodPat::default().name("tick")),
+        ];
+        let kinds: Vec<_> = lint(&pats)
+            .into_iter()
+            .map(|lint| (lint.pattern, lint.kind))
+            .collect();
+        assert_eq!(kinds, vec![
+            (1, LintKind::Duplicate(0)),
+            (2, LintKind::Unconstrained),
+            (3, LintKind::OnlyFlags),
+            (4, LintKind::ContradictoryFlags(None)),
+            (4, LintKind::ContradictoryFlags(Some(0))),
+            (5, LintKind::NamedMember(0)),
+        ]);
+    }
+}
@@ -0,0 +1,148 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+use jars::service::JarService;
+use jars::{parse_patterns, ClassName, ClassPat, Jar, MalformedPolicy, MatchSet, SearchOptions, Searcher};
+
+/// Searches JAR archives for classes matching a set of patterns.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// File with the patterns to search for, written in the pattern text format
+    #[arg(short, long, required_unless_present = "serve")]
+    patterns: Option<PathBuf>,
+    /// Format of the output
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// File to write the output to instead of the standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Report classes that cannot be parsed as warnings instead of failing
+    #[arg(long)]
+    skip_malformed: bool,
+    /// Collect provenance of the patterns and report a confidence score for each match
+    #[arg(long)]
+    provenance: bool,
+    /// Serve line-delimited JSON queries on the standard input and output instead of searching
+    #[arg(long, conflicts_with_all = ["patterns", "format", "output"])]
+    serve: bool,
+    /// Check the patterns for likely mistakes instead of searching, fails if any are found
+    #[arg(long, requires = "patterns", conflicts_with_all = ["format", "output"])]
+    lint: bool,
+    /// Archives to search, or to open upfront when serving
+    #[arg(required_unless_present_any = ["serve", "lint"])]
+    jars: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    /// One line per match
+    Text,
+    /// A match set in the versioned JSON schema
+    Json,
+    /// A ProGuard mapping from pattern names to matched classes, only includes unique matches
+    Mapping,
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> jars::Result<ExitCode> {
+    let policy = if args.skip_malformed {
+        MalformedPolicy::CollectWarnings
+    } else {
+        MalformedPolicy::FailFast
+    };
+    let options = SearchOptions::default()
+        .malformed(policy)
+        .provenance(args.provenance);
+    let Some(patterns) = &args.patterns else {
+        serve(&args.jars, options)?;
+        return Ok(ExitCode::SUCCESS);
+    };
+    let pats = parse_patterns(&fs::read_to_string(patterns)?)?;
+    if args.lint {
+        return Ok(lint(&pats));
+    }
+    let searcher = Searcher::new(pats.iter().map(|(_, pat)| pat.clone())).with_options(options);
+
+    let mut set = MatchSet {
+        patterns: pats.into_iter().map(|(name, _)| name).collect(),
+        ..Default::default()
+    };
+    for path in &args.jars {
+        let mut jar = Jar::open(path)?;
+        for warning in set.add_report(searcher.search(&mut jar)?) {
+            eprintln!("warning: {warning}");
+        }
+    }
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    match args.format {
+        Format::Text => {
+            for mat in &set.matches {
+                let name = set.pattern_name(mat).unwrap_or_default();
+                let class = &mat.entry.header()?.this_class;
+                write!(out, "{}: {} ({})", name, class, mat.entry.path())?;
+                if let Some(prov) = set.provenance(mat) {
+                    write!(out, " [confidence {:.2}]", prov.confidence())?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &set).map_err(io::Error::from)?;
+            writeln!(out)?;
+        }
+        Format::Mapping => {
+            for (i, name) in set.patterns.iter().enumerate() {
+                let mut found = set.matches.iter().filter(|mat| mat.pattern == i);
+                match (found.next(), found.next()) {
+                    (Some(mat), None) => {
+                        let class = mat.entry.header()?.this_class.binary_name();
+                        writeln!(out, "{} -> {}:", ClassName::new(name).binary_name(), class)?;
+                    }
+                    (None, _) => eprintln!("warning: pattern {name} not found"),
+                    (Some(_), Some(_)) => eprintln!("warning: pattern {name} has more than one match"),
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn lint(pats: &[(String, ClassPat)]) -> ExitCode {
+    let (names, pats): (Vec<_>, Vec<_>) = pats.iter().cloned().unzip();
+    let lints = jars::lint(&pats);
+    for lint in &lints {
+        eprintln!("warning: {}: {}", names[lint.pattern], lint.kind);
+    }
+    if lints.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn serve(jars: &[PathBuf], options: SearchOptions) -> jars::Result<()> {
+    let mut service = JarService::default().with_options(options);
+    for path in jars {
+        service.open(path.display().to_string(), path)?;
+    }
+    service.serve(io::stdin().lock(), io::stdout().lock())?;
+    Ok(())
+}
@@ -0,0 +1,649 @@
+use alloc::collections::btree_map::Entry;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::BitAnd;
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+
+use crate::descriptor::{Descriptor, MethodDescriptor};
+use crate::indy::{lambda_bodies, CallSite};
+use crate::model::{ClassModel, MemberView};
+use crate::name::is_obfuscated_name;
+use crate::pat::{BasePat, CallSitePat, ClassPat, FieldPat, MatchStrategy, MemberPat, MethodPat, TypePat};
+
+/// Types bound to the named types of a pattern by a match, keyed by name.
+///
+/// Both [`TypePat::Capture`] and [`TypePat::Ref`] names are captured, the types are stored as descriptors.
+/// A pattern only matches when every occurrence of a name is bound to the same type.
+/// Captures can be substituted into other patterns with [`ClassPat::resolve_captures`].
+pub type Captures = BTreeMap<String, String>;
+
+/// Identifies the class member matched by a [`MemberPat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "kind", content = "index", rename_all = "lowercase")
+)]
+pub enum MemberBinding {
+    /// Index of the matched method in [`ClassFile::methods`](cafebabe::ClassFile::methods).
+    Method(usize),
+    /// Index of the matched field in [`ClassFile::fields`](cafebabe::ClassFile::fields).
+    Field(usize),
+}
+
+/// What the archive of a class declares about it in its metadata rather than in the class itself,
+/// checked by [`ClassPat::provides_service`] and [`ClassPat::entry_point`].
+pub(crate) trait Declared {
+    /// Returns whether the class is declared as a provider of a service in `META-INF/services`,
+    /// the service is identified by its internal name.
+    fn provides_service(&self, service: &str) -> bool;
+
+    /// Returns whether the class is declared as an entry point of the provided kind,
+    /// see [`Jar::entry_points`](crate::Jar::entry_points).
+    fn is_entry_point(&self, kind: &str) -> bool;
+}
+
+/// The declarations of a class matched on its own, without an archive.
+pub(crate) struct Undeclared;
+
+impl Declared for Undeclared {
+    #[inline]
+    fn provides_service(&self, _service: &str) -> bool {
+        false
+    }
+
+    #[inline]
+    fn is_entry_point(&self, _kind: &str) -> bool {
+        false
+    }
+}
+
+impl MemberBinding {
+    /// Returns the index of the matched method or field.
+    #[inline]
+    pub fn index(self) -> usize {
+        match self {
+            Self::Method(i) | Self::Field(i) => i,
+        }
+    }
+}
+pub(crate) fn check_class<C: ClassModel + ?Sized>(class: &C, pat: &ClassPat) -> Option<Vec<MemberBinding>> {
+    check_class_with(class, &Undeclared, pat, &[]).map(|(bindings, _)| bindings)
+}
+
+pub(crate) fn check_class_with<C: ClassModel + ?Sized>(
+    class: &C,
+    declared: &dyn Declared,
+    pat: &ClassPat,
+    descriptors: &[Option<String>],
+) -> Option<(Vec<MemberBinding>, Captures)> {
+    // ACC_SUPER is set by all modern compilers and carries no information
+    let class_flags = class.access_flags() - ClassAccessFlags::SUPER;
+    if !check_flags(class_flags, pat.flags, pat.exact_flags) || !pat.visibility.accepts(class_flags.bits())
+    {
+        return None;
+    }
+    if !check_base(class, pat) || !check_impls(class, pat) {
+        return None;
+    }
+    if !check_services(declared, pat) || !check_entry_points(declared, pat) {
+        return None;
+    }
+
+    let res = if pat.strategy != MatchStrategy::Positional || pat.separate_static {
+        bind_members(class, pat, descriptors)?
+    } else {
+        bind_positional(class, pat, descriptors)?
+    };
+    // predicates are arbitrary code, so they run last
+    check_predicates(class, pat).then_some(res)
+}
+
+/// Binds the member patterns to the members of a class in the order of the patterns.
+fn bind_positional<C: ClassModel + ?Sized>(
+    class: &C,
+    pat: &ClassPat,
+    descriptors: &[Option<String>],
+) -> Option<(Vec<MemberBinding>, Captures)> {
+    let mut methods = matched_methods(class, pat)
+        .into_iter()
+        .map(|i| (i, class.method(i)));
+    let mut fields = (0..class.field_count()).map(|i| (i, class.field(i)));
+    let mut bindings = Vec::with_capacity(pat.members.len());
+
+    for (j, member) in pat.members.iter().enumerate() {
+        let descriptor = descriptors.get(j).and_then(Option::as_deref);
+        match member {
+            MemberPat::Method(method_pat) => {
+                let (i, method) = methods.next()?;
+                check_method(method, method_pat, pat.exact_flags, descriptor)?;
+                if !check_call_sites(class, i, method_pat) {
+                    return None;
+                }
+                bindings.push(MemberBinding::Method(i));
+            }
+            MemberPat::Field(field_pat) => {
+                let (i, field) = fields.next()?;
+                check_field(field, field_pat, pat.exact_flags, descriptor)?;
+                if !check_field_value(class, i, field_pat) {
+                    return None;
+                }
+                bindings.push(MemberBinding::Field(i));
+            }
+            MemberPat::Custom(custom) if member.is_method() => {
+                let (i, method) = methods.next()?;
+                custom.check_method(method).then_some(())?;
+                bindings.push(MemberBinding::Method(i));
+            }
+            MemberPat::Custom(custom) => {
+                let (i, field) = fields.next()?;
+                custom.check_field(field).then_some(())?;
+                bindings.push(MemberBinding::Field(i));
+            }
+        }
+    }
+
+    if methods.len() > 0 || fields.len() > 0 {
+        return None;
+    }
+
+    let captures = captures(class, pat, &bindings)?;
+    Some((bindings, captures))
+}
+
+/// Binds the member patterns to the members of a class according to the [`MatchStrategy`] of the pattern,
+/// trying every candidate member of each pattern until the named types are consistent.
+fn bind_members<C: ClassModel + ?Sized>(
+    class: &C,
+    pat: &ClassPat,
+    descriptors: &[Option<String>],
+) -> Option<(Vec<MemberBinding>, Captures)> {
+    let methods = matched_methods(class, pat);
+    let method_pats = pat.members.iter().filter(|member| member.is_method()).count();
+    let field_pats = pat.members.len() - method_pats;
+    if pat.strategy.is_exhaustive() && (methods.len() != method_pats || class.field_count() != field_pats) {
+        return None;
+    }
+
+    // static members form their own sequences when they're separated
+    let statics: BTreeSet<MemberBinding> = if pat.separate_static {
+        let methods = methods
+            .iter()
+            .filter(|&&i| class.method(i).access_flags.contains(MethodAccessFlags::STATIC))
+            .map(|&i| MemberBinding::Method(i));
+        let fields = (0..class.field_count())
+            .filter(|&i| class.field(i).access_flags.contains(FieldAccessFlags::STATIC))
+            .map(MemberBinding::Field);
+        methods.chain(fields).collect()
+    } else {
+        BTreeSet::new()
+    };
+
+    let candidates: Vec<Vec<MemberBinding>> = pat
+        .members
+        .iter()
+        .enumerate()
+        .map(|(j, member)| {
+            let descriptor = descriptors.get(j).and_then(Option::as_deref);
+            let (candidates, is_static): (Vec<_>, _) = match member {
+                MemberPat::Method(method) => (
+                    methods
+                        .iter()
+                        .filter(|&&i| {
+                            check_method(class.method(i), method, pat.exact_flags, descriptor).is_some()
+                                && check_call_sites(class, i, method)
+                        })
+                        .map(|&i| MemberBinding::Method(i))
+                        .collect(),
+                    Some(method.flags.contains(MethodAccessFlags::STATIC)),
+                ),
+                MemberPat::Field(field) => (
+                    (0..class.field_count())
+                        .filter(|&i| {
+                            check_field(class.field(i), field, pat.exact_flags, descriptor).is_some()
+                                && check_field_value(class, i, field)
+                        })
+                        .map(MemberBinding::Field)
+                        .collect(),
+                    Some(field.flags.contains(FieldAccessFlags::STATIC)),
+                ),
+                // custom patterns don't declare whether they match static members
+                MemberPat::Custom(custom) if member.is_method() => (
+                    methods
+                        .iter()
+                        .filter(|&&i| custom.check_method(class.method(i)))
+                        .map(|&i| MemberBinding::Method(i))
+                        .collect(),
+                    None,
+                ),
+                MemberPat::Custom(custom) => (
+                    (0..class.field_count())
+                        .filter(|&i| custom.check_field(class.field(i)))
+                        .map(MemberBinding::Field)
+                        .collect(),
+                    None,
+                ),
+            };
+            if let (true, Some(is_static)) = (pat.separate_static, is_static) {
+                let candidates = candidates.into_iter();
+                candidates
+                    .filter(|binding| statics.contains(binding) == is_static)
+                    .collect()
+            } else {
+                candidates
+            }
+        })
+        .collect();
+    let ordered = pat.strategy.is_ordered();
+    if !can_bind(&candidates, ordered, &statics) {
+        return None;
+    }
+
+    let mut bindings = Vec::with_capacity(pat.members.len());
+    assign(class, pat, &candidates, &statics, &mut bindings)
+}
+
+fn assign<C: ClassModel + ?Sized>(
+    class: &C,
+    pat: &ClassPat,
+    candidates: &[Vec<MemberBinding>],
+    statics: &BTreeSet<MemberBinding>,
+    bindings: &mut Vec<MemberBinding>,
+) -> Option<(Vec<MemberBinding>, Captures)> {
+    let Some(options) = candidates.get(bindings.len()) else {
+        let captures = captures(class, pat, bindings)?;
+        return Some((bindings.clone(), captures));
+    };
+    for &binding in options {
+        if !is_free(bindings, binding, pat.strategy.is_ordered(), statics) {
+            continue;
+        }
+        bindings.push(binding);
+        if let Some(res) = assign(class, pat, candidates, statics, bindings) {
+            return Some(res);
+        }
+        bindings.pop();
+    }
+    None
+}
+
+/// Checks whether a member can be bound after the bindings made so far,
+/// ordered bindings must follow the last bound member of the same sequence.
+///
+/// Methods and fields form separate sequences, which are further split by the `statics` members.
+fn is_free(
+    bindings: &[MemberBinding],
+    binding: MemberBinding,
+    ordered: bool,
+    statics: &BTreeSet<MemberBinding>,
+) -> bool {
+    let mut same_kind = bindings.iter().filter(|other| {
+        mem::discriminant(*other) == mem::discriminant(&binding)
+            && statics.contains(other) == statics.contains(&binding)
+    });
+    if ordered {
+        match same_kind.next_back() {
+            Some(last) => last.index() < binding.index(),
+            None => true,
+        }
+    } else {
+        !same_kind.any(|other| *other == binding)
+    }
+}
+
+/// Checks whether every member pattern can be bound to a distinct member, ignoring the named types.
+///
+/// This rejects most classes before [`assign`] searches through the combinations of candidates.
+fn can_bind(candidates: &[Vec<MemberBinding>], ordered: bool, statics: &BTreeSet<MemberBinding>) -> bool {
+    if ordered {
+        // binding each pattern to the first member following the previous one is optimal
+        let mut bindings = vec![];
+        for options in candidates {
+            let Some(&binding) = options
+                .iter()
+                .find(|&&binding| is_free(&bindings, binding, true, statics))
+            else {
+                return false;
+            };
+            bindings.push(binding);
+        }
+        return true;
+    }
+
+    // augmenting paths of a bipartite matching between the patterns and the members
+    fn augment(
+        candidates: &[Vec<MemberBinding>],
+        pattern: usize,
+        owners: &mut BTreeMap<MemberBinding, usize>,
+        visited: &mut BTreeSet<MemberBinding>,
+    ) -> bool {
+        for &binding in &candidates[pattern] {
+            if !visited.insert(binding) {
+                continue;
+            }
+            let free = match owners.get(&binding) {
+                Some(&owner) => augment(candidates, owner, owners, visited),
+                None => true,
+            };
+            if free {
+                owners.insert(binding, pattern);
+                return true;
+            }
+        }
+        false
+    }
+    let mut owners = BTreeMap::new();
+    (0..candidates.len()).all(|pattern| augment(candidates, pattern, &mut owners, &mut BTreeSet::new()))
+}
+
+/// Returns the types bound to the named types of a pattern, or [`None`] if a name is bound to different types.
+fn captures<C: ClassModel + ?Sized>(
+    class: &C,
+    pat: &ClassPat,
+    bindings: &[MemberBinding],
+) -> Option<Captures> {
+    let mut captures = Captures::new();
+    let mut consistent = true;
+    for_each_bound_type(class, pat, bindings, |pat, typ| {
+        let (TypePat::Capture(name) | TypePat::Ref(name)) = pat else {
+            return;
+        };
+        let typ = typ.to_string();
+        match captures.entry(name.clone()) {
+            Entry::Occupied(existing) => consistent &= *existing.get() == typ,
+            Entry::Vacant(entry) => {
+                entry.insert(typ);
+            }
+        }
+    });
+    consistent.then_some(captures)
+}
+
+/// Calls `f` with the type patterns of a matched pattern and the types of the class they're bound to.
+pub(crate) fn for_each_bound_type<C: ClassModel + ?Sized>(
+    class: &C,
+    pat: &ClassPat,
+    bindings: &[MemberBinding],
+    mut f: impl FnMut(&TypePat, Descriptor<'_>),
+) {
+    if let (Some(base), Some(super_class)) = (pat.base.type_pat(), class.super_class()) {
+        f(base, Descriptor::Object(super_class));
+    }
+    for (i, interface) in pat.impls.iter().enumerate() {
+        f(interface, Descriptor::Object(class.interface(i)));
+    }
+    for (member, binding) in pat.members.iter().zip(bindings) {
+        match (member, binding) {
+            (MemberPat::Method(method), MemberBinding::Method(i)) => {
+                let Ok(mut params) = MethodDescriptor::param_types(class.method(*i).descriptor) else {
+                    continue;
+                };
+                for param in &method.param_types {
+                    if let Some(Ok(typ)) = params.next() {
+                        f(param, typ);
+                    }
+                }
+                if let Ok(Some(typ)) = params.return_type() {
+                    f(&method.ret_type, typ);
+                }
+            }
+            (MemberPat::Field(field), MemberBinding::Field(i)) => {
+                if let Ok(typ) = Descriptor::parse(class.field(*i).descriptor) {
+                    f(&field.field_type, typ);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn check_services(declared: &dyn Declared, pat: &ClassPat) -> bool {
+    pat.services
+        .iter()
+        .all(|service| declared.provides_service(service))
+}
+
+pub(crate) fn check_entry_points(declared: &dyn Declared, pat: &ClassPat) -> bool {
+    pat.entry_points.iter().all(|kind| declared.is_entry_point(kind))
+}
+
+pub(crate) fn check_predicates<C: ClassModel + ?Sized>(class: &C, pat: &ClassPat) -> bool {
+    pat.predicates.iter().all(|predicate| predicate.check(class))
+}
+
+pub(crate) fn check_base<C: ClassModel + ?Sized>(class: &C, pat: &ClassPat) -> bool {
+    let check_super = |pat: &TypePat, base: &str| {
+        matches!(pat, TypePat::Any | TypePat::Capture(_) | TypePat::Ref(_))
+            || pat.class_name() == Some(base)
+            || check_class_name(pat, base)
+    };
+    match (&pat.base, class.super_class()) {
+        (BasePat::Any, _) => true,
+        (BasePat::None, base) => base.is_none(),
+        (BasePat::Object, base) => base == Some(OBJECT),
+        (BasePat::Is(pat), Some(base)) => check_super(pat, base),
+        (BasePat::Extends(pat), Some(base)) => base != OBJECT && check_super(pat, base),
+        (BasePat::Is(_) | BasePat::Extends(_), None) => false,
+    }
+}
+
+const OBJECT: &str = "java/lang/Object";
+
+pub(crate) fn check_impls<C: ClassModel + ?Sized>(class: &C, pat: &ClassPat) -> bool {
+    pat.impls.len() <= class.interface_count()
+        && pat.impls.iter().enumerate().all(|(i, pat)| {
+            matches!(pat, TypePat::Capture(_) | TypePat::Ref(_))
+                || pat.class_name() == Some(class.interface(i))
+                || check_class_name(pat, class.interface(i))
+        })
+}
+
+/// Checks a class name against the patterns matching classes without naming them.
+fn check_class_name(pat: &TypePat, class: &str) -> bool {
+    match pat {
+        TypePat::Obfuscated => is_obfuscated_name(class),
+        #[cfg(feature = "regex")]
+        TypePat::Regex(regex) => regex.is_match(&alloc::format!("L{class};")),
+        _ => false,
+    }
+}
+
+/// Flags of methods that don't provide an inheritable implementation.
+pub(crate) const NOT_DEFAULT_IMPL: MethodAccessFlags = MethodAccessFlags::ABSTRACT
+    .union(MethodAccessFlags::STATIC)
+    .union(MethodAccessFlags::PRIVATE);
+
+pub(crate) fn check_field_value<C: ClassModel + ?Sized>(class: &C, index: usize, pat: &FieldPat) -> bool {
+    let Some(expected) = &pat.value else {
+        return true;
+    };
+    class.field_value(index) == Some(expected.as_constant())
+}
+
+/// Returns the indices of the methods that are matched positionally by the member patterns.
+pub(crate) fn matched_methods<C: ClassModel + ?Sized>(class: &C, pat: &ClassPat) -> Vec<usize> {
+    if pat.skip_lambda_bodies {
+        let bodies = lambda_bodies(class);
+        (0..class.method_count())
+            .filter(|i| !bodies.contains(i))
+            .collect()
+    } else {
+        (0..class.method_count()).collect()
+    }
+}
+
+pub(crate) fn check_call_sites<C: ClassModel + ?Sized>(class: &C, index: usize, pat: &MethodPat) -> bool {
+    if pat.call_sites.is_empty() && pat.call_site_count.is_none() {
+        return true;
+    }
+    let Some(sites) = class.call_sites(index) else {
+        return false;
+    };
+    if pat.call_site_count.is_some_and(|count| sites.len() != count) {
+        return false;
+    }
+    pat.call_sites
+        .iter()
+        .all(|pat| sites.iter().any(|site| check_call_site(class, site, pat)))
+}
+
+fn check_call_site<C: ClassModel + ?Sized>(class: &C, site: &CallSite, pat: &CallSitePat) -> bool {
+    if pat
+        .bootstrap
+        .as_ref()
+        .is_some_and(|class| *class != site.bootstrap.class)
+    {
+        return false;
+    }
+    let ret_matches = match (&pat.ret_type, site.return_type()) {
+        (None, _) => true,
+        (Some(TypePat::Void), None) => true,
+        (Some(pat), Some(typ)) => check_type(typ, pat).is_some(),
+        (Some(_), None) => false,
+    };
+    if !ret_matches {
+        return false;
+    }
+    let Some(target_pat) = &pat.target else {
+        return true;
+    };
+    let Some(target) = site
+        .lambda_target()
+        .filter(|target| target.class == class.this_class())
+    else {
+        return false;
+    };
+    (0..class.method_count()).any(|i| {
+        let method = class.method(i);
+        method.name == target.name
+            && method.descriptor == target.descriptor
+            && check_method(method, target_pat, false, None).is_some()
+            && check_call_sites(class, i, target_pat)
+    })
+}
+
+pub(crate) fn check_method(
+    method: MemberView<'_, MethodAccessFlags>,
+    pat: &MethodPat,
+    exact_flags: bool,
+    descriptor: Option<&str>,
+) -> Option<()> {
+    if !check_flags(method.access_flags, pat.flags, exact_flags)
+        || !pat.visibility.accepts(method.access_flags.bits())
+    {
+        return None;
+    }
+    if pat.default_impl && method.access_flags.intersects(NOT_DEFAULT_IMPL) {
+        return None;
+    }
+    if pat.name.as_ref().is_some_and(|name| *name != method.name) {
+        return None;
+    }
+    if let Some(descriptor) = descriptor {
+        return (method.descriptor == descriptor).then_some(());
+    }
+
+    #[cfg(feature = "archive")]
+    if let Some(parsed) = crate::cache::method_descriptor(method.descriptor) {
+        let parsed = parsed?;
+        check_params(pat, parsed.param_types.iter().map(|typ| Some(typ.get())))?;
+        return check_return(pat, parsed.return_type.as_ref().map(|typ| typ.get()));
+    }
+
+    let mut params = MethodDescriptor::param_types(method.descriptor).ok()?;
+    check_params(pat, params.by_ref().map(Result::ok))?;
+    check_return(pat, params.return_type().ok()?)
+}
+
+fn check_params<'a>(
+    pat: &MethodPat,
+    mut params: impl Iterator<Item = Option<Descriptor<'a>>>,
+) -> Option<()> {
+    let varargs = pat.flags.contains(MethodAccessFlags::VARARGS);
+    for (i, typ) in pat.param_types.iter().enumerate() {
+        let param = params.next()??;
+        if varargs && i + 1 == pat.param_types.len() {
+            check_varargs(param, typ)?;
+        } else {
+            check_type(param, typ)?;
+        }
+    }
+    params.next().is_none().then_some(())
+}
+
+fn check_return(pat: &MethodPat, return_type: Option<Descriptor<'_>>) -> Option<()> {
+    match (&pat.ret_type, return_type) {
+        (TypePat::Void, None) => Some(()),
+        (tp, Some(ty)) => check_type(ty, tp),
+        _ => None,
+    }
+}
+
+pub(crate) fn check_field(
+    field: MemberView<'_, FieldAccessFlags>,
+    pat: &FieldPat,
+    exact_flags: bool,
+    descriptor: Option<&str>,
+) -> Option<()> {
+    if !check_flags(field.access_flags, pat.flags, exact_flags)
+        || !pat.visibility.accepts(field.access_flags.bits())
+    {
+        return None;
+    }
+    if pat.name.as_ref().is_some_and(|name| *name != field.name) {
+        return None;
+    }
+    if let Some(descriptor) = descriptor {
+        return (field.descriptor == descriptor).then_some(());
+    }
+    #[cfg(feature = "archive")]
+    if let Some(parsed) = crate::cache::field_descriptor(field.descriptor) {
+        return check_type(parsed?.get(), &pat.field_type);
+    }
+    let descriptor = Descriptor::parse(field.descriptor).ok()?;
+    check_type(descriptor, &pat.field_type)
+}
+
+pub(crate) fn check_flags<F: Copy + PartialEq + BitAnd<Output = F>>(
+    actual: F,
+    expected: F,
+    exact: bool,
+) -> bool {
+    if exact {
+        actual == expected
+    } else {
+        actual & expected == expected
+    }
+}
+
+/// Checks the trailing array parameter of a varargs method,
+/// which can be matched either as an array or as the type of its elements.
+fn check_varargs(descriptor: Descriptor, pat: &TypePat) -> Option<()> {
+    let Descriptor::Array(elem) = &descriptor else {
+        return None;
+    };
+    check_type((**elem).clone(), pat).or_else(|| check_type(descriptor, pat))
+}
+
+fn check_type(descriptor: Descriptor, pat: &TypePat) -> Option<()> {
+    match pat {
+        TypePat::Any | TypePat::Capture(_) => Some(()),
+        TypePat::Match(expected) if descriptor == *expected => Some(()),
+        TypePat::Exact(expected)
+            if Descriptor::parse(expected).is_ok_and(|expected| descriptor == expected) =>
+        {
+            Some(())
+        }
+        TypePat::Ref(_) if matches!(descriptor, Descriptor::Object(_)) => Some(()),
+        TypePat::Obfuscated if matches!(descriptor, Descriptor::Object(name) if is_obfuscated_name(name)) => {
+            Some(())
+        }
+        #[cfg(feature = "regex")]
+        TypePat::Regex(regex) if regex.is_match(&descriptor.to_string()) => Some(()),
+        _ => None,
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+use std::io::{Read, Seek};
+
+use crate::filter::{ClassFilter, ClassInfo};
+use crate::jar::Jar;
+use crate::mods::{manifest_attributes, MANIFEST};
+use crate::name::{package_prefix, to_internal};
+use crate::result::Result;
+
+const MAVEN_DIR: &[u8] = b"META-INF/maven/";
+const POM_PROPERTIES: &[u8] = b"/pom.properties";
+
+/// A Maven artifact bundled in an archive, declared by a `pom.properties` file under `META-INF/maven`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MavenArtifact {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+    /// Path of the `pom.properties` file declaring the artifact.
+    pub source: String,
+}
+
+impl MavenArtifact {
+    /// Returns the package named after the group id, e.g. `org/slf4j/` for `org.slf4j`,
+    /// which is where artifacts usually keep their classes.
+    pub fn package(&self) -> String {
+        package_prefix(&self.group_id)
+    }
+
+    fn parse(source: &str, text: &str) -> Option<Self> {
+        let (mut group_id, mut artifact_id, mut version) = (None, None, None);
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with(['#', '!']) {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(['=', ':']) else {
+                continue;
+            };
+            let value = Some(value.trim().to_owned());
+            match key.trim() {
+                "groupId" => group_id = value,
+                "artifactId" => artifact_id = value,
+                "version" => version = value,
+                _ => {}
+            }
+        }
+        Some(Self {
+            group_id: group_id?,
+            artifact_id: artifact_id?,
+            version: version.unwrap_or_default(),
+            source: source.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for MavenArtifact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group_id, self.artifact_id, self.version)
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Reads the Maven artifacts declared in `META-INF/maven`, which identify the libraries
+    /// bundled in fat jars along with the artifact of the archive itself.
+    pub fn maven_artifacts(&mut self) -> Result<Vec<MavenArtifact>> {
+        Ok(self.read_maven_metadata()?.0)
+    }
+
+    fn read_maven_metadata(&mut self) -> Result<(Vec<MavenArtifact>, Option<String>)> {
+        let mut artifacts = vec![];
+        let mut main_class = None;
+        for entry in self.resources_where(is_maven_metadata) {
+            let entry = entry?;
+            let text = String::from_utf8_lossy(entry.bytes());
+            if entry.name() == MANIFEST {
+                main_class = manifest_attributes(&text, "Main-Class").pop();
+            } else {
+                artifacts.extend(MavenArtifact::parse(entry.name(), &text));
+            }
+        }
+        Ok((artifacts, main_class))
+    }
+}
+
+fn is_maven_metadata(name: &[u8]) -> bool {
+    name == MANIFEST.as_bytes() || name.starts_with(MAVEN_DIR) && name.ends_with(POM_PROPERTIES)
+}
+
+/// Rejects the classes in the packages of the libraries bundled in an archive.
+#[derive(Debug, Default)]
+pub(crate) struct Libraries {
+    packages: Vec<String>,
+}
+
+impl Libraries {
+    /// Collects the packages of the Maven artifacts of an archive.
+    ///
+    /// The artifact containing the `Main-Class` of the manifest is the archive itself, so it's not a library.
+    pub(crate) fn read<R: Read + Seek>(jar: &mut Jar<R>) -> Result<Self> {
+        let (artifacts, main_class) = jar.read_maven_metadata()?;
+        let main_class = main_class.map(|name| to_internal(&name));
+        let packages = artifacts
+            .iter()
+            .map(MavenArtifact::package)
+            .filter(|package| !main_class.as_ref().is_some_and(|main| main.starts_with(package)))
+            .collect();
+        Ok(Self { packages })
+    }
+}
+
+impl ClassFilter for Libraries {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        Some(
+            !self
+                .packages
+                .iter()
+                .any(|package| class.name.starts_with(package)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{TestClass, TestJar};
+    use crate::{ClassPat, SearchOptions, Searcher};
+
+    #[test]
+    fn skip_maven_libraries() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("com/example/Main"))
+            .class(TestClass::new("org/slf4j/Logger"))
+            .entry(
+                "META-INF/maven/org.slf4j/slf4j-api/pom.properties",
+                "#Generated by Maven\ngroupId=org.slf4j\nartifactId=slf4j-api\nversion=2.0.9\n",
+            )
+            .entry(
+                "META-INF/maven/com.example/app/pom.properties",
+                "groupId=com.example\nartifactId=app\nversion=1.0\n",
+            )
+            .entry(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\nMain-Class: com.example.Main\n",
+            )
+            .open()
+            .unwrap();
+
+        let artifacts = jar.maven_artifacts().unwrap();
+        let names: Vec<_> = artifacts.iter().map(ToString::to_string).collect();
+        assert_eq!(names, ["org.slf4j:slf4j-api:2.0.9", "com.example:app:1.0"]);
+
+        let matches = Searcher::new([ClassPat::default()])
+            .with_options(SearchOptions::default().skip_libraries(true))
+            .search_many(&mut jar)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry.name(), "com/example/Main.class");
+    }
+}
@@ -0,0 +1,348 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use cafebabe::attributes::{AttributeData, AttributeInfo};
+use cafebabe::constant_pool::LiteralConstant;
+use cafebabe::{ClassAccessFlags, ClassFile, FieldAccessFlags, MethodAccessFlags};
+
+use crate::indy::{self, CallSite};
+
+/// A view of a class consumed by the pattern matcher.
+///
+/// This decouples matching from a specific class file parser,
+/// a [`ClassFile`] parsed by `cafebabe` is the default implementation.
+pub trait ClassModel {
+    fn access_flags(&self) -> ClassAccessFlags;
+    fn this_class(&self) -> &str;
+    fn super_class(&self) -> Option<&str>;
+
+    fn interface_count(&self) -> usize;
+    fn interface(&self, index: usize) -> &str;
+
+    fn method_count(&self) -> usize;
+    fn method(&self, index: usize) -> MemberView<'_, MethodAccessFlags>;
+
+    fn field_count(&self) -> usize;
+    fn field(&self, index: usize) -> MemberView<'_, FieldAccessFlags>;
+
+    /// Returns the constant value the field at `index` is initialized with, if it has one.
+    fn field_constant(&self, index: usize) -> Option<Constant<'_>>;
+
+    /// Returns the initial value of the field at `index`, either its constant value
+    /// or a constant assigned to it in the static initializer.
+    ///
+    /// Only the constant value is available if the model doesn't provide bytecode.
+    fn field_value(&self, index: usize) -> Option<Constant<'_>> {
+        self.field_constant(index)
+    }
+
+    /// Returns the `invokedynamic` call sites of the method at `index`,
+    /// or [`None`] if the model doesn't provide bytecode.
+    ///
+    /// A [`ClassFile`] provides them if it was parsed with its bytecode, see
+    /// [`SearchOptions::parse_bytecode`](crate::SearchOptions::parse_bytecode).
+    fn call_sites(&self, _index: usize) -> Option<Vec<CallSite>> {
+        None
+    }
+
+    /// Calls `f` with the parsed class file the model is backed by, checked by the predicates of
+    /// [`ClassPat::with_predicate`](crate::ClassPat::with_predicate).
+    ///
+    /// `f` isn't called if the model isn't backed by a class file. It's a callback rather than
+    /// a getter since a [`ClassFile`] cannot be borrowed for less than the bytes it's parsed from.
+    fn with_class_file(&self, _f: &mut dyn FnMut(&ClassFile<'_>)) {}
+
+    /// Returns the attributes of the class the parser doesn't model, e.g. obfuscator watermarks or Scala signatures.
+    fn raw_attributes(&self) -> Vec<RawAttribute<'_>> {
+        Vec::new()
+    }
+
+    /// Returns the attributes of the method at `index` the parser doesn't model.
+    fn method_raw_attributes(&self, _index: usize) -> Vec<RawAttribute<'_>> {
+        Vec::new()
+    }
+
+    /// Returns the attributes of the field at `index` the parser doesn't model.
+    fn field_raw_attributes(&self, _index: usize) -> Vec<RawAttribute<'_>> {
+        Vec::new()
+    }
+}
+
+/// An attribute left unparsed, with its contents as stored in the class file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawAttribute<'a> {
+    pub name: &'a str,
+    pub bytes: &'a [u8],
+}
+
+/// A view of a field or a method of a class.
+#[derive(Debug, Clone, Copy)]
+pub struct MemberView<'a, F> {
+    pub access_flags: F,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+}
+
+/// A view of a method or a field of a class, checked by a [`MemberPredicate`](crate::MemberPredicate).
+#[derive(Debug, Clone, Copy)]
+pub enum Member<'a> {
+    Method(MemberView<'a, MethodAccessFlags>),
+    Field(MemberView<'a, FieldAccessFlags>),
+}
+
+impl<'a> Member<'a> {
+    #[inline]
+    pub fn name(&self) -> &'a str {
+        match self {
+            Self::Method(method) => method.name,
+            Self::Field(field) => field.name,
+        }
+    }
+
+    #[inline]
+    pub fn descriptor(&self) -> &'a str {
+        match self {
+            Self::Method(method) => method.descriptor,
+            Self::Field(field) => field.descriptor,
+        }
+    }
+
+    /// Returns the raw access flags of the member.
+    #[inline]
+    pub fn access_flags(&self) -> u16 {
+        match self {
+            Self::Method(method) => method.access_flags.bits(),
+            Self::Field(field) => field.access_flags.bits(),
+        }
+    }
+}
+
+/// A constant value of a field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constant<'a> {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(&'a str),
+}
+
+impl Constant<'_> {
+    /// Converts the constant into a [`ConstantValue`] that owns its string.
+    pub fn into_owned(self) -> ConstantValue {
+        match self {
+            Self::Integer(i) => ConstantValue::Integer(i),
+            Self::Float(f) => ConstantValue::Float(f),
+            Self::Long(l) => ConstantValue::Long(l),
+            Self::Double(d) => ConstantValue::Double(d),
+            Self::String(str) => ConstantValue::String(str.to_owned()),
+        }
+    }
+}
+
+/// An owned version of [`Constant`], used to match fields on their values and to write them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(String),
+}
+
+impl ConstantValue {
+    #[inline]
+    pub fn as_constant(&self) -> Constant<'_> {
+        match self {
+            Self::Integer(i) => Constant::Integer(*i),
+            Self::Float(f) => Constant::Float(*f),
+            Self::Long(l) => Constant::Long(*l),
+            Self::Double(d) => Constant::Double(*d),
+            Self::String(str) => Constant::String(str),
+        }
+    }
+}
+
+impl From<i32> for ConstantValue {
+    #[inline]
+    fn from(value: i32) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<f32> for ConstantValue {
+    #[inline]
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<i64> for ConstantValue {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Self::Long(value)
+    }
+}
+
+impl From<f64> for ConstantValue {
+    #[inline]
+    fn from(value: f64) -> Self {
+        Self::Double(value)
+    }
+}
+
+impl From<&str> for ConstantValue {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<String> for ConstantValue {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl ClassModel for ClassFile<'_> {
+    #[inline]
+    fn access_flags(&self) -> ClassAccessFlags {
+        self.access_flags
+    }
+
+    #[inline]
+    fn this_class(&self) -> &str {
+        &self.this_class
+    }
+
+    #[inline]
+    fn super_class(&self) -> Option<&str> {
+        self.super_class.as_deref()
+    }
+
+    #[inline]
+    fn interface_count(&self) -> usize {
+        self.interfaces.len()
+    }
+
+    #[inline]
+    fn interface(&self, index: usize) -> &str {
+        &self.interfaces[index]
+    }
+
+    #[inline]
+    fn method_count(&self) -> usize {
+        self.methods.len()
+    }
+
+    #[inline]
+    fn method(&self, index: usize) -> MemberView<'_, MethodAccessFlags> {
+        let method = &self.methods[index];
+        MemberView {
+            access_flags: method.access_flags,
+            name: &method.name,
+            descriptor: &method.descriptor,
+        }
+    }
+
+    #[inline]
+    fn field_count(&self) -> usize {
+        self.fields.len()
+    }
+
+    #[inline]
+    fn field(&self, index: usize) -> MemberView<'_, FieldAccessFlags> {
+        let field = &self.fields[index];
+        MemberView {
+            access_flags: field.access_flags,
+            name: &field.name,
+            descriptor: &field.descriptor,
+        }
+    }
+
+    fn field_constant(&self, index: usize) -> Option<Constant<'_>> {
+        self.fields[index]
+            .attributes
+            .iter()
+            .find_map(|attr| match &attr.data {
+                AttributeData::ConstantValue(value) => Some(value),
+                _ => None,
+            })
+            .and_then(|value| match value {
+                LiteralConstant::Integer(i) => Some(Constant::Integer(*i)),
+                LiteralConstant::Float(f) => Some(Constant::Float(*f)),
+                LiteralConstant::Long(l) => Some(Constant::Long(*l)),
+                LiteralConstant::Double(d) => Some(Constant::Double(*d)),
+                LiteralConstant::String(str) => Some(Constant::String(str)),
+                // strings that aren't valid modified UTF-8
+                LiteralConstant::StringBytes(_) => None,
+            })
+    }
+
+    #[inline]
+    fn call_sites(&self, index: usize) -> Option<Vec<CallSite>> {
+        indy::call_sites(self, index)
+    }
+
+    #[inline]
+    fn with_class_file(&self, f: &mut dyn FnMut(&ClassFile<'_>)) {
+        f(self);
+    }
+
+    #[inline]
+    fn raw_attributes(&self) -> Vec<RawAttribute<'_>> {
+        unparsed(&self.attributes)
+    }
+
+    #[inline]
+    fn method_raw_attributes(&self, index: usize) -> Vec<RawAttribute<'_>> {
+        unparsed(&self.methods[index].attributes)
+    }
+
+    #[inline]
+    fn field_raw_attributes(&self, index: usize) -> Vec<RawAttribute<'_>> {
+        unparsed(&self.fields[index].attributes)
+    }
+}
+
+fn unparsed<'a>(attributes: &'a [AttributeInfo<'_>]) -> Vec<RawAttribute<'a>> {
+    attributes
+        .iter()
+        .filter_map(|attr| match attr.data {
+            AttributeData::Other(bytes) => Some(RawAttribute {
+                name: &attr.name,
+                bytes,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::parse_class;
+
+    use super::*;
+    use crate::writer::{ClassBuilder, FieldBuilder, MethodBuilder};
+
+    #[test]
+    fn expose_raw_attributes() {
+        let bytes = ClassBuilder::new("a")
+            .attribute("Watermark", [0xCA, 0xFE])
+            .with_field(FieldBuilder::new(FieldAccessFlags::PRIVATE, "x", "I").attribute("ScalaSig", [1]))
+            .with_method(MethodBuilder::new(MethodAccessFlags::PUBLIC, "y", "()V").signature("()V"))
+            .to_bytes();
+        let class = parse_class(&bytes).unwrap();
+        assert_eq!(class.raw_attributes(), [RawAttribute {
+            name: "Watermark",
+            bytes: &[0xCA, 0xFE]
+        }]);
+        assert_eq!(class.field_raw_attributes(0), [RawAttribute {
+            name: "ScalaSig",
+            bytes: &[1]
+        }]);
+        assert!(class.method_raw_attributes(0).is_empty());
+    }
+}
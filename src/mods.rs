@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use cafebabe::MethodAccessFlags;
+
+use crate::jar::Jar;
+use crate::json::Json;
+use crate::name::to_internal;
+use crate::result::Result;
+
+const FABRIC_METADATA: &str = "fabric.mod.json";
+const QUILT_METADATA: &str = "quilt.mod.json";
+pub(crate) const MANIFEST: &str = "META-INF/MANIFEST.MF";
+const MAIN_DESCRIPTOR: &str = "([Ljava/lang/String;)V";
+/// Classes of the platform whose subclasses are instantiated by a container, along with the kind of their entry points.
+const EXECUTED_BASES: [(&str, &str); 6] = [
+    ("java/applet/Applet", "applet"),
+    ("javax/swing/JApplet", "applet"),
+    ("javax/servlet/GenericServlet", "servlet"),
+    ("javax/servlet/http/HttpServlet", "servlet"),
+    ("jakarta/servlet/GenericServlet", "servlet"),
+    ("jakarta/servlet/http/HttpServlet", "servlet"),
+];
+
+/// A class declared by the metadata of a mod, which identifies it even in an obfuscated archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    /// Internal name of the class.
+    pub class: String,
+    /// The kind of the entry point, see [`Jar::entry_points`].
+    pub kind: String,
+    /// Path of the metadata file declaring the class.
+    pub source: String,
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Collects the classes where the execution of the archive can begin.
+    ///
+    /// Besides the classes declared by metadata, see below, these are classes with a
+    /// `public static void main(String[])` method of the `main_method` kind, subclasses of applets and servlets
+    /// of the `applet` and `servlet` kinds and service providers declared in `META-INF/services`
+    /// of the `service` kind. Declared entry points come first, followed by the others in the order of the archive.
+    ///
+    /// The kind of an entry point declared by metadata is the name of the entrypoint for those declared
+    /// in `fabric.mod.json` or `quilt.mod.json`, e.g. `main` or `client`, `mixin` for mixin classes and
+    /// `mixin_plugin` for mixin config plugins. Mixin configs are found through the mod metadata, `mods.toml`,
+    /// `neoforge.mods.toml` and the `MixinConfigs` attribute of the manifest. The `Main-Class` attribute
+    /// of the manifest declares a `main_class` and the `Premain-Class`, `Agent-Class` and `Launcher-Agent-Class`
+    /// attributes declare `agent` entry points.
+    pub fn entry_points(&mut self) -> Result<Vec<EntryPoint>> {
+        let mut entry_points = self.declared_entry_points()?;
+        let mut supers = HashMap::new();
+        let mut classes = vec![];
+        for entry in self.classes() {
+            let entry = entry?;
+            let class = entry.parse_without_bytecode()?;
+            let has_main = class.methods.iter().any(|method| {
+                method.name == "main"
+                    && method.descriptor == MAIN_DESCRIPTOR
+                    && method
+                        .access_flags
+                        .contains(MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC)
+            });
+            if has_main {
+                entry_points.push(EntryPoint {
+                    class: class.this_class.to_string(),
+                    kind: "main_method".to_owned(),
+                    source: entry.name().to_owned(),
+                });
+            }
+            let name = class.this_class.to_string();
+            supers.insert(name.clone(), class.super_class.as_deref().map(str::to_owned));
+            classes.push((name, entry.name().to_owned()));
+        }
+
+        for (class, source) in classes {
+            let mut seen = HashSet::new();
+            let mut current = supers.get(&class).cloned().flatten();
+            while let Some(base) = current.filter(|base| seen.insert(base.clone())) {
+                if let Some(&(_, kind)) = EXECUTED_BASES.iter().find(|(name, _)| *name == base) {
+                    entry_points.push(EntryPoint {
+                        class,
+                        kind: kind.to_owned(),
+                        source,
+                    });
+                    break;
+                }
+                current = supers.get(&base).cloned().flatten();
+            }
+        }
+
+        for (service, providers) in self.services()? {
+            let source = format!("META-INF/services/{}", service.replace('/', "."));
+            entry_points.extend(providers.into_iter().map(|class| EntryPoint {
+                class,
+                kind: "service".to_owned(),
+                source: source.clone(),
+            }));
+        }
+        Ok(entry_points)
+    }
+
+    /// Collects the entry points declared by the metadata of the archive, which can be required by patterns.
+    pub(crate) fn declared_entry_points(&mut self) -> Result<Vec<EntryPoint>> {
+        let mut files = vec![];
+        for entry in self.resources_where(is_metadata) {
+            let entry = entry?;
+            let text = String::from_utf8_lossy(entry.bytes()).into_owned();
+            files.push((entry.name().to_owned(), text));
+        }
+
+        let mut entry_points = vec![];
+        let mut mixin_configs = vec![];
+        for (name, text) in &files {
+            let mut declare = |class: &str, kind: &str| {
+                entry_points.push(EntryPoint {
+                    class: to_internal(class),
+                    kind: kind.to_owned(),
+                    source: name.clone(),
+                });
+            };
+            match name.as_str() {
+                FABRIC_METADATA | QUILT_METADATA => {
+                    let Some(json) = Json::parse(text) else {
+                        continue;
+                    };
+                    let (entrypoints, mixins) = if name == QUILT_METADATA {
+                        let loader = json.get("quilt_loader");
+                        (
+                            loader.and_then(|loader| loader.get("entrypoints")),
+                            json.get("mixin"),
+                        )
+                    } else {
+                        (json.get("entrypoints"), json.get("mixins"))
+                    };
+                    for (kind, classes) in entrypoints.map_or(&[][..], Json::entries) {
+                        for class in as_list(classes) {
+                            let class = class.get("value").unwrap_or(class);
+                            if let Some(class) = class.as_str() {
+                                // entrypoints can also refer to a static method or field
+                                declare(class.split_once("::").map_or(class, |(class, _)| class), kind);
+                            }
+                        }
+                    }
+                    for config in mixins.map(as_list).unwrap_or_default() {
+                        let config = config.get("config").unwrap_or(config);
+                        mixin_configs.extend(config.as_str().map(str::to_owned));
+                    }
+                }
+                MANIFEST => {
+                    for class in manifest_attributes(text, "Main-Class") {
+                        declare(&class, "main_class");
+                    }
+                    for attribute in ["Premain-Class", "Agent-Class", "Launcher-Agent-Class"] {
+                        for class in manifest_attributes(text, attribute) {
+                            declare(&class, "agent");
+                        }
+                    }
+                    for configs in manifest_attributes(text, "MixinConfigs") {
+                        let configs = configs
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|config| !config.is_empty());
+                        mixin_configs.extend(configs.map(str::to_owned));
+                    }
+                }
+                _ if name.ends_with("mods.toml") => mixin_configs.extend(toml_mixin_configs(text)),
+                _ => {}
+            }
+        }
+
+        for (name, text) in &files {
+            if !mixin_configs.contains(name) {
+                continue;
+            }
+            let Some(json) = Json::parse(text) else {
+                continue;
+            };
+            let package = json.get("package").and_then(Json::as_str).unwrap_or_default();
+            let mut declare = |class: String, kind: &str| {
+                entry_points.push(EntryPoint {
+                    class: to_internal(&class),
+                    kind: kind.to_owned(),
+                    source: name.clone(),
+                });
+            };
+            for key in ["mixins", "client", "server"] {
+                for mixin in json.get(key).map_or(&[][..], Json::elements) {
+                    if let Some(mixin) = mixin.as_str() {
+                        declare(format!("{package}.{mixin}"), "mixin");
+                    }
+                }
+            }
+            if let Some(plugin) = json.get("plugin").and_then(Json::as_str) {
+                declare(plugin.to_owned(), "mixin_plugin");
+            }
+        }
+        Ok(entry_points)
+    }
+
+    /// Reads the text of the manifest, if the archive has one.
+    pub(crate) fn manifest(&mut self) -> Result<Option<String>> {
+        let Some(entry) = self.resources_where(|name| name == MANIFEST.as_bytes()).next() else {
+            return Ok(None);
+        };
+        Ok(Some(String::from_utf8_lossy(entry?.bytes()).into_owned()))
+    }
+}
+
+fn is_metadata(name: &[u8]) -> bool {
+    name == MANIFEST.as_bytes()
+        || name.starts_with(b"META-INF/") && name.ends_with(b"mods.toml")
+        || name.ends_with(b".json") && !name.contains(&b'/')
+}
+
+/// Returns the elements of an array, or the value itself if it's not an array.
+fn as_list(json: &Json) -> Vec<&Json> {
+    match json {
+        Json::Array(elements) => elements.iter().collect(),
+        other => vec![other],
+    }
+}
+
+/// Reads the `config` keys of the `[[mixins]]` tables of a `mods.toml` file.
+fn toml_mixin_configs(text: &str) -> Vec<String> {
+    let mut configs = vec![];
+    let mut in_mixins = false;
+    for line in text.lines() {
+        let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+        if line.starts_with('[') {
+            in_mixins = line == "[[mixins]]";
+        } else if let Some((key, value)) = line.split_once('=') {
+            if in_mixins && key.trim() == "config" {
+                configs.push(value.trim().trim_matches(['"', '\'']).to_owned());
+            }
+        }
+    }
+    configs
+}
+
+/// Reads the values of an attribute of a manifest.
+pub(crate) fn manifest_attributes(text: &str, name: &str) -> Vec<String> {
+    // long lines are continued on the next line after a single space
+    let text = text.replace("\r\n", "\n").replace("\n ", "");
+    let prefix = format!("{name}:");
+    text.lines()
+        .filter_map(|line| Some(line.strip_prefix(&prefix)?.trim().to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::{ClassPat, Searcher};
+
+    #[test]
+    fn discover_entry_points() {
+        let fabric = r#"{
+            "id": "example",
+            "entrypoints": {
+                "main": ["com.example.Mod"],
+                "client": [{ "adapter": "kotlin", "value": "com.example.Client::init" }]
+            },
+            "mixins": ["example.mixins.json"]
+        }"#;
+        let mixins = r#"{ "package": "com.example.mixin", "mixins": ["A"], "client": ["b.B"], "plugin": "com.example.Plugin" }"#;
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a"))
+            .class(TestClass::new("com/example/Mod"))
+            .entry("fabric.mod.json", fabric)
+            .entry("example.mixins.json", mixins)
+            .entry(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\r\nMixinConfigs: example.mixins.json\r\n",
+            )
+            .open()
+            .unwrap();
+
+        let entry_points: Vec<_> = jar
+            .entry_points()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.class, entry.kind))
+            .collect();
+        let expected = [
+            ("com/example/Mod", "main"),
+            ("com/example/Client", "client"),
+            ("com/example/mixin/A", "mixin"),
+            ("com/example/mixin/b/B", "mixin"),
+            ("com/example/Plugin", "mixin_plugin"),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(class, kind)| (class.to_owned(), kind.to_owned()))
+            .collect();
+        assert_eq!(entry_points, expected);
+
+        let matches = Searcher::new([ClassPat::default().entry_point("main")])
+            .search_many(&mut jar)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry.name(), "com/example/Mod.class");
+
+        assert_eq!(
+            toml_mixin_configs("[[mixins]]\nconfig = \"a.mixins.json\"\n[[mods]]\nconfig=\"b\""),
+            vec!["a.mixins.json"]
+        );
+    }
+
+    #[test]
+    fn discover_execution_entry_points() {
+        let main = MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC;
+        let mut jar = TestJar::default()
+            .class(TestClass::new("app/Main").method(main, "main", "([Ljava/lang/String;)V"))
+            .class(TestClass::new("app/Launcher"))
+            .class(TestClass::new("app/Tool").method(main, "main", "()V"))
+            .class(TestClass::new("app/Base").extends("javax/servlet/http/HttpServlet"))
+            .class(TestClass::new("app/Servlet").extends("app/Base"))
+            .class(TestClass::new("app/Provider"))
+            .entry(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\nMain-Class: app.Launcher\n",
+            )
+            .entry("META-INF/services/java.sql.Driver", "app.Provider\n")
+            .open()
+            .unwrap();
+
+        let entry_points: Vec<_> = jar
+            .entry_points()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.class, entry.kind, entry.source))
+            .collect();
+        let expected = [
+            ("app/Launcher", "main_class", "META-INF/MANIFEST.MF"),
+            ("app/Main", "main_method", "app/Main.class"),
+            ("app/Base", "servlet", "app/Base.class"),
+            ("app/Servlet", "servlet", "app/Servlet.class"),
+            ("app/Provider", "service", "META-INF/services/java.sql.Driver"),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(class, kind, source)| (class.to_owned(), kind.to_owned(), source.to_owned()))
+            .collect();
+        assert_eq!(entry_points, expected);
+
+        let matches = Searcher::new([ClassPat::default().entry_point("main_class")])
+            .search_many(&mut jar)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry.name(), "app/Launcher.class");
+    }
+}
@@ -0,0 +1,334 @@
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// An internal name of a class, e.g. `com/example/Foo`.
+///
+/// Names are reference counted and shared by all of their clones, which makes them cheap to clone,
+/// a name is freed once its last clone is dropped.
+///
+/// # Examples
+/// ```
+/// use jars::ClassName;
+///
+/// let name = ClassName::new("com/example/Foo");
+/// assert_eq!(name, ClassName::from("com/example/Foo".to_owned()));
+/// assert_eq!(name.as_str(), "com/example/Foo");
+/// assert!(name.ends_with("Foo"));
+/// ```
+#[derive(Clone)]
+pub struct ClassName(Arc<str>);
+
+impl ClassName {
+    /// Creates a name from an internal name.
+    #[inline]
+    pub fn new(name: &str) -> Self {
+        Self(name.into())
+    }
+
+    /// Creates a name from a binary name, e.g. `com.example.Foo$Bar`.
+    #[inline]
+    pub fn from_binary(name: &str) -> Self {
+        Self::new(&to_internal(name))
+    }
+
+    /// Returns the name as a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the binary name of the class, e.g. `com.example.Foo$Bar`.
+    #[inline]
+    pub fn binary_name(&self) -> String {
+        self.0.replace('/', ".")
+    }
+
+    /// Returns the internal name of the package of the class, e.g. `com/example`,
+    /// or an empty string for the default package.
+    #[inline]
+    pub fn package(&self) -> &str {
+        self.0.rsplit_once('/').map_or("", |(package, _)| package)
+    }
+
+    /// Returns the name of the class without its package and enclosing classes, e.g. `Bar` for `com/example/Foo$Bar`.
+    #[inline]
+    pub fn simple_name(&self) -> &str {
+        let name = self.0.rsplit_once('/').map_or(&*self.0, |(_, name)| name);
+        split_nested(name).map_or(name, |(_, name)| name)
+    }
+
+    /// Returns the class enclosing a nested class, e.g. `com/example/Foo` for `com/example/Foo$Bar`.
+    ///
+    /// Nesting is inferred from the name, a `$` at the start or at the end of a name doesn't separate classes.
+    #[inline]
+    pub fn outer_class(&self) -> Option<Self> {
+        let start = self.0.rfind('/').map_or(0, |i| i + 1);
+        let (outer, _) = split_nested(&self.0[start..])?;
+        Some(Self::new(&self.0[..start + outer.len()]))
+    }
+
+    /// Returns the outermost class enclosing a nested class, or the class itself if it's not nested.
+    #[inline]
+    pub fn top_level(&self) -> Self {
+        let mut name = self.clone();
+        while let Some(outer) = name.outer_class() {
+            name = outer;
+        }
+        name
+    }
+
+    /// Returns whether the class is nested in another class, see [`ClassName::outer_class`].
+    #[inline]
+    pub fn is_nested(&self) -> bool {
+        let name = self.0.rsplit_once('/').map_or(&*self.0, |(_, name)| name);
+        split_nested(name).is_some()
+    }
+
+    /// Returns whether the name looks like it was given by an obfuscator.
+    ///
+    /// This is a heuristic: a name is considered obfuscated when the simple name of the class,
+    /// or of its enclosing class for anonymous classes, is short and lowercase like `a` or `bc`,
+    /// isn't a valid Java identifier or is made of characters that are hard to tell apart, like `IlI1lI` or `O0OO0`.
+    /// Classes of the platform packages, e.g. `java/` or `kotlin/`, are never obfuscated.
+    #[inline]
+    pub fn is_obfuscated(&self) -> bool {
+        is_obfuscated_name(&self.0)
+    }
+}
+
+/// Packages of the platform, which are never obfuscated.
+const PLATFORM_PACKAGES: [&str; 6] = ["java/", "javax/", "jdk/", "sun/", "com/sun/", "kotlin/"];
+
+/// Returns whether an internal name looks like it was given by an obfuscator, see [`ClassName::is_obfuscated`].
+pub(crate) fn is_obfuscated_name(name: &str) -> bool {
+    if PLATFORM_PACKAGES.iter().any(|package| name.starts_with(package)) {
+        return false;
+    }
+    let mut name = name.rsplit_once('/').map_or(name, |(_, name)| name);
+    // anonymous classes are named by numbers, their enclosing classes tell more
+    while let Some((outer, inner)) = split_nested(name) {
+        if !inner.bytes().all(|c| c.is_ascii_digit()) {
+            name = inner;
+            break;
+        }
+        name = outer;
+    }
+    let short = name.len() <= 3 && name.bytes().all(|c| c.is_ascii_lowercase());
+    short || is_confusing(name) || !is_identifier(name)
+}
+
+/// Returns whether a name is made of characters that are hard to tell apart, e.g. `IlI1lI`.
+pub(crate) fn is_confusing(name: &str) -> bool {
+    const DICTIONARIES: [&[u8]; 2] = [b"Il1", b"O0o"];
+    !name.is_ascii()
+        || name.len() >= 3
+            && DICTIONARIES
+                .iter()
+                .any(|dictionary| name.bytes().all(|c| dictionary.contains(&c)))
+}
+
+/// Returns whether a name is a valid Java identifier made of ASCII characters.
+fn is_identifier(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    bytes
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == b'_' || c == b'$')
+        && bytes.all(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'$')
+}
+
+/// Splits a name without a package at its last `$` separating an enclosing class from a nested one.
+fn split_nested(name: &str) -> Option<(&str, &str)> {
+    let (outer, inner) = name.rsplit_once('$')?;
+    if outer.is_empty() || inner.is_empty() {
+        return None;
+    }
+    Some((outer, inner))
+}
+
+/// Converts a binary name, e.g. `com.example.Foo`, to an internal name, leaving internal names unchanged.
+#[inline]
+pub(crate) fn to_internal(name: &str) -> String {
+    name.replace('.', "/")
+}
+
+/// Converts the binary or internal name of a package to a prefix of the internal names of its classes,
+/// e.g. `com/example/` for `com.example`.
+#[cfg(feature = "archive")]
+pub(crate) fn package_prefix(name: &str) -> String {
+    let mut prefix = to_internal(name);
+    if !prefix.ends_with('/') {
+        prefix.push('/');
+    }
+    prefix
+}
+
+impl PartialEq for ClassName {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for ClassName {}
+
+impl PartialEq<str> for ClassName {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        *self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for ClassName {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<String> for ClassName {
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialOrd for ClassName {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ClassName {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for ClassName {
+    // hashed like `str`, so that maps keyed by names can be queried with string slices
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Deref for ClassName {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ClassName {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for ClassName {
+    #[inline]
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for ClassName {
+    #[inline]
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for ClassName {
+    #[inline]
+    fn from(name: String) -> Self {
+        Self(name.into())
+    }
+}
+
+impl From<ClassName> for String {
+    #[inline]
+    fn from(name: ClassName) -> Self {
+        name.0.to_string()
+    }
+}
+
+impl fmt::Debug for ClassName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for ClassName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ClassName {
+    #[inline]
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_class_names() {
+        let name = ClassName::from_binary("com.example.Foo$Bar$1");
+        assert_eq!(name, "com/example/Foo$Bar$1");
+        assert_eq!(name.binary_name(), "com.example.Foo$Bar$1");
+        assert_eq!(name.package(), "com/example");
+        assert_eq!(name.simple_name(), "1");
+        assert_eq!(name.outer_class(), Some(ClassName::new("com/example/Foo$Bar")));
+        assert_eq!(name.top_level(), "com/example/Foo");
+        assert!(name.is_nested());
+
+        let name = ClassName::new("$Proxy$");
+        assert_eq!(name.package(), "");
+        assert_eq!(name.simple_name(), "$Proxy$");
+        assert_eq!(name.outer_class(), None);
+        assert_eq!(name.top_level(), name);
+
+        assert_eq!(package_prefix("com.example"), "com/example/");
+        assert_eq!(package_prefix("com/example/"), "com/example/");
+    }
+
+    #[test]
+    fn detect_obfuscated_names() {
+        for name in [
+            "a",
+            "com/example/ab",
+            "a$1",
+            "Foo$b",
+            "IlI1lI",
+            "O0OO0",
+            "com/example/do-it",
+        ] {
+            assert!(ClassName::new(name).is_obfuscated(), "{name}");
+        }
+        for name in [
+            "com/example/Foo",
+            "Foo$1",
+            "java/lang/a",
+            "kotlin/io/io",
+            "app/Main$Inner",
+        ] {
+            assert!(!ClassName::new(name).is_obfuscated(), "{name}");
+        }
+    }
+}
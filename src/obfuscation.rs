@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{Read, Seek};
+
+use cafebabe::attributes::AttributeData;
+use cafebabe::MethodAccessFlags;
+
+use crate::bytecode;
+use crate::info::{contains, OBFUSCATOR_MARKERS};
+use crate::jar::Jar;
+use crate::name::{is_confusing, ClassName};
+use crate::result::Result;
+
+/// Descriptors of the static methods obfuscators decrypt string literals with.
+const DECRYPTOR_DESCRIPTORS: [&str; 4] = [
+    "(Ljava/lang/String;)Ljava/lang/String;",
+    "(Ljava/lang/String;I)Ljava/lang/String;",
+    "(I)Ljava/lang/String;",
+    "(II)Ljava/lang/String;",
+];
+/// Opcodes of `goto`, `jsr`, `tableswitch`, `lookupswitch`, `goto_w` and `jsr_w`.
+const JUMPS: [u8; 6] = [0xa7, 0xa8, 0xaa, 0xab, 0xc8, 0xc9];
+
+/// An obfuscator recognized by [`detect_obfuscator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Obfuscator {
+    ProGuard,
+    Allatori,
+    ZelixKlassMaster,
+    /// An obfuscator that left signs of obfuscation, but no signature of a known one.
+    Unknown,
+}
+
+impl Obfuscator {
+    /// Returns the name of the obfuscator, e.g. `ProGuard`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ProGuard => "ProGuard",
+            Self::Allatori => "Allatori",
+            Self::ZelixKlassMaster => "Zelix KlassMaster",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        match name {
+            "ProGuard" => Self::ProGuard,
+            "Allatori" => Self::Allatori,
+            "Zelix KlassMaster" => Self::ZelixKlassMaster,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Obfuscator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The signs of obfuscation found by [`detect_obfuscator`], fractions are between 0 and 1.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObfuscationSignals {
+    /// Fraction of classes with simple names of one or two lowercase letters, like the ones assigned by ProGuard.
+    pub short_names: f64,
+    /// Fraction of classes with names meant to be confused, made only of `I`, `l` and `1` or not ASCII.
+    pub confusing_names: f64,
+    /// Fraction of methods with bodies dominated by jumps and switches.
+    pub flow_obfuscation: f64,
+    /// Fraction of classes calling a static method of the archive shaped like a string decryptor,
+    /// e.g. `static String a(String)`.
+    pub string_encryption: f64,
+    /// Obfuscators whose watermarks were found in the classes.
+    pub watermarks: Vec<Obfuscator>,
+}
+
+/// The result of [`detect_obfuscator`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObfuscatorReport {
+    /// The obfuscators the archive was likely processed with and the confidence of each, between 0 and 1,
+    /// the most likely first.
+    pub candidates: Vec<(Obfuscator, f64)>,
+    pub signals: ObfuscationSignals,
+}
+
+impl ObfuscatorReport {
+    /// Returns the most likely obfuscator, if there are signs of obfuscation.
+    #[inline]
+    pub fn most_likely(&self) -> Option<Obfuscator> {
+        self.candidates.first().map(|(obfuscator, _)| *obfuscator)
+    }
+}
+
+/// Looks for the fingerprints of obfuscators in the classes of an archive.
+///
+/// Watermarks identify an obfuscator with high confidence, otherwise the confidence is derived from the
+/// [`ObfuscationSignals`] typical of each obfuscator: ProGuard renames classes to short names,
+/// Allatori to confusing ones and encrypts strings, Zelix KlassMaster obfuscates control flow and encrypts strings.
+/// Confidences are heuristic, they're meant to rank the candidates rather than to be compared between archives.
+///
+/// # Examples
+/// ```no_run
+/// let mut jar = jars::Jar::open("app.jar").unwrap();
+/// let report = jars::detect_obfuscator(&mut jar).unwrap();
+/// if let Some(obfuscator) = report.most_likely() {
+///     println!("obfuscated with {obfuscator}");
+/// }
+/// ```
+pub fn detect_obfuscator<R: Read + Seek>(jar: &mut Jar<R>) -> Result<ObfuscatorReport> {
+    let (mut classes, mut short_names, mut confusing_names) = (0, 0, 0);
+    let (mut methods, mut flow_obfuscated) = (0, 0);
+    let mut watermarks = [false; OBFUSCATOR_MARKERS.len()];
+    let mut decryptors = HashSet::new();
+    let mut decryptor_calls = vec![];
+
+    for entry in jar.classes() {
+        let entry = entry?;
+        let class = entry.parse()?;
+        let pool = entry.constant_pool()?;
+        classes += 1;
+
+        let name = ClassName::new(&class.this_class);
+        let simple_name = name.simple_name();
+        short_names +=
+            usize::from(simple_name.len() <= 2 && simple_name.bytes().all(|c| c.is_ascii_lowercase()));
+        confusing_names += usize::from(is_confusing(simple_name));
+        for (found, (_, marker)) in watermarks.iter_mut().zip(OBFUSCATOR_MARKERS) {
+            *found |= contains(entry.bytes(), marker);
+        }
+
+        for method in &class.methods {
+            if method.access_flags.contains(MethodAccessFlags::STATIC)
+                && DECRYPTOR_DESCRIPTORS.contains(&&*method.descriptor)
+            {
+                decryptors.insert((
+                    name.clone(),
+                    method.name.to_string(),
+                    method.descriptor.to_string(),
+                ));
+            }
+            let Some(code) = method.attributes.iter().find_map(|attr| match &attr.data {
+                AttributeData::Code(code) => Some(code),
+                _ => None,
+            }) else {
+                continue;
+            };
+            methods += 1;
+            // code that can't be decoded is obfuscated too
+            let Ok(instructions) = bytecode::disassemble(code.code, &pool) else {
+                flow_obfuscated += 1;
+                continue;
+            };
+            let jumps = instructions
+                .iter()
+                .filter(|instr| JUMPS.contains(&instr.opcode))
+                .count();
+            flow_obfuscated += usize::from(jumps >= 4 && jumps * 5 >= instructions.len());
+        }
+
+        let calls: Vec<_> = pool
+            .method_refs()
+            .filter(|method| DECRYPTOR_DESCRIPTORS.contains(&method.descriptor.as_str()))
+            .map(|method| (ClassName::new(&method.class), method.name, method.descriptor))
+            .collect();
+        decryptor_calls.push(calls);
+    }
+
+    let encrypting = decryptor_calls
+        .iter()
+        .filter(|calls| calls.iter().any(|call| decryptors.contains(call)))
+        .count();
+    let fraction = |count: usize, total: usize| {
+        if total == 0 {
+            0.
+        } else {
+            count as f64 / total as f64
+        }
+    };
+    let signals = ObfuscationSignals {
+        short_names: fraction(short_names, classes),
+        confusing_names: fraction(confusing_names, classes),
+        flow_obfuscation: fraction(flow_obfuscated, methods),
+        string_encryption: fraction(encrypting, classes),
+        watermarks: OBFUSCATOR_MARKERS
+            .iter()
+            .zip(watermarks)
+            .filter(|(_, found)| *found)
+            .map(|((name, _), _)| Obfuscator::from_name(name))
+            .collect(),
+    };
+    Ok(ObfuscatorReport {
+        candidates: candidates(&signals),
+        signals,
+    })
+}
+
+/// Derives the confidence of each obfuscator from the signals.
+fn candidates(signals: &ObfuscationSignals) -> Vec<(Obfuscator, f64)> {
+    let ObfuscationSignals {
+        short_names,
+        confusing_names,
+        flow_obfuscation,
+        string_encryption,
+        ref watermarks,
+    } = *signals;
+    let mut candidates: Vec<_> = watermarks.iter().map(|&obfuscator| (obfuscator, 0.9)).collect();
+    // ProGuard neither encrypts strings nor obfuscates control flow
+    candidates.push((
+        Obfuscator::ProGuard,
+        short_names * (1. - flow_obfuscation.max(string_encryption)),
+    ));
+    candidates.push((
+        Obfuscator::Allatori,
+        0.8 * (confusing_names + string_encryption) / 2.,
+    ));
+    candidates.push((
+        Obfuscator::ZelixKlassMaster,
+        0.8 * (flow_obfuscation + string_encryption) / 2.,
+    ));
+    candidates.push((
+        Obfuscator::Unknown,
+        0.5 * confusing_names.max(flow_obfuscation).max(string_encryption),
+    ));
+
+    candidates.sort_by(|(a, a_conf), (b, b_conf)| b_conf.total_cmp(a_conf).then(a.cmp(b)));
+    let mut seen = HashSet::new();
+    candidates.retain(|&(obfuscator, confidence)| confidence > 0. && seen.insert(obfuscator));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn detect_obfuscators() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a"))
+            .class(TestClass::new("b"))
+            .class(TestClass::new("c/a"))
+            .class(TestClass::new("com/example/Main"))
+            .open()
+            .unwrap();
+        let report = detect_obfuscator(&mut jar).unwrap();
+        assert_eq!(report.signals.short_names, 0.75);
+        assert_eq!(report.candidates, [(Obfuscator::ProGuard, 0.75)]);
+
+        let mut jar = TestJar::default()
+            .class(TestClass::new("IlIl1"))
+            .class(TestClass::new("com/example/Main").extends("ALLATORIxDEMO"))
+            .open()
+            .unwrap();
+        let report = detect_obfuscator(&mut jar).unwrap();
+        assert_eq!(report.signals.watermarks, [Obfuscator::Allatori]);
+        assert_eq!(report.most_likely(), Some(Obfuscator::Allatori));
+        assert_eq!(report.candidates[1], (Obfuscator::Unknown, 0.25));
+    }
+}
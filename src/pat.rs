@@ -1,23 +1,46 @@
-use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use cafebabe::{ClassAccessFlags, ClassFile, FieldAccessFlags, MethodAccessFlags};
 
 use crate::descriptor::Descriptor;
+use crate::indy::LAMBDA_METAFACTORY;
+use crate::matching::{check_class, Captures, MemberBinding};
+use crate::model::{ClassModel, ConstantValue, Member, MemberView};
+use crate::name::to_internal;
 
 /// A pattern used to find classes in a JAR file.
 ///
 /// Typically this would represent an obfuscated class.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ClassPat {
     pub(crate) flags: ClassAccessFlags,
     pub(crate) members: Vec<MemberPat>,
-    pub(crate) base: Option<TypePat>,
+    pub(crate) base: BasePat,
     pub(crate) impls: Vec<TypePat>,
+    pub(crate) exact_flags: bool,
+    pub(crate) skip_lambda_bodies: bool,
+    pub(crate) services: Vec<String>,
+    pub(crate) entry_points: Vec<String>,
+    pub(crate) strategy: MatchStrategy,
+    pub(crate) separate_static: bool,
+    pub(crate) visibility: Visibilities,
+    pub(crate) predicates: Vec<ClassPredicate>,
+    pub(crate) priority: i32,
 }
 
 impl ClassPat {
     /// Creates a pattern that matches any interface.
+    ///
+    /// Interfaces are always abstract, so the pattern requires both `ACC_INTERFACE` and `ACC_ABSTRACT`,
+    /// which keeps it matching interfaces when combined with [`exact_flags`](Self::exact_flags).
     pub fn interface() -> Self {
         let mut this = Self::default();
-        this.flags |= ClassAccessFlags::INTERFACE;
+        this.flags |= ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT;
         this
     }
 
@@ -28,6 +51,26 @@ impl ClassPat {
         self
     }
 
+    /// Requires the class to have the provided visibility.
+    #[inline]
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = Visibilities::only(visibility);
+        self
+    }
+
+    /// Extends the pattern to match any package-private class.
+    #[inline]
+    pub fn package_private(self) -> Self {
+        self.visibility(Visibility::Package)
+    }
+
+    /// Extends the pattern to match any class that isn't public.
+    #[inline]
+    pub fn not_public(mut self) -> Self {
+        self.visibility = Visibilities::except(Visibility::Public);
+        self
+    }
+
     /// Extends the pattern to match any final class.
     #[inline]
     pub fn final_(mut self) -> Self {
@@ -42,11 +85,41 @@ impl ClassPat {
         self
     }
 
+    /// Makes the pattern require the flags of the class and its members to match exactly,
+    /// instead of only requiring the specified flags to be present.
+    ///
+    /// For example, a `public` method pattern will no longer match a `public final` method.
+    /// `ACC_SUPER` is ignored on classes, since it's set by every modern compiler,
+    /// so `ClassPat::default().public().exact_flags()` matches a class with `ACC_PUBLIC | ACC_SUPER`.
+    #[inline]
+    pub fn exact_flags(mut self) -> Self {
+        self.exact_flags = true;
+        self
+    }
+
+    /// Makes the pattern ignore the synthetic methods implementing the lambdas of the class,
+    /// so that member patterns only describe the methods written in the source.
+    ///
+    /// Lambda bodies are resolved from bytecode, see [`MethodPat::call_site`].
+    /// Their bodies can still be matched with [`CallSitePat::target`].
+    #[inline]
+    pub fn skip_lambda_bodies(mut self) -> Self {
+        self.skip_lambda_bodies = true;
+        self
+    }
+
     /// Extends the pattern with a [`TypePat`],
-    /// which will be used to filter on the base class.
+    /// which will be used to filter on the base class, see [`BasePat::Is`].
     #[inline]
     pub fn with_base(mut self, base: TypePat) -> Self {
-        self.base = Some(base);
+        self.base = BasePat::Is(base);
+        self
+    }
+
+    /// Sets the [`BasePat`] used to filter on the base class, [`BasePat::Object`] by default.
+    #[inline]
+    pub fn base(mut self, base: BasePat) -> Self {
+        self.base = base;
         self
     }
 
@@ -58,16 +131,146 @@ impl ClassPat {
         self
     }
 
+    /// Requires the class to be declared as a provider of a service in `META-INF/services`,
+    /// e.g. `java/sql/Driver`.
+    ///
+    /// Service descriptors name classes even in obfuscated archives,
+    /// this can only match classes searched in an archive.
+    #[inline]
+    pub fn provides_service(mut self, service: impl Into<String>) -> Self {
+        self.services.push(to_internal(&service.into()));
+        self
+    }
+
+    /// Requires the class to be declared as an entry point of the provided kind by the metadata of the archive,
+    /// e.g. `main` for the main Fabric entrypoint or `main_class` for the `Main-Class` of the manifest,
+    /// see [`Jar::entry_points`](crate::Jar::entry_points). Kinds found by inspecting classes aren't declared.
+    #[inline]
+    pub fn entry_point(mut self, kind: impl Into<String>) -> Self {
+        self.entry_points.push(kind.into());
+        self
+    }
+
+    /// Sets the priority of the pattern, 0 by default.
+    ///
+    /// A class satisfying more than one pattern of a search is matched by the one with the highest priority,
+    /// or by the first one of those with the same priority, see [`Match::satisfied`](crate::Match::satisfied).
+    #[inline]
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets the [`MatchStrategy`] binding the member patterns to the members of a class,
+    /// [`MatchStrategy::Positional`] by default.
+    #[inline]
+    pub fn strategy(mut self, strategy: MatchStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Makes the pattern match static and instance members as separate sequences,
+    /// since compilers and obfuscators often reorder members across that boundary.
+    ///
+    /// Member patterns with the `static` flag are only bound to static members and the others
+    /// only to instance members, the order is only checked within each sequence.
+    #[inline]
+    pub fn separate_static(mut self) -> Self {
+        self.separate_static = true;
+        self
+    }
+
     /// Extends a pattern with a [`MemberPat`],
     /// which will be used to match a class member.
     ///
     /// Calls to this method must follow the same order as
-    /// the order in which the members are defined in.
+    /// the order in which the members are defined in, unless the pattern has a [`MatchStrategy`]
+    /// ignoring the order.
+    /// Class files keep methods and fields in separate tables, so only the order of methods among methods
+    /// and of fields among fields is known, and a method can be declared before or after a field.
     #[inline]
-    pub fn with(mut self, member: MemberPat) -> Self {
-        self.members.push(member);
+    pub fn with(mut self, member: impl Into<MemberPat>) -> Self {
+        self.members.push(member.into());
         self
     }
+
+    /// Requires the class to satisfy a predicate, checked after the rest of the pattern matches.
+    ///
+    /// Predicates inspect the [`ClassFile`], so they can't match classes of models
+    /// that aren't backed by one, like the classes of DEX files.
+    ///
+    /// # Examples
+    /// ```
+    /// use jars::ClassPat;
+    ///
+    /// let pat = ClassPat::default().with_predicate(|class| class.attributes.len() > 2);
+    /// ```
+    #[inline]
+    pub fn with_predicate(
+        mut self,
+        predicate: impl Fn(&ClassFile<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicates.push(ClassPredicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Checks whether the pattern matches an already parsed class,
+    /// typically a [`ClassFile`](cafebabe::ClassFile).
+    #[inline]
+    pub fn matches<C: ClassModel + ?Sized>(&self, class: &C) -> bool {
+        check_class(class, self).is_some()
+    }
+
+    /// Checks whether the pattern matches an already parsed class and returns
+    /// the class member matched by each of the member patterns, in the order of the patterns.
+    #[inline]
+    pub fn matches_with_bindings<C: ClassModel + ?Sized>(&self, class: &C) -> Option<Vec<MemberBinding>> {
+        check_class(class, self)
+    }
+
+    /// Replaces the named types of the pattern that have been captured by another match with the captured types,
+    /// so that the classes identified by one search can drive the next one.
+    pub fn resolve_captures(mut self, captures: &Captures) -> Self {
+        self.for_each_type_mut(|typ| {
+            let (TypePat::Capture(name) | TypePat::Ref(name)) = typ else {
+                return;
+            };
+            if let Some(desc) = captures.get(name).filter(|desc| Descriptor::parse(desc).is_ok()) {
+                *typ = TypePat::Exact(desc.clone());
+            }
+        });
+        self
+    }
+
+    /// Calls `f` with the type patterns of the supertypes and the members of the pattern.
+    pub(crate) fn for_each_type_mut(&mut self, mut f: impl FnMut(&mut TypePat)) {
+        if let BasePat::Is(base) | BasePat::Extends(base) = &mut self.base {
+            f(base);
+        }
+        self.impls.iter_mut().for_each(&mut f);
+        for member in &mut self.members {
+            match member {
+                MemberPat::Method(method) => {
+                    method.param_types.iter_mut().for_each(&mut f);
+                    f(&mut method.ret_type);
+                }
+                MemberPat::Field(field) => f(&mut field.field_type),
+                MemberPat::Custom(_) => {}
+            }
+        }
+    }
+
+    /// Returns whether matching the pattern requires the call sites of methods, which are read from bytecode.
+    #[cfg(feature = "archive")]
+    pub(crate) fn uses_call_sites(&self) -> bool {
+        self.skip_lambda_bodies
+            || self.members.iter().any(|member| match member {
+                MemberPat::Method(method) => {
+                    !method.call_sites.is_empty() || method.call_site_count.is_some()
+                }
+                _ => false,
+            })
+    }
 }
 
 impl Default for ClassPat {
@@ -75,28 +278,496 @@ impl Default for ClassPat {
         Self {
             flags: ClassAccessFlags::empty(),
             members: vec![],
-            base: None,
+            base: BasePat::default(),
             impls: vec![],
+            exact_flags: false,
+            skip_lambda_bodies: false,
+            services: vec![],
+            entry_points: vec![],
+            strategy: MatchStrategy::default(),
+            separate_static: false,
+            visibility: Visibilities::default(),
+            predicates: vec![],
+            priority: 0,
         }
     }
 }
 
+/// Determines how the member patterns of a [`ClassPat`] are bound to the members of a class.
+///
+/// Methods and fields are bound separately, so the order of a method relative to a field never matters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchStrategy {
+    /// Members are matched in the order they're declared and the class cannot have any other members.
+    #[default]
+    Positional,
+    /// Members are matched in the order they're declared, skipping the other members of the class.
+    Subsequence,
+    /// Members are matched in any order, but the class cannot have any other members.
+    Set,
+    /// Members are matched in any order, skipping the other members of the class.
+    BestEffort,
+}
+
+impl MatchStrategy {
+    #[inline]
+    pub(crate) fn is_ordered(self) -> bool {
+        matches!(self, Self::Positional | Self::Subsequence)
+    }
+
+    #[inline]
+    pub(crate) fn is_exhaustive(self) -> bool {
+        matches!(self, Self::Positional | Self::Set)
+    }
+}
+
+/// The visibility of a class or a member, ordered from the least to the most visible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Visibility {
+    Private,
+    /// The visibility of classes and members without any visibility modifier.
+    Package,
+    Protected,
+    Public,
+}
+
+impl Visibility {
+    /// Returns the visibility declared by the bits of access flags,
+    /// which are the same for classes, methods and fields.
+    pub fn from_bits(bits: u16) -> Self {
+        if bits & 0x0001 != 0 {
+            Self::Public
+        } else if bits & 0x0004 != 0 {
+            Self::Protected
+        } else if bits & 0x0002 != 0 {
+            Self::Private
+        } else {
+            Self::Package
+        }
+    }
+
+    #[inline]
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The visibilities accepted by a pattern, any visibility if empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Visibilities(u8);
+
+impl Visibilities {
+    #[inline]
+    fn only(visibility: Visibility) -> Self {
+        Self(visibility.bit())
+    }
+
+    #[inline]
+    fn except(visibility: Visibility) -> Self {
+        Self(0b1111 & !visibility.bit())
+    }
+
+    #[inline]
+    pub(crate) fn accepts(self, bits: u16) -> bool {
+        self.0 == 0 || self.0 & Visibility::from_bits(bits).bit() != 0
+    }
+}
+
+/// A predicate added with [`ClassPat::with_predicate`], equal only to its copies.
+#[derive(Clone)]
+pub(crate) struct ClassPredicate(Arc<dyn Fn(&ClassFile<'_>) -> bool + Send + Sync>);
+
+impl ClassPredicate {
+    #[inline]
+    pub(crate) fn check<C: ClassModel + ?Sized>(&self, class: &C) -> bool {
+        let mut accepted = false;
+        class.with_class_file(&mut |class| accepted = (self.0)(class));
+        accepted
+    }
+}
+
+impl PartialEq for ClassPredicate {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for ClassPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClassPredicate").finish_non_exhaustive()
+    }
+}
+
 /// A pattern used to match on class members.
-#[derive(Debug)]
+///
+/// The `Method` and `Field` variants used to be struct variants with `flags`, `param_types`,
+/// `ret_type` and `field_type` fields. They now wrap a [`MethodPat`] and a [`FieldPat`],
+/// so patterns written as struct literals have to be ported to the builders:
+///
+/// ```
+/// use cafebabe::MethodAccessFlags;
+/// use jars::{MemberPat, MethodPat, TypePat};
+///
+/// // previously `MemberPat::Method { flags, param_types: vec![TypePat::Any], ret_type: TypePat::Void }`
+/// let pat: MemberPat = MethodPat::default()
+///     .flags(MethodAccessFlags::PUBLIC)
+///     .param(TypePat::Any)
+///     .returns(TypePat::Void)
+///     .into();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum MemberPat {
-    Method {
-        flags: MethodAccessFlags,
-        param_types: Vec<TypePat>,
-        ret_type: TypePat,
-    },
-    Field {
-        flags: FieldAccessFlags,
-        field_type: TypePat,
-    },
+    Method(MethodPat),
+    Field(FieldPat),
+    /// A method or a field accepted by a user-defined predicate.
+    Custom(CustomMemberPat),
+}
+
+impl MemberPat {
+    /// Creates a pattern that matches the methods accepted by the predicate.
+    ///
+    /// # Examples
+    /// ```
+    /// use jars::{ClassPat, Member, MemberPat};
+    ///
+    /// let pat = ClassPat::default().with(MemberPat::method_where(|method: Member<'_>| {
+    ///     method.descriptor().len() > 30
+    /// }));
+    /// ```
+    #[inline]
+    pub fn method_where(predicate: impl MemberPredicate + 'static) -> Self {
+        Self::Custom(CustomMemberPat {
+            is_method: true,
+            predicate: Arc::new(predicate),
+        })
+    }
+
+    /// Creates a pattern that matches the fields accepted by the predicate.
+    #[inline]
+    pub fn field_where(predicate: impl MemberPredicate + 'static) -> Self {
+        Self::Custom(CustomMemberPat {
+            is_method: false,
+            predicate: Arc::new(predicate),
+        })
+    }
+
+    /// Returns whether the pattern is bound to a method rather than a field.
+    #[inline]
+    pub(crate) fn is_method(&self) -> bool {
+        match self {
+            Self::Method(_) => true,
+            Self::Field(_) => false,
+            Self::Custom(custom) => custom.is_method,
+        }
+    }
+}
+
+impl From<MethodPat> for MemberPat {
+    #[inline]
+    fn from(pat: MethodPat) -> Self {
+        Self::Method(pat)
+    }
+}
+
+impl From<FieldPat> for MemberPat {
+    #[inline]
+    fn from(pat: FieldPat) -> Self {
+        Self::Field(pat)
+    }
+}
+
+/// A user-defined condition on class members, see [`MemberPat::method_where`] and [`MemberPat::field_where`].
+///
+/// It's implemented for closures taking a [`Member`].
+pub trait MemberPredicate: Send + Sync {
+    fn matches(&self, member: Member<'_>) -> bool;
+}
+
+impl<F: Fn(Member<'_>) -> bool + Send + Sync> MemberPredicate for F {
+    #[inline]
+    fn matches(&self, member: Member<'_>) -> bool {
+        self(member)
+    }
+}
+
+/// A member pattern checked by a [`MemberPredicate`].
+///
+/// Custom patterns are equal only to their copies, since predicates can't be compared.
+#[derive(Clone)]
+pub struct CustomMemberPat {
+    is_method: bool,
+    predicate: Arc<dyn MemberPredicate>,
+}
+
+impl CustomMemberPat {
+    #[inline]
+    pub(crate) fn check_method(&self, method: MemberView<'_, MethodAccessFlags>) -> bool {
+        self.is_method && self.predicate.matches(Member::Method(method))
+    }
+
+    #[inline]
+    pub(crate) fn check_field(&self, field: MemberView<'_, FieldAccessFlags>) -> bool {
+        !self.is_method && self.predicate.matches(Member::Field(field))
+    }
+}
+
+impl PartialEq for CustomMemberPat {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.is_method == other.is_method && Arc::ptr_eq(&self.predicate, &other.predicate)
+    }
+}
+
+impl fmt::Debug for CustomMemberPat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_method { "method" } else { "field" };
+        f.debug_struct("CustomMemberPat")
+            .field("kind", &kind)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A pattern used to match on methods.
+///
+/// This is the programmatic equivalent of the [`method!`](crate::method) macro.
+/// A default pattern matches any method without parameters that returns void.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodPat {
+    pub(crate) flags: MethodAccessFlags,
+    pub(crate) visibility: Visibilities,
+    pub(crate) param_types: Vec<TypePat>,
+    pub(crate) ret_type: TypePat,
+    pub(crate) name: Option<String>,
+    pub(crate) default_impl: bool,
+    pub(crate) call_sites: Vec<CallSitePat>,
+    pub(crate) call_site_count: Option<usize>,
+}
+
+impl MethodPat {
+    /// Creates a pattern that matches any constructor without parameters.
+    #[inline]
+    pub fn constructor() -> Self {
+        Self::default().name("<init>")
+    }
+
+    /// Extends the pattern with the specified access flags.
+    ///
+    /// The flags are ORed into the ones already required, so repeated calls accumulate.
+    #[inline]
+    pub fn flags(mut self, flags: MethodAccessFlags) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    /// Requires the method to have the provided visibility, e.g. [`Visibility::Package`]
+    /// which cannot be expressed with access flags.
+    #[inline]
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = Visibilities::only(visibility);
+        self
+    }
+
+    /// Requires the method not to be public.
+    #[inline]
+    pub fn not_public(mut self) -> Self {
+        self.visibility = Visibilities::except(Visibility::Public);
+        self
+    }
+
+    /// Extends the pattern with a [`TypePat`] for the next parameter of the method.
+    #[inline]
+    pub fn param(mut self, typ: TypePat) -> Self {
+        self.param_types.push(typ);
+        self
+    }
+
+    /// Sets the [`TypePat`] used to match on the return type of the method.
+    #[inline]
+    pub fn returns(mut self, typ: TypePat) -> Self {
+        self.ret_type = typ;
+        self
+    }
+
+    /// Requires the method to have the specified name.
+    #[inline]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Requires the method to provide an inheritable implementation,
+    /// i.e. not to be abstract, static or private.
+    ///
+    /// In interfaces this distinguishes default methods from abstract and static ones,
+    /// which can't be expressed with flags alone since all of them are public.
+    #[inline]
+    pub fn default_impl(mut self) -> Self {
+        self.default_impl = true;
+        self
+    }
+
+    /// Requires the method to contain an `invokedynamic` call site matching a [`CallSitePat`].
+    ///
+    /// Call sites are read from bytecode, so patterns using them only match classes
+    /// whose [`ClassModel`] provides it, such as the classes of a searched archive.
+    #[inline]
+    pub fn call_site(mut self, pat: CallSitePat) -> Self {
+        self.call_sites.push(pat);
+        self
+    }
+
+    /// Requires the method to contain exactly `count` `invokedynamic` call sites.
+    #[inline]
+    pub fn call_site_count(mut self, count: usize) -> Self {
+        self.call_site_count = Some(count);
+        self
+    }
+}
+
+impl Default for MethodPat {
+    fn default() -> Self {
+        Self {
+            flags: MethodAccessFlags::empty(),
+            visibility: Visibilities::default(),
+            param_types: vec![],
+            ret_type: TypePat::Void,
+            name: None,
+            default_impl: false,
+            call_sites: vec![],
+            call_site_count: None,
+        }
+    }
+}
+
+/// A pattern used to match on `invokedynamic` call sites of methods.
+///
+/// A default pattern matches any call site.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallSitePat {
+    pub(crate) bootstrap: Option<String>,
+    pub(crate) ret_type: Option<TypePat>,
+    pub(crate) target: Option<Box<MethodPat>>,
+}
+
+impl CallSitePat {
+    /// Creates a pattern that matches lambdas and method references.
+    #[inline]
+    pub fn lambda() -> Self {
+        Self::default().bootstrap(LAMBDA_METAFACTORY)
+    }
+
+    /// Creates a pattern that matches string concatenations compiled for Java 9 and later.
+    #[inline]
+    pub fn string_concat() -> Self {
+        Self::default().bootstrap("java/lang/invoke/StringConcatFactory")
+    }
+
+    /// Requires the bootstrap method to be declared in the specified class.
+    #[inline]
+    pub fn bootstrap(mut self, class: impl Into<String>) -> Self {
+        self.bootstrap = Some(class.into());
+        self
+    }
+
+    /// Sets the [`TypePat`] used to match on the type produced by the call site,
+    /// for lambdas this is the implemented functional interface.
+    #[inline]
+    pub fn returns(mut self, typ: TypePat) -> Self {
+        self.ret_type = Some(typ);
+        self
+    }
+
+    /// Requires the call site to be a lambda implemented by a method of the same class,
+    /// which matches the provided [`MethodPat`].
+    ///
+    /// The implementation is resolved through the arguments of `LambdaMetafactory`, so this can be used
+    /// to match the parameters, return type and nested call sites of the body of a lambda.
+    #[inline]
+    pub fn target(mut self, pat: MethodPat) -> Self {
+        self.target = Some(Box::new(pat));
+        self
+    }
+}
+
+/// A pattern used to match on fields.
+///
+/// This is the programmatic equivalent of the [`field!`](crate::field) macro.
+/// A default pattern matches any field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPat {
+    pub(crate) flags: FieldAccessFlags,
+    pub(crate) visibility: Visibilities,
+    pub(crate) field_type: TypePat,
+    pub(crate) name: Option<String>,
+    pub(crate) value: Option<ConstantValue>,
+}
+
+impl FieldPat {
+    /// Extends the pattern with the specified access flags.
+    ///
+    /// The flags are ORed into the ones already required, so repeated calls accumulate.
+    #[inline]
+    pub fn flags(mut self, flags: FieldAccessFlags) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    /// Requires the field to have the provided visibility, e.g. [`Visibility::Package`]
+    /// which cannot be expressed with access flags.
+    #[inline]
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = Visibilities::only(visibility);
+        self
+    }
+
+    /// Requires the field not to be public.
+    #[inline]
+    pub fn not_public(mut self) -> Self {
+        self.visibility = Visibilities::except(Visibility::Public);
+        self
+    }
+
+    /// Sets the [`TypePat`] used to match on the type of the field.
+    #[inline]
+    pub fn of_type(mut self, typ: TypePat) -> Self {
+        self.field_type = typ;
+        self
+    }
+
+    /// Requires the field to have the specified name.
+    #[inline]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Requires the field to be initialized with the specified value.
+    ///
+    /// Besides constant values, this matches constants assigned to static fields in the static initializer
+    /// when the class is searched in an archive.
+    #[inline]
+    pub fn value(mut self, value: impl Into<ConstantValue>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+}
+
+impl Default for FieldPat {
+    fn default() -> Self {
+        Self {
+            flags: FieldAccessFlags::empty(),
+            visibility: Visibilities::default(),
+            field_type: TypePat::Any,
+            name: None,
+            value: None,
+        }
+    }
 }
 
 /// A pattern used to match on types.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TypePat {
     /// Matches on any type.
     Any,
@@ -104,29 +775,116 @@ pub enum TypePat {
     Void,
     /// Matches on the specified [`Descriptor`].
     Match(Descriptor<'static>),
+    /// Matches on the type with the specified descriptor, e.g. `[Ljava/lang/String;`.
+    /// Unlike [`TypePat::Match`] it owns the names it refers to, for the types only known at runtime.
+    Exact(String),
+    /// Matches on any class and refers to it by name, so that it can be identified
+    /// by [`propagate`](crate::propagate) once the class it appears in is identified.
+    Ref(String),
+    /// Matches on any type and captures it by name, see [`Captures`].
+    Capture(String),
+    /// Matches on the classes with names that look obfuscated, i.e. application classes rather than
+    /// the ones of the platform or of libraries, see [`ClassName::is_obfuscated`](crate::ClassName::is_obfuscated).
+    Obfuscated,
+    /// Matches on the types with descriptors matching a regular expression, see [`TypeRegex`].
+    #[cfg(feature = "regex")]
+    Regex(TypeRegex),
 }
 
 impl TypePat {
-    pub fn class_name(&self) -> Option<&'static str> {
-        if let Self::Match(Descriptor::Object(obj)) = self {
-            Some(obj)
-        } else {
-            None
+    pub fn class_name(&self) -> Option<&str> {
+        match self.descriptor()? {
+            Descriptor::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`Descriptor`] of the type if the pattern matches exactly one type.
+    pub fn descriptor(&self) -> Option<Descriptor<'_>> {
+        match self {
+            Self::Match(desc) => Some(desc.clone()),
+            Self::Exact(desc) => Descriptor::parse(desc).ok(),
+            _ => None,
         }
     }
 }
 
+/// A regular expression matching the whole descriptor of a type, e.g. `L[^/;]{2};`
+/// for the classes of the default package with two-character names.
+///
+/// Superclasses and interfaces are matched on their descriptors too, e.g. `Lcom/example/Foo;`.
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct TypeRegex(regex::Regex);
+
+#[cfg(feature = "regex")]
+impl TypeRegex {
+    /// Compiles the expression, which is anchored to match the whole descriptor.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        regex::Regex::new(&alloc::format!("^(?:{pattern})$")).map(Self)
+    }
+
+    /// Returns whether the descriptor matches the expression.
+    #[inline]
+    pub fn is_match(&self, descriptor: &str) -> bool {
+        self.0.is_match(descriptor)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl PartialEq for TypeRegex {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+/// A pattern used to match on the superclass of a class.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum BasePat {
+    /// Matches classes without a superclass, which are only `java/lang/Object` itself and module descriptors.
+    None,
+    /// Matches classes extending `java/lang/Object`, i.e. declared without an `extends` clause.
+    #[default]
+    Object,
+    /// Matches any superclass or none at all.
+    Any,
+    /// Matches the superclass on the [`TypePat`], which can be `java/lang/Object`.
+    Is(TypePat),
+    /// Matches the superclass on the [`TypePat`] unless it's `java/lang/Object`,
+    /// so that [`TypePat::Any`] requires an `extends` clause.
+    Extends(TypePat),
+}
+
+impl BasePat {
+    /// Returns the pattern of the superclass, if it has one.
+    pub fn type_pat(&self) -> Option<&TypePat> {
+        match self {
+            Self::Is(pat) | Self::Extends(pat) => Some(pat),
+            Self::None | Self::Object | Self::Any => None,
+        }
+    }
+}
+
+#[cfg(feature = "macros")]
 #[macro_export]
 macro_rules! method_mods {
-    ($($ident:ident)*) => {
-        $crate::paste::paste!($($crate::cafebabe::MethodAccessFlags::[<$ident:upper>])|*)
+    () => {
+        $crate::cafebabe::MethodAccessFlags::empty()
+    };
+    (strictfp $($rest:ident)*) => {
+        $crate::cafebabe::MethodAccessFlags::STRICT | $crate::method_mods!($($rest)*)
+    };
+    ($ident:ident $($rest:ident)*) => {
+        $crate::paste::paste!($crate::cafebabe::MethodAccessFlags::[<$ident:upper>]) | $crate::method_mods!($($rest)*)
     };
 }
 
+#[cfg(feature = "macros")]
 #[macro_export]
 macro_rules! field_mods {
     ($($ident:ident)*) => {
-        $crate::paste::paste!($($crate::cafebabe::FieldAccessFlags::[<$ident:upper>])|*)
+        $crate::paste::paste!($crate::cafebabe::FieldAccessFlags::empty() $(| $crate::cafebabe::FieldAccessFlags::[<$ident:upper>])*)
     };
 }
 
@@ -140,17 +898,29 @@ macro_rules! field_mods {
 /// use jars::method;
 ///
 /// method!(public static (String) -> i32);
+/// method!(package final () -> ());
 /// ```
-/// The example above maps to `public static int method(String str)` in Java.
+/// The first example maps to `public static int method(String str)` in Java.
+/// Modifiers are named as in Java, along with `bridge`, `synthetic` and `varargs`.
+/// The `package` modifier requires a package-private method and has to come first.
+/// The last parameter of a `varargs` method is an array, which can be written as the type of its elements.
+#[cfg(feature = "macros")]
 #[macro_export]
 macro_rules! method {
+    (@pat $($mod:ident)* ($($arg:ty),*) -> $ret:ty) => {
+        $crate::MethodPat::default()
+            .flags($crate::method_mods!($($mod)*))
+            $(.param(<$arg as $crate::HasTypePat>::pattern()))*
+            .returns(<$ret as $crate::HasTypePat>::pattern())
+    };
+    (package $($mod:ident)* ($($arg:ty),*) -> $ret:ty) => {
+        $crate::MemberPat::Method(
+            $crate::method!(@pat $($mod)* ($($arg),*) -> $ret).visibility($crate::Visibility::Package)
+        )
+    };
     ($($mod:ident)* ($($arg:ty),*) -> $ret:ty) => {
-        $crate::MemberPat::Method {
-            flags: $crate::method_mods!($($mod)*),
-            param_types: vec![$(<$arg as $crate::HasTypePat>::pattern()),*],
-            ret_type: <$ret as $crate::HasTypePat>::pattern()
-        }
-    }
+        $crate::MemberPat::Method($crate::method!(@pat $($mod)* ($($arg),*) -> $ret))
+    };
 }
 
 /// Macro used as a shorthand to create field patterns.
@@ -160,20 +930,31 @@ macro_rules! method {
 /// use jars::field;
 ///
 /// field!([public] i32);
+/// field!([package static] i64);
 /// ```
+/// The `package` modifier requires a package-private field and has to come first.
+#[cfg(feature = "macros")]
 #[macro_export]
 macro_rules! field {
-    ($typ:ty) => {
-        $crate::MemberPat::Field {
-            flags: $crate::cafebabe::FieldAccessFlags::empty(),
-            field_type: <$typ as $crate::HasTypePat>::pattern()
-        }
+    ([package $($mod:ident)*] $typ:ty) => {
+        $crate::MemberPat::Field(
+            $crate::FieldPat::default()
+                .flags($crate::field_mods!($($mod)*))
+                .visibility($crate::Visibility::Package)
+                .of_type(<$typ as $crate::HasTypePat>::pattern())
+        )
     };
     ([$($mod:ident)*] $typ:ty) => {
-        $crate::MemberPat::Field {
-            flags: $crate::field_mods!($($mod)*),
-            field_type: <$typ as $crate::HasTypePat>::pattern()
-        }
+        $crate::MemberPat::Field(
+            $crate::FieldPat::default()
+                .flags($crate::field_mods!($($mod)*))
+                .of_type(<$typ as $crate::HasTypePat>::pattern())
+        )
+    };
+    ($typ:ty) => {
+        $crate::MemberPat::Field(
+            $crate::FieldPat::default().of_type(<$typ as $crate::HasTypePat>::pattern())
+        )
     }
 }
 
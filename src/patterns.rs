@@ -0,0 +1,168 @@
+//! Patterns for common Java idioms.
+//!
+//! Each pattern describes the members that make up the idiom, in the order in which
+//! javac usually emits them. The remaining members of the class should be appended
+//! with [`ClassPat::with`].
+
+use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+use crate::pat::{ClassPat, FieldPat, MethodPat, TypePat};
+use crate::{field, method, Any};
+
+/// Creates a pattern for a singleton class.
+///
+/// The pattern matches a class looking like this:
+/// ```java
+/// public class MyClass {
+///   private static final MyClass INSTANCE = new MyClass();
+///   private MyClass();
+///   public static MyClass getInstance();
+/// }
+/// ```
+///
+/// The instance is created eagerly, so the class also has a static initializer,
+/// which javac emits after the other methods.
+pub fn singleton() -> ClassPat {
+    ClassPat::default()
+        .with(field!([private static final] Any))
+        .with(MethodPat::constructor().flags(MethodAccessFlags::PRIVATE))
+        .with(method!(public static () -> Any))
+        .with(
+            MethodPat::default()
+                .flags(MethodAccessFlags::STATIC)
+                .name("<clinit>"),
+        )
+}
+
+/// Creates a pattern for a class exposing static factory methods only.
+///
+/// The pattern matches a class looking like this:
+/// ```java
+/// public class MyClass {
+///   private MyClass();
+///   public static Product create(* arg1);
+/// }
+/// ```
+pub fn static_factory(product: TypePat) -> ClassPat {
+    ClassPat::default()
+        .with(MethodPat::constructor().flags(MethodAccessFlags::PRIVATE))
+        .with(
+            MethodPat::default()
+                .flags(MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC)
+                .param(TypePat::Any)
+                .returns(product),
+        )
+}
+
+/// Creates a pattern for a builder of the `product` type with a single property.
+///
+/// The pattern matches a class looking like this:
+/// ```java
+/// public class MyBuilder {
+///   private * value;
+///   public MyBuilder();
+///   public MyBuilder setValue(* value);
+///   public Product build();
+/// }
+/// ```
+pub fn builder(product: TypePat) -> ClassPat {
+    ClassPat::default()
+        .with(FieldPat::default().flags(FieldAccessFlags::PRIVATE))
+        .with(MethodPat::constructor().flags(MethodAccessFlags::PUBLIC))
+        .with(method!(public (Any) -> Any))
+        .with(
+            MethodPat::default()
+                .flags(MethodAccessFlags::PUBLIC)
+                .returns(product),
+        )
+}
+
+/// Creates a pattern for a listener implementing the `iface` interface.
+///
+/// The pattern matches a class looking like this:
+/// ```java
+/// public class MyListener implements Listener {
+///   public MyListener();
+///   public void onEvent(* event);
+/// }
+/// ```
+pub fn event_listener(iface: TypePat) -> ClassPat {
+    ClassPat::default()
+        .with_impl(iface)
+        .with(MethodPat::constructor().flags(MethodAccessFlags::PUBLIC))
+        .with(method!(public (Any) -> ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptor::Descriptor;
+    use crate::testing::TestClass;
+
+    fn product() -> TypePat {
+        TypePat::Match(Descriptor::Object("Product"))
+    }
+
+    #[test]
+    fn match_singleton() {
+        let class = TestClass::new("a")
+            .field(
+                FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+                "INSTANCE",
+                "La;",
+            )
+            .method(MethodAccessFlags::PRIVATE, "<init>", "()V")
+            .method(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+                "getInstance",
+                "()La;",
+            );
+        let eager = class
+            .clone()
+            .method(MethodAccessFlags::STATIC, "<clinit>", "()V")
+            .to_bytes();
+        assert!(singleton().matches(&cafebabe::parse_class(&eager).unwrap()));
+        let lazy = class.to_bytes();
+        assert!(!singleton().matches(&cafebabe::parse_class(&lazy).unwrap()));
+    }
+
+    #[test]
+    fn match_static_factory() {
+        let class = TestClass::new("a")
+            .method(MethodAccessFlags::PRIVATE, "<init>", "()V")
+            .method(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+                "create",
+                "(I)LProduct;",
+            )
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        assert!(static_factory(product()).matches(&class));
+        assert!(!static_factory(TypePat::Match(Descriptor::Object("Other"))).matches(&class));
+    }
+
+    #[test]
+    fn match_builder() {
+        let class = TestClass::new("a")
+            .field(FieldAccessFlags::PRIVATE, "value", "I")
+            .method(MethodAccessFlags::PUBLIC, "<init>", "()V")
+            .method(MethodAccessFlags::PUBLIC, "setValue", "(I)La;")
+            .method(MethodAccessFlags::PUBLIC, "build", "()LProduct;")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        assert!(builder(product()).matches(&class));
+        assert!(!builder(TypePat::Void).matches(&class));
+    }
+
+    #[test]
+    fn match_event_listener() {
+        let class = TestClass::new("a")
+            .implements("Listener")
+            .method(MethodAccessFlags::PUBLIC, "<init>", "()V")
+            .method(MethodAccessFlags::PUBLIC, "onEvent", "(LEvent;)V")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        assert!(event_listener(TypePat::Match(Descriptor::Object("Listener"))).matches(&class));
+        assert!(!event_listener(TypePat::Match(Descriptor::Object("Other"))).matches(&class));
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Seek};
+
+use crate::jar::Jar;
+use crate::matching::Captures;
+use crate::name::ClassName;
+use crate::pat::ClassPat;
+use crate::result::{Error, Result};
+use crate::search::{MatchSet, SearchOptions, Searcher};
+
+/// A search performed in stages, where the results of a stage resolve the named types of the next ones.
+///
+/// After each stage, every pattern that matched exactly one class binds its name to that class and
+/// the captures of the matches are collected, see [`Captures`]. The [`TypePat::Ref`](crate::TypePat::Ref)
+/// and [`TypePat::Capture`](crate::TypePat::Capture) types of later stages are replaced with the types bound
+/// to their names with [`ClassPat::resolve_captures`]. Patterns whose name has already been identified
+/// are not searched for again, and stages with nothing left to search don't read the archive.
+///
+/// # Examples
+/// ```no_run
+/// # let mut jar = jars::Jar::open("app.jar").unwrap();
+/// let report = jars::Pipeline::default()
+///     .stage(jars::parse_patterns("class Server\n  field private $World\n").unwrap())
+///     .stage(jars::parse_patterns("class Level\n  field private final @World\n").unwrap())
+///     .run(&mut jar)
+///     .unwrap();
+/// println!("Level is {:?}", report.identities.get("Level"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<Vec<(String, ClassPat)>>,
+    options: SearchOptions,
+}
+
+impl Pipeline {
+    /// Appends a stage of named patterns, e.g. ones parsed with [`parse_patterns`](crate::parse_patterns).
+    #[inline]
+    pub fn stage(mut self, pats: impl IntoIterator<Item = (String, ClassPat)>) -> Self {
+        self.stages.push(pats.into_iter().collect());
+        self
+    }
+
+    /// Sets the [`SearchOptions`] used by every stage.
+    #[inline]
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Runs the stages in order against an archive.
+    pub fn run<R: Read + Seek>(&self, jar: &mut Jar<R>) -> Result<PipelineReport> {
+        let mut report = PipelineReport::default();
+        let mut ambiguous = HashSet::new();
+
+        for stage in &self.stages {
+            let (names, pats): (Vec<_>, Vec<_>) = stage
+                .iter()
+                .filter(|(name, _)| !report.identities.contains_key(name))
+                .map(|(name, pat)| (name.clone(), pat.clone().resolve_captures(&report.captures)))
+                .unzip();
+            let mut set = MatchSet {
+                patterns: names,
+                ..MatchSet::default()
+            };
+            if !pats.is_empty() {
+                let res = Searcher::new(pats)
+                    .with_options(self.options.clone())
+                    .search(jar)?;
+                report.warnings.extend(set.add_report(res));
+            }
+
+            for (i, name) in set.patterns.iter().enumerate() {
+                let mut matches = set.matches.iter().filter(|mat| mat.pattern == i);
+                if let (Some(mat), None) = (matches.next(), matches.next()) {
+                    let class = mat.entry.header()?.this_class.clone();
+                    bind(&mut report.captures, &mut ambiguous, name, format!("L{class};"));
+                    report.identities.insert(name.clone(), class);
+                }
+            }
+            for mat in &set.matches {
+                for (name, typ) in &mat.captures {
+                    bind(&mut report.captures, &mut ambiguous, name, typ.clone());
+                }
+            }
+            report.stages.push(set);
+        }
+        Ok(report)
+    }
+}
+
+/// Binds a name to a type, unbinding it for good once it's bound to different types.
+fn bind(captures: &mut Captures, ambiguous: &mut HashSet<String>, name: &str, typ: String) {
+    if ambiguous.contains(name) {
+        return;
+    }
+    match captures.entry(name.to_owned()) {
+        Entry::Occupied(existing) if *existing.get() != typ => {
+            existing.remove();
+            ambiguous.insert(name.to_owned());
+        }
+        Entry::Occupied(_) => {}
+        Entry::Vacant(entry) => {
+            entry.insert(typ);
+        }
+    }
+}
+
+/// The outcome of [`Pipeline::run`].
+#[derive(Debug, Default)]
+pub struct PipelineReport {
+    /// Matches of each stage, named after the patterns that were searched for in the stage.
+    pub stages: Vec<MatchSet>,
+    /// Internal names of the classes matched by exactly one pattern, keyed by the name of the pattern.
+    pub identities: BTreeMap<String, ClassName>,
+    /// Types bound to names by the identities and the captures of the matches,
+    /// excluding the names that were bound to different types.
+    pub captures: Captures,
+    /// Errors of the classes that were skipped with [`MalformedPolicy::CollectWarnings`](crate::MalformedPolicy).
+    pub warnings: Vec<Error>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::FieldAccessFlags;
+
+    use super::*;
+    use crate::parse_patterns;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn run_stages() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a").field(FieldAccessFlags::PRIVATE, "b", "Lc;"))
+            .class(TestClass::new("c").field(FieldAccessFlags::PROTECTED, "d", "I"))
+            .class(TestClass::new("e").field(FieldAccessFlags::PUBLIC, "f", "La;"))
+            .class(TestClass::new("g").field(FieldAccessFlags::PUBLIC, "h", "Lc;"))
+            .open()
+            .unwrap();
+        let first = parse_patterns("class Server\n  field private $World\n").unwrap();
+        let second =
+            parse_patterns("class Level\n  field public $World\nclass Server\n  field *\n").unwrap();
+        let report = Pipeline::default()
+            .stage(first)
+            .stage(second)
+            .run(&mut jar)
+            .unwrap();
+
+        assert_eq!(
+            report.identities.get("Server").map(|name| name.as_str()),
+            Some("a")
+        );
+        assert_eq!(
+            report.identities.get("Level").map(|name| name.as_str()),
+            Some("g")
+        );
+        assert_eq!(report.captures.get("World").map(String::as_str), Some("Lc;"));
+        assert_eq!(report.stages[1].patterns, ["Level"]);
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{Read, Seek};
+
+use crate::descriptor::Descriptor;
+use crate::indy::WithStaticValues;
+use crate::jar::{Jar, JarEntry};
+use crate::matching::{check_class, for_each_bound_type, MemberBinding};
+use crate::model::ClassModel;
+use crate::name::ClassName;
+use crate::pat::{ClassPat, TypePat};
+use crate::result::Result;
+
+/// A class identified by [`propagate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// Internal name of the class in the archive.
+    pub class: ClassName,
+    /// Name of the identified class whose pattern referred to this one, [`None`] for seeds.
+    pub via: Option<String>,
+}
+
+/// An inference rejected by [`propagate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropagationConflict {
+    pub name: String,
+    /// The class inferred for the name.
+    pub class: ClassName,
+    /// Name of the identified class whose pattern referred to this one.
+    pub via: String,
+    /// The class the name was identified as before,
+    /// or [`None`] if the inferred class doesn't match the pattern of the name.
+    pub existing: Option<ClassName>,
+}
+
+/// The result of [`propagate`].
+#[derive(Debug, Clone, Default)]
+pub struct Propagation {
+    /// Identified classes by name, including the seeds.
+    pub identities: BTreeMap<String, Identity>,
+    pub conflicts: Vec<PropagationConflict>,
+}
+
+/// Identifies classes related to a seed set of identified classes, iterating to a fixed point.
+///
+/// Seeds map names to internal names of classes in the archive, typically found by searching for anchor patterns.
+/// For every identified class with a pattern in `pats`, the class is matched against the pattern and each
+/// [`TypePat::Ref`] of the pattern identifies the class it's bound to, e.g. the type of a field, a parameter,
+/// the superclass or an interface. Classes identified this way are checked against their own patterns when
+/// they have one, and are expanded in turn.
+///
+/// # Examples
+/// ```no_run
+/// # let mut jar = jars::Jar::open("app.jar").unwrap();
+/// let pats = jars::parse_patterns("class Server\n  field private @World\n").unwrap();
+/// let res = jars::propagate(&mut jar, &pats, [("Server".to_owned(), "abc".to_owned())]).unwrap();
+/// println!("World is {:?}", res.identities.get("World"));
+/// ```
+pub fn propagate<R: Read + Seek>(
+    jar: &mut Jar<R>,
+    pats: &[(String, ClassPat)],
+    seeds: impl IntoIterator<Item = (String, String)>,
+) -> Result<Propagation> {
+    let mut entries: HashMap<ClassName, JarEntry> = HashMap::new();
+    for entry in jar.classes() {
+        let entry = entry?;
+        entries.insert(entry.header()?.this_class.clone(), entry);
+    }
+    let pats: HashMap<&str, &ClassPat> = pats.iter().map(|(name, pat)| (name.as_str(), pat)).collect();
+
+    let mut res = Propagation::default();
+    let mut queue = VecDeque::new();
+    for (name, class) in seeds {
+        queue.push_back(name.clone());
+        res.identities.insert(name, Identity {
+            class: class.into(),
+            via: None,
+        });
+    }
+
+    while let Some(name) = queue.pop_front() {
+        let Some(pat) = pats.get(name.as_str()) else {
+            continue;
+        };
+        let Some(entry) = entries.get(&res.identities[&name].class) else {
+            continue;
+        };
+        let class = entry.parse()?;
+        let class = WithStaticValues::new(&class, entry.bytes());
+        let Some(bindings) = check_class(&class, pat) else {
+            continue;
+        };
+
+        for (target, inferred) in references(&class, pat, &bindings) {
+            match res.identities.get(&target) {
+                Some(existing) if existing.class == inferred => {}
+                existing => {
+                    let existing = existing.map(|identity| identity.class.clone());
+                    let accepted = match (&existing, pats.get(target.as_str())) {
+                        (Some(_), _) => false,
+                        (None, Some(pat)) => matches_pattern(&entries, &inferred, pat)?,
+                        (None, None) => true,
+                    };
+                    if accepted {
+                        res.identities.insert(target.clone(), Identity {
+                            class: inferred,
+                            via: Some(name.clone()),
+                        });
+                        queue.push_back(target);
+                    } else {
+                        res.conflicts.push(PropagationConflict {
+                            name: target,
+                            class: inferred,
+                            via: name.clone(),
+                            existing,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(res)
+}
+
+fn matches_pattern(entries: &HashMap<ClassName, JarEntry>, class: &str, pat: &ClassPat) -> Result<bool> {
+    let Some(entry) = entries.get(class) else {
+        return Ok(false);
+    };
+    let parsed = entry.parse()?;
+    Ok(check_class(&WithStaticValues::new(&parsed, entry.bytes()), pat).is_some())
+}
+
+/// Returns the names referred to by a pattern along with the classes they're bound to.
+fn references<C: ClassModel + ?Sized>(
+    class: &C,
+    pat: &ClassPat,
+    bindings: &[MemberBinding],
+) -> Vec<(String, ClassName)> {
+    let mut refs = vec![];
+    for_each_bound_type(class, pat, bindings, |pat, typ| {
+        if let (TypePat::Ref(name), Descriptor::Object(class)) = (pat, typ) {
+            refs.push((name.clone(), ClassName::new(class)));
+        }
+    });
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::parse_patterns;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn propagate_identities() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a").field(FieldAccessFlags::PRIVATE, "b", "Lc;"))
+            .class(
+                TestClass::new("c")
+                    .extends("d")
+                    .method(MethodAccessFlags::PUBLIC, "e", "(Lf;)V"),
+            )
+            .class(TestClass::new("d"))
+            .class(TestClass::new("f").field(FieldAccessFlags::PRIVATE, "g", "La;"))
+            .open()
+            .unwrap();
+        let src = "
+            class Server
+                field private @World
+            class World extends @Level
+                method public (@Player) -> ()
+            class Player
+                field private @World
+        ";
+        let pats = parse_patterns(src).unwrap();
+        let res = propagate(&mut jar, &pats, [("Server".to_owned(), "a".to_owned())]).unwrap();
+
+        let identities: Vec<_> = res
+            .identities
+            .iter()
+            .map(|(name, identity)| (name.as_str(), identity.class.as_str(), identity.via.as_deref()))
+            .collect();
+        assert_eq!(identities, vec![
+            ("Level", "d", Some("World")),
+            ("Player", "f", Some("World")),
+            ("Server", "a", None),
+            ("World", "c", Some("Server")),
+        ]);
+        // the field of the player refers to the server rather than the world
+        assert_eq!(res.conflicts, vec![PropagationConflict {
+            name: "World".to_owned(),
+            class: ClassName::new("a"),
+            via: "Player".to_owned(),
+            existing: Some(ClassName::new("c")),
+        }]);
+    }
+}
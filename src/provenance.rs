@@ -0,0 +1,281 @@
+use crate::matching::{
+    check_base, check_call_sites, check_entry_points, check_field, check_field_value, check_flags, check_impls, check_method, check_predicates, check_services, matched_methods, Declared
+};
+use crate::model::ClassModel;
+use crate::pat::{ClassPat, MatchStrategy, MemberPat};
+
+/// A part of a [`ClassPat`] that is checked against a class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "kind", content = "index", rename_all = "snake_case")
+)]
+pub enum Constraint {
+    /// The access flags of the class.
+    Flags,
+    /// The superclass.
+    Base,
+    /// The implemented interfaces.
+    Interfaces,
+    /// The services provided by the class.
+    Services,
+    /// The kinds of entry points the class is declared as.
+    EntryPoints,
+    /// The number of methods and fields, or the minimum number of them if the [`MatchStrategy`] allows other members.
+    MemberCount,
+    /// A member pattern, identified by its index in [`ClassPat::members`].
+    Member(usize),
+    /// The predicates added with [`ClassPat::with_predicate`].
+    Predicates,
+}
+
+/// Describes how a pattern discriminated between the classes of an archive,
+/// collected when [`SearchOptions::provenance`](crate::SearchOptions::provenance) is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Provenance {
+    /// Constraints that rejected at least one class of the archive.
+    pub discriminating: Vec<Constraint>,
+    /// Constraints that every class satisfied, these had no effect on the result.
+    pub redundant: Vec<Constraint>,
+    /// Number of classes that satisfy the pattern.
+    pub matches: usize,
+    /// The smallest number of constraints failed by a class that doesn't satisfy the pattern.
+    ///
+    /// A margin of 1 means that a single change to some class could produce another match.
+    pub margin: Option<usize>,
+}
+
+impl Provenance {
+    /// Returns a score between 0 and 1 estimating how likely the pattern identifies the right class.
+    ///
+    /// The score is 0 without matches and decreases with the number of matches
+    /// and with a smaller [`margin`](Self::margin).
+    pub fn confidence(&self) -> f64 {
+        if self.matches == 0 {
+            return 0.0;
+        }
+        let robustness = self
+            .margin
+            .map_or(1.0, |margin| margin as f64 / (margin + 1) as f64);
+        robustness / self.matches as f64
+    }
+
+    /// Combines the provenance of the same pattern collected in another archive.
+    pub fn merge(&mut self, other: &Provenance) {
+        for constraint in &other.discriminating {
+            if !self.discriminating.contains(constraint) {
+                self.discriminating.push(*constraint);
+            }
+        }
+        self.redundant
+            .retain(|constraint| !other.discriminating.contains(constraint));
+        self.matches += other.matches;
+        self.margin = match (self.margin, other.margin) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+/// Lists the constraints of a pattern in the order they're evaluated by [`evaluate`].
+fn constraints(pat: &ClassPat) -> impl Iterator<Item = Constraint> {
+    [
+        Constraint::Flags,
+        Constraint::Base,
+        Constraint::Interfaces,
+        Constraint::Services,
+        Constraint::EntryPoints,
+        Constraint::MemberCount,
+    ]
+    .into_iter()
+    .chain((0..pat.members.len()).map(Constraint::Member))
+    .chain((!pat.predicates.is_empty()).then_some(Constraint::Predicates))
+}
+
+/// Checks every constraint of a pattern against a class without stopping at the first failure.
+fn evaluate<C: ClassModel + ?Sized>(class: &C, declared: &dyn Declared, pat: &ClassPat) -> Vec<bool> {
+    let mut res = Vec::with_capacity(6 + pat.members.len());
+    let flags = class.access_flags() - cafebabe::ClassAccessFlags::SUPER;
+    res.push(check_flags(flags, pat.flags, pat.exact_flags) && pat.visibility.accepts(flags.bits()));
+    res.push(check_base(class, pat));
+    res.push(check_impls(class, pat));
+    res.push(check_services(declared, pat));
+    res.push(check_entry_points(declared, pat));
+
+    let method_count = pat.members.iter().filter(|member| member.is_method()).count();
+    let field_count = pat.members.len() - method_count;
+    let methods = matched_methods(class, pat);
+    if pat.strategy.is_exhaustive() {
+        res.push(methods.len() == method_count && class.field_count() == field_count);
+    } else {
+        res.push(methods.len() >= method_count && class.field_count() >= field_count);
+    }
+
+    if pat.strategy != MatchStrategy::Positional || pat.separate_static {
+        // members are checked against every member of the class they could be bound to
+        for member in &pat.members {
+            let ok = match member {
+                MemberPat::Method(method) => methods.iter().any(|&i| {
+                    check_method(class.method(i), method, pat.exact_flags, None).is_some()
+                        && check_call_sites(class, i, method)
+                }),
+                MemberPat::Field(field) => (0..class.field_count()).any(|i| {
+                    check_field(class.field(i), field, pat.exact_flags, None).is_some()
+                        && check_field_value(class, i, field)
+                }),
+                MemberPat::Custom(custom) if member.is_method() => {
+                    methods.iter().any(|&i| custom.check_method(class.method(i)))
+                }
+                MemberPat::Custom(custom) => {
+                    (0..class.field_count()).any(|i| custom.check_field(class.field(i)))
+                }
+            };
+            res.push(ok);
+        }
+        if !pat.predicates.is_empty() {
+            res.push(check_predicates(class, pat));
+        }
+        return res;
+    }
+
+    let mut methods = methods.into_iter();
+    let mut fields = 0;
+    for member in &pat.members {
+        let ok = match member {
+            MemberPat::Method(method) => methods.next().is_some_and(|i| {
+                check_method(class.method(i), method, pat.exact_flags, None).is_some()
+                    && check_call_sites(class, i, method)
+            }),
+            MemberPat::Field(field) => {
+                fields += 1;
+                fields <= class.field_count()
+                    && check_field(class.field(fields - 1), field, pat.exact_flags, None).is_some()
+                    && check_field_value(class, fields - 1, field)
+            }
+            MemberPat::Custom(custom) if member.is_method() => methods
+                .next()
+                .is_some_and(|i| custom.check_method(class.method(i))),
+            MemberPat::Custom(custom) => {
+                fields += 1;
+                fields <= class.field_count() && custom.check_field(class.field(fields - 1))
+            }
+        };
+        res.push(ok);
+    }
+    if !pat.predicates.is_empty() {
+        res.push(check_predicates(class, pat));
+    }
+    res
+}
+
+#[derive(Debug, Default)]
+struct PatternStats {
+    failures: Vec<usize>,
+    matches: usize,
+    margin: Option<usize>,
+}
+
+/// Accumulates [`Provenance`] of patterns over the classes of an archive.
+#[derive(Debug)]
+pub(crate) struct ProvenanceCollector {
+    stats: Vec<PatternStats>,
+}
+
+impl ProvenanceCollector {
+    pub fn new(pattern_count: usize) -> Self {
+        let stats = (0..pattern_count).map(|_| PatternStats::default()).collect();
+        Self { stats }
+    }
+
+    pub fn inspect<'a, C: ClassModel + ?Sized>(
+        &mut self,
+        class: &C,
+        declared: &dyn Declared,
+        pats: impl Iterator<Item = &'a ClassPat>,
+    ) {
+        for (stats, pat) in self.stats.iter_mut().zip(pats) {
+            let results = evaluate(class, declared, pat);
+            stats.failures.resize(results.len(), 0);
+
+            let mut failed = 0;
+            for (count, ok) in stats.failures.iter_mut().zip(&results) {
+                if !ok {
+                    *count += 1;
+                    failed += 1;
+                }
+            }
+            if failed == 0 {
+                stats.matches += 1;
+            } else {
+                stats.margin = Some(stats.margin.map_or(failed, |margin| margin.min(failed)));
+            }
+        }
+    }
+
+    pub fn finish<'a>(self, pats: impl Iterator<Item = &'a ClassPat>) -> Vec<Provenance> {
+        self.stats
+            .into_iter()
+            .zip(pats)
+            .map(|(stats, pat)| {
+                let (discriminating, redundant) = constraints(pat)
+                    .zip(stats.failures.iter().copied().chain(std::iter::repeat(0)))
+                    .partition::<Vec<_>, _>(|(_, failures)| *failures > 0);
+                Provenance {
+                    discriminating: discriminating.into_iter().map(|(c, _)| c).collect(),
+                    redundant: redundant.into_iter().map(|(c, _)| c).collect(),
+                    matches: stats.matches,
+                    margin: stats.margin,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::{field, method, SearchOptions, Searcher};
+
+    #[test]
+    fn collect_provenance() {
+        let mut jar = TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .field(FieldAccessFlags::PRIVATE, "b", "I")
+                    .method(MethodAccessFlags::PUBLIC, "c", "()V"),
+            )
+            .class(
+                TestClass::new("d")
+                    .field(FieldAccessFlags::PRIVATE, "e", "J")
+                    .method(MethodAccessFlags::PUBLIC, "f", "()V"),
+            )
+            .open()
+            .unwrap();
+        let pats = [
+            ClassPat::default()
+                .with(method!(public () -> ()))
+                .with(field!([private] i32)),
+            ClassPat::default().with(method!(public () -> ())),
+        ];
+        let report = Searcher::new(pats)
+            .with_options(SearchOptions::default().provenance(true))
+            .search(&mut jar)
+            .unwrap();
+
+        let prov = &report.provenance[0];
+        assert_eq!(prov.discriminating, vec![Constraint::Member(1)]);
+        assert_eq!(prov.matches, 1);
+        assert_eq!(prov.margin, Some(1));
+        assert_eq!(prov.confidence(), 0.5);
+
+        let prov = &report.provenance[1];
+        assert_eq!(prov.discriminating, vec![Constraint::MemberCount]);
+        assert_eq!(prov.matches, 0);
+        assert_eq!(prov.confidence(), 0.0);
+    }
+}
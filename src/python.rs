@@ -0,0 +1,241 @@
+//! Python bindings, built as the `jars` extension module.
+//!
+//! ```python
+//! import jars
+//!
+//! jar = jars.Jar.open("myjar.jar")
+//! pat = jars.ClassPat().public().method(["public", "static"], ["String"], "int")
+//! for match in jars.search(jar, [pat]):
+//!     print(match.class_name, match.bindings)
+//! ```
+//! Types are written the same way as in the [text format](crate::parse_patterns).
+
+use std::io::Cursor;
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+use crate::dsl::{self, field_flag, method_flag, parse_type_name};
+use crate::matching::MemberBinding;
+use crate::pat::{FieldPat, MethodPat, TypePat};
+use crate::search::Searcher;
+use crate::{ClassPat, Error};
+
+create_exception!(
+    jars,
+    JarsError,
+    PyException,
+    "An error raised by the jars library."
+);
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        JarsError::new_err(err.to_string())
+    }
+}
+
+/// A JAR archive loaded into memory.
+#[pyclass(name = "Jar")]
+struct PyJar(crate::Jar<Cursor<Vec<u8>>>);
+
+#[pymethods]
+impl PyJar {
+    /// Reads an archive from the file system.
+    #[staticmethod]
+    fn open(path: &str) -> PyResult<Self> {
+        let bytes = std::fs::read(path).map_err(Error::from)?;
+        Ok(Self(crate::Jar::from_bytes(bytes)?.named(path)))
+    }
+
+    /// Reads an archive from bytes.
+    #[staticmethod]
+    fn from_bytes(bytes: Vec<u8>) -> PyResult<Self> {
+        Ok(Self(crate::Jar::from_bytes(bytes)?))
+    }
+
+    #[getter]
+    fn name(&self) -> Option<&str> {
+        self.0.name()
+    }
+}
+
+/// A pattern used to find classes, see the `ClassPat` type of the Rust library.
+#[pyclass(name = "ClassPat")]
+#[derive(Clone, Default)]
+struct PyClassPat(ClassPat);
+
+#[pymethods]
+impl PyClassPat {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    #[staticmethod]
+    fn interface() -> Self {
+        Self(ClassPat::interface())
+    }
+
+    fn public(&self) -> Self {
+        Self(self.0.clone().public())
+    }
+
+    fn final_(&self) -> Self {
+        Self(self.0.clone().final_())
+    }
+
+    fn abstract_(&self) -> Self {
+        Self(self.0.clone().abstract_())
+    }
+
+    fn exact_flags(&self) -> Self {
+        Self(self.0.clone().exact_flags())
+    }
+
+    fn with_base(&self, base: &str) -> PyResult<Self> {
+        Ok(Self(self.0.clone().with_base(type_pat(base)?)))
+    }
+
+    fn with_impl(&self, interface: &str) -> PyResult<Self> {
+        Ok(Self(self.0.clone().with_impl(type_pat(interface)?)))
+    }
+
+    /// Adds a method pattern with modifiers such as `public`, parameter types and a return type.
+    #[pyo3(signature = (modifiers, params, returns = "void", name = None))]
+    fn method(
+        &self,
+        modifiers: Vec<String>,
+        params: Vec<String>,
+        returns: &str,
+        name: Option<String>,
+    ) -> PyResult<Self> {
+        let mut pat = MethodPat::default();
+        for modifier in &modifiers {
+            pat = match modifier.as_str() {
+                "default" => pat.default_impl(),
+                modifier => pat.flags(method_flag(modifier).ok_or_else(|| invalid_modifier(modifier))?),
+            };
+        }
+        for param in &params {
+            pat = pat.param(type_pat(param)?);
+        }
+        pat = pat.returns(type_pat(returns)?);
+        if let Some(name) = name {
+            pat = pat.name(name);
+        }
+        Ok(Self(self.0.clone().with(pat)))
+    }
+
+    /// Adds a field pattern with modifiers such as `private` and a type.
+    #[pyo3(signature = (modifiers, type_ = "*", name = None))]
+    fn field(&self, modifiers: Vec<String>, type_: &str, name: Option<String>) -> PyResult<Self> {
+        let mut pat = FieldPat::default();
+        for modifier in &modifiers {
+            pat = pat.flags(field_flag(modifier).ok_or_else(|| invalid_modifier(modifier))?);
+        }
+        pat = pat.of_type(type_pat(type_)?);
+        if let Some(name) = name {
+            pat = pat.name(name);
+        }
+        Ok(Self(self.0.clone().with(pat)))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// A class matched by a pattern.
+#[pyclass(name = "Match", get_all)]
+struct PyMatch {
+    /// Index of the matched pattern.
+    pattern: usize,
+    class_name: String,
+    entry: String,
+    archive: Option<String>,
+    /// Matched members as `(kind, index)` pairs, where kind is `method` or `field`.
+    bindings: Vec<(&'static str, usize)>,
+}
+
+#[pymethods]
+impl PyMatch {
+    fn __repr__(&self) -> String {
+        format!(
+            "Match(pattern={}, class_name={:?})",
+            self.pattern, self.class_name
+        )
+    }
+}
+
+/// Parses patterns written in the text format into a list of `(name, ClassPat)` pairs.
+#[pyfunction]
+fn parse_patterns(src: &str) -> PyResult<Vec<(String, PyClassPat)>> {
+    let pats = dsl::parse_patterns(src).map_err(Error::from)?;
+    Ok(pats
+        .into_iter()
+        .map(|(name, pat)| (name, PyClassPat(pat)))
+        .collect())
+}
+
+/// Searches for the patterns in an archive, allowing for more than one match per pattern.
+#[pyfunction]
+fn search(jar: &mut PyJar, patterns: Vec<PyClassPat>) -> PyResult<Vec<PyMatch>> {
+    let searcher = Searcher::new(patterns.into_iter().map(|pat| pat.0));
+    let matches = searcher.search_many(&mut jar.0)?;
+    matches
+        .into_iter()
+        .map(|mat| {
+            Ok(PyMatch {
+                pattern: mat.pattern,
+                class_name: mat.entry.header()?.this_class.to_string(),
+                entry: mat.entry.name().to_owned(),
+                archive: mat.entry.archive().map(ToOwned::to_owned),
+                bindings: mat
+                    .bindings
+                    .iter()
+                    .map(|binding| match binding {
+                        MemberBinding::Method(i) => ("method", *i),
+                        MemberBinding::Field(i) => ("field", *i),
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Searches for the patterns in an archive, expecting exactly one match per pattern.
+///
+/// Returns the names of the matched classes in the order of the patterns.
+#[pyfunction]
+fn search_exact(jar: &mut PyJar, patterns: Vec<PyClassPat>) -> PyResult<Vec<String>> {
+    let searcher = Searcher::new(patterns.into_iter().map(|pat| pat.0));
+    searcher
+        .search_exact(&mut jar.0)?
+        .iter()
+        .map(|entry| Ok(entry.header()?.this_class.to_string()))
+        .collect()
+}
+
+fn type_pat(str: &str) -> PyResult<TypePat> {
+    match str {
+        "()" => Ok(TypePat::Void),
+        str => parse_type_name(str).map_err(JarsError::new_err),
+    }
+}
+
+fn invalid_modifier(modifier: &str) -> PyErr {
+    JarsError::new_err(format!("invalid modifier `{modifier}`"))
+}
+
+#[pymodule]
+fn jars(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("JarsError", m.py().get_type::<JarsError>())?;
+    m.add_class::<PyJar>()?;
+    m.add_class::<PyClassPat>()?;
+    m.add_class::<PyMatch>()?;
+    m.add_function(wrap_pyfunction!(parse_patterns, m)?)?;
+    m.add_function(wrap_pyfunction!(search, m)?)?;
+    m.add_function(wrap_pyfunction!(search_exact, m)?)?;
+    Ok(())
+}
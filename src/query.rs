@@ -0,0 +1,370 @@
+//! Declarative queries over facts extracted from an archive.
+//!
+//! An archive is summarized as a set of [`Facts`], tuples of strings belonging to a [`Relation`],
+//! e.g. a class extending another one or a class referring to a string literal.
+//! A [`Query`] is a conjunction of relations whose [`Term`]s can be variables shared between them,
+//! and running it returns every assignment of the variables satisfying all relations at once.
+//!
+//! # Examples
+//! ```no_run
+//! use jars::query::{Facts, Query};
+//!
+//! let mut jar = jars::Jar::open("app.jar").unwrap();
+//! let facts = Facts::extract(&mut jar).unwrap();
+//! // classes registering an entity that subclasses a class containing a tick method
+//! let query = Query::default()
+//!     .references_string("?registry", "minecraft:zombie")
+//!     .calls("?registry", "?entity", "<init>", "_")
+//!     .inherits("?entity", "?base")
+//!     .has_method("?base", "_", "()V");
+//! for solution in query.run(&facts) {
+//!     println!("{} -> {}", solution["?registry"], solution["?entity"]);
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Seek};
+
+use crate::jar::Jar;
+use crate::model::ClassModel;
+use crate::result::Result;
+
+/// A kind of fact, the first term of every relation is the class the fact is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Relation {
+    /// `(class, superclass)`
+    Extends,
+    /// `(class, interface)` for the interfaces implemented directly by the class.
+    Implements,
+    /// `(class, supertype)` for every class and interface the class inherits from, derived from
+    /// [`Extends`](Self::Extends) and [`Implements`](Self::Implements).
+    Inherits,
+    /// `(class, name, descriptor)` for the methods declared by the class.
+    HasMethod,
+    /// `(class, name, descriptor)` for the fields declared by the class.
+    HasField,
+    /// `(class, owner, name, descriptor)` for the methods referred to by the class.
+    Calls,
+    /// `(class, string)` for the string literals used by the class.
+    ReferencesString,
+}
+
+impl Relation {
+    /// Returns the number of terms of the relation.
+    pub fn arity(self) -> usize {
+        match self {
+            Self::Extends | Self::Implements | Self::Inherits | Self::ReferencesString => 2,
+            Self::HasMethod | Self::HasField => 3,
+            Self::Calls => 4,
+        }
+    }
+}
+
+/// Facts about the classes of an archive, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Facts {
+    tables: HashMap<Relation, Vec<Box<[String]>>>,
+    by_class: HashMap<(Relation, String), Vec<usize>>,
+}
+
+impl Facts {
+    /// Extracts the facts of every class of an archive.
+    pub fn extract<R: Read + Seek>(jar: &mut Jar<R>) -> Result<Self> {
+        let mut facts = Self::default();
+        for entry in jar.classes() {
+            let entry = entry?;
+            let class = entry.parse_without_bytecode()?;
+            let name = class.this_class();
+            if let Some(base) = class.super_class() {
+                facts.insert(Relation::Extends, [name, base]);
+            }
+            for i in 0..class.interface_count() {
+                facts.insert(Relation::Implements, [name, class.interface(i)]);
+            }
+            for i in 0..class.method_count() {
+                let method = class.method(i);
+                facts.insert(Relation::HasMethod, [name, method.name, method.descriptor]);
+            }
+            for i in 0..class.field_count() {
+                let field = class.field(i);
+                facts.insert(Relation::HasField, [name, field.name, field.descriptor]);
+            }
+
+            let pool = entry.constant_pool()?;
+            for method in pool.method_refs() {
+                facts.insert(Relation::Calls, [
+                    name,
+                    method.class.as_str(),
+                    &method.name,
+                    &method.descriptor,
+                ]);
+            }
+            for str in pool.strings() {
+                facts.insert(Relation::ReferencesString, [name, &*str]);
+            }
+        }
+        Ok(facts)
+    }
+
+    /// Adds a fact, e.g. one obtained from another source than the archive.
+    ///
+    /// # Panics
+    /// Panics if the number of terms doesn't match the [arity](Relation::arity) of the relation,
+    /// or if the relation is [`Relation::Inherits`], which is always derived from other facts.
+    pub fn insert(&mut self, relation: Relation, tuple: impl IntoIterator<Item = impl Into<String>>) {
+        let tuple: Box<[String]> = tuple.into_iter().map(Into::into).collect();
+        assert_eq!(
+            tuple.len(),
+            relation.arity(),
+            "wrong number of terms for {relation:?}"
+        );
+        assert_ne!(
+            relation,
+            Relation::Inherits,
+            "inheritance is derived from other facts"
+        );
+
+        let table = self.tables.entry(relation).or_default();
+        self.by_class
+            .entry((relation, tuple[0].clone()))
+            .or_default()
+            .push(table.len());
+        table.push(tuple);
+    }
+
+    /// Returns the facts of a relation, optionally restricted to the ones about a class.
+    pub fn tuples<'a>(&'a self, relation: Relation, class: Option<&'a str>) -> Vec<Vec<&'a str>> {
+        if relation == Relation::Inherits {
+            return match class {
+                Some(class) => self.supertypes(class),
+                None => {
+                    let classes: HashSet<&str> = [Relation::Extends, Relation::Implements]
+                        .into_iter()
+                        .flat_map(|relation| self.stored(relation, None))
+                        .map(|tuple| tuple[0].as_str())
+                        .collect();
+                    classes
+                        .into_iter()
+                        .flat_map(|class| self.supertypes(class))
+                        .collect()
+                }
+            };
+        }
+        self.stored(relation, class)
+            .map(|tuple| tuple.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    fn stored(&self, relation: Relation, class: Option<&str>) -> Box<dyn Iterator<Item = &[String]> + '_> {
+        let Some(table) = self.tables.get(&relation) else {
+            return Box::new(std::iter::empty());
+        };
+        match class {
+            Some(class) => {
+                let indices = self
+                    .by_class
+                    .get(&(relation, class.to_owned()))
+                    .map_or(&[][..], Vec::as_slice);
+                Box::new(indices.iter().map(|&i| &*table[i]))
+            }
+            None => Box::new(table.iter().map(|tuple| &**tuple)),
+        }
+    }
+
+    fn supertypes<'a>(&'a self, class: &'a str) -> Vec<Vec<&'a str>> {
+        let mut seen = HashSet::new();
+        let mut pending = vec![class];
+        let mut res = vec![];
+        while let Some(current) = pending.pop() {
+            for relation in [Relation::Extends, Relation::Implements] {
+                for tuple in self.stored(relation, Some(current)) {
+                    if seen.insert(tuple[1].as_str()) {
+                        res.push(vec![class, tuple[1].as_str()]);
+                        pending.push(&tuple[1]);
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+/// A term of a relation in a [`Query`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    /// Binds the value to a variable, all occurrences of a variable must have the same value.
+    Var(String),
+    /// Requires the provided value.
+    Value(String),
+    /// Matches any value without binding it.
+    Any,
+}
+
+impl From<&str> for Term {
+    /// Parses a term, names prefixed with `?` are variables, `_` matches anything and everything else is a value.
+    fn from(str: &str) -> Self {
+        match str {
+            "_" => Self::Any,
+            str if str.starts_with('?') => Self::Var(str.to_owned()),
+            str => Self::Value(str.to_owned()),
+        }
+    }
+}
+
+/// An assignment of the variables of a [`Query`], keyed by the names of the variables.
+pub type Solution = BTreeMap<String, String>;
+
+/// A conjunction of relations over [`Facts`], see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    atoms: Vec<(Relation, Vec<Term>)>,
+}
+
+impl Query {
+    /// Requires a class to extend another one.
+    #[inline]
+    pub fn extends(self, class: impl Into<Term>, base: impl Into<Term>) -> Self {
+        self.with(Relation::Extends, vec![class.into(), base.into()])
+    }
+
+    /// Requires a class to directly implement an interface.
+    #[inline]
+    pub fn implements(self, class: impl Into<Term>, interface: impl Into<Term>) -> Self {
+        self.with(Relation::Implements, vec![class.into(), interface.into()])
+    }
+
+    /// Requires a class to inherit from a class or an interface, directly or not.
+    #[inline]
+    pub fn inherits(self, class: impl Into<Term>, supertype: impl Into<Term>) -> Self {
+        self.with(Relation::Inherits, vec![class.into(), supertype.into()])
+    }
+
+    /// Requires a class to declare a method.
+    #[inline]
+    pub fn has_method(
+        self,
+        class: impl Into<Term>,
+        name: impl Into<Term>,
+        descriptor: impl Into<Term>,
+    ) -> Self {
+        self.with(Relation::HasMethod, vec![
+            class.into(),
+            name.into(),
+            descriptor.into(),
+        ])
+    }
+
+    /// Requires a class to declare a field.
+    #[inline]
+    pub fn has_field(
+        self,
+        class: impl Into<Term>,
+        name: impl Into<Term>,
+        descriptor: impl Into<Term>,
+    ) -> Self {
+        self.with(Relation::HasField, vec![
+            class.into(),
+            name.into(),
+            descriptor.into(),
+        ])
+    }
+
+    /// Requires a class to refer to a method.
+    #[inline]
+    pub fn calls(
+        self,
+        class: impl Into<Term>,
+        owner: impl Into<Term>,
+        name: impl Into<Term>,
+        descriptor: impl Into<Term>,
+    ) -> Self {
+        let terms = vec![class.into(), owner.into(), name.into(), descriptor.into()];
+        self.with(Relation::Calls, terms)
+    }
+
+    /// Requires a class to use a string literal.
+    #[inline]
+    pub fn references_string(self, class: impl Into<Term>, string: impl Into<Term>) -> Self {
+        self.with(Relation::ReferencesString, vec![class.into(), string.into()])
+    }
+
+    fn with(mut self, relation: Relation, terms: Vec<Term>) -> Self {
+        self.atoms.push((relation, terms));
+        self
+    }
+
+    /// Returns every distinct assignment of the variables satisfying the query, in sorted order.
+    ///
+    /// Relations are joined in the order they were added, so starting with the most selective ones is faster.
+    pub fn run(&self, facts: &Facts) -> Vec<Solution> {
+        let mut solutions = vec![Solution::new()];
+        for (relation, terms) in &self.atoms {
+            solutions = solutions
+                .iter()
+                .flat_map(|solution| {
+                    let class = match &terms[0] {
+                        Term::Var(name) => solution.get(name).map(String::as_str),
+                        Term::Value(value) => Some(value.as_str()),
+                        Term::Any => None,
+                    };
+                    facts
+                        .tuples(*relation, class)
+                        .into_iter()
+                        .filter_map(|tuple| unify(terms, &tuple, solution))
+                })
+                .collect();
+        }
+        solutions.sort();
+        solutions.dedup();
+        solutions
+    }
+}
+
+fn unify(terms: &[Term], tuple: &[&str], solution: &Solution) -> Option<Solution> {
+    let mut solution = solution.clone();
+    for (term, &value) in terms.iter().zip(tuple) {
+        match term {
+            Term::Any => {}
+            Term::Value(expected) if expected != value => return None,
+            Term::Value(_) => {}
+            Term::Var(name) => match solution.get(name) {
+                Some(bound) if bound != value => return None,
+                Some(_) => {}
+                None => {
+                    solution.insert(name.clone(), value.to_owned());
+                }
+            },
+        }
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::MethodAccessFlags;
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn run_query() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a").method(MethodAccessFlags::PUBLIC, "tick", "()V"))
+            .class(TestClass::new("b").extends("a"))
+            .class(TestClass::new("c").extends("b").implements("d"))
+            .class(TestClass::new("e").implements("d"))
+            .open()
+            .unwrap();
+        let facts = Facts::extract(&mut jar).unwrap();
+
+        let solutions = Query::default()
+            .implements("?class", "d")
+            .inherits("?class", "?base")
+            .has_method("?base", "tick", "_")
+            .run(&facts);
+        assert_eq!(solutions, [Solution::from([
+            ("?base".to_owned(), "a".to_owned()),
+            ("?class".to_owned(), "c".to_owned())
+        ])]);
+    }
+}
@@ -0,0 +1,189 @@
+use std::borrow::Cow;
+
+use cafebabe::{ClassAccessFlags, ClassFile, FieldAccessFlags, MethodAccessFlags};
+
+use crate::indy::CallSite;
+use crate::model::{ClassModel, Constant, MemberView};
+use crate::name::package_prefix;
+
+/// Package prefixes of classes renamed back to their original names, e.g. to undo the relocation of shaded libraries.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Relocations(Vec<(String, String)>);
+
+impl Relocations {
+    pub(crate) fn push(&mut self, from: &str, to: &str) {
+        self.0.push((package_prefix(from), package_prefix(to)));
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Renames a class with the first matching prefix.
+    pub(crate) fn map_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        for (from, to) in &self.0 {
+            if let Some(rest) = name.strip_prefix(from.as_str()) {
+                return Cow::Owned(format!("{to}{rest}"));
+            }
+        }
+        Cow::Borrowed(name)
+    }
+
+    /// Renames the classes referenced by a field or a method descriptor.
+    pub(crate) fn map_descriptor<'a>(&self, descriptor: &'a str) -> Cow<'a, str> {
+        let mut res = String::new();
+        let mut rest = descriptor;
+        let mut changed = false;
+        while let Some(start) = rest.find('L') {
+            let Some(end) = rest[start..].find(';') else {
+                break;
+            };
+            let name = self.map_name(&rest[start + 1..start + end]);
+            changed |= matches!(name, Cow::Owned(_));
+            res.push_str(&rest[..=start]);
+            res.push_str(&name);
+            rest = &rest[start + end..];
+        }
+        if !changed {
+            return Cow::Borrowed(descriptor);
+        }
+        res.push_str(rest);
+        Cow::Owned(res)
+    }
+}
+
+/// A class seen with the names of the classes it refers to relocated, see [`Relocations`].
+#[derive(Debug)]
+pub(crate) struct Relocated<C> {
+    class: C,
+    names: Option<Names>,
+}
+
+#[derive(Debug)]
+struct Names {
+    this_class: String,
+    super_class: Option<String>,
+    interfaces: Vec<String>,
+    methods: Vec<String>,
+    fields: Vec<String>,
+}
+
+impl<C: ClassModel> Relocated<C> {
+    pub(crate) fn new(class: C, relocations: &Relocations) -> Self {
+        if relocations.is_empty() {
+            return Self { class, names: None };
+        }
+        let names = Names {
+            this_class: relocations.map_name(class.this_class()).into_owned(),
+            super_class: class
+                .super_class()
+                .map(|name| relocations.map_name(name).into_owned()),
+            interfaces: (0..class.interface_count())
+                .map(|i| relocations.map_name(class.interface(i)).into_owned())
+                .collect(),
+            methods: (0..class.method_count())
+                .map(|i| {
+                    relocations
+                        .map_descriptor(class.method(i).descriptor)
+                        .into_owned()
+                })
+                .collect(),
+            fields: (0..class.field_count())
+                .map(|i| relocations.map_descriptor(class.field(i).descriptor).into_owned())
+                .collect(),
+        };
+        Self {
+            class,
+            names: Some(names),
+        }
+    }
+}
+
+impl<C: ClassModel> ClassModel for Relocated<C> {
+    #[inline]
+    fn access_flags(&self) -> ClassAccessFlags {
+        self.class.access_flags()
+    }
+
+    #[inline]
+    fn this_class(&self) -> &str {
+        match &self.names {
+            Some(names) => &names.this_class,
+            None => self.class.this_class(),
+        }
+    }
+
+    #[inline]
+    fn super_class(&self) -> Option<&str> {
+        match &self.names {
+            Some(names) => names.super_class.as_deref(),
+            None => self.class.super_class(),
+        }
+    }
+
+    #[inline]
+    fn interface_count(&self) -> usize {
+        self.class.interface_count()
+    }
+
+    #[inline]
+    fn interface(&self, index: usize) -> &str {
+        match &self.names {
+            Some(names) => &names.interfaces[index],
+            None => self.class.interface(index),
+        }
+    }
+
+    #[inline]
+    fn method_count(&self) -> usize {
+        self.class.method_count()
+    }
+
+    fn method(&self, index: usize) -> MemberView<'_, MethodAccessFlags> {
+        let method = self.class.method(index);
+        match &self.names {
+            Some(names) => MemberView {
+                descriptor: &names.methods[index],
+                ..method
+            },
+            None => method,
+        }
+    }
+
+    #[inline]
+    fn field_count(&self) -> usize {
+        self.class.field_count()
+    }
+
+    fn field(&self, index: usize) -> MemberView<'_, FieldAccessFlags> {
+        let field = self.class.field(index);
+        match &self.names {
+            Some(names) => MemberView {
+                descriptor: &names.fields[index],
+                ..field
+            },
+            None => field,
+        }
+    }
+
+    #[inline]
+    fn field_constant(&self, index: usize) -> Option<Constant<'_>> {
+        self.class.field_constant(index)
+    }
+
+    #[inline]
+    fn field_value(&self, index: usize) -> Option<Constant<'_>> {
+        self.class.field_value(index)
+    }
+
+    #[inline]
+    fn call_sites(&self, index: usize) -> Option<Vec<CallSite>> {
+        self.class.call_sites(index)
+    }
+
+    #[inline]
+    fn with_class_file(&self, f: &mut dyn FnMut(&ClassFile<'_>)) {
+        self.class.with_class_file(f);
+    }
+}
@@ -1,10 +1,21 @@
-use std::io;
+use std::sync::Arc;
+use std::{fmt, io};
 
 use cafebabe::ParseError;
 use thiserror::Error;
+#[cfg(feature = "archive")]
 use zip::result::ZipError;
 
+#[cfg(feature = "archive")]
+use crate::bytecode::BytecodeError;
 use crate::descriptor::DescriptorError;
+#[cfg(feature = "dex")]
+use crate::dex::DexError;
+use crate::dsl::PatternError;
+#[cfg(feature = "archive")]
+use crate::jar::{LimitError, UnsupportedFeature};
+#[cfg(feature = "archive")]
+use crate::writer::WriteError;
 
 pub type Result<A, E = Error> = std::result::Result<A, E>;
 
@@ -13,13 +24,88 @@ pub enum Error {
     #[error("{0}")]
     ClassError(ParseError),
     #[error("archive error: {0}")]
+    #[cfg(feature = "archive")]
     ArchiveError(#[from] ZipError),
+    #[error("file is not a zip archive, expected a JAR file")]
+    NotAnArchive,
+    #[error("archive contains no class files")]
+    NoClasses,
+    #[error("file is a DEX file, Android classes need to be converted to JAR first (e.g. with dex2jar)")]
+    DexFile,
+    #[error("archive is an Android package (APK), its classes are stored in DEX format")]
+    AndroidPackage,
+    #[cfg(feature = "dex")]
+    #[error("DEX error: {0}")]
+    DexError(#[from] DexError),
     #[error("method descriptor error: {0}")]
     DescriptorError(#[from] DescriptorError),
+    #[error("bytecode error: {0}")]
+    #[cfg(feature = "archive")]
+    BytecodeError(#[from] BytecodeError),
+    #[error("pattern error: {0}")]
+    PatternError(#[from] PatternError),
+    #[error("archive limit exceeded: {0}")]
+    #[cfg(feature = "archive")]
+    LimitExceeded(#[from] LimitError),
+    #[error("archive uses an unsupported feature: {0}")]
+    #[cfg(feature = "archive")]
+    Unsupported(#[from] UnsupportedFeature),
+    #[error("archive is encrypted and no password was provided")]
+    PasswordRequired,
+    #[error("invalid archive password")]
+    InvalidPassword,
+    #[error("class write error: {0}")]
+    #[cfg(feature = "archive")]
+    WriteError(#[from] WriteError),
     #[error("I/O error: {0}")]
     IoError(#[from] io::Error),
+    #[error("decompiler error: {0}")]
+    DecompilerError(String),
+    #[cfg(feature = "service")]
+    #[error("no open archive named {0}")]
+    UnknownArchive(String),
     #[error("too many matches for pattern {0}")]
     TooManyMatches(usize),
     #[error("pattern {0} not found")]
     PatternNotFound(usize),
+    #[error("search session was started with different patterns")]
+    SessionMismatch,
+    #[error("matches violate the constraint of group {0}")]
+    GroupViolated(usize),
+    #[error("error in {path}: {source}")]
+    EntryError { path: EntryPath, source: Box<Error> },
+}
+
+impl Error {
+    /// Returns the path of the archive entry that caused the error, if the error concerns an entry.
+    pub fn entry_path(&self) -> Option<&EntryPath> {
+        match self {
+            Self::EntryError { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// Identifies an entry of an archive, displayed as `archive!entry`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPath {
+    pub archive: Option<Arc<str>>,
+    pub entry: Box<str>,
+}
+
+#[cfg(feature = "archive")]
+impl EntryPath {
+    #[inline]
+    pub(crate) fn new(archive: Option<Arc<str>>, entry: Box<str>) -> Self {
+        Self { archive, entry }
+    }
+}
+
+impl fmt::Display for EntryPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.archive {
+            Some(archive) => write!(f, "{}!{}", archive, self.entry),
+            None => f.write_str(&self.entry),
+        }
+    }
 }
@@ -0,0 +1,123 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use zip::read::read_zipfile_from_stream;
+
+use crate::jar::{is_class_name, read_entry, JarEntry, Limits};
+use crate::result::Result;
+
+const LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+
+/// A reader recovering the classes of a damaged archive from the headers preceding its entries,
+/// for archives with a missing or corrupt central directory that can't be opened as a [`Jar`](crate::Jar).
+///
+/// Entries are found by scanning the archive for their headers, so anything between them is skipped.
+/// Entries that are cut off are returned as errors, entries that are encrypted
+/// or that only declare their size after their contents can't be recovered.
+///
+/// # Examples
+/// ```no_run
+/// let bytes = std::fs::read("broken.jar").unwrap();
+/// for entry in jars::Salvage::new(bytes).named("broken.jar").filter_map(Result::ok) {
+///     println!("recovered {}", entry.name());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Salvage<T> {
+    bytes: T,
+    pos: usize,
+    limits: Limits,
+    name: Option<Arc<str>>,
+    total_size: u64,
+}
+
+impl<T: AsRef<[u8]>> Salvage<T> {
+    /// Creates a reader of an archive held in memory.
+    #[inline]
+    pub fn new(bytes: T) -> Self {
+        Self::with_limits(bytes, Limits::default())
+    }
+
+    /// Creates a reader of an archive held in memory, reading it within the provided [`Limits`].
+    pub fn with_limits(bytes: T, limits: Limits) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            limits,
+            name: None,
+            total_size: 0,
+        }
+    }
+
+    /// Sets the name of the archive, it will be included in errors that concern its entries.
+    #[inline]
+    pub fn named(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+impl<T: AsRef<[u8]>> Iterator for Salvage<T> {
+    type Item = Result<JarEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.bytes.as_ref();
+        loop {
+            let start = self.pos + find(&bytes[self.pos..], LOCAL_HEADER)?;
+            let mut cursor = Cursor::new(&bytes[start..]);
+            let entry = match read_zipfile_from_stream(&mut cursor) {
+                Ok(Some(file)) if is_class_name(file.name_raw()) => Some(read_entry(
+                    file,
+                    self.name.as_ref(),
+                    None,
+                    &self.limits,
+                    &mut self.total_size,
+                )),
+                Ok(_) => None,
+                // the signature could be a part of the contents of an entry, the next one is looked for after it
+                Err(_) => {
+                    self.pos = start + LOCAL_HEADER.len();
+                    continue;
+                }
+            };
+            // entries are drained when they're dropped, which leaves the cursor past their contents
+            self.pos = start + cursor.position() as usize;
+            if let Some(entry) = entry {
+                return Some(entry);
+            }
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn recover_truncated_archives() {
+        let a = TestClass::new("a").to_bytes();
+        let bytes = TestJar::default()
+            .class(TestClass::new("a"))
+            .entry("b.txt", b"b".as_slice())
+            .class(TestClass::new("c"))
+            .to_bytes()
+            .unwrap();
+        // cut the archive in the middle of the last class
+        let end = bytes.windows(7).position(|window| window == b"c.class").unwrap() + 20;
+        let mut bytes = bytes[..end].to_vec();
+        bytes.splice(0..0, b"garbage".iter().copied());
+
+        let mut salvage = Salvage::new(bytes).named("broken.jar");
+        let entry = salvage.next().unwrap().unwrap();
+        assert_eq!(entry.name(), "a.class");
+        assert_eq!(entry.bytes(), a);
+        let err = salvage.next().unwrap().unwrap_err();
+        assert_eq!(err.entry_path().unwrap().to_string(), "broken.jar!c.class");
+        assert!(salvage.next().is_none());
+    }
+}
@@ -0,0 +1,286 @@
+//! Software bills of materials listing the libraries bundled in an archive.
+//!
+//! With the `serde` feature an [`Sbom`] serializes to a [CycloneDX](https://cyclonedx.org) 1.5 document:
+//! ```json
+//! {
+//!   "bomFormat": "CycloneDX",
+//!   "specVersion": "1.5",
+//!   "version": 1,
+//!   "metadata": { "component": { "type": "application", "name": "app", "version": "1.0" } },
+//!   "components": [{
+//!     "type": "library",
+//!     "bom-ref": "pkg:maven/org.slf4j/slf4j-api@1.7.36",
+//!     "group": "org.slf4j",
+//!     "name": "slf4j-api",
+//!     "version": "1.7.36",
+//!     "purl": "pkg:maven/org.slf4j/slf4j-api@1.7.36",
+//!     "properties": [{ "name": "jars:source", "value": "META-INF/maven/org.slf4j/slf4j-api/pom.properties" }]
+//!   }]
+//! }
+//! ```
+
+use std::io::{Read, Seek};
+
+#[cfg(feature = "fingerprint")]
+use crate::fingerprint::LibraryMatch;
+use crate::jar::Jar;
+use crate::mods::{manifest_attributes, MANIFEST};
+use crate::result::Result;
+
+/// The components of an archive, see [`Jar::sbom`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sbom {
+    /// The archive itself, described by its manifest.
+    pub component: Component,
+    /// The libraries bundled in the archive.
+    pub components: Vec<Component>,
+}
+
+impl Sbom {
+    /// Adds the libraries identified by their fingerprints, unless they're already declared by Maven metadata.
+    ///
+    /// Libraries named like Maven artifacts, e.g. `com.google.guava:guava:31.1`, are added as such,
+    /// other names are split on the last colon into a name and a version, e.g. `guava:31.1`.
+    #[cfg(feature = "fingerprint")]
+    pub fn add_libraries(&mut self, libraries: &[LibraryMatch]) {
+        for library in libraries {
+            let mut parts = library.library.rsplitn(3, ':').collect::<Vec<_>>();
+            parts.reverse();
+            let (group, name, version) = match parts[..] {
+                [group, name, version] => (Some(group), name, Some(version)),
+                [name, version] => (None, name, Some(version)),
+                _ => (None, library.library.as_str(), None),
+            };
+            let declared = self
+                .components
+                .iter()
+                .any(|component| component.group.as_deref() == group && component.name == name);
+            if !declared {
+                self.components.push(Component {
+                    group: group.map(str::to_owned),
+                    name: name.to_owned(),
+                    version: version.map(str::to_owned),
+                    publisher: None,
+                    source: format!("fingerprints of {} classes", library.entries.len()),
+                });
+            }
+        }
+    }
+}
+
+/// A piece of software listed in an [`Sbom`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// The Maven group id, if the component is a Maven artifact.
+    pub group: Option<String>,
+    pub name: String,
+    pub version: Option<String>,
+    pub publisher: Option<String>,
+    /// Where the component was found, e.g. the path of a `pom.properties` file.
+    pub source: String,
+}
+
+impl Component {
+    /// Returns the package URL identifying a Maven artifact, e.g. `pkg:maven/org.slf4j/slf4j-api@1.7.36`.
+    pub fn purl(&self) -> Option<String> {
+        let group = self.group.as_ref()?;
+        Some(match &self.version {
+            Some(version) => format!("pkg:maven/{group}/{}@{version}", self.name),
+            None => format!("pkg:maven/{group}/{}", self.name),
+        })
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Lists the archive and the Maven artifacts it declares in `META-INF/maven` in a software bill of materials.
+    ///
+    /// The archive is named after the `Implementation-Title` or `Bundle-SymbolicName` attribute of its manifest,
+    /// falling back to the name of the archive. Libraries without Maven metadata can be added
+    /// with [`Sbom::add_libraries`] once they're identified by a [`FingerprintDb`](crate::fingerprint::FingerprintDb).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut jar = jars::Jar::open("app.jar")?;
+    /// for component in jar.sbom()?.components {
+    ///     println!("{}: {:?}", component.name, component.version);
+    /// }
+    /// # Ok::<(), jars::Error>(())
+    /// ```
+    pub fn sbom(&mut self) -> Result<Sbom> {
+        let manifest = self.manifest()?.unwrap_or_default();
+        let attribute = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| manifest_attributes(&manifest, name).pop())
+        };
+        let file_name = self.name().map(|name| {
+            let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+            name.rsplit_once('.').map_or(name, |(stem, _)| stem).to_owned()
+        });
+        let component = Component {
+            group: None,
+            name: attribute(&["Implementation-Title", "Bundle-SymbolicName"])
+                .or(file_name)
+                .unwrap_or_default(),
+            version: attribute(&["Implementation-Version", "Bundle-Version"]),
+            publisher: attribute(&["Implementation-Vendor", "Bundle-Vendor"]),
+            source: MANIFEST.to_owned(),
+        };
+        let components = self
+            .maven_artifacts()?
+            .into_iter()
+            .map(|artifact| Component {
+                group: Some(artifact.group_id),
+                name: artifact.artifact_id,
+                version: Some(artifact.version).filter(|version| !version.is_empty()),
+                publisher: None,
+                source: artifact.source,
+            })
+            .collect();
+        Ok(Sbom {
+            component,
+            components,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+mod cyclonedx {
+    use serde::ser::SerializeStruct;
+    use serde::{Serialize, Serializer};
+
+    use super::{Component, Sbom};
+
+    const SPEC_VERSION: &str = "1.5";
+
+    #[derive(Serialize)]
+    struct ComponentRecord<'a> {
+        #[serde(rename = "type")]
+        kind: &'a str,
+        #[serde(rename = "bom-ref")]
+        bom_ref: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        group: Option<&'a str>,
+        name: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        publisher: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        purl: Option<String>,
+        properties: [Property<'a>; 1],
+    }
+
+    #[derive(Serialize)]
+    struct Property<'a> {
+        name: &'a str,
+        value: &'a str,
+    }
+
+    impl<'a> ComponentRecord<'a> {
+        fn new(component: &'a Component, kind: &'a str) -> Self {
+            let purl = component.purl();
+            let bom_ref = purl.clone().unwrap_or_else(|| match &component.version {
+                Some(version) => format!("{}@{version}", component.name),
+                None => component.name.clone(),
+            });
+            Self {
+                kind,
+                bom_ref,
+                group: component.group.as_deref(),
+                name: &component.name,
+                version: component.version.as_deref(),
+                publisher: component.publisher.as_deref(),
+                purl,
+                properties: [Property {
+                    name: "jars:source",
+                    value: &component.source,
+                }],
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Metadata<'a> {
+        component: ComponentRecord<'a>,
+    }
+
+    impl Serialize for Sbom {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let components: Vec<_> = self
+                .components
+                .iter()
+                .map(|component| ComponentRecord::new(component, "library"))
+                .collect();
+            let mut bom = serializer.serialize_struct("Sbom", 5)?;
+            bom.serialize_field("bomFormat", "CycloneDX")?;
+            bom.serialize_field("specVersion", SPEC_VERSION)?;
+            bom.serialize_field("version", &1)?;
+            bom.serialize_field("metadata", &Metadata {
+                component: ComponentRecord::new(&self.component, "application"),
+            })?;
+            bom.serialize_field("components", &components)?;
+            bom.end()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn list_components() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("com/example/Main"))
+            .entry(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\nImplementation-Title: app\nImplementation-Version: 1.0\n",
+            )
+            .entry(
+                "META-INF/maven/org.slf4j/slf4j-api/pom.properties",
+                "groupId=org.slf4j\nartifactId=slf4j-api\nversion=1.7.36\n",
+            )
+            .open()
+            .unwrap();
+        let sbom = jar.sbom().unwrap();
+        assert_eq!(sbom.component.name, "app");
+        assert_eq!(sbom.component.version.as_deref(), Some("1.0"));
+        assert_eq!(sbom.components.len(), 1);
+        assert_eq!(
+            sbom.components[0].purl().as_deref(),
+            Some("pkg:maven/org.slf4j/slf4j-api@1.7.36")
+        );
+
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_value(&sbom).unwrap(),
+            serde_json::json!({
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.5",
+                "version": 1,
+                "metadata": {
+                    "component": {
+                        "type": "application",
+                        "bom-ref": "app@1.0",
+                        "name": "app",
+                        "version": "1.0",
+                        "properties": [{ "name": "jars:source", "value": "META-INF/MANIFEST.MF" }]
+                    }
+                },
+                "components": [{
+                    "type": "library",
+                    "bom-ref": "pkg:maven/org.slf4j/slf4j-api@1.7.36",
+                    "group": "org.slf4j",
+                    "name": "slf4j-api",
+                    "version": "1.7.36",
+                    "purl": "pkg:maven/org.slf4j/slf4j-api@1.7.36",
+                    "properties": [{
+                        "name": "jars:source",
+                        "value": "META-INF/maven/org.slf4j/slf4j-api/pom.properties"
+                    }]
+                }]
+            })
+        );
+    }
+}
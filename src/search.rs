@@ -1,29 +1,46 @@
-use std::io;
+use std::any::Any;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::{fmt, io};
 
-use cafebabe::ClassFile;
-use from_iter::FromIterator;
+use cafebabe::{ClassFile, MethodAccessFlags, ParseOptions};
 
-use crate::descriptor::{Descriptor, MethodDescriptor};
+use crate::cache;
+use crate::decompiler::Decompiler;
+use crate::decrypt::{SharedDecryptor, StringDecryptor};
+use crate::filter::{ClassFilter, Classes, SharedFilter};
+use crate::groups::{check_groups, GroupConstraint, PatternGroup, Violation};
+use crate::indy::WithStaticValues;
+use crate::inherit::{superclass_chain, Superclasses, WithInherited};
 use crate::jar::{Jar, JarEntry};
-use crate::pat::{ClassPat, MemberPat, TypePat};
+#[cfg(feature = "dex")]
+use crate::matching::Undeclared;
+use crate::matching::{check_class_with, Captures, Declared, MemberBinding};
+use crate::maven::Libraries;
+use crate::model::ClassModel;
+use crate::mods::EntryPoint;
+use crate::name::ClassName;
+use crate::pat::{ClassPat, MemberPat, MethodPat, TypePat};
+use crate::provenance::{Provenance, ProvenanceCollector};
+use crate::relocate::{Relocated, Relocations};
 use crate::result::{Error, Result};
+use crate::services::Services;
+use crate::stats::{timed, Counting, SearchStats};
 
 /// Searches for the provided patterns in an archive.
 ///
 /// This function allows for more than one match per pattern.
+/// Matches are ordered by class name, regardless of the order of the entries in the archive.
 pub fn search_many<R: io::Read + io::Seek>(jar: &mut Jar<R>, pats: &[ClassPat]) -> Result<Vec<Match>> {
-    let mut results = vec![];
-    for entry in jar.classes() {
-        let entry = entry?;
-        let class = entry.parse_without_bytecode()?;
-        for (i, pat) in pats.iter().enumerate() {
-            if check_class(&class, pat).is_some() {
-                results.push(Match { entry, pattern: i });
-                break;
-            }
-        }
-    }
-    Ok(results)
+    let check = |class: &SearchedClass, declared: &DeclaredClass, i: usize| {
+        check_class_with(class, declared, &pats[i], &[])
+    };
+    let order = priority_order(pats.iter());
+    let defaults = SearchOptions::default();
+    let options = defaults.for_patterns(pats.iter());
+    let report = search_by(jar, &options, &order, check, |_, _| {})?;
+    Ok(report.matches)
 }
 
 /// Searches for the provided patterns in an archive.
@@ -33,96 +50,1732 @@ pub fn search_exact<R: io::Read + io::Seek, const N: usize>(
     jar: &mut Jar<R>,
     pats: &[ClassPat; N],
 ) -> Result<[JarEntry; N]> {
-    let mut matches = search_many(jar, pats)?;
-    matches.sort_by_key(|mat| mat.pattern);
+    jar.require_classes()?;
+    let matches = exact_matches(search_many(jar, pats)?, N)?;
+    let entries: Vec<_> = matches.into_iter().map(|mat| mat.entry).collect();
+    // there's exactly one match per pattern
+    Ok(entries.try_into().unwrap_or_else(|_| unreachable!()))
+}
+
+/// Searches for the provided patterns in an archive.
+///
+/// This function expects to find exactly one match per pattern and fails otherwise,
+/// like [`search_exact`] but for a number of patterns only known at runtime.
+/// The returned entries are in the same order as the patterns.
+pub fn search_exact_dyn<R: io::Read + io::Seek>(
+    jar: &mut Jar<R>,
+    pats: &[ClassPat],
+) -> Result<Vec<JarEntry>> {
+    jar.require_classes()?;
+    let matches = exact_matches(search_many(jar, pats)?, pats.len())?;
+    Ok(matches.into_iter().map(|mat| mat.entry).collect())
+}
+
+/// Checks every pattern against every class of an archive and reports the patterns
+/// that don't identify exactly one class, see [`Searcher::validate`].
+pub fn validate_patterns<R: io::Read + io::Seek>(
+    jar: &mut Jar<R>,
+    pats: &[ClassPat],
+) -> Result<ValidationReport> {
+    Searcher::new(pats.iter().cloned()).validate(jar)
+}
+
+/// A set of patterns prepared to be searched for in any number of archives.
+///
+/// Preparing the patterns upfront allows the searcher to reject classes based on
+/// their member counts and to compare fully specified descriptors without parsing them.
+#[derive(Debug, Clone)]
+pub struct Searcher {
+    pats: Vec<CompiledPat>,
+    options: SearchOptions,
+    groups: Vec<PatternGroup>,
+}
+
+impl Searcher {
+    pub fn new(pats: impl IntoIterator<Item = ClassPat>) -> Self {
+        let pats = pats.into_iter().map(CompiledPat::new).collect();
+        Self {
+            pats,
+            options: SearchOptions::default(),
+            groups: vec![],
+        }
+    }
+
+    /// Sets the [`SearchOptions`] used by this searcher.
+    #[inline]
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Declares a group of patterns, identified by their indices, whose matches have to satisfy a [`GroupConstraint`].
+    ///
+    /// Violations of the constraints are reported in [`SearchReport::violations`]
+    /// and make [`search_exact`](Self::search_exact) fail, since they usually mean that a pattern matched the wrong class.
+    ///
+    /// # Panics
+    /// Panics if an index is out of range of the patterns.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use jars::{ClassPat, GroupConstraint, Searcher};
+    ///
+    /// let searcher = Searcher::new([ClassPat::default(), ClassPat::interface()])
+    ///     .group([0, 1], GroupConstraint::SamePackage);
+    /// for violation in searcher.search(&mut jars::Jar::open("app.jar")?)?.violations {
+    ///     eprintln!("{violation}");
+    /// }
+    /// # Ok::<(), jars::Error>(())
+    /// ```
+    pub fn group(mut self, patterns: impl IntoIterator<Item = usize>, constraint: GroupConstraint) -> Self {
+        let patterns: Vec<_> = patterns.into_iter().collect();
+        if let Some(&pattern) = patterns.iter().find(|&&i| i >= self.pats.len()) {
+            panic!("pattern {pattern} of a group is out of range");
+        }
+        self.groups.push(PatternGroup { patterns, constraint });
+        self
+    }
+
+    /// Checks the matches of a report against the constraints of the groups of this searcher,
+    /// e.g. after merging the reports of [`Shard`](crate::Shard)s, which only checked their own matches.
+    pub fn check_groups(&self, report: &SearchReport) -> Result<Vec<Violation>> {
+        check_groups(&self.groups, &report.matches)
+    }
+
+    /// Returns the patterns searched for, in the order they were added.
+    pub(crate) fn patterns(&self) -> impl Iterator<Item = &ClassPat> {
+        self.pats.iter().map(|pat| &pat.pat)
+    }
+
+    /// Returns the indices of the patterns in the order they're checked in, see [`ClassPat::priority`].
+    pub(crate) fn priority_order(&self) -> Vec<usize> {
+        priority_order(self.patterns())
+    }
+
+    /// Checks a class that doesn't come from an archive against the patterns, in the provided order.
+    #[cfg(feature = "dex")]
+    pub(crate) fn check_model<C: ClassModel + ?Sized>(
+        &self,
+        class: &C,
+        order: &[usize],
+    ) -> Option<FoundMatch> {
+        first_match(order, |i| self.pats[i].check(class, &Undeclared))
+    }
+
+    /// Searches for the patterns in an archive and returns a [`SearchReport`].
+    ///
+    /// This method allows for more than one match per pattern.
+    /// Matches are ordered by class name, regardless of the order of the entries in the archive.
+    pub fn search<R: io::Read + io::Seek>(&self, jar: &mut Jar<R>) -> Result<SearchReport> {
+        let patterns = || self.patterns();
+        let mut collector = self
+            .options
+            .provenance
+            .then(|| ProvenanceCollector::new(self.pats.len()));
+        let check =
+            |class: &SearchedClass, declared: &DeclaredClass, i: usize| self.pats[i].check(class, declared);
+        let inspect = |class: &SearchedClass, declared: &DeclaredClass| {
+            if let Some(collector) = &mut collector {
+                collector.inspect(class, declared, patterns());
+            }
+        };
+        let order = self.priority_order();
+        let options = self.options.for_patterns(patterns());
+        let mut report = search_by(jar, &options, &order, check, inspect)?;
+        if let Some(collector) = collector {
+            report.provenance = collector.finish(patterns());
+        }
+        report.violations = check_groups(&self.groups, &report.matches)?;
+        Ok(report)
+    }
+
+    /// Searches for the patterns in an archive.
+    ///
+    /// This method allows for more than one match per pattern.
+    #[inline]
+    pub fn search_many<R: io::Read + io::Seek>(&self, jar: &mut Jar<R>) -> Result<Vec<Match>> {
+        Ok(self.search(jar)?.matches)
+    }
+
+    /// Searches for the patterns in an archive.
+    ///
+    /// This method expects to find exactly one match per pattern and fails otherwise,
+    /// or if the matches violate the constraint of a group, see [`Searcher::group`].
+    /// The returned entries are in the same order as the patterns.
+    pub fn search_exact<R: io::Read + io::Seek>(&self, jar: &mut Jar<R>) -> Result<Vec<JarEntry>> {
+        jar.require_classes()?;
+        let report = self.search(jar)?;
+        if let Some(violation) = report.violations.first() {
+            return Err(Error::GroupViolated(violation.group));
+        }
+        let matches = exact_matches(report.matches, self.pats.len())?;
+        Ok(matches.into_iter().map(|mat| mat.entry).collect())
+    }
+
+    /// Checks every pattern against every class of an archive and reports the patterns
+    /// that don't identify exactly one class.
+    ///
+    /// Unlike [`search_exact`](Self::search_exact), this doesn't stop at the first problem,
+    /// which makes it suitable for maintaining large pattern sets.
+    pub fn validate<R: io::Read + io::Seek>(&self, jar: &mut Jar<R>) -> Result<ValidationReport> {
+        let mut matches = vec![vec![]; self.pats.len()];
+        let mut conflicts = vec![];
+        let inspect = |class: &SearchedClass, declared: &DeclaredClass| {
+            let claimed: Vec<usize> = (0..self.pats.len())
+                .filter(|&i| self.pats[i].check(class, declared).is_some())
+                .collect();
+            let name = ClassName::new(class.this_class());
+            for &i in &claimed {
+                matches[i].push(name.clone());
+            }
+            if claimed.len() > 1 {
+                conflicts.push(Conflict {
+                    class: name,
+                    patterns: claimed,
+                });
+            }
+        };
+        let options = self.options.for_patterns(self.patterns());
+        let report = search_by(jar, &options, &[], |_, _, _| None, inspect)?;
+        matches.iter_mut().for_each(|classes| classes.sort());
+        conflicts.sort_by(|a, b| a.class.cmp(&b.class));
+        Ok(ValidationReport {
+            matches,
+            conflicts,
+            warnings: report.warnings,
+        })
+    }
+}
+
+/// Options controlling how a [`Searcher`] processes the classes of an archive.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    parse_bytecode: bool,
+    malformed: MalformedPolicy,
+    provenance: bool,
+    filter: Option<SharedFilter>,
+    inherited_members: bool,
+    skip_libraries: bool,
+    relocations: Relocations,
+    stats: bool,
+    decryptor: Option<SharedDecryptor>,
+    max_matches: Option<usize>,
+    first_match: bool,
+    excluded: Classes,
+    restricted: Option<Classes>,
+    extractor: Option<Extractor>,
+}
+
+impl SearchOptions {
+    /// Sets whether the bytecode of methods should be parsed, disabled by default.
+    ///
+    /// Parsed bytecode is what call sites are read from, it's enabled automatically when a pattern
+    /// matches them with [`MethodPat::call_site`](crate::MethodPat::call_site). Otherwise it only
+    /// exposes `CodeData::bytecode` to custom predicates and extractors.
+    #[inline]
+    pub fn parse_bytecode(mut self, parse: bool) -> Self {
+        self.parse_bytecode = parse;
+        self
+    }
+
+    /// Sets the [`MalformedPolicy`] applied to classes that cannot be read or parsed.
+    #[inline]
+    pub fn malformed(mut self, policy: MalformedPolicy) -> Self {
+        self.malformed = policy;
+        self
+    }
+
+    /// Sets whether [`Provenance`] of the patterns should be collected, disabled by default.
+    ///
+    /// This checks every pattern against every class in full, which makes searches slower.
+    #[inline]
+    pub fn provenance(mut self, provenance: bool) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// Sets whether member patterns can match the members inherited from superclasses in the archive,
+    /// disabled by default.
+    ///
+    /// Inherited members are matched after the members of the class, those of the nearest superclass first,
+    /// and their [`MemberBinding`]s have indices past the members of the class.
+    /// This helps with obfuscators moving members to synthetic base classes.
+    #[inline]
+    pub fn inherited_members(mut self, inherited: bool) -> Self {
+        self.inherited_members = inherited;
+        self
+    }
+
+    /// Sets whether the classes of the libraries bundled in the archive should be skipped, disabled by default.
+    ///
+    /// Libraries are identified by their [`MavenArtifact`](crate::MavenArtifact)s
+    /// and skipped by the packages named after their group ids.
+    #[inline]
+    pub fn skip_libraries(mut self, skip: bool) -> Self {
+        self.skip_libraries = skip;
+        self
+    }
+
+    /// Adds a package renamed before classes are matched, e.g. `org/example/shadow/guava` to `com/google/common`,
+    /// so that patterns referring to the classes of a library also match its shaded copies.
+    ///
+    /// Renaming applies to the names of classes, their supertypes and the descriptors of their members.
+    /// Packages are checked in the order they were added and subpackages are renamed along with them.
+    #[inline]
+    pub fn relocate(mut self, from: &str, to: &str) -> Self {
+        self.relocations.push(from, to);
+        self
+    }
+
+    /// Skips the classes with the provided internal names, e.g. the ones identified by a previous search,
+    /// see [`SearchReport::class_names`].
+    ///
+    /// This allows narrowing down the remaining classes with looser patterns.
+    pub fn exclude_classes(mut self, names: impl IntoIterator<Item = impl Into<ClassName>>) -> Self {
+        for name in names {
+            self.excluded.insert(name);
+        }
+        self
+    }
+
+    /// Only searches the classes with the provided internal names, adding to the names provided before.
+    pub fn only_classes(mut self, names: impl IntoIterator<Item = impl Into<ClassName>>) -> Self {
+        let restricted = self.restricted.get_or_insert_with(Classes::default);
+        for name in names {
+            restricted.insert(name);
+        }
+        self
+    }
+
+    /// Sets the number of matches kept for each pattern, the search stops once every pattern has reached it.
+    ///
+    /// The kept matches are the first ones in the order of the archive, the others are dropped
+    /// and [`SearchReport::truncated`] is set.
+    #[inline]
+    pub fn max_matches_per_pattern(mut self, max: usize) -> Self {
+        self.max_matches = Some(max);
+        self
+    }
+
+    /// Makes the search stop at the first match of any pattern.
+    #[inline]
+    pub fn first_match_only(mut self) -> Self {
+        self.first_match = true;
+        self
+    }
 
-    if let Some((pat, mat)) = matches.iter().enumerate().find(|(i, m)| *i != m.pattern) {
-        if pat > mat.pattern {
-            return Err(Error::TooManyMatches(mat.pattern));
+    /// Sets whether [`SearchStats`] should be collected, disabled by default.
+    #[inline]
+    pub fn stats(mut self, stats: bool) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Sets a [`StringDecryptor`] recovering the strings that obfuscators replace with calls to decryption stubs,
+    /// so that [`FieldPat::value`](crate::FieldPat::value) matches their plaintext.
+    #[inline]
+    pub fn string_decryptor(mut self, decryptor: impl StringDecryptor + Send + Sync + 'static) -> Self {
+        self.decryptor = Some(SharedDecryptor(Arc::new(decryptor)));
+        self
+    }
+
+    /// Sets a [`ClassFilter`] skipping the classes that can't match before they're parsed.
+    #[inline]
+    pub fn filter(mut self, filter: impl ClassFilter + Send + Sync + 'static) -> Self {
+        self.filter = Some(SharedFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Sets a function attaching data to every match, called with the matched class and the index of its pattern,
+    /// e.g. to read a version string from a constant without another pass over the archive.
+    ///
+    /// The data is available with [`Match::data`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use jars::{ClassModel, ClassPat, SearchOptions, Searcher};
+    ///
+    /// let options = SearchOptions::default()
+    ///     .match_data(|class, _| class.field_value(0).map(|value| value.into_owned()));
+    /// let searcher = Searcher::new([ClassPat::default()]).with_options(options);
+    /// for mat in searcher.search_many(&mut jars::Jar::open("app.jar")?)? {
+    ///     println!("{}: {:?}", mat.entry.name(), mat.data::<jars::ConstantValue>());
+    /// }
+    /// # Ok::<(), jars::Error>(())
+    /// ```
+    #[inline]
+    pub fn match_data<T: Any + Send + Sync>(
+        mut self,
+        extract: impl Fn(&ClassFile<'_>, usize) -> Option<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.extractor = Some(Extractor(Arc::new(move |class, pattern| {
+            extract(class, pattern).map(|data| Arc::new(data) as _)
+        })));
+        self
+    }
+
+    /// Returns the options with bytecode parsing enabled if any of the patterns matches call sites.
+    fn for_patterns<'a>(&self, mut pats: impl Iterator<Item = &'a ClassPat>) -> Cow<'_, Self> {
+        if !self.parse_bytecode && pats.any(ClassPat::uses_call_sites) {
+            Cow::Owned(self.clone().parse_bytecode(true))
         } else {
-            return Err(Error::PatternNotFound(pat));
+            Cow::Borrowed(self)
         }
     }
 
-    let res = <[JarEntry; N]>::from_iter(matches.into_iter().map(|mat| mat.entry));
-    Ok(res)
+    fn parse_options(&self) -> ParseOptions {
+        let mut opts = ParseOptions::default();
+        opts.parse_bytecode(self.parse_bytecode);
+        opts
+    }
 }
 
-fn check_class(class: &ClassFile, pat: &ClassPat) -> Option<()> {
-    if !class.access_flags.contains(pat.flags) {
-        return None;
+type ExtractFn = dyn Fn(&ClassFile<'_>, usize) -> Option<Arc<dyn Any + Send + Sync>> + Send + Sync;
+
+/// A function set with [`SearchOptions::match_data`], shared between copies of the options.
+#[derive(Clone)]
+struct Extractor(Arc<ExtractFn>);
+
+impl fmt::Debug for Extractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Extractor")
     }
-    match (&pat.base, class.super_class.as_deref()) {
-        (None, None) => {}
-        (None, Some("java/lang/Object")) => {}
-        (Some(TypePat::Any), Some(_)) => {}
-        (Some(pat), Some(base)) if pat.class_name()? == base => {}
-        _ => return None,
+}
+
+/// Determines what happens when a class in the archive cannot be read or parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MalformedPolicy {
+    /// Aborts the search with the error.
+    #[default]
+    FailFast,
+    /// Ignores the class.
+    SkipMalformed,
+    /// Ignores the class and records the error in [`SearchReport::warnings`].
+    CollectWarnings,
+}
+
+impl MalformedPolicy {
+    fn handle(self, err: Error, warnings: &mut Vec<Error>) -> Result<()> {
+        match self {
+            Self::FailFast => return Err(err),
+            Self::SkipMalformed => {}
+            Self::CollectWarnings => warnings.push(err),
+        }
+        Ok(())
     }
+}
 
-    for (i, pat) in pat.impls.iter().enumerate() {
-        if class.interfaces.get(i)? != pat.class_name()? {
-            return None;
+/// The outcome of a search performed by a [`Searcher`].
+#[derive(Debug, Default)]
+pub struct SearchReport {
+    pub matches: Vec<Match>,
+    /// Errors of the classes that were skipped with [`MalformedPolicy::CollectWarnings`].
+    pub warnings: Vec<Error>,
+    /// Provenance of each pattern, only collected with [`SearchOptions::provenance`].
+    pub provenance: Vec<Provenance>,
+    /// Performance counters, only collected with [`SearchOptions::stats`].
+    pub stats: Option<SearchStats>,
+    /// Whether matches were dropped or classes left unchecked because of
+    /// [`SearchOptions::max_matches_per_pattern`] or [`SearchOptions::first_match_only`].
+    pub truncated: bool,
+    /// Constraints of the groups of patterns violated by the matches, see [`Searcher::group`].
+    pub violations: Vec<Violation>,
+}
+
+impl SearchReport {
+    /// Combines the report of a search of another part of the archive, e.g. of another [`Shard`](crate::Shard),
+    /// keeping the matches ordered by class name.
+    ///
+    /// Both reports have to come from searches with the same patterns.
+    pub fn merge(&mut self, other: SearchReport) {
+        self.matches.extend(other.matches);
+        sort_matches(&mut self.matches);
+        self.warnings.extend(other.warnings);
+        if self.provenance.is_empty() {
+            self.provenance = other.provenance;
+        } else {
+            for (prov, other) in self.provenance.iter_mut().zip(&other.provenance) {
+                prov.merge(other);
+            }
         }
+        self.stats = match (self.stats.take(), other.stats) {
+            (Some(mut stats), Some(other)) => {
+                stats.merge(&other);
+                Some(stats)
+            }
+            (stats, other) => stats.or(other),
+        };
+        self.truncated |= other.truncated;
+        self.violations.extend(other.violations);
     }
 
-    let mut methods = class.methods.iter();
-    let mut fields = class.fields.iter();
+    /// Combines the reports of searches of the parts of an archive, see [`SearchReport::merge`].
+    pub fn merged(reports: impl IntoIterator<Item = SearchReport>) -> Self {
+        reports.into_iter().fold(Self::default(), |mut merged, report| {
+            merged.merge(report);
+            merged
+        })
+    }
 
-    for member in &pat.members {
-        match member {
-            MemberPat::Method {
-                flags,
-                param_types,
-                ret_type,
-            } => {
-                let method = methods.next()?;
-                if !method.access_flags.contains(*flags) {
-                    return None;
-                }
+    /// Returns the names of the matched classes.
+    pub fn class_names(&self) -> Vec<ClassName> {
+        self.matches
+            .iter()
+            .filter_map(|mat| Some(mat.entry.header().ok()?.this_class.clone()))
+            .collect()
+    }
+}
 
-                let descriptor = MethodDescriptor::parse(&method.descriptor).ok()?;
-                if descriptor.param_types.len() != param_types.len() {
-                    return None;
-                }
+/// The outcome of [`Searcher::validate`].
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Names of the classes matched by each pattern, indexed by pattern.
+    pub matches: Vec<Vec<ClassName>>,
+    /// Classes matched by more than one pattern.
+    pub conflicts: Vec<Conflict>,
+    /// Errors of the classes that were skipped with [`MalformedPolicy::CollectWarnings`].
+    pub warnings: Vec<Error>,
+}
+
+impl ValidationReport {
+    /// Returns whether every pattern matched exactly one class and no class was matched twice.
+    pub fn is_valid(&self) -> bool {
+        self.conflicts.is_empty() && self.matches.iter().all(|classes| classes.len() == 1)
+    }
+
+    /// Returns the indices of the patterns that didn't match any class.
+    pub fn missing(&self) -> impl Iterator<Item = usize> + '_ {
+        self.matches
+            .iter()
+            .enumerate()
+            .filter(|(_, classes)| classes.is_empty())
+            .map(|(i, _)| i)
+    }
 
-                match (ret_type, descriptor.return_type) {
-                    (TypePat::Void, None) => {}
-                    (tp, Some(ty)) => check_type(ty, tp)?,
-                    _ => return None,
+    /// Returns the indices of the patterns that matched more than one class, along with the classes.
+    pub fn ambiguous(&self) -> impl Iterator<Item = (usize, &[ClassName])> + '_ {
+        self.matches
+            .iter()
+            .enumerate()
+            .filter(|(_, classes)| classes.len() > 1)
+            .map(|(i, classes)| (i, classes.as_slice()))
+    }
+}
+
+/// A class matched by more than one pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub class: ClassName,
+    /// Indices of the patterns that matched the class.
+    pub patterns: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledPat {
+    pat: ClassPat,
+    method_count: usize,
+    field_count: usize,
+    descriptors: Vec<Option<String>>,
+}
+
+impl CompiledPat {
+    fn new(pat: ClassPat) -> Self {
+        let mut method_count = 0;
+        let mut field_count = 0;
+        let descriptors = pat
+            .members
+            .iter()
+            .map(|member| match member {
+                MemberPat::Method(pat) => {
+                    method_count += 1;
+                    method_descriptor_string(pat)
                 }
-                for (pat, desc) in param_types.iter().zip(descriptor.param_types) {
-                    check_type(desc, pat)?;
+                MemberPat::Field(pat) => {
+                    field_count += 1;
+                    pat.field_type.descriptor().map(|desc| desc.to_string())
                 }
-            }
-            MemberPat::Field { flags, field_type } => {
-                let field = fields.next()?;
-                if !field.access_flags.contains(*flags) {
-                    return None;
+                MemberPat::Custom(_) => {
+                    if member.is_method() {
+                        method_count += 1;
+                    } else {
+                        field_count += 1;
+                    }
+                    None
                 }
-                let descriptor = Descriptor::parse(&field.descriptor).ok()?;
-                check_type(descriptor, field_type)?;
-            }
+            })
+            .collect();
+
+        Self {
+            pat,
+            method_count,
+            field_count,
+            descriptors,
+        }
+    }
+
+    fn check<C: ClassModel + ?Sized>(
+        &self,
+        class: &C,
+        declared: &dyn Declared,
+    ) -> Option<(Vec<MemberBinding>, Captures)> {
+        // strategies skipping members, as well as lambda bodies, which are only known after reading the bytecode,
+        // allow the class to have more members than the pattern
+        let exhaustive = self.pat.strategy.is_exhaustive();
+        let methods_match = if exhaustive && !self.pat.skip_lambda_bodies {
+            class.method_count() == self.method_count
+        } else {
+            class.method_count() >= self.method_count
+        };
+        let fields_match = if exhaustive {
+            class.field_count() == self.field_count
+        } else {
+            class.field_count() >= self.field_count
+        };
+        if !methods_match || !fields_match {
+            return None;
         }
+        check_class_with(class, declared, &self.pat, &self.descriptors)
     }
+}
 
-    if methods.len() > 0 || fields.len() > 0 {
+fn method_descriptor_string(pat: &MethodPat) -> Option<String> {
+    // the last parameter of varargs methods matches on either the array or its elements
+    if pat.flags.contains(MethodAccessFlags::VARARGS) {
         return None;
     }
+    let mut str = String::from("(");
+    for param in &pat.param_types {
+        str.push_str(&param.descriptor()?.to_string());
+    }
+    str.push(')');
+    match &pat.ret_type {
+        TypePat::Void => str.push('V'),
+        typ => str.push_str(&typ.descriptor()?.to_string()),
+    }
+    Some(str)
+}
 
-    Some(())
+/// A class parsed during a search, along with its bytes and, if enabled, its inherited members
+/// and relocated names.
+type SearchedClass<'a, 'b, 'c, 'd> =
+    Relocated<WithInherited<WithStaticValues<'a, ClassFile<'b>>, WithStaticValues<'c, ClassFile<'d>>>>;
+
+/// Returns the indices of the patterns from the highest priority, keeping the order of patterns of the same one.
+fn priority_order<'a>(pats: impl Iterator<Item = &'a ClassPat>) -> Vec<usize> {
+    let mut order: Vec<_> = pats.enumerate().map(|(i, pat)| (i, pat.priority)).collect();
+    order.sort_by_key(|&(_, priority)| Reverse(priority));
+    order.into_iter().map(|(i, _)| i).collect()
 }
 
-fn check_type(descriptor: Descriptor, pat: &TypePat) -> Option<()> {
-    match pat {
-        TypePat::Any => Some(()),
-        TypePat::Match(expected) if descriptor == *expected => Some(()),
-        _ => None,
+/// The pattern matched by a class with its bindings, and the sorted indices of all patterns satisfied by the class.
+pub(crate) type FoundMatch = ((usize, (Vec<MemberBinding>, Captures)), Vec<usize>);
+
+/// Checks all patterns in the provided order and returns the first one satisfied.
+fn first_match(
+    order: &[usize],
+    check: impl Fn(usize) -> Option<(Vec<MemberBinding>, Captures)>,
+) -> Option<FoundMatch> {
+    let mut found = None;
+    let mut satisfied = vec![];
+    for &i in order {
+        if let Some(bound) = check(i) {
+            satisfied.push(i);
+            found.get_or_insert((i, bound));
+        }
     }
+    satisfied.sort_unstable();
+    found.map(|found| (found, satisfied))
+}
+
+/// Searches an archive, checking the patterns in the provided order, see [`priority_order`].
+fn search_by<R: io::Read + io::Seek>(
+    jar: &mut Jar<R>,
+    options: &SearchOptions,
+    order: &[usize],
+    check: impl Fn(&SearchedClass, &DeclaredClass, usize) -> Option<(Vec<MemberBinding>, Captures)>,
+    mut inspect: impl FnMut(&SearchedClass, &DeclaredClass),
+) -> Result<SearchReport> {
+    let parse_options = options.parse_options();
+    let _cache = cache::scope();
+    let mut report = SearchReport::default();
+    let declarations = Declarations::read(jar, options, &mut report.warnings)?;
+    let superclasses = if options.inherited_members {
+        Superclasses::read(jar)
+    } else {
+        Superclasses::default()
+    };
+    let superclasses = superclasses.parse(&parse_options);
+    let libraries = if options.skip_libraries {
+        Libraries::read(jar)?
+    } else {
+        Libraries::default()
+    };
+    let mut stats = options.stats.then(SearchStats::default);
+    let classes = ClassFilter::and(options.restricted.as_ref(), (&options.excluded).not());
+    let filter = Counting::new(ClassFilter::and(options.filter.as_ref(), &libraries).and(classes));
+    let pattern_count = order.len();
+    let mut counts = vec![0; pattern_count];
+    let decryptor = options
+        .decryptor
+        .as_ref()
+        .map(|shared| &*shared.0 as &dyn StringDecryptor);
+    for entry in jar.classes_filtered(&filter) {
+        let saturated = options
+            .max_matches
+            .is_some_and(|max| pattern_count > 0 && counts.iter().all(|&count| count >= max));
+        if saturated || (options.first_match && !report.matches.is_empty()) {
+            report.truncated = true;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                options.malformed.handle(err, &mut report.warnings)?;
+                continue;
+            }
+        };
+        if let Some(stats) = &mut stats {
+            stats.classes_scanned += 1;
+            stats.bytes_read += entry.bytes().len() as u64;
+        }
+        let parse_time = stats.as_mut().map(|stats| &mut stats.parse_time);
+        let class = match timed(parse_time, || entry.parse_with_options(&parse_options)) {
+            Ok(class) => class,
+            Err(err) => {
+                options.malformed.handle(err, &mut report.warnings)?;
+                continue;
+            }
+        };
+        let match_time = stats.as_mut().map(|stats| &mut stats.match_time);
+        let found = timed(match_time, || {
+            let supers = superclass_chain(&superclasses, &class)
+                .into_iter()
+                .map(|(base, bytes)| WithStaticValues::new(base, bytes))
+                .collect();
+            let declared = declarations.of(&class.this_class);
+            let class = WithStaticValues::new(&class, entry.bytes()).with_decryptor(decryptor);
+            let class = Relocated::new(WithInherited::new(class, supers), &options.relocations);
+            inspect(&class, &declared);
+            first_match(order, |i| check(&class, &declared, i))
+        });
+        if let Some(((pattern, (bindings, captures)), satisfied)) = found {
+            if options.max_matches.is_some_and(|max| counts[pattern] >= max) {
+                report.truncated = true;
+                continue;
+            }
+            counts[pattern] += 1;
+            entry.cache_header(&class);
+            let data = options
+                .extractor
+                .as_ref()
+                .and_then(|extractor| (extractor.0)(&class, pattern));
+            report.matches.push(Match {
+                entry,
+                pattern,
+                bindings,
+                captures,
+                satisfied,
+                data,
+            });
+        }
+    }
+    sort_matches(&mut report.matches);
+    report.stats = stats.map(|stats| SearchStats {
+        classes_filtered: filter.rejected(),
+        ..stats
+    });
+    Ok(report)
+}
+
+/// Sorts matches by class name, since entries are read in the order of the archive,
+/// which can differ between otherwise identical archives.
+fn sort_matches(matches: &mut [Match]) {
+    matches.sort_by_cached_key(|mat| {
+        let class = mat.entry.header().map(|header| header.this_class.clone()).ok();
+        (class, mat.entry.name().to_owned())
+    });
+}
+
+/// Classes declared by the metadata of an archive, which can be required by patterns.
+#[derive(Debug, Default)]
+struct Declarations {
+    pub services: Services,
+    pub entry_points: Vec<EntryPoint>,
+}
+
+impl Declarations {
+    fn read<R: io::Read + io::Seek>(
+        jar: &mut Jar<R>,
+        options: &SearchOptions,
+        warnings: &mut Vec<Error>,
+    ) -> Result<Self> {
+        let services = match jar.services() {
+            Ok(services) => services,
+            Err(err) => {
+                options.malformed.handle(err, warnings)?;
+                Services::default()
+            }
+        };
+        let entry_points = match jar.declared_entry_points() {
+            Ok(entry_points) => entry_points,
+            Err(err) => {
+                options.malformed.handle(err, warnings)?;
+                vec![]
+            }
+        };
+        Ok(Self {
+            services,
+            entry_points,
+        })
+    }
+
+    /// Returns the declarations concerning a class, identified by its name in the archive.
+    fn of<'a>(&'a self, class: &'a str) -> DeclaredClass<'a> {
+        DeclaredClass {
+            declarations: self,
+            class,
+        }
+    }
+}
+
+/// The declarations of an archive concerning one of its classes, checked separately from the class.
+struct DeclaredClass<'a> {
+    declarations: &'a Declarations,
+    class: &'a str,
+}
+
+impl Declared for DeclaredClass<'_> {
+    fn provides_service(&self, service: &str) -> bool {
+        self.declarations
+            .services
+            .get(service)
+            .is_some_and(|providers| providers.iter().any(|provider| provider == self.class))
+    }
+
+    fn is_entry_point(&self, kind: &str) -> bool {
+        self.declarations
+            .entry_points
+            .iter()
+            .any(|entry| entry.kind == kind && entry.class == self.class)
+    }
+}
+
+fn exact_matches(matches: Vec<Match>, pattern_count: usize) -> Result<Vec<Match>> {
+    let mut found: Vec<Option<Match>> = (0..pattern_count).map(|_| None).collect();
+    for mat in matches {
+        let pattern = mat.pattern;
+        if found[pattern].replace(mat).is_some() {
+            return Err(Error::TooManyMatches(pattern));
+        }
+    }
+    found
+        .into_iter()
+        .enumerate()
+        .map(|(i, mat)| mat.ok_or(Error::PatternNotFound(i)))
+        .collect()
 }
 
 #[derive(Debug)]
 pub struct Match {
     pub entry: JarEntry,
     pub pattern: usize,
+    pub bindings: Vec<MemberBinding>,
+    /// Types bound to the named types of the pattern, see [`Captures`].
+    pub captures: Captures,
+    /// Indices of every pattern the class satisfies, sorted, including [`Match::pattern`]
+    /// which is the one with the highest priority, see [`ClassPat::priority`].
+    pub satisfied: Vec<usize>,
+    /// Data attached by the function set with [`SearchOptions::match_data`].
+    pub data: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Match {
+    /// Returns the data attached to the match, if there's any of type `T`.
+    #[inline]
+    pub fn data<T: Any>(&self) -> Option<&T> {
+        self.data.as_deref()?.downcast_ref()
+    }
+
+    /// Decompiles the matched class to Java source with the provided [`Decompiler`].
+    #[inline]
+    pub fn decompile(&self, decompiler: &impl Decompiler) -> Result<String> {
+        self.entry.decompile(decompiler)
+    }
+}
+
+/// Matches of a set of named patterns, e.g. ones parsed with [`parse_patterns`](crate::parse_patterns).
+///
+/// With the `serde` feature this serializes to a stable schema intended for other tools.
+#[derive(Debug, Default)]
+pub struct MatchSet {
+    /// Names of the patterns, indexed by [`Match::pattern`].
+    pub patterns: Vec<String>,
+    pub matches: Vec<Match>,
+    /// Provenance of the patterns, indexed by [`Match::pattern`], empty if it wasn't collected.
+    pub provenance: Vec<Provenance>,
+}
+
+impl MatchSet {
+    /// Returns the name of the pattern of a match.
+    #[inline]
+    pub fn pattern_name(&self, mat: &Match) -> Option<&str> {
+        self.patterns.get(mat.pattern).map(String::as_str)
+    }
+
+    /// Adds the matches of a [`SearchReport`] and merges its provenance, returning its warnings.
+    pub fn add_report(&mut self, report: SearchReport) -> Vec<Error> {
+        self.matches.extend(report.matches);
+        if self.provenance.is_empty() {
+            self.provenance = report.provenance;
+        } else {
+            for (prov, other) in self.provenance.iter_mut().zip(&report.provenance) {
+                prov.merge(other);
+            }
+        }
+        report.warnings
+    }
+
+    /// Returns the provenance of the pattern of a match, if it was collected.
+    #[inline]
+    pub fn provenance(&self, mat: &Match) -> Option<&Provenance> {
+        self.provenance.get(mat.pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::descriptor::Descriptor;
+    use crate::model::{ConstantValue, Member};
+    use crate::pat::{BasePat, FieldPat, MatchStrategy};
+    use crate::testing::{TestClass, TestJar};
+    use crate::writer::{ClassBuilder, Code, FieldBuilder, MethodBuilder};
+    use crate::{field, method, Visibility};
+
+    fn sample_jar() -> TestJar {
+        TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .field(FieldAccessFlags::PRIVATE, "b", "I")
+                    .method(MethodAccessFlags::PUBLIC, "c", "(Ljava/lang/String;)V"),
+            )
+            .class(TestClass::new("d").method(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::FINAL,
+                "e",
+                "(Ljava/lang/String;)V",
+            ))
+    }
+
+    #[test]
+    fn search_with_bindings() {
+        let mut jar = sample_jar().open().unwrap();
+        let pat = ClassPat::default()
+            .with(method!(public (String) -> ()))
+            .with(field!([private] i32));
+
+        let matches = search_many(&mut jar, &[pat]).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry.header().unwrap().this_class, "a");
+        assert_eq!(matches[0].bindings, vec![
+            MemberBinding::Method(0),
+            MemberBinding::Field(0)
+        ]);
+    }
+
+    #[test]
+    fn build_member_patterns() {
+        let class = TestClass::new("a")
+            .method(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::FINAL,
+                "run",
+                "(I)Ljava/lang/String;",
+            )
+            .field(FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC, "x", "J")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let matches = |member: MemberPat| {
+            ClassPat::default()
+                .strategy(MatchStrategy::BestEffort)
+                .with(member)
+                .matches(&class)
+        };
+
+        let method = MethodPat::default()
+            .param(TypePat::Match(Descriptor::Integer))
+            .returns(TypePat::Match(Descriptor::Object("java/lang/String")));
+        assert!(matches(method.clone().into()));
+        assert!(matches(method.clone().name("run").into()));
+        assert!(!matches(method.clone().name("stop").into()));
+        assert!(!matches(method.clone().param(TypePat::Any).into()));
+        assert!(!matches(method.clone().returns(TypePat::Void).into()));
+        assert!(!matches(MethodPat::default().returns(TypePat::Any).into()));
+
+        let flags = method
+            .clone()
+            .flags(MethodAccessFlags::PUBLIC)
+            .flags(MethodAccessFlags::FINAL);
+        assert_eq!(
+            flags,
+            method
+                .clone()
+                .flags(MethodAccessFlags::PUBLIC | MethodAccessFlags::FINAL)
+        );
+        assert!(matches(flags.into()));
+        assert!(!matches(method.flags(MethodAccessFlags::STATIC).into()));
+
+        let field = FieldPat::default().of_type(TypePat::Match(Descriptor::Long));
+        assert!(matches(field.clone().name("x").into()));
+        assert!(!matches(field.clone().name("y").into()));
+        assert!(!matches(
+            FieldPat::default()
+                .of_type(TypePat::Match(Descriptor::Integer))
+                .into()
+        ));
+        let flags = field
+            .clone()
+            .flags(FieldAccessFlags::PRIVATE)
+            .flags(FieldAccessFlags::STATIC);
+        assert!(matches(flags.into()));
+        assert!(!matches(field.flags(FieldAccessFlags::FINAL).into()));
+    }
+
+    #[test]
+    fn search_exact_flags() {
+        let pat = ClassPat::default().with(method!(public (String) -> ()));
+        let mut jar = sample_jar().open().unwrap();
+        let entries = search_exact_dyn(&mut jar, std::slice::from_ref(&pat)).unwrap();
+        assert_eq!(entries[0].header().unwrap().this_class, "d");
+        let [entry] = search_exact(&mut jar, &[pat]).unwrap();
+        assert_eq!(entry.header().unwrap().this_class, "d");
+
+        let pat = ClassPat::default()
+            .with(method!(public (String) -> ()))
+            .exact_flags();
+        let mut jar = sample_jar().open().unwrap();
+        assert!(matches!(
+            search_exact(&mut jar, &[pat]),
+            Err(Error::PatternNotFound(0))
+        ));
+    }
+
+    #[test]
+    fn match_parsed_classes() {
+        let bytes = TestClass::new("a")
+            .field(FieldAccessFlags::PRIVATE, "b", "I")
+            .method(MethodAccessFlags::PUBLIC, "c", "(Ljava/lang/String;)V")
+            .to_bytes();
+        let class = cafebabe::parse_class(&bytes).unwrap();
+
+        let pat = ClassPat::default()
+            .strategy(MatchStrategy::Set)
+            .with(method!(public (String) -> ()))
+            .with(field!([private] i32));
+        assert!(pat.matches(&class));
+        assert_eq!(
+            pat.matches_with_bindings(&class),
+            Some(vec![MemberBinding::Method(0), MemberBinding::Field(0)])
+        );
+
+        let pat = ClassPat::default().with(method!(public (String) -> ()));
+        assert!(!pat.matches(&class));
+        assert_eq!(pat.matches_with_bindings(&class), None);
+    }
+
+    #[test]
+    fn match_exact_flags() {
+        let class = TestClass::new("a")
+            .method(MethodAccessFlags::PUBLIC | MethodAccessFlags::FINAL, "b", "()V")
+            .field(FieldAccessFlags::PRIVATE, "c", "I")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let pat = || {
+            ClassPat::default()
+                .strategy(MatchStrategy::BestEffort)
+                .exact_flags()
+        };
+        assert!(pat().public().matches(&class));
+        assert!(!pat().matches(&class));
+        assert!(pat()
+            .public()
+            .with(method!(public final () -> ()))
+            .matches(&class));
+        assert!(!pat().public().with(method!(public () -> ())).matches(&class));
+        assert!(pat().public().with(field!([private] i32)).matches(&class));
+        assert!(!pat().public().with(field!(i32)).matches(&class));
+
+        let interface = TestClass::new("d")
+            .flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT)
+            .to_bytes();
+        let interface = cafebabe::parse_class(&interface).unwrap();
+        assert!(ClassPat::interface().matches(&interface));
+        assert!(ClassPat::interface().public().exact_flags().matches(&interface));
+        assert!(!ClassPat::interface().exact_flags().matches(&interface));
+        assert!(!ClassPat::interface().matches(&class));
+    }
+
+    #[test]
+    fn parse_bytecode_for_predicates() {
+        let method = MethodBuilder::new(MethodAccessFlags::PUBLIC, "run", "()V").code(Code {
+            max_stack: 0,
+            max_locals: 1,
+            bytecode: vec![0xb1],
+        });
+        let class = ClassBuilder::new("a").with_method(method);
+        let mut jar = TestJar::default()
+            .entry("a.class", class.to_bytes())
+            .open()
+            .unwrap();
+        let pat = ClassPat::default()
+            .strategy(MatchStrategy::BestEffort)
+            .with_predicate(|class| {
+                class
+                    .methods
+                    .iter()
+                    .filter_map(|method| crate::indy::code(&method.attributes))
+                    .any(|code| code.bytecode.is_some())
+            });
+        let searcher = Searcher::new([pat]);
+        assert!(searcher.search_many(&mut jar).unwrap().is_empty());
+
+        let searcher = searcher.with_options(SearchOptions::default().parse_bytecode(true));
+        assert_eq!(searcher.search_many(&mut jar).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn limit_matches() {
+        let jar = || {
+            ["a", "b", "c"]
+                .into_iter()
+                .map(|name| TestClass::new(name).method(MethodAccessFlags::PUBLIC, "x", "()V"))
+                .fold(TestJar::default(), TestJar::class)
+                .open()
+                .unwrap()
+        };
+        let searcher = |options| {
+            Searcher::new([ClassPat::default().with(method!(public () -> ()))]).with_options(options)
+        };
+
+        let report = searcher(SearchOptions::default()).search(&mut jar()).unwrap();
+        assert_eq!(report.matches.len(), 3);
+        assert!(!report.truncated);
+
+        let options = SearchOptions::default().max_matches_per_pattern(2);
+        let report = searcher(options).search(&mut jar()).unwrap();
+        assert_eq!(report.matches.len(), 2);
+        assert!(report.truncated);
+
+        let options = SearchOptions::default().first_match_only();
+        let report = searcher(options).search(&mut jar()).unwrap();
+        assert_eq!(report.matches.len(), 1);
+        assert!(report.truncated);
+
+        let options = SearchOptions::default().exclude_classes(report.class_names());
+        let report = searcher(options).search(&mut jar()).unwrap();
+        assert_eq!(report.class_names(), ["b", "c"]);
+        let options = SearchOptions::default()
+            .only_classes(["a", "c"])
+            .exclude_classes(["c"]);
+        let report = searcher(options).search(&mut jar()).unwrap();
+        assert_eq!(report.class_names(), ["a"]);
+    }
+
+    #[test]
+    fn order_independent_results() {
+        let classes = [
+            TestClass::new("b").method(MethodAccessFlags::PUBLIC, "x", "()V"),
+            TestClass::new("c").method(MethodAccessFlags::PUBLIC, "y", "()V"),
+            TestClass::new("a").method(MethodAccessFlags::PUBLIC, "z", "()V"),
+        ];
+        let pats = [ClassPat::default().with(method!(public () -> ()))];
+        let forward = classes.iter().cloned().fold(TestJar::default(), TestJar::class);
+        let backward = classes
+            .iter()
+            .rev()
+            .cloned()
+            .fold(TestJar::default(), TestJar::class);
+
+        let names = |jar: TestJar| {
+            search_many(&mut jar.open().unwrap(), &pats)
+                .unwrap()
+                .iter()
+                .map(|mat| mat.entry.header().unwrap().this_class.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(forward), vec!["a", "b", "c"]);
+        assert_eq!(names(backward), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn validate_pattern_set() {
+        let mut jar = sample_jar()
+            .class(TestClass::new("f").method(MethodAccessFlags::PUBLIC, "g", "(Ljava/lang/String;)V"))
+            .open()
+            .unwrap();
+        let pats = [
+            ClassPat::default().with(method!(public (String) -> ())),
+            ClassPat::default().with(method!(public final (String) -> ())),
+            ClassPat::default().with(method!(public () -> ())),
+        ];
+        let report = validate_patterns(&mut jar, &pats).unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(report.missing().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(report.ambiguous().collect::<Vec<_>>(), vec![(
+            0,
+            [ClassName::new("d"), ClassName::new("f")].as_slice()
+        )]);
+        assert_eq!(report.conflicts, vec![Conflict {
+            class: ClassName::new("d"),
+            patterns: vec![0, 1]
+        }]);
+    }
+
+    fn malformed_search(policy: MalformedPolicy) -> Result<SearchReport> {
+        let mut jar = sample_jar()
+            .entry("broken.class", b"garbage".as_slice())
+            .open()
+            .unwrap();
+        Searcher::new([ClassPat::default().with(method!(public final (String) -> ()))])
+            .with_options(SearchOptions::default().malformed(policy))
+            .search(&mut jar)
+    }
+
+    #[test]
+    fn fail_on_malformed_classes() {
+        assert_eq!(SearchOptions::default().malformed, MalformedPolicy::FailFast);
+        assert!(malformed_search(MalformedPolicy::FailFast).is_err());
+    }
+
+    #[test]
+    fn skip_malformed_classes() {
+        let report = malformed_search(MalformedPolicy::SkipMalformed).unwrap();
+        assert_eq!(report.matches.len(), 1);
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn collect_malformed_class_warnings() {
+        let report = malformed_search(MalformedPolicy::CollectWarnings).unwrap();
+        assert_eq!(report.matches.len(), 1);
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    #[test]
+    fn read_past_corrupt_entries() {
+        let mut bytes = sample_jar()
+            .class(TestClass::new("f").method(MethodAccessFlags::PUBLIC, "g", "(Ljava/lang/String;)V"))
+            .to_bytes()
+            .unwrap();
+        // break the local header of the entry of `d`, between the ones of `a` and `f`
+        let header = bytes
+            .windows(4)
+            .enumerate()
+            .filter(|(_, sig)| sig == b"PK\x03\x04")
+            .nth(1);
+        let (offset, _) = header.unwrap();
+        bytes[offset..offset + 4].copy_from_slice(b"JUNK");
+
+        let search = |policy| {
+            let mut jar = Jar::from_bytes(bytes.clone()).unwrap();
+            Searcher::new([ClassPat::default().with(method!(public (String) -> ()))])
+                .with_options(SearchOptions::default().malformed(policy))
+                .search(&mut jar)
+        };
+        assert!(search(MalformedPolicy::FailFast).is_err());
+        let report = search(MalformedPolicy::CollectWarnings).unwrap();
+        let names: Vec<_> = report.matches.iter().map(|mat| mat.entry.name()).collect();
+        assert_eq!(names, ["f.class"]);
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(search(MalformedPolicy::SkipMalformed).unwrap().matches.len(), 1);
+    }
+
+    #[test]
+    fn match_base_class() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a"))
+            .class(TestClass::new("b").extends("a"))
+            .open()
+            .unwrap();
+        let mut classes = |base| {
+            let matches = search_many(&mut jar, &[ClassPat::default().base(base)]).unwrap();
+            matches
+                .iter()
+                .map(|mat| mat.entry.header().unwrap().this_class.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(classes(BasePat::Object), ["a"]);
+        assert_eq!(classes(BasePat::Is(TypePat::Any)), ["a", "b"]);
+        assert_eq!(classes(BasePat::Extends(TypePat::Any)), ["b"]);
+        assert_eq!(classes(BasePat::None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn match_inherited_members() {
+        let mut jar = TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .method(MethodAccessFlags::PUBLIC, "<init>", "()V")
+                    .method(MethodAccessFlags::PUBLIC, "b", "(Ljava/lang/String;)V")
+                    .field(FieldAccessFlags::PRIVATE, "c", "I"),
+            )
+            .class(
+                TestClass::new("d")
+                    .extends("a")
+                    .field(FieldAccessFlags::PRIVATE, "e", "J"),
+            )
+            .open()
+            .unwrap();
+        let searcher = Searcher::new([ClassPat::default()
+            .with_base(TypePat::Any)
+            .with(method!(public (String) -> ()))
+            .with(field!([private] i64))]);
+        assert!(searcher.search(&mut jar).unwrap().matches.is_empty());
+
+        let options = SearchOptions::default().inherited_members(true);
+        let matches = searcher.with_options(options).search(&mut jar).unwrap().matches;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].bindings, [
+            MemberBinding::Method(0),
+            MemberBinding::Field(0)
+        ]);
+    }
+
+    #[test]
+    fn match_with_strategies() {
+        let class = TestClass::new("a")
+            .method(MethodAccessFlags::PUBLIC, "b", "()V")
+            .method(MethodAccessFlags::PUBLIC, "c", "(I)V")
+            .method(MethodAccessFlags::PUBLIC, "d", "(J)V")
+            .field(FieldAccessFlags::PUBLIC, "e", "I")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let bind = |strategy, members: Vec<MemberPat>| {
+            let pat = members
+                .into_iter()
+                .fold(ClassPat::default().strategy(strategy), ClassPat::with);
+            pat.matches_with_bindings(&class)
+        };
+        let in_order = || vec![method!(public () -> ()), method!(public (i64) -> ())];
+        let reversed = || vec![method!(public (i64) -> ()), method!(public () -> ())];
+        let all = || {
+            vec![
+                method!(public (i64) -> ()),
+                field!([public] i32),
+                method!(public (i32) -> ()),
+                method!(public () -> ()),
+            ]
+        };
+
+        assert_eq!(bind(MatchStrategy::Positional, in_order()), None);
+        assert_eq!(
+            bind(MatchStrategy::Subsequence, in_order()),
+            Some(vec![MemberBinding::Method(0), MemberBinding::Method(2)])
+        );
+        assert_eq!(bind(MatchStrategy::Subsequence, reversed()), None);
+        assert_eq!(bind(MatchStrategy::Set, reversed()), None);
+        assert_eq!(
+            bind(MatchStrategy::Set, all()),
+            Some(vec![
+                MemberBinding::Method(2),
+                MemberBinding::Field(0),
+                MemberBinding::Method(1),
+                MemberBinding::Method(0)
+            ])
+        );
+        assert_eq!(
+            bind(MatchStrategy::BestEffort, reversed()),
+            Some(vec![MemberBinding::Method(2), MemberBinding::Method(0)])
+        );
+    }
+
+    #[test]
+    fn search_with_strategies_skipping_members() {
+        let mut jar = TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .method(MethodAccessFlags::PUBLIC, "b", "()V")
+                    .method(MethodAccessFlags::PUBLIC, "c", "(I)V")
+                    .method(MethodAccessFlags::PUBLIC, "d", "(J)V")
+                    .field(FieldAccessFlags::PUBLIC, "e", "I"),
+            )
+            .open()
+            .unwrap();
+        let mut search = |strategy| {
+            let pat = ClassPat::default()
+                .strategy(strategy)
+                .with(method!(public (i64) -> ()))
+                .with(method!(public () -> ()));
+            Searcher::new([pat]).search_many(&mut jar).unwrap()
+        };
+        assert!(search(MatchStrategy::Positional).is_empty());
+        assert!(search(MatchStrategy::Set).is_empty());
+        assert!(search(MatchStrategy::Subsequence).is_empty());
+        let matches = search(MatchStrategy::BestEffort);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].bindings, [
+            MemberBinding::Method(2),
+            MemberBinding::Method(0)
+        ]);
+
+        let pat = ClassPat::default()
+            .strategy(MatchStrategy::Subsequence)
+            .with(method!(public () -> ()))
+            .with(method!(public (i64) -> ()));
+        let matches = crate::search_many(&mut jar, &[pat]).unwrap();
+        assert_eq!(matches[0].bindings, [
+            MemberBinding::Method(0),
+            MemberBinding::Method(2)
+        ]);
+    }
+
+    #[test]
+    fn bind_interleaved_members() {
+        let class = TestClass::new("a")
+            .field(FieldAccessFlags::PRIVATE, "b", "I")
+            .field(FieldAccessFlags::PRIVATE, "c", "J")
+            .method(MethodAccessFlags::PUBLIC, "d", "()V")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let interleaved = ClassPat::default()
+            .with(field!([private] i32))
+            .with(method!(public () -> ()))
+            .with(field!([private] i64));
+        assert_eq!(
+            interleaved.matches_with_bindings(&class),
+            Some(vec![
+                MemberBinding::Field(0),
+                MemberBinding::Method(0),
+                MemberBinding::Field(1)
+            ])
+        );
+
+        let swapped = ClassPat::default()
+            .with(field!([private] i64))
+            .with(method!(public () -> ()))
+            .with(field!([private] i32));
+        assert!(!swapped.matches(&class));
+        let leftover = ClassPat::default()
+            .with(field!([private] i32))
+            .with(method!(public () -> ()));
+        assert!(!leftover.matches(&class));
+    }
+
+    #[test]
+    fn separate_static_members() {
+        let class = TestClass::new("a")
+            .method(MethodAccessFlags::PUBLIC, "b", "()V")
+            .method(MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC, "c", "()I")
+            .method(MethodAccessFlags::PUBLIC, "d", "()J")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let pat = ClassPat::default()
+            .with(method!(public static () -> i32))
+            .with(method!(public () -> ()))
+            .with(method!(public () -> i64));
+        assert!(!pat.matches(&class));
+        assert_eq!(
+            pat.separate_static().matches_with_bindings(&class),
+            Some(vec![
+                MemberBinding::Method(1),
+                MemberBinding::Method(0),
+                MemberBinding::Method(2)
+            ])
+        );
+    }
+
+    #[test]
+    fn match_method_modifiers() {
+        let bytes = TestClass::new("a")
+            .method(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::VARARGS,
+                "b",
+                "(Ljava/lang/String;[Ljava/lang/Object;)V",
+            )
+            .method(
+                MethodAccessFlags::NATIVE | MethodAccessFlags::STRICT | MethodAccessFlags::SYNCHRONIZED,
+                "c",
+                "()J",
+            )
+            .to_bytes();
+        let class = cafebabe::parse_class(&bytes).unwrap();
+        let native = method!(strictfp native synchronized () -> i64);
+        let pat = |varargs| ClassPat::default().with(varargs).with(native.clone());
+        assert!(pat(method!(public varargs (String, crate::java::Object) -> ())).matches(&class));
+        assert!(pat(method!(public varargs (String, &[crate::java::Object]) -> ())).matches(&class));
+        assert!(!pat(method!(public varargs (String, String) -> ())).matches(&class));
+        assert!(!pat(method!(public varargs (String) -> ())).matches(&class));
+
+        let mut jar = TestJar::default().entry("a.class", bytes.clone()).open().unwrap();
+        let searcher = Searcher::new([
+            pat(method!(public varargs (String, crate::java::Object) -> ())),
+            pat(method!(public varargs (String, String) -> ())),
+        ]);
+        let matches = searcher.search_many(&mut jar).unwrap();
+        assert_eq!(matches.iter().map(|mat| mat.pattern).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn match_visibility() {
+        let class = TestClass::new("a")
+            .flags(ClassAccessFlags::FINAL)
+            .method(MethodAccessFlags::empty(), "b", "()V")
+            .method(MethodAccessFlags::PROTECTED, "c", "()V")
+            .field(FieldAccessFlags::STATIC, "d", "J")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let pat = ClassPat::default()
+            .package_private()
+            .with(method!(package () -> ()))
+            .with(method!(protected () -> ()))
+            .with(field!([package static] i64));
+        assert!(pat.matches(&class));
+        assert!(!pat.clone().public().matches(&class));
+
+        let not_public = |pat: MethodPat| {
+            let class = ClassPat::default().not_public();
+            class.with(pat.clone()).with(pat).with(field!(i64))
+        };
+        assert!(not_public(MethodPat::default().not_public()).matches(&class));
+        assert!(!not_public(MethodPat::default().visibility(Visibility::Package)).matches(&class));
+    }
+
+    #[test]
+    fn match_relocated_types() {
+        let mut jar = TestJar::default()
+            .class(
+                TestClass::new("a/shadow/guava/Cache")
+                    .implements("a/shadow/guava/Map")
+                    .field(FieldAccessFlags::PRIVATE, "b", "[La/shadow/guava/Entry;"),
+            )
+            .open()
+            .unwrap();
+        let typ = |name| TypePat::Match(Descriptor::Object(name));
+        let searcher = Searcher::new([ClassPat::default().with_impl(typ("com/google/common/Map")).with(
+            FieldPat::default().of_type(TypePat::Match(Descriptor::Array(
+                Descriptor::Object("com/google/common/Entry").into(),
+            ))),
+        )]);
+        assert!(searcher.search(&mut jar).unwrap().matches.is_empty());
+
+        let options = SearchOptions::default().relocate("a.shadow.guava", "com/google/common");
+        let matches = searcher.with_options(options).search(&mut jar).unwrap().matches;
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[test]
+    fn capture_types() {
+        let mut jar = TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .field(FieldAccessFlags::PRIVATE, "b", "Lx;")
+                    .method(MethodAccessFlags::PUBLIC, "c", "(Lx;)V"),
+            )
+            .class(
+                TestClass::new("d")
+                    .field(FieldAccessFlags::PRIVATE, "e", "Lx;")
+                    .method(MethodAccessFlags::PUBLIC, "f", "(Ly;)V"),
+            )
+            .class(TestClass::new("g").method(MethodAccessFlags::PUBLIC, "h", "(Lx;)V"))
+            .class(TestClass::new("i").method(MethodAccessFlags::PUBLIC, "j", "(Ly;)V"))
+            .open()
+            .unwrap();
+        let capture = || TypePat::Capture("Entity".to_owned());
+        let pat = ClassPat::default()
+            .with(FieldPat::default().of_type(capture()))
+            .with(MethodPat::default().param(capture()).returns(TypePat::Void));
+
+        let matches = search_many(&mut jar, &[pat]).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            matches[0].captures,
+            Captures::from([("Entity".to_owned(), "Lx;".to_owned())])
+        );
+
+        let pat = ClassPat::default()
+            .with(MethodPat::default().param(capture()).returns(TypePat::Void))
+            .resolve_captures(&matches[0].captures);
+        let matches = search_many(&mut jar, &[pat]).unwrap();
+        let classes: Vec<_> = matches
+            .iter()
+            .map(|mat| mat.entry.header().unwrap().this_class.clone())
+            .collect();
+        assert_eq!(classes, ["g"]);
+    }
+
+    #[test]
+    fn collect_search_stats() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a/b"))
+            .class(TestClass::new("a/c"))
+            .class(TestClass::new("d/e"))
+            .open()
+            .unwrap();
+        let mut search = |options: SearchOptions| {
+            Searcher::new([ClassPat::default()])
+                .with_options(options)
+                .search(&mut jar)
+                .unwrap()
+        };
+
+        assert_eq!(search(SearchOptions::default()).stats, None);
+        let report = search(
+            SearchOptions::default()
+                .filter(crate::filter::package("a"))
+                .stats(true),
+        );
+        let stats = report.stats.unwrap();
+        assert_eq!(report.matches.len(), 2);
+        assert_eq!(stats.classes_scanned, 2);
+        assert_eq!(stats.classes_filtered, 1);
+        assert!(stats.bytes_read > 0);
+        assert!((stats.filter_rejection_rate() - 1. / 3.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn match_class_predicates() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a").method(MethodAccessFlags::PUBLIC, "b", "()V"))
+            .class(TestClass::new("c").method(MethodAccessFlags::PUBLIC, "d", "()V"))
+            .open()
+            .unwrap();
+        let pat = ClassPat::default()
+            .with(method!(public () -> ()))
+            .with_predicate(|class| class.this_class == "c");
+
+        let [entry] = crate::search_exact(&mut jar, std::array::from_ref(&pat)).unwrap();
+        assert_eq!(entry.name(), "c.class");
+        let class = TestClass::new("a")
+            .method(MethodAccessFlags::PUBLIC, "b", "()V")
+            .to_bytes();
+        assert!(!pat.matches(&cafebabe::parse_class(&class).unwrap()));
+    }
+
+    #[test]
+    fn prefer_patterns_with_higher_priority() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a").method(MethodAccessFlags::PUBLIC, "b", "()V"))
+            .class(TestClass::new("c").method(MethodAccessFlags::PUBLIC, "d", "()V"))
+            .open()
+            .unwrap();
+        let pats = [
+            ClassPat::default().with(MethodPat::default()),
+            ClassPat::default().with(method!(public () -> ())).priority(1),
+            ClassPat::default()
+                .with(MethodPat::default().name("d"))
+                .priority(1),
+        ];
+        let matches = Searcher::new(pats).search_many(&mut jar).unwrap();
+        let found: Vec<_> = matches
+            .iter()
+            .map(|mat| (mat.pattern, &mat.satisfied[..]))
+            .collect();
+        assert_eq!(found, [(1, &[0, 1][..]), (1, &[0, 1, 2][..])]);
+    }
+
+    #[test]
+    fn match_obfuscated_types() {
+        let class = TestClass::new("a")
+            .field(FieldAccessFlags::PRIVATE, "b", "Lcom/example/c;")
+            .field(FieldAccessFlags::PRIVATE, "c", "Ljava/util/List;")
+            .extends("b")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let pat = |first, second| {
+            ClassPat::default()
+                .with_base(TypePat::Obfuscated)
+                .with(FieldPat::default().of_type(first))
+                .with(FieldPat::default().of_type(second))
+        };
+        assert!(pat(TypePat::Obfuscated, TypePat::Any).matches(&class));
+        assert!(!pat(TypePat::Any, TypePat::Obfuscated).matches(&class));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn match_types_with_regex() {
+        use crate::pat::TypeRegex;
+
+        let short_name = || TypePat::Regex(TypeRegex::new("L[^/;]{2};").unwrap());
+        let class = TestClass::new("a")
+            .field(FieldAccessFlags::PRIVATE, "b", "Lab;")
+            .implements("cd")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let pat = |typ| ClassPat::default().with(FieldPat::default().of_type(typ));
+        assert!(pat(short_name()).with_impl(short_name()).matches(&class));
+        // the expression is anchored to the whole descriptor
+        assert!(!pat(TypePat::Regex(TypeRegex::new("L[^/;]").unwrap())).matches(&class));
+        assert!(!pat(TypePat::Regex(TypeRegex::new("L[^/;]{3};").unwrap())).matches(&class));
+    }
+
+    #[test]
+    fn attach_match_data() {
+        let version = |name: &str, version: &str| {
+            ClassBuilder::new(name)
+                .with_field(
+                    FieldBuilder::new(FieldAccessFlags::STATIC, "VERSION", "Ljava/lang/String;")
+                        .constant(version.into()),
+                )
+                .to_bytes()
+        };
+        let mut jar = TestJar::default()
+            .entry("a.class", version("a", "1.0"))
+            .entry("b.class", version("b", "2.0"))
+            .open()
+            .unwrap();
+        let options = SearchOptions::default().match_data(|class, pattern| {
+            let value = class.field_constant(0)?.into_owned();
+            Some((pattern, value))
+        });
+        let pat = ClassPat::default().with(FieldPat::default().name("VERSION"));
+        let matches = Searcher::new([pat])
+            .with_options(options)
+            .search_many(&mut jar)
+            .unwrap();
+
+        let data: Vec<_> = matches
+            .iter()
+            .map(|mat| mat.data::<(usize, ConstantValue)>())
+            .collect();
+        assert_eq!(data, [
+            Some(&(0, ConstantValue::from("1.0"))),
+            Some(&(0, ConstantValue::from("2.0")))
+        ]);
+        assert_eq!(matches[0].data::<String>(), None);
+    }
+
+    #[test]
+    fn match_custom_members() {
+        let class = TestClass::new("a")
+            .field(FieldAccessFlags::PRIVATE, "b", "Ljava/util/Map;")
+            .method(
+                MethodAccessFlags::PUBLIC,
+                "c",
+                "(Ljava/lang/String;Ljava/lang/String;)V",
+            )
+            .method(MethodAccessFlags::PUBLIC, "d", "()V")
+            .to_bytes();
+        let class = cafebabe::parse_class(&class).unwrap();
+        let long_method = || MemberPat::method_where(|method: Member<'_>| method.descriptor().len() > 30);
+        let map_field =
+            || MemberPat::field_where(|field: Member<'_>| field.descriptor() == "Ljava/util/Map;");
+
+        let pat = ClassPat::default()
+            .with(map_field())
+            .with(long_method())
+            .with(method!(public () -> ()));
+        assert!(pat.matches(&class));
+        assert!(pat.clone().strategy(MatchStrategy::Set).matches(&class));
+        assert!(!ClassPat::default()
+            .with(long_method())
+            .with(long_method())
+            .strategy(MatchStrategy::BestEffort)
+            .matches(&class));
+        assert!(!ClassPat::default()
+            .with(MemberPat::method_where(|method: Member<'_>| method.name() == "b"))
+            .strategy(MatchStrategy::BestEffort)
+            .matches(&class));
+        assert_ne!(long_method(), long_method());
+    }
 }
@@ -0,0 +1,129 @@
+//! Serialization of search results.
+//!
+//! The schema is versioned with [`SCHEMA_VERSION`], a match is serialized as:
+//! ```json
+//! {
+//!   "class": "com/example/Foo",
+//!   "archive": "libs/example.jar",
+//!   "entry": "com/example/Foo.class",
+//!   "pattern": 0,
+//!   "pattern_name": "Foo",
+//!   "bindings": [{ "kind": "method", "index": 2 }],
+//!   "score": 1.0
+//! }
+//! ```
+//! `archive` is `null` for unnamed archives and `pattern_name` is only present in a [`MatchSet`],
+//! which is serialized as `{ "version": 1, "patterns": [..], "matches": [..] }`.
+//! When provenance was collected, the set also contains a `provenance` entry for each pattern
+//! and the score of a match is the [confidence](crate::Provenance::confidence) of its pattern.
+
+use serde::ser::{Error, SerializeStruct};
+use serde::{Serialize, Serializer};
+
+use crate::matching::{Captures, MemberBinding};
+use crate::provenance::Provenance;
+use crate::search::{Match, MatchSet};
+
+/// Version of the schema used to serialize [`MatchSet`], incremented on breaking changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct MatchRecord<'a> {
+    class: &'a str,
+    archive: Option<&'a str>,
+    entry: &'a str,
+    pattern: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern_name: Option<&'a str>,
+    bindings: &'a [MemberBinding],
+    #[serde(skip_serializing_if = "Captures::is_empty")]
+    captures: &'a Captures,
+    // structural matches are exact, unless provenance was collected the score is always 1
+    score: f64,
+}
+
+impl<'a> MatchRecord<'a> {
+    fn new<E: Error>(mat: &'a Match, pattern_name: Option<&'a str>, score: f64) -> Result<Self, E> {
+        let header = mat.entry.header().map_err(E::custom)?;
+        Ok(Self {
+            class: &header.this_class,
+            archive: mat.entry.archive(),
+            entry: mat.entry.name(),
+            pattern: mat.pattern,
+            pattern_name,
+            bindings: &mat.bindings,
+            captures: &mat.captures,
+            score,
+        })
+    }
+}
+
+impl Serialize for Match {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MatchRecord::new(self, None, 1.0)?.serialize(serializer)
+    }
+}
+
+impl Serialize for MatchSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let matches = self
+            .matches
+            .iter()
+            .map(|mat| {
+                let score = self.provenance(mat).map_or(1.0, Provenance::confidence);
+                MatchRecord::new(mat, self.pattern_name(mat), score)
+            })
+            .collect::<Result<Vec<_>, S::Error>>()?;
+
+        let mut set = serializer.serialize_struct("MatchSet", 4)?;
+        set.serialize_field("version", &SCHEMA_VERSION)?;
+        set.serialize_field("patterns", &self.patterns)?;
+        set.serialize_field("matches", &matches)?;
+        if self.provenance.is_empty() {
+            set.skip_field("provenance")?;
+        } else {
+            set.serialize_field("provenance", &self.provenance)?;
+        }
+        set.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::{method, ClassPat, Searcher};
+
+    #[test]
+    fn serialize_match_set() {
+        let class =
+            TestClass::new("com/example/Foo").method(cafebabe::MethodAccessFlags::PUBLIC, "run", "()V");
+        let mut jar = TestJar::default()
+            .class(class)
+            .open()
+            .unwrap()
+            .named("example.jar");
+        let pat = ClassPat::default().with(method!(public () -> ()));
+        let matches = Searcher::new([pat]).search_many(&mut jar).unwrap();
+        let set = MatchSet {
+            patterns: vec!["Foo".to_owned()],
+            matches,
+            ..Default::default()
+        };
+
+        let expected = serde_json::json!({
+            "version": 1,
+            "patterns": ["Foo"],
+            "matches": [{
+                "class": "com/example/Foo",
+                "archive": "example.jar",
+                "entry": "com/example/Foo.class",
+                "pattern": 0,
+                "pattern_name": "Foo",
+                "bindings": [{ "kind": "method", "index": 0 }],
+                "score": 1.0
+            }]
+        });
+        assert_eq!(serde_json::to_value(&set).unwrap(), expected);
+    }
+}
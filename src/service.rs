@@ -0,0 +1,222 @@
+//! A long-lived service answering queries about a set of open archives.
+//!
+//! The service speaks a line-delimited JSON protocol, each request is a single line:
+//! ```json
+//! {"id": 1, "method": "open", "params": {"name": "game", "path": "game.jar"}}
+//! {"id": 2, "method": "search", "params": {"jar": "game", "patterns": "public class Foo\n  method public () -> ()"}}
+//! {"id": 3, "method": "list"}
+//! {"id": 4, "method": "close", "params": {"name": "game"}}
+//! ```
+//! and is answered with a line containing either `{"id": .., "result": ..}` or `{"id": .., "error": ".."}`.
+//! Search results use the [`MatchSet`] schema, searches without a `jar` run against all open archives.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::dsl::parse_patterns;
+use crate::jar::Jar;
+use crate::result::{Error, Result};
+use crate::search::{MatchSet, SearchOptions, Searcher};
+
+/// A request handled by a [`JarService`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum Request {
+    /// Opens an archive and registers it under a name, by default its path.
+    Open { path: PathBuf, name: Option<String> },
+    /// Closes an archive.
+    Close { name: String },
+    /// Lists the names of the open archives.
+    List,
+    /// Searches for patterns written in the [text format](crate::parse_patterns).
+    Search { patterns: String, jar: Option<String> },
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    request: Request,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A service that keeps archives open between queries.
+#[derive(Debug, Default)]
+pub struct JarService {
+    jars: BTreeMap<String, Jar<BufReader<File>>>,
+    options: SearchOptions,
+}
+
+impl JarService {
+    /// Sets the [`SearchOptions`] used by searches.
+    #[inline]
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Opens an archive and registers it under the provided name, replacing any archive with the same name.
+    pub fn open(&mut self, name: impl Into<String>, path: impl Into<PathBuf>) -> Result<()> {
+        let jar = Jar::open(path.into())?;
+        self.jars.insert(name.into(), jar);
+        Ok(())
+    }
+
+    /// Closes an archive, returns `false` if there was no archive with the provided name.
+    pub fn close(&mut self, name: &str) -> bool {
+        self.jars.remove(name).is_some()
+    }
+
+    /// Returns the names of the open archives.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.jars.keys().map(String::as_str)
+    }
+
+    /// Searches for the patterns in the named archive, or in all of them if no name is provided.
+    pub fn search(&mut self, searcher: &Searcher, jar: Option<&str>) -> Result<MatchSet> {
+        let mut set = MatchSet::default();
+        match jar {
+            Some(name) => {
+                let jar = self
+                    .jars
+                    .get_mut(name)
+                    .ok_or_else(|| Error::UnknownArchive(name.to_owned()))?;
+                set.add_report(searcher.search(jar)?);
+            }
+            None => {
+                for jar in self.jars.values_mut() {
+                    set.add_report(searcher.search(jar)?);
+                }
+            }
+        }
+        Ok(set)
+    }
+
+    /// Handles a single request and returns its result as JSON.
+    pub fn handle(&mut self, request: Request) -> Result<Value> {
+        match request {
+            Request::Open { path, name } => {
+                let name = name.unwrap_or_else(|| path.display().to_string());
+                self.open(name.clone(), path)?;
+                Ok(json!({ "name": name }))
+            }
+            Request::Close { name } => {
+                if !self.close(&name) {
+                    return Err(Error::UnknownArchive(name));
+                }
+                Ok(Value::Null)
+            }
+            Request::List => Ok(json!(self.names().collect::<Vec<_>>())),
+            Request::Search { patterns, jar } => {
+                let pats = parse_patterns(&patterns)?;
+                let searcher = Searcher::new(pats.iter().map(|(_, pat)| pat.clone()))
+                    .with_options(self.options.clone());
+                let mut set = self.search(&searcher, jar.as_deref())?;
+                set.patterns = pats.into_iter().map(|(name, _)| name).collect();
+                serde_json::to_value(&set).map_err(|err| Error::IoError(err.into()))
+            }
+        }
+    }
+
+    /// Serves requests read line by line from `input` until it's exhausted,
+    /// writing a response line for each of them to `output`.
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Envelope>(&line) {
+                Ok(envelope) => match self.handle(envelope.request) {
+                    Ok(result) => Response {
+                        id: envelope.id,
+                        result: Some(result),
+                        error: None,
+                    },
+                    Err(err) => Response {
+                        id: envelope.id,
+                        result: None,
+                        error: Some(err.to_string()),
+                    },
+                },
+                Err(err) => Response {
+                    id: Value::Null,
+                    result: None,
+                    error: Some(format!("invalid request: {err}")),
+                },
+            };
+            serde_json::to_writer(&mut output, &response)?;
+            writeln!(output)?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Serves connections to a Unix domain socket created at `path`, one at a time.
+    #[cfg(unix)]
+    pub fn serve_unix(&mut self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+        for stream in listener.incoming() {
+            let stream = stream?;
+            self.serve(BufReader::new(stream.try_clone()?), stream)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn serve_requests() {
+        let path = std::env::temp_dir().join(format!("jars-service-{}.jar", std::process::id()));
+        let jar = TestJar::default().class(TestClass::new("a").method(
+            cafebabe::MethodAccessFlags::PUBLIC,
+            "run",
+            "()V",
+        ));
+        std::fs::write(&path, jar.to_bytes().unwrap()).unwrap();
+
+        let open = json!({ "id": 1, "method": "open", "params": { "name": "test", "path": path } });
+        let search = json!({
+            "id": 2,
+            "method": "search",
+            "params": { "jar": "test", "patterns": "class Runner\n method public () -> ()" }
+        });
+        let input = format!("{open}\n{search}\n{{\"id\": 3, \"method\": \"list\"}}\nnot json\n");
+        let mut output = vec![];
+        JarService::default()
+            .serve(Cursor::new(input), &mut output)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let responses: Vec<Value> = output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(responses[0], json!({ "id": 1, "result": { "name": "test" } }));
+        assert_eq!(responses[1]["result"]["matches"][0]["class"], "a");
+        assert_eq!(responses[1]["result"]["matches"][0]["pattern_name"], "Runner");
+        assert_eq!(responses[2], json!({ "id": 3, "result": ["test"] }));
+        assert!(responses[3]["error"].is_string());
+    }
+}
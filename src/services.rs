@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+
+use crate::jar::Jar;
+use crate::name::to_internal;
+use crate::result::Result;
+
+const SERVICES_DIR: &[u8] = b"META-INF/services/";
+
+/// Providers of services declared in `META-INF/services`, mapped from the internal name of each service
+/// to the internal names of the classes implementing it.
+pub type Services = BTreeMap<String, Vec<String>>;
+
+impl<R: Read + Seek> Jar<R> {
+    /// Reads the service descriptors of the archive, which are used by `java.util.ServiceLoader`.
+    ///
+    /// Descriptors name concrete classes, so they identify classes even in obfuscated archives.
+    pub fn services(&mut self) -> Result<Services> {
+        let mut services = Services::new();
+        for entry in self.resources_where(|name| name.starts_with(SERVICES_DIR)) {
+            let entry = entry?;
+            let service = &entry.name()[SERVICES_DIR.len()..];
+            if service.is_empty() || service.contains('/') {
+                continue;
+            }
+            let providers = services.entry(to_internal(service)).or_default();
+            for line in String::from_utf8_lossy(entry.bytes()).lines() {
+                let provider = line.split_once('#').map_or(line, |(line, _)| line).trim();
+                if !provider.is_empty() {
+                    providers.push(to_internal(provider));
+                }
+            }
+        }
+        Ok(services)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{TestClass, TestJar};
+    use crate::{ClassPat, Searcher};
+
+    #[test]
+    fn match_service_providers() {
+        let mut jar = TestJar::default()
+            .class(TestClass::new("a/b"))
+            .class(TestClass::new("a/c"))
+            .entry("META-INF/services/java.sql.Driver", "# drivers\na.c\n")
+            .open()
+            .unwrap();
+
+        let services = jar.services().unwrap();
+        assert_eq!(services["java/sql/Driver"], vec!["a/c"]);
+
+        let matches = Searcher::new([ClassPat::default().provides_service("java/sql/Driver")])
+            .search_many(&mut jar)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].entry.name(), "a/c.class");
+    }
+}
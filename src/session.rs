@@ -0,0 +1,207 @@
+use std::io::{Read, Seek};
+
+use crate::jar::Jar;
+use crate::matching::{Captures, MemberBinding};
+use crate::result::{Error, Result};
+use crate::search::{SearchReport, Searcher};
+
+/// Progress of a search over a collection of archives, which can be saved to resume the search later.
+///
+/// An archive is the unit of progress, it's recorded with its matches once it's fully searched.
+/// With the `serde` feature the session can be serialized, e.g. to checkpoint a batch job.
+///
+/// # Examples
+/// ```no_run
+/// use jars::{ClassPat, Searcher};
+///
+/// let searcher = Searcher::new([ClassPat::default()]);
+/// let mut session = searcher.session();
+/// for path in ["a.jar", "b.jar"] {
+///     let mut jar = jars::Jar::open(path)?.named(path);
+///     searcher.search_in_session(&mut jar, &mut session)?;
+/// }
+/// assert_eq!(session.position(), 2);
+/// # Ok::<(), jars::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchSession {
+    /// Hash of the patterns the session was started with.
+    pub patterns: u64,
+    /// Archives searched so far, in the order they were searched.
+    pub archives: Vec<SearchedArchive>,
+}
+
+impl SearchSession {
+    /// Returns the number of archives searched so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.archives.len()
+    }
+
+    /// Returns the matches found so far along with the names of their archives.
+    pub fn matches(&self) -> impl Iterator<Item = (Option<&str>, &SavedMatch)> + '_ {
+        self.archives
+            .iter()
+            .flat_map(|archive| archive.matches.iter().map(|mat| (archive.name.as_deref(), mat)))
+    }
+
+    fn find(&self, name: Option<&str>) -> Option<usize> {
+        self.archives
+            .iter()
+            .position(|archive| archive.name.as_deref() == name)
+    }
+}
+
+/// An archive recorded in a [`SearchSession`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchedArchive {
+    /// Name of the archive, see [`Jar::named`].
+    pub name: Option<String>,
+    /// Hash of the names, sizes and checksums of the entries, an archive is searched again when it changes.
+    pub fingerprint: u64,
+    pub matches: Vec<SavedMatch>,
+}
+
+/// A [`Match`](crate::Match) detached from its archive.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SavedMatch {
+    pub class: String,
+    pub entry: String,
+    pub pattern: usize,
+    pub bindings: Vec<MemberBinding>,
+    pub captures: Captures,
+}
+
+impl Searcher {
+    /// Starts a [`SearchSession`] for the patterns of this searcher.
+    pub fn session(&self) -> SearchSession {
+        let mut hash = FNV_OFFSET;
+        for pat in self.patterns() {
+            hash = fnv(hash, format!("{pat:?}").as_bytes());
+        }
+        SearchSession {
+            patterns: hash,
+            archives: vec![],
+        }
+    }
+
+    /// Searches an archive and records its matches in the session, unless it's already recorded.
+    ///
+    /// Archives are identified by their names, an archive that changed since it was recorded is searched again.
+    /// Returns the report of the search, or `None` if the archive was skipped.
+    /// Fails with [`Error::SessionMismatch`] if the session was started with different patterns.
+    pub fn search_in_session<R: Read + Seek>(
+        &self,
+        jar: &mut Jar<R>,
+        session: &mut SearchSession,
+    ) -> Result<Option<SearchReport>> {
+        if session.patterns != self.session().patterns {
+            return Err(Error::SessionMismatch);
+        }
+        let fingerprint = jar.content_hash()?;
+        let existing = session.find(jar.name());
+        if let Some(i) = existing {
+            if session.archives[i].fingerprint == fingerprint {
+                return Ok(None);
+            }
+        }
+        let report = self.search(jar)?;
+        let matches = report
+            .matches
+            .iter()
+            .map(|mat| {
+                Ok(SavedMatch {
+                    class: mat.entry.header()?.this_class.to_string(),
+                    entry: mat.entry.name().to_owned(),
+                    pattern: mat.pattern,
+                    bindings: mat.bindings.clone(),
+                    captures: mat.captures.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        let archive = SearchedArchive {
+            name: jar.name().map(str::to_owned),
+            fingerprint,
+            matches,
+        };
+        match existing {
+            Some(i) => session.archives[i] = archive,
+            None => session.archives.push(archive),
+        }
+        Ok(Some(report))
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Hashes the names, sizes and checksums of the entries without reading their contents.
+    fn content_hash(&mut self) -> Result<u64> {
+        let mut entries = vec![];
+        self.raw_entries(|file| entries.push((file.name_raw().to_vec(), file.size(), file.crc32())))?;
+        entries.sort_unstable();
+        let hash = entries.iter().fold(FNV_OFFSET, |hash, (name, size, crc)| {
+            let hash = fnv(hash, name);
+            let hash = fnv(hash, &size.to_be_bytes());
+            fnv(hash, &crc.to_be_bytes())
+        });
+        Ok(hash)
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// the hashes are persisted, so they can't use the randomly seeded hasher of the standard library
+fn fnv(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::MethodAccessFlags;
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::{ClassPat, MethodPat};
+
+    #[test]
+    fn resume_search_session() {
+        let jar = |class: &str| {
+            TestJar::default()
+                .class(TestClass::new(class).method(MethodAccessFlags::PUBLIC, "x", "()V"))
+                .open()
+                .unwrap()
+                .named(class)
+        };
+        let searcher = Searcher::new([ClassPat::default().with(MethodPat::default().name("x"))]);
+        let mut session = searcher.session();
+        assert!(searcher
+            .search_in_session(&mut jar("a"), &mut session)
+            .unwrap()
+            .is_some());
+
+        #[cfg(feature = "serde")]
+        let mut session: SearchSession =
+            serde_json::from_value(serde_json::to_value(&session).unwrap()).unwrap();
+        assert!(searcher
+            .search_in_session(&mut jar("a"), &mut session)
+            .unwrap()
+            .is_none());
+        assert!(searcher
+            .search_in_session(&mut jar("b"), &mut session)
+            .unwrap()
+            .is_some());
+        assert_eq!(session.position(), 2);
+        let classes: Vec<_> = session.matches().map(|(_, mat)| mat.class.as_str()).collect();
+        assert_eq!(classes, ["a", "b"]);
+
+        let other = Searcher::new([ClassPat::default()]);
+        assert!(matches!(
+            other.search_in_session(&mut jar("c"), &mut session),
+            Err(Error::SessionMismatch)
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use crate::filter::{ClassFilter, ClassInfo};
+use crate::jar::Jar;
+use crate::result::Result;
+
+/// A part of the classes of an archive, searched on its own by passing it to
+/// [`SearchOptions::filter`](crate::SearchOptions::filter), see [`Jar::shards`].
+#[derive(Debug, Clone)]
+pub struct Shard {
+    index: usize,
+    count: usize,
+    entries: Arc<HashSet<Box<str>>>,
+}
+
+impl Shard {
+    /// Returns the position of the shard among the shards of the archive.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of shards the archive was split into.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the number of classes in the shard.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the shard has no classes, which happens with more shards than classes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl ClassFilter for Shard {
+    #[inline]
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        Some(self.entries.contains(class.name))
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Splits the classes of the archive into `count` shards of nearly equal sizes.
+    ///
+    /// Classes are assigned by the sorted names of their entries, so the same archive is always split the same way,
+    /// regardless of the order of its entries. This allows workers to each open the archive and search their own shard,
+    /// the reports can be combined with [`SearchReport::merge`](crate::SearchReport::merge).
+    ///
+    /// # Panics
+    /// Panics if `count` is zero.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use jars::{ClassPat, SearchOptions, Searcher};
+    ///
+    /// let mut jar = jars::Jar::open("app.jar")?;
+    /// let shard = jar.shards(4)?.swap_remove(1);
+    /// let searcher = Searcher::new([ClassPat::default()]).with_options(SearchOptions::default().filter(shard));
+    /// let report = searcher.search(&mut jar)?;
+    /// # Ok::<(), jars::Error>(())
+    /// ```
+    pub fn shards(&mut self, count: usize) -> Result<Vec<Shard>> {
+        assert!(count > 0, "an archive can't be split into zero shards");
+        let mut names = self.class_entry_names()?;
+        names.sort_unstable();
+        names.dedup();
+
+        let mut names = names.into_iter();
+        let len = names.len();
+        let shards = (0..count)
+            .map(|index| {
+                let size = (index + 1) * len / count - index * len / count;
+                Shard {
+                    index,
+                    count,
+                    entries: Arc::new(names.by_ref().take(size).collect()),
+                }
+            })
+            .collect();
+        Ok(shards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::MethodAccessFlags;
+
+    use crate::testing::{TestClass, TestJar};
+    use crate::{ClassPat, MethodPat, SearchOptions, SearchReport, Searcher};
+
+    #[test]
+    fn search_shards() {
+        let jar = ["e", "d", "c", "b", "a"]
+            .into_iter()
+            .map(|name| TestClass::new(name).method(MethodAccessFlags::PUBLIC, "x", "()V"))
+            .fold(TestJar::default(), TestJar::class);
+        let mut jar = jar.open().unwrap();
+        let shards = jar.shards(2).unwrap();
+        assert_eq!(shards.iter().map(|shard| shard.len()).collect::<Vec<_>>(), [2, 3]);
+
+        let pats = [ClassPat::default().with(MethodPat::default().name("x"))];
+        let reports = shards.into_iter().map(|shard| {
+            let options = SearchOptions::default().filter(shard).stats(true);
+            Searcher::new(pats.clone())
+                .with_options(options)
+                .search(&mut jar)
+                .unwrap()
+        });
+        let report = SearchReport::merged(reports.collect::<Vec<_>>());
+        assert_eq!(report.class_names(), ["a", "b", "c", "d", "e"]);
+        assert_eq!(report.stats.unwrap().classes_scanned, 5);
+    }
+}
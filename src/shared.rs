@@ -0,0 +1,120 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::jar::Jar;
+use crate::result::Result;
+
+/// A reader of a file that can be cloned to read it from many threads at once.
+///
+/// Clones share the file handle but keep their own positions, reads are positioned
+/// and don't go through the cursor of the handle.
+#[derive(Debug, Clone)]
+pub struct SharedFile {
+    file: Arc<File>,
+    pos: u64,
+    len: u64,
+}
+
+impl SharedFile {
+    pub fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len();
+        Ok(Self {
+            file: Arc::new(file),
+            pos: 0,
+            len,
+        })
+    }
+}
+
+impl Read for SharedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = read_at(&self.file, buf, self.pos)?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SharedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        let Some(pos) = pos else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek to a negative or overflowing position",
+            ));
+        };
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+impl Jar<SharedFile> {
+    /// Opens an archive from the file system so that it can be read from many threads at once.
+    ///
+    /// Reading requires a mutable reference, so each thread should read from its own clone of the archive.
+    /// Clones share the file handle and the central directory of the archive, which is only read once.
+    /// Archives held in memory can be shared the same way by opening them
+    /// with [`Jar::from_shared_bytes`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let jar = jars::Jar::open_shared("app.jar").unwrap();
+    /// std::thread::scope(|scope| {
+    ///     for _ in 0..4 {
+    ///         let mut jar = jar.clone();
+    ///         scope.spawn(move || jar.classes().count());
+    ///     }
+    /// });
+    /// ```
+    pub fn open_shared(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let jar = Self::new(SharedFile::new(File::open(path)?)?)?;
+        Ok(jar.named(path.display().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn read_from_threads() {
+        let path = std::env::temp_dir().join(format!("jars-shared-{}.jar", std::process::id()));
+        let bytes = TestJar::default()
+            .class(TestClass::new("a"))
+            .class(TestClass::new("b"))
+            .to_bytes()
+            .unwrap();
+        std::fs::write(&path, bytes).unwrap();
+
+        let jar = Jar::open_shared(&path).unwrap();
+        let counts: Vec<usize> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let mut jar = jar.clone();
+                    scope.spawn(move || jar.classes().filter(|entry| entry.is_ok()).count())
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(counts, [2; 4]);
+    }
+}
@@ -0,0 +1,895 @@
+//! Conversion of Java declarations into class patterns.
+//!
+//! A class skeleton written in Java describes the class to find:
+//! ```text
+//! package com.example;
+//!
+//! import java.util.List;
+//!
+//! public final class Registry<T> implements Iterable<T> {
+//!     private final List<T> entries = new ArrayList<>();
+//!
+//!     public Registry(String... names) { /* bodies are skipped */ }
+//!
+//!     public void register(T entry) {}
+//! }
+//! ```
+//! Only the signatures are used, generic types are erased with type variables matching any type.
+//! Simple names are resolved with the single-type imports, the common `java.lang` classes
+//! and the package of the file, in that order.
+//! Member names are left out of the patterns, like in [`parse_javap`](crate::parse_javap),
+//! and the classes declared in the same input are referred to with [`TypePat::Ref`].
+//!
+//! The compiler adds members that cannot be seen in the source, such as the methods implementing lambdas,
+//! so the patterns use [`MatchStrategy::Subsequence`]. The implicit constructors are included.
+//! Nested classes are skipped, they can be declared separately at the top level.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+
+use crate::descriptor::Descriptor;
+use crate::dsl::{field_flag, method_flag, parse_type_name, PatternError, JAVA_LANG};
+use crate::javap::refer_to_declared;
+use crate::pat::{ClassPat, FieldPat, MatchStrategy, MemberPat, MethodPat, TypePat, Visibility};
+
+/// Parses the classes declared in Java source code into named patterns,
+/// the names being the qualified names of the classes.
+///
+/// # Examples
+/// ```
+/// let src = "
+///     public class Foo implements Runnable {
+///         private static final long serialVersionUID = 1L;
+///         public void run() {}
+///     }
+/// ";
+/// let pats = jars::parse_java(src).unwrap();
+/// assert_eq!(pats[0].0, "Foo");
+/// ```
+pub fn parse_java(src: &str) -> Result<Vec<(String, ClassPat)>, PatternError> {
+    let mut parser = Parser::new(tokenize(src));
+    if let Err(message) = parser.parse_file() {
+        return Err(PatternError::new(parser.line(), message));
+    }
+    refer_to_declared(&mut parser.pats);
+    Ok(parser.pats)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+/// Splits source code into identifiers, literals and punctuation, skipping comments.
+fn tokenize(src: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut rem = src;
+    while let Some(c) = rem.chars().next() {
+        let len = if c.is_whitespace() {
+            line += usize::from(c == '\n');
+            rem = &rem[c.len_utf8()..];
+            continue;
+        } else if let Some(comment) = rem.strip_prefix("//") {
+            rem = comment.find('\n').map_or("", |end| &comment[end..]);
+            continue;
+        } else if let Some(comment) = rem.strip_prefix("/*") {
+            let end = comment.find("*/").map_or(comment.len(), |end| end + 2);
+            line += comment[..end].matches('\n').count();
+            rem = &comment[end..];
+            continue;
+        } else if let Some(block) = rem.strip_prefix(r#"""""#) {
+            block.find(r#"""""#).map_or(rem.len(), |end| end + 6)
+        } else if c == '"' || c == '\'' {
+            literal_len(rem, c)
+        } else if rem.starts_with("...") {
+            3
+        } else if is_ident_char(c) {
+            rem.find(|c| !is_ident_char(c)).unwrap_or(rem.len())
+        } else {
+            c.len_utf8()
+        };
+        let text = &rem[..len];
+        tokens.push(Token { text, line });
+        line += text.matches('\n').count();
+        rem = &rem[len..];
+    }
+    tokens
+}
+
+fn literal_len(str: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in str.char_indices().skip(1) {
+        match c {
+            '\\' => escaped = !escaped,
+            '\n' => return i,
+            c if c == quote && !escaped => return i + 1,
+            _ => escaped = false,
+        }
+    }
+    str.len()
+}
+
+#[inline]
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+#[inline]
+fn is_ident(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| is_ident_char(c) && !c.is_ascii_digit())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Class,
+    Interface,
+    Annotation,
+    Enum,
+    Record,
+}
+
+/// The class being declared.
+#[derive(Debug)]
+struct Class<'a> {
+    name: &'a str,
+    kind: Kind,
+    pat: ClassPat,
+    has_constructor: bool,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    package: String,
+    imports: Vec<(String, String)>,
+    type_vars: Vec<&'a str>,
+    pats: Vec<(String, ClassPat)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            package: String::new(),
+            imports: vec![],
+            type_vars: vec![],
+            pats: vec![],
+        }
+    }
+
+    fn parse_file(&mut self) -> Result<(), String> {
+        while let Some(token) = self.peek() {
+            match token {
+                "package" => {
+                    self.next();
+                    self.package = self.qualified_name()?;
+                    self.expect(";")?;
+                }
+                "import" => {
+                    self.next();
+                    let is_static = self.eat("static");
+                    let name = self.qualified_name()?;
+                    let is_wildcard = self.eat(".");
+                    if is_wildcard {
+                        self.expect("*")?;
+                    }
+                    self.expect(";")?;
+                    if !is_static && !is_wildcard {
+                        let simple = name.rsplit('.').next().unwrap_or_default().to_owned();
+                        self.imports.push((simple, name));
+                    }
+                }
+                ";" => self.next(),
+                _ => self.parse_class()?,
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_class(&mut self) -> Result<(), String> {
+        self.skip_annotations()?;
+        let mut pat = ClassPat::default();
+        let kind = loop {
+            let token = self.expect_any()?;
+            pat = match token {
+                "public" => pat.public(),
+                "final" => pat.final_(),
+                "abstract" => pat.abstract_(),
+                "static" | "strictfp" | "sealed" => pat,
+                "non" if self.eat("-") => {
+                    self.expect("sealed")?;
+                    pat
+                }
+                "class" => break Kind::Class,
+                "interface" => break Kind::Interface,
+                "enum" => break Kind::Enum,
+                "record" => break Kind::Record,
+                "@" if self.eat("interface") => break Kind::Annotation,
+                other => return Err(format!("unexpected `{other}`, expected a class declaration")),
+            }
+        };
+        if !pat.flags.contains(ClassAccessFlags::PUBLIC) {
+            pat = pat.package_private();
+        }
+        match kind {
+            Kind::Class => {}
+            Kind::Interface => pat.flags |= ClassPat::interface().flags,
+            Kind::Annotation => {
+                pat.flags |= ClassPat::interface().flags | ClassAccessFlags::ANNOTATION;
+                pat = pat.with_impl(parse_type_name("java.lang.annotation.Annotation")?);
+            }
+            Kind::Enum => {
+                pat.flags |= ClassAccessFlags::ENUM;
+                pat = pat.with_base(parse_type_name("java.lang.Enum")?);
+            }
+            Kind::Record => pat = pat.final_().with_base(parse_type_name("java.lang.Record")?),
+        }
+
+        let name = self.expect_ident()?;
+        self.type_vars = if self.peek() == Some("<") {
+            self.type_params()?
+        } else {
+            vec![]
+        };
+        let mut class = Class {
+            name,
+            kind,
+            pat: pat.strategy(MatchStrategy::Subsequence),
+            has_constructor: false,
+        };
+        if kind == Kind::Record {
+            self.parse_components(&mut class)?;
+        }
+        if self.eat("extends") {
+            loop {
+                let typ = self.parse_type()?;
+                class.pat = match kind {
+                    Kind::Class => class.pat.with_base(typ),
+                    _ => class.pat.with_impl(typ),
+                };
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        if self.eat("implements") {
+            loop {
+                class.pat = class.pat.with_impl(self.parse_type()?);
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        if self.eat("permits") {
+            while self.peek() != Some("{") {
+                self.expect_any()?;
+            }
+        }
+        self.parse_body(&mut class)?;
+
+        let qualified = if self.package.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{name}", self.package)
+        };
+        self.pats.push((qualified, class.pat));
+        Ok(())
+    }
+
+    fn parse_components(&mut self, class: &mut Class<'a>) -> Result<(), String> {
+        self.expect("(")?;
+        while !self.eat(")") {
+            self.skip_annotations()?;
+            let typ = self.parse_type()?;
+            self.expect_ident()?;
+            let field = FieldPat::default()
+                .flags(FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL)
+                .of_type(typ);
+            class.pat.members.push(field.into());
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_body(&mut self, class: &mut Class<'a>) -> Result<(), String> {
+        self.expect("{")?;
+        if class.kind == Kind::Enum {
+            self.parse_enum_constants(class)?;
+        }
+        while !self.eat("}") {
+            if !self.eat(";") {
+                self.parse_member(class)?;
+            }
+        }
+
+        if !class.has_constructor {
+            let constructor = match class.kind {
+                Kind::Class if class.pat.flags.contains(ClassAccessFlags::PUBLIC) => {
+                    MethodPat::constructor().flags(MethodAccessFlags::PUBLIC)
+                }
+                Kind::Class => MethodPat::constructor().visibility(Visibility::Package),
+                Kind::Enum => enum_constructor(),
+                Kind::Interface | Kind::Annotation | Kind::Record => return Ok(()),
+            };
+            // the implicit constructor is the first method of a class
+            let pos = class
+                .pat
+                .members
+                .iter()
+                .position(|member| matches!(member, MemberPat::Method(_)))
+                .unwrap_or(class.pat.members.len());
+            class.pat.members.insert(pos, constructor.into());
+        }
+        Ok(())
+    }
+
+    fn parse_enum_constants(&mut self, class: &mut Class<'a>) -> Result<(), String> {
+        while !matches!(self.peek(), Some(";" | "}")) {
+            self.skip_annotations()?;
+            self.expect_ident()?;
+            if self.peek() == Some("(") {
+                self.skip_balanced()?;
+            }
+            if self.peek() == Some("{") {
+                self.skip_balanced()?;
+            }
+            let field = FieldPat::default()
+                .flags(
+                    FieldAccessFlags::PUBLIC
+                        | FieldAccessFlags::STATIC
+                        | FieldAccessFlags::FINAL
+                        | FieldAccessFlags::ENUM,
+                )
+                .of_type(self.resolve(&[class.name], 0)?);
+            class.pat.members.push(field.into());
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.eat(";");
+        Ok(())
+    }
+
+    fn parse_member(&mut self, class: &mut Class<'a>) -> Result<(), String> {
+        self.skip_annotations()?;
+        let start = self.pos;
+        while self.peek().is_some_and(is_modifier) {
+            if self.next_text() == "non" {
+                self.expect("-")?;
+                self.expect("sealed")?;
+            }
+        }
+        let modifiers: Vec<_> = self.tokens[start..self.pos]
+            .iter()
+            .map(|token| token.text)
+            .collect();
+        let is_static = modifiers.contains(&"static");
+
+        match self.peek() {
+            Some("{") => {
+                self.skip_balanced()?;
+                if is_static {
+                    let clinit = MethodPat::default()
+                        .name("<clinit>")
+                        .flags(MethodAccessFlags::STATIC);
+                    class.pat.members.push(clinit.into());
+                }
+                return Ok(());
+            }
+            Some("class" | "interface" | "enum" | "record") | Some("@") => {
+                while self.peek() != Some("{") {
+                    self.expect_any()?;
+                }
+                return self.skip_balanced();
+            }
+            _ => {}
+        }
+
+        let vars = self.type_vars.len();
+        if self.peek() == Some("<") {
+            let method_vars = self.type_params()?;
+            self.type_vars.extend(method_vars);
+        }
+        let res = if self.peek() == Some(class.name) && self.peek_at(1) == Some("(") {
+            self.next();
+            let method = self.parse_method(class, &modifiers, None)?;
+            class.has_constructor = true;
+            class.pat.members.push(method.into());
+            Ok(())
+        } else if class.kind == Kind::Record
+            && self.peek() == Some(class.name)
+            && self.peek_at(1) == Some("{")
+        {
+            // a compact canonical constructor
+            self.next();
+            class.has_constructor = true;
+            self.skip_balanced()
+        } else {
+            let (segments, dims) = self.parse_type_parts()?;
+            self.expect_ident()?;
+            if self.peek() == Some("(") {
+                let ret = self.resolve(&segments, dims)?;
+                let method = self.parse_method(class, &modifiers, Some(ret))?;
+                class.pat.members.push(method.into());
+                Ok(())
+            } else {
+                self.parse_fields(class, &modifiers, &segments, dims)
+            }
+        };
+        self.type_vars.truncate(vars);
+        res
+    }
+
+    fn parse_method(
+        &mut self,
+        class: &Class<'a>,
+        modifiers: &[&str],
+        ret: Option<TypePat>,
+    ) -> Result<MethodPat, String> {
+        let is_constructor = ret.is_none();
+        let mut pat = match ret {
+            Some(ret) => MethodPat::default().returns(ret),
+            None if class.kind == Kind::Enum => enum_constructor(),
+            None => MethodPat::constructor(),
+        };
+        self.expect("(")?;
+        while !self.eat(")") {
+            self.skip_annotations()?;
+            self.eat("final");
+            self.skip_annotations()?;
+            let (segments, mut dims) = self.parse_type_parts()?;
+            if self.eat("...") {
+                pat = pat.flags(MethodAccessFlags::VARARGS);
+                dims += 1;
+            }
+            let name = self.expect_ident()?;
+            dims += self.array_dims();
+            // receiver parameters only annotate the type of `this`
+            if name != "this" {
+                pat = pat.param(self.resolve(&segments, dims)?);
+            }
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        self.array_dims();
+        if self.eat("throws") {
+            loop {
+                self.parse_type_parts()?;
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        let has_body = self.peek() == Some("{");
+        if has_body {
+            self.skip_balanced()?;
+        } else {
+            // the default values of the elements of annotations
+            if self.eat("default") {
+                self.skip_initializer()?;
+            }
+            self.expect(";")?;
+        }
+
+        // enum constructors are always private
+        let mut visible = is_constructor && class.kind == Kind::Enum;
+        for &modifier in modifiers {
+            if modifier == "default" {
+                pat = pat.default_impl();
+            } else if let Some(flag) = method_flag(modifier) {
+                visible |= is_visibility(modifier);
+                pat = pat.flags(flag);
+            }
+        }
+        if matches!(class.kind, Kind::Interface | Kind::Annotation) {
+            if !modifiers.contains(&"private") {
+                pat = pat.flags(MethodAccessFlags::PUBLIC);
+            }
+            if !has_body && !modifiers.contains(&"static") {
+                pat = pat.flags(MethodAccessFlags::ABSTRACT);
+            }
+        } else if !visible {
+            pat = pat.visibility(Visibility::Package);
+        }
+        Ok(pat)
+    }
+
+    fn parse_fields(
+        &mut self,
+        class: &mut Class<'a>,
+        modifiers: &[&str],
+        segments: &[&'a str],
+        dims: usize,
+    ) -> Result<(), String> {
+        let mut pat = FieldPat::default();
+        let mut visible = false;
+        for &modifier in modifiers {
+            if let Some(flag) = field_flag(modifier) {
+                visible |= is_visibility(modifier);
+                pat = pat.flags(flag);
+            }
+        }
+        if matches!(class.kind, Kind::Interface | Kind::Annotation) {
+            pat = pat.flags(FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL);
+        } else if !visible {
+            pat = pat.visibility(Visibility::Package);
+        }
+        loop {
+            let dims = dims + self.array_dims();
+            let field = pat.clone().of_type(self.resolve(segments, dims)?);
+            class.pat.members.push(field.into());
+            if self.eat("=") {
+                self.skip_initializer()?;
+            }
+            if !self.eat(",") {
+                break self.expect(";");
+            }
+            self.expect_ident()?;
+        }
+    }
+
+    /// Parses a type into the segments of its name and its number of dimensions, skipping type arguments.
+    fn parse_type_parts(&mut self) -> Result<(Vec<&'a str>, usize), String> {
+        self.skip_annotations()?;
+        let mut segments = vec![self.expect_ident()?];
+        loop {
+            if self.peek() == Some("<") {
+                self.skip_type_args()?;
+            }
+            if self.peek() == Some(".") && self.peek_at(1).is_some_and(is_ident) {
+                self.next();
+                segments.push(self.expect_ident()?);
+            } else {
+                break;
+            }
+        }
+        Ok((segments, self.array_dims()))
+    }
+
+    fn parse_type(&mut self) -> Result<TypePat, String> {
+        let (segments, dims) = self.parse_type_parts()?;
+        self.resolve(&segments, dims)
+    }
+
+    /// Resolves a type written in the source, e.g. `Map.Entry` imported from `java.util.Map`.
+    fn resolve(&self, segments: &[&'a str], dims: usize) -> Result<TypePat, String> {
+        let [first, nested @ ..] = segments else {
+            return Err("expected a type".to_owned());
+        };
+        if nested.is_empty() && self.type_vars.contains(first) {
+            return Ok(TypePat::Any);
+        }
+        let is_class = |segment: &&str| segment.starts_with(|c: char| c.is_uppercase());
+        let (class, nested) = if let Some((_, import)) = self.imports.iter().find(|(name, _)| name == first)
+        {
+            (import.clone(), nested)
+        } else if !is_class(first) && nested.iter().any(is_class) {
+            // a qualified name, e.g. `java.util.Map.Entry`
+            let split = nested.iter().position(is_class).unwrap() + 1;
+            (segments[..=split].join("."), &nested[split..])
+        } else if nested.is_empty() && (is_primitive(first) || JAVA_LANG.contains(first)) {
+            ((*first).to_owned(), nested)
+        } else if JAVA_LANG.contains(first) {
+            (format!("java.lang.{first}"), nested)
+        } else if self.package.is_empty() {
+            ((*first).to_owned(), nested)
+        } else {
+            (format!("{}.{first}", self.package), nested)
+        };
+        let mut name = class;
+        for segment in nested {
+            name.push('$');
+            name.push_str(segment);
+        }
+        for _ in 0..dims {
+            name.push_str("[]");
+        }
+        parse_type_name(&name)
+    }
+
+    fn type_params(&mut self) -> Result<Vec<&'a str>, String> {
+        self.expect("<")?;
+        let mut names = vec![];
+        loop {
+            self.skip_annotations()?;
+            names.push(self.expect_ident()?);
+            let mut depth = 1;
+            loop {
+                match self.expect_any()? {
+                    "<" => depth += 1,
+                    ">" if depth == 1 => return Ok(names),
+                    ">" => depth -= 1,
+                    "," if depth == 1 => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn skip_type_args(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        loop {
+            match self.expect_any()? {
+                "<" => depth += 1,
+                ">" if depth == 1 => return Ok(()),
+                ">" => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_annotations(&mut self) -> Result<(), String> {
+        while self.peek() == Some("@") && self.peek_at(1) != Some("interface") {
+            self.next();
+            self.qualified_name()?;
+            if self.peek() == Some("(") {
+                self.skip_balanced()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips the tokens up to the next `,` or `;` outside of parentheses, brackets and braces.
+    fn skip_initializer(&mut self) -> Result<(), String> {
+        while !matches!(self.peek(), Some("," | ";")) {
+            match self.peek() {
+                Some("(" | "[" | "{") => self.skip_balanced()?,
+                _ => {
+                    self.expect_any()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips a group of tokens enclosed in matching parentheses, brackets or braces.
+    fn skip_balanced(&mut self) -> Result<(), String> {
+        let mut depth = 0usize;
+        loop {
+            match self.expect_any()? {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn array_dims(&mut self) -> usize {
+        let mut dims = 0;
+        while self.peek() == Some("[") && self.peek_at(1) == Some("]") {
+            self.pos += 2;
+            dims += 1;
+        }
+        dims
+    }
+
+    fn qualified_name(&mut self) -> Result<String, String> {
+        let mut name = self.expect_ident()?.to_owned();
+        while self.peek() == Some(".") && self.peek_at(1).is_some_and(is_ident) {
+            self.next();
+            name.push('.');
+            name.push_str(self.expect_ident()?);
+        }
+        Ok(name)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&'a str> {
+        self.peek_at(0)
+    }
+
+    #[inline]
+    fn peek_at(&self, offset: usize) -> Option<&'a str> {
+        self.tokens.get(self.pos + offset).map(|token| token.text)
+    }
+
+    #[inline]
+    fn next(&mut self) {
+        self.pos += 1;
+    }
+
+    fn next_text(&mut self) -> &'a str {
+        let text = self.tokens[self.pos].text;
+        self.pos += 1;
+        text
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let matches = self.peek() == Some(token);
+        if matches {
+            self.next();
+        }
+        matches
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.peek() {
+            Some(found) if found == token => {
+                self.next();
+                Ok(())
+            }
+            Some(found) => Err(format!("unexpected `{found}`, expected `{token}`")),
+            None => Err(format!("unexpected end of input, expected `{token}`")),
+        }
+    }
+
+    fn expect_any(&mut self) -> Result<&'a str, String> {
+        let token = self.peek().ok_or("unexpected end of input")?;
+        self.next();
+        Ok(token)
+    }
+
+    fn expect_ident(&mut self) -> Result<&'a str, String> {
+        match self.peek() {
+            Some(token) if is_ident(token) => {
+                self.next();
+                Ok(token)
+            }
+            Some(token) => Err(format!("unexpected `{token}`, expected an identifier")),
+            None => Err("unexpected end of input, expected an identifier".to_owned()),
+        }
+    }
+
+    /// Returns the line of the current token, used to report errors.
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos.min(self.tokens.len().saturating_sub(1)))
+            .map_or(1, |token| token.line)
+    }
+}
+
+/// Enum constructors are private and take the name and the ordinal of the constant first.
+fn enum_constructor() -> MethodPat {
+    MethodPat::constructor()
+        .flags(MethodAccessFlags::PRIVATE)
+        .param(TypePat::Match(Descriptor::Object("java/lang/String")))
+        .param(TypePat::Match(Descriptor::Integer))
+}
+
+#[inline]
+fn is_modifier(token: &str) -> bool {
+    matches!(
+        token,
+        "public"
+            | "protected"
+            | "private"
+            | "static"
+            | "final"
+            | "abstract"
+            | "synchronized"
+            | "native"
+            | "transient"
+            | "volatile"
+            | "strictfp"
+            | "default"
+            | "sealed"
+            | "non"
+    )
+}
+
+#[inline]
+fn is_visibility(modifier: &str) -> bool {
+    matches!(modifier, "public" | "private" | "protected")
+}
+
+#[inline]
+fn is_primitive(name: &str) -> bool {
+    matches!(
+        name,
+        "boolean" | "byte" | "short" | "int" | "long" | "float" | "double" | "char" | "void"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+
+    #[test]
+    fn parse_and_search_java() {
+        let src = r#"
+            package com.example;
+
+            import java.util.List;
+            import java.util.Map;
+            import java.util.function.Function;
+
+            /** A cache with {@code braces} in comments. */
+            @SuppressWarnings({"unchecked", "rawtypes"})
+            public final class Cache<K, V extends Comparable<V>> implements Iterable<V> {
+                private final Map<K, List<V>> entries = Map.of("}", new int[] {1, 2}), extra;
+                static final int SIZE = 16;
+
+                static {
+                    System.out.println("{");
+                }
+
+                public Cache(String... names) {
+                    this.entries = null;
+                }
+
+                public <T> T get(K key, Function<K, T> fn) throws java.io.IOException {
+                    return fn.apply(key);
+                }
+
+                private static class Node {}
+
+                Map.Entry<K, V>[] entries() { return null; }
+            }
+
+            interface Listener extends java.util.EventListener {
+                int PRIORITY = 0;
+
+                void onEvent(Cache<?, ?> cache);
+
+                default boolean enabled() { return '}' != 0; }
+            }
+        "#;
+        let pats = parse_java(src).unwrap();
+        assert_eq!(pats.len(), 2);
+        assert_eq!(pats[0].0, "com.example.Cache");
+        assert_eq!(pats[1].0, "com.example.Listener");
+
+        let public = MethodAccessFlags::PUBLIC;
+        let map = "Ljava/util/Map;";
+        let cache = TestClass::new("a")
+            .flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL)
+            .implements("java/lang/Iterable")
+            .field(FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL, "b", map)
+            .field(FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL, "c", map)
+            .field(FieldAccessFlags::STATIC | FieldAccessFlags::FINAL, "d", "I")
+            .method(MethodAccessFlags::STATIC, "<clinit>", "()V")
+            .method(
+                public | MethodAccessFlags::VARARGS,
+                "<init>",
+                "([Ljava/lang/String;)V",
+            )
+            .method(
+                public,
+                "e",
+                "(Ljava/lang/Object;Ljava/util/function/Function;)Ljava/lang/Object;",
+            )
+            .method(
+                MethodAccessFlags::PRIVATE | MethodAccessFlags::SYNTHETIC,
+                "f",
+                "()V",
+            )
+            .method(MethodAccessFlags::empty(), "g", "()[Ljava/util/Map$Entry;");
+        let listener = TestClass::new("h")
+            .flags(ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT)
+            .implements("java/util/EventListener")
+            .field(
+                FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+                "i",
+                "I",
+            )
+            .method(public | MethodAccessFlags::ABSTRACT, "j", "(La;)V")
+            .method(public, "k", "()Z");
+        let mut jar = TestJar::default().class(cache).class(listener).open().unwrap();
+        let [cache, listener] =
+            crate::search_exact(&mut jar, &[pats[0].1.clone(), pats[1].1.clone()]).unwrap();
+        assert_eq!(cache.name(), "a.class");
+        assert_eq!(listener.name(), "h.class");
+
+        let err = parse_java("class A {\n  void run( {}\n}").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,116 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crate::filter::{ClassFilter, ClassInfo};
+
+/// Counters describing where the time of a search went, collected with
+/// [`SearchOptions::stats`](crate::SearchOptions::stats).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchStats {
+    /// Number of classes read and parsed.
+    pub classes_scanned: usize,
+    /// Number of classes rejected by the [`ClassFilter`] of the search before being parsed,
+    /// including the libraries skipped with [`SearchOptions::skip_libraries`](crate::SearchOptions::skip_libraries).
+    pub classes_filtered: usize,
+    /// Total size in bytes of the classes read.
+    pub bytes_read: u64,
+    /// Time spent parsing classes.
+    pub parse_time: Duration,
+    /// Time spent checking the patterns against the parsed classes.
+    pub match_time: Duration,
+}
+
+impl SearchStats {
+    /// Returns the fraction of the classes rejected by the filter before being parsed, between 0 and 1.
+    pub fn filter_rejection_rate(&self) -> f64 {
+        let total = self.classes_scanned + self.classes_filtered;
+        if total == 0 {
+            return 0.;
+        }
+        self.classes_filtered as f64 / total as f64
+    }
+
+    /// Adds the counters of another search, e.g. of another part of the same archive.
+    pub fn merge(&mut self, other: &SearchStats) {
+        self.classes_scanned += other.classes_scanned;
+        self.classes_filtered += other.classes_filtered;
+        self.bytes_read += other.bytes_read;
+        self.parse_time += other.parse_time;
+        self.match_time += other.match_time;
+    }
+}
+
+/// Runs a step of a search, adding the time it took to `time` if statistics are collected.
+///
+/// Clocks are only read when needed, since they're not available on all targets.
+#[inline]
+pub(crate) fn timed<A>(time: Option<&mut Duration>, f: impl FnOnce() -> A) -> A {
+    match time {
+        Some(time) => {
+            let start = Instant::now();
+            let res = f();
+            *time += start.elapsed();
+            res
+        }
+        None => f(),
+    }
+}
+
+/// A filter counting the classes it rejects.
+#[derive(Debug)]
+pub(crate) struct Counting<F> {
+    filter: F,
+    rejected: Cell<usize>,
+}
+
+impl<F> Counting<F> {
+    #[inline]
+    pub(crate) fn new(filter: F) -> Self {
+        Self {
+            filter,
+            rejected: Cell::new(0),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn rejected(&self) -> usize {
+        self.rejected.get()
+    }
+}
+
+impl<F: ClassFilter> ClassFilter for Counting<F> {
+    fn accepts(&self, class: &ClassInfo<'_>) -> Option<bool> {
+        let accepted = self.filter.accepts(class);
+        // a class is rejected at most once, either by its name or by its flags
+        if accepted == Some(false) {
+            self.rejected.set(self.rejected.get() + 1);
+        }
+        accepted
+    }
+}
+
+/// Counters describing how the entries of an archive are compressed,
+/// see [`Jar::compression_stats`](crate::Jar::compression_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    /// Number of entries, excluding directories.
+    pub entries: usize,
+    /// Number of entries stored without compression.
+    pub stored: usize,
+    /// Number of entries compressed with deflate.
+    pub deflated: usize,
+    /// Total size in bytes of the entries as stored in the archive.
+    pub compressed_size: u64,
+    /// Total size in bytes of the entries once decompressed.
+    pub size: u64,
+}
+
+impl CompressionStats {
+    /// Returns the ratio between the decompressed and the compressed size of the entries.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.compressed_size == 0 {
+            return 1.;
+        }
+        self.size as f64 / self.compressed_size as f64
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeSet;
+use std::io::{Read, Seek};
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+
+use crate::indy::code;
+use crate::jar::{Jar, JarEntry};
+use crate::result::Result;
+
+/// Statistics of a class computed without parsing its bytecode, see [`JarEntry::summary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSummary {
+    /// Internal name of the class.
+    pub name: String,
+    pub access_flags: ClassAccessFlags,
+    pub super_class: Option<String>,
+    pub interface_count: usize,
+    /// Number of methods, including constructors and the static initializer.
+    pub method_count: usize,
+    pub constructor_count: usize,
+    pub static_method_count: usize,
+    pub abstract_method_count: usize,
+    pub field_count: usize,
+    pub static_field_count: usize,
+    /// Internal names of the other classes referenced by the constant pool, sorted by name.
+    pub referenced_types: Vec<String>,
+    /// Number of distinct string literals.
+    pub string_count: usize,
+    /// Total size of the bytecode of all methods in bytes.
+    pub code_size: usize,
+}
+
+impl JarEntry {
+    /// Computes the [`ClassSummary`] of this class.
+    pub fn summary(&self) -> Result<ClassSummary> {
+        let class = self.parse_without_bytecode()?;
+        let pool = self.constant_pool()?;
+        let code_size = class
+            .methods
+            .iter()
+            .filter_map(|method| code(&method.attributes))
+            .map(|code| code.code.len())
+            .sum();
+
+        let count_methods = |flags| {
+            let methods = class.methods.iter();
+            methods
+                .filter(|method| method.access_flags.contains(flags))
+                .count()
+        };
+        let referenced_types: BTreeSet<String> = pool
+            .class_names()
+            .filter(|name| *name != class.this_class)
+            .map(|name| name.into_owned())
+            .collect();
+        let strings: BTreeSet<_> = pool.strings().collect();
+        Ok(ClassSummary {
+            name: class.this_class.to_string(),
+            access_flags: class.access_flags,
+            super_class: class.super_class.as_ref().map(ToString::to_string),
+            interface_count: class.interfaces.len(),
+            method_count: class.methods.len(),
+            constructor_count: class
+                .methods
+                .iter()
+                .filter(|method| method.name == "<init>")
+                .count(),
+            static_method_count: count_methods(MethodAccessFlags::STATIC),
+            abstract_method_count: count_methods(MethodAccessFlags::ABSTRACT),
+            field_count: class.fields.len(),
+            static_field_count: class
+                .fields
+                .iter()
+                .filter(|field| field.access_flags.contains(FieldAccessFlags::STATIC))
+                .count(),
+            referenced_types: referenced_types.into_iter().collect(),
+            string_count: strings.len(),
+            code_size,
+        })
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Returns an iterator over the [`ClassSummary`] of every class in the archive.
+    pub fn summaries(&mut self) -> impl Iterator<Item = Result<ClassSummary>> + '_ {
+        self.classes().map(|entry| entry?.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestJar;
+    use crate::writer::{ClassBuilder, Code, FieldBuilder, MethodBuilder};
+
+    #[test]
+    fn summarize_classes() {
+        let mut class = ClassBuilder::new("a").implements("java/lang/Runnable");
+        class.pool().string("hello");
+        let init = MethodBuilder::new(MethodAccessFlags::PUBLIC, "<init>", "()V").code(Code {
+            max_stack: 0,
+            max_locals: 1,
+            bytecode: vec![0xb1],
+        });
+        let run = MethodBuilder::new(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            "run",
+            "()V",
+        )
+        .code(Code {
+            max_stack: 0,
+            max_locals: 0,
+            bytecode: vec![0x00, 0xb1],
+        });
+        let bytes = class
+            .with_field(FieldBuilder::new(FieldAccessFlags::STATIC, "b", "I"))
+            .with_method(init)
+            .with_method(run)
+            .to_bytes();
+        let mut jar = TestJar::default().entry("a.class", bytes).open().unwrap();
+
+        let summary = jar.summaries().next().unwrap().unwrap();
+        assert_eq!(summary.name, "a");
+        assert_eq!(summary.interface_count, 1);
+        assert_eq!(
+            (
+                summary.method_count,
+                summary.constructor_count,
+                summary.static_method_count
+            ),
+            (2, 1, 1)
+        );
+        assert_eq!((summary.field_count, summary.static_field_count), (1, 1));
+        assert_eq!(summary.referenced_types, vec![
+            "java/lang/Object",
+            "java/lang/Runnable"
+        ]);
+        assert_eq!(summary.string_count, 1);
+        assert_eq!(summary.code_size, 3);
+    }
+}
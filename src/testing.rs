@@ -0,0 +1,161 @@
+//! Utilities for testing patterns against synthetic classes.
+//!
+//! [`TestClass`] assembles minimal class files in memory and [`TestJar`] packs them into
+//! an in-memory archive, so patterns can be tested without shipping binary fixtures.
+//!
+//! # Examples
+//! ```
+//! use jars::cafebabe::MethodAccessFlags;
+//! use jars::testing::{TestClass, TestJar};
+//! use jars::{method, ClassPat};
+//!
+//! let class = TestClass::new("com/example/Foo")
+//!     .method(MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC, "run", "(Ljava/lang/String;)I");
+//! let mut jar = TestJar::default().class(class).open().unwrap();
+//!
+//! let pat = ClassPat::default().with(method!(public static (String) -> i32));
+//! let [entry] = jars::search_exact(&mut jar, &[pat]).unwrap();
+//! assert_eq!(entry.name(), "com/example/Foo.class");
+//! ```
+
+use std::io::Cursor;
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+use zip::CompressionMethod;
+
+use crate::jar::Jar;
+use crate::result::Result;
+use crate::writer::{ClassBuilder, FieldBuilder, JarWriter, MethodBuilder};
+
+/// A builder of minimal class files.
+///
+/// Classes are written without any attributes, which means methods have no bytecode.
+/// See [`ClassBuilder`] for writing complete classes.
+#[derive(Debug, Clone)]
+pub struct TestClass {
+    access_flags: ClassAccessFlags,
+    name: String,
+    super_class: Option<String>,
+    interfaces: Vec<String>,
+    fields: Vec<(FieldAccessFlags, String, String)>,
+    methods: Vec<(MethodAccessFlags, String, String)>,
+}
+
+impl TestClass {
+    /// Creates a public class with the provided internal name, extending `java/lang/Object`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            name: name.into(),
+            super_class: Some("java/lang/Object".to_owned()),
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+        }
+    }
+
+    /// Sets the access flags of the class.
+    #[inline]
+    pub fn flags(mut self, flags: ClassAccessFlags) -> Self {
+        self.access_flags = flags;
+        self
+    }
+
+    /// Sets the base class of the class.
+    #[inline]
+    pub fn extends(mut self, name: impl Into<String>) -> Self {
+        self.super_class = Some(name.into());
+        self
+    }
+
+    /// Adds an implemented interface to the class.
+    #[inline]
+    pub fn implements(mut self, name: impl Into<String>) -> Self {
+        self.interfaces.push(name.into());
+        self
+    }
+
+    /// Adds a field with the provided type descriptor to the class.
+    #[inline]
+    pub fn field(
+        mut self,
+        flags: FieldAccessFlags,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+    ) -> Self {
+        self.fields.push((flags, name.into(), descriptor.into()));
+        self
+    }
+
+    /// Adds a method with the provided method descriptor to the class.
+    #[inline]
+    pub fn method(
+        mut self,
+        flags: MethodAccessFlags,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+    ) -> Self {
+        self.methods.push((flags, name.into(), descriptor.into()));
+        self
+    }
+
+    /// Returns the internal name of the class.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Writes the class file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let class = ClassBuilder::new(&self.name)
+            .flags(self.access_flags)
+            .extends(self.super_class.as_deref());
+        let class = self
+            .interfaces
+            .iter()
+            .fold(class, |class, name| class.implements(name));
+        let class = self.fields.iter().fold(class, |class, (flags, name, desc)| {
+            class.with_field(FieldBuilder::new(*flags, name, desc))
+        });
+        let class = self.methods.iter().fold(class, |class, (flags, name, desc)| {
+            class.with_method(MethodBuilder::new(*flags, name, desc))
+        });
+        class.to_bytes()
+    }
+}
+
+/// A builder of in-memory archives.
+#[derive(Debug, Clone, Default)]
+pub struct TestJar {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl TestJar {
+    /// Adds a class to the archive, stored under a path derived from its name.
+    #[inline]
+    pub fn class(self, class: TestClass) -> Self {
+        let name = format!("{}.class", class.name());
+        self.entry(name, class.to_bytes())
+    }
+
+    /// Adds an arbitrary entry to the archive.
+    #[inline]
+    pub fn entry(mut self, name: impl Into<String>, contents: impl Into<Vec<u8>>) -> Self {
+        self.entries.push((name.into(), contents.into()));
+        self
+    }
+
+    /// Writes the archive.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut writer = JarWriter::new(Cursor::new(vec![])).compression(CompressionMethod::Stored);
+        for (name, contents) in &self.entries {
+            writer.write_entry(name, contents)?;
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+
+    /// Writes the archive and opens it as a [`Jar`].
+    pub fn open(&self) -> Result<Jar<Cursor<Vec<u8>>>> {
+        Jar::from_bytes(self.to_bytes()?)
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use crate::jar::{Jar, JarEntry};
+use crate::result::Result;
+
+/// A reference to a type by another class, see [`Jar::type_usages`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TypeUsage {
+    /// A field of the type, or of an array of it.
+    Field {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    /// A method taking or returning the type.
+    Method {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    /// A class whose constant pool refers to the type, e.g. to call its methods, create or cast to it.
+    ConstantPool { class: String },
+}
+
+impl TypeUsage {
+    /// Returns the internal name of the class the reference is made by.
+    pub fn class(&self) -> &str {
+        match self {
+            Self::Field { class, .. } | Self::Method { class, .. } | Self::ConstantPool { class } => class,
+        }
+    }
+}
+
+impl JarEntry {
+    /// Returns the references of this class to a type, identified by its internal name.
+    pub fn type_usages(&self, name: &str) -> Result<Vec<TypeUsage>> {
+        let class = self.parse_without_bytecode()?;
+        if class.this_class == name {
+            return Ok(vec![]);
+        }
+        let object = format!("L{name};");
+        let mut usages = vec![];
+        for field in class
+            .fields
+            .iter()
+            .filter(|field| field.descriptor.contains(&object))
+        {
+            usages.push(TypeUsage::Field {
+                class: class.this_class.to_string(),
+                name: field.name.to_string(),
+                descriptor: field.descriptor.to_string(),
+            });
+        }
+        for method in class
+            .methods
+            .iter()
+            .filter(|method| method.descriptor.contains(&object))
+        {
+            usages.push(TypeUsage::Method {
+                class: class.this_class.to_string(),
+                name: method.name.to_string(),
+                descriptor: method.descriptor.to_string(),
+            });
+        }
+
+        let pool = self.constant_pool()?;
+        let mut members = pool.field_refs().chain(pool.method_refs());
+        // array classes are named by their descriptors, e.g. `[Lcom/example/Foo;`
+        let referenced = pool
+            .class_names()
+            .any(|class| class == name || class.starts_with('[') && class.contains(&object))
+            || members.any(|member| member.descriptor.contains(&object));
+        if referenced {
+            usages.push(TypeUsage::ConstantPool {
+                class: class.this_class.to_string(),
+            });
+        }
+        Ok(usages)
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Finds every reference to a type, identified by its internal name, by the other classes of the archive.
+    ///
+    /// Types are found in the descriptors of fields and methods and in the constant pool,
+    /// the usages are sorted by class.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut jar = jars::Jar::open("app.jar")?;
+    /// for usage in jar.type_usages("com/example/Foo")? {
+    ///     println!("{usage:?}");
+    /// }
+    /// # Ok::<(), jars::Error>(())
+    /// ```
+    pub fn type_usages(&mut self, name: &str) -> Result<Vec<TypeUsage>> {
+        let mut usages = vec![];
+        for entry in self.classes() {
+            usages.extend(entry?.type_usages(name)?);
+        }
+        usages.sort_by(|a, b| a.class().cmp(b.class()).then_with(|| a.cmp(b)));
+        Ok(usages)
+    }
+
+    /// Counts the fields of every type in the archive, keyed by their descriptors
+    /// and sorted from the most common type.
+    pub fn field_type_histogram(&mut self) -> Result<Vec<(String, usize)>> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for entry in self.classes() {
+            let entry = entry?;
+            for field in entry.parse_without_bytecode()?.fields {
+                *counts.entry(field.descriptor.into_owned()).or_default() += 1;
+            }
+        }
+        let mut histogram: Vec<_> = counts.into_iter().collect();
+        histogram.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+        Ok(histogram)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::writer::ClassBuilder;
+
+    #[test]
+    fn find_type_usages() {
+        let mut jar = TestJar::default()
+            .class(
+                TestClass::new("a")
+                    .field(FieldAccessFlags::PRIVATE, "foo", "[Lfoo/Foo;")
+                    .field(FieldAccessFlags::PRIVATE, "count", "I"),
+            )
+            .class(TestClass::new("b").method(MethodAccessFlags::PUBLIC, "get", "()Lfoo/Foo;"))
+            .class(TestClass::new("foo/Foo").field(FieldAccessFlags::PRIVATE, "self", "Lfoo/Foo;"))
+            .entry("c.class", ClassBuilder::new("c").implements("foo/Foo").to_bytes())
+            .open()
+            .unwrap();
+
+        assert_eq!(jar.type_usages("foo/Foo").unwrap(), [
+            TypeUsage::Field {
+                class: "a".to_owned(),
+                name: "foo".to_owned(),
+                descriptor: "[Lfoo/Foo;".to_owned(),
+            },
+            TypeUsage::Method {
+                class: "b".to_owned(),
+                name: "get".to_owned(),
+                descriptor: "()Lfoo/Foo;".to_owned(),
+            },
+            TypeUsage::ConstantPool {
+                class: "c".to_owned()
+            },
+        ]);
+        assert_eq!(jar.field_type_histogram().unwrap(), [
+            ("I".to_owned(), 1),
+            ("Lfoo/Foo;".to_owned(), 1),
+            ("[Lfoo/Foo;".to_owned(), 1)
+        ]);
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt;
+use std::io::{Read, Seek};
+
+use cafebabe::{FieldAccessFlags, MethodAccessFlags};
+
+use crate::jar::Jar;
+use crate::matching::{check_field, check_flags, check_method, MemberBinding};
+use crate::model::{ClassModel, MemberView};
+use crate::pat::{ClassPat, FieldPat, MemberPat, MethodPat, Visibilities, Visibility};
+use crate::result::Result;
+use crate::session::SavedMatch;
+
+// the bits of access flags declaring the visibility, the same for methods and fields
+const VISIBILITY_BITS: u16 = 0x0001 | 0x0002 | 0x0004;
+const STATIC_BIT: u16 = 0x0008;
+
+impl<R: Read + Seek> Jar<R> {
+    /// Checks that the members bound by saved matches still satisfy their patterns,
+    /// e.g. to find out which entries of a mapping generated for an older version of the archive broke.
+    ///
+    /// Each member is checked on its own, so unlike a new search this reports what changed about it:
+    /// its name, visibility, staticness, other flags or descriptor. Matches are looked up by their classes
+    /// and refer to `pats` by index.
+    ///
+    /// # Panics
+    /// Panics if a match refers to a pattern outside of `pats`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use jars::{ClassPat, Searcher};
+    ///
+    /// let searcher = Searcher::new([ClassPat::default()]);
+    /// let mut session = searcher.session();
+    /// searcher.search_in_session(&mut jars::Jar::open("app-1.0.jar")?, &mut session)?;
+    ///
+    /// let mut jar = jars::Jar::open("app-1.1.jar")?;
+    /// let matches: Vec<_> = session.matches().map(|(_, mat)| mat.clone()).collect();
+    /// for mismatch in jar.verify_matches(&[ClassPat::default()], &matches)? {
+    ///     eprintln!("{mismatch}");
+    /// }
+    /// # Ok::<(), jars::Error>(())
+    /// ```
+    pub fn verify_matches(&mut self, pats: &[ClassPat], matches: &[SavedMatch]) -> Result<Vec<Mismatch>> {
+        let mut mismatches = vec![];
+        for mat in matches {
+            let pat = &pats[mat.pattern];
+            let mut report = |member, kind| {
+                mismatches.push(Mismatch {
+                    class: mat.class.clone(),
+                    pattern: mat.pattern,
+                    member,
+                    kind,
+                });
+            };
+            let Some(entry) = self.class_by_name(&mat.class)? else {
+                report(None, MismatchKind::MissingClass);
+                continue;
+            };
+            let class = entry.parse_without_bytecode()?;
+            for (j, (member, &binding)) in pat.members.iter().zip(&mat.bindings).enumerate() {
+                let kinds = match binding {
+                    MemberBinding::Method(i) if member.is_method() && i < class.method_count() => {
+                        verify_method(class.method(i), member, pat.exact_flags)
+                    }
+                    MemberBinding::Field(i) if !member.is_method() && i < class.field_count() => {
+                        verify_field(class.field(i), member, pat.exact_flags)
+                    }
+                    _ => vec![MismatchKind::MissingMember],
+                };
+                kinds.into_iter().for_each(|kind| report(Some(j), kind));
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+fn verify_method(
+    method: MemberView<'_, MethodAccessFlags>,
+    pat: &MemberPat,
+    exact: bool,
+) -> Vec<MismatchKind> {
+    let pat = match pat {
+        MemberPat::Method(pat) => pat,
+        MemberPat::Custom(custom) => return predicate_mismatch(custom.check_method(method)),
+        MemberPat::Field(_) => return vec![MismatchKind::MissingMember],
+    };
+    let mut kinds = verify_member(
+        method.name,
+        method.access_flags.bits(),
+        pat.name.as_deref(),
+        pat.flags.bits(),
+        pat.visibility,
+        exact,
+    );
+    // only the shape of the descriptor is left to check
+    let shape = MethodPat {
+        flags: pat.flags & MethodAccessFlags::VARARGS,
+        visibility: Visibilities::default(),
+        name: None,
+        default_impl: false,
+        call_sites: vec![],
+        call_site_count: None,
+        ..pat.clone()
+    };
+    if check_method(method, &shape, false, None).is_none() {
+        kinds.push(MismatchKind::Descriptor {
+            found: method.descriptor.to_owned(),
+        });
+    }
+    kinds
+}
+
+fn verify_field(
+    field: MemberView<'_, FieldAccessFlags>,
+    pat: &MemberPat,
+    exact: bool,
+) -> Vec<MismatchKind> {
+    let pat = match pat {
+        MemberPat::Field(pat) => pat,
+        MemberPat::Custom(custom) => return predicate_mismatch(custom.check_field(field)),
+        MemberPat::Method(_) => return vec![MismatchKind::MissingMember],
+    };
+    let mut kinds = verify_member(
+        field.name,
+        field.access_flags.bits(),
+        pat.name.as_deref(),
+        pat.flags.bits(),
+        pat.visibility,
+        exact,
+    );
+    let shape = FieldPat::default().of_type(pat.field_type.clone());
+    if check_field(field, &shape, false, None).is_none() {
+        kinds.push(MismatchKind::Descriptor {
+            found: field.descriptor.to_owned(),
+        });
+    }
+    kinds
+}
+
+fn verify_member(
+    name: &str,
+    flags: u16,
+    expected_name: Option<&str>,
+    expected_flags: u16,
+    visibility: Visibilities,
+    exact: bool,
+) -> Vec<MismatchKind> {
+    let mut kinds = vec![];
+    if let Some(expected) = expected_name.filter(|expected| *expected != name) {
+        kinds.push(MismatchKind::Renamed {
+            expected: expected.to_owned(),
+            found: name.to_owned(),
+        });
+    }
+    let visibility_ok = visibility.accepts(flags)
+        && check_flags(flags & VISIBILITY_BITS, expected_flags & VISIBILITY_BITS, exact);
+    if !visibility_ok {
+        kinds.push(MismatchKind::Visibility {
+            found: Visibility::from_bits(flags),
+        });
+    }
+    if !check_flags(flags & STATIC_BIT, expected_flags & STATIC_BIT, exact) {
+        kinds.push(MismatchKind::Static {
+            expected: expected_flags & STATIC_BIT != 0,
+        });
+    }
+    let other = !(VISIBILITY_BITS | STATIC_BIT);
+    if !check_flags(flags & other, expected_flags & other, exact) {
+        kinds.push(MismatchKind::Flags {
+            expected: expected_flags & other,
+            found: flags & other,
+        });
+    }
+    kinds
+}
+
+fn predicate_mismatch(accepted: bool) -> Vec<MismatchKind> {
+    if accepted {
+        vec![]
+    } else {
+        vec![MismatchKind::Predicate]
+    }
+}
+
+/// A difference between a member bound by a saved match and its pattern, see [`Jar::verify_matches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Internal name of the matched class.
+    pub class: String,
+    /// Index of the pattern of the match.
+    pub pattern: usize,
+    /// Index of the member in the pattern, or `None` if the whole class is affected.
+    pub member: Option<usize>,
+    pub kind: MismatchKind,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.member {
+            Some(member) => write!(
+                f,
+                "{}, member {} of pattern {}: ",
+                self.class, member, self.pattern
+            )?,
+            None => write!(f, "{}, pattern {}: ", self.class, self.pattern)?,
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+/// The kind of a [`Mismatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// The class isn't in the archive anymore.
+    MissingClass,
+    /// The bound member doesn't exist anymore, or it's not a member of the kind the pattern expects.
+    MissingMember,
+    /// The member doesn't have the name required by the pattern.
+    Renamed { expected: String, found: String },
+    /// The visibility of the member isn't accepted by the pattern anymore.
+    Visibility { found: Visibility },
+    /// The member is static when the pattern expects an instance member, or the other way around.
+    Static { expected: bool },
+    /// The other access flags don't match, as bits of the access flags of the member.
+    Flags { expected: u16, found: u16 },
+    /// The descriptor of the member doesn't match the types of the pattern.
+    Descriptor { found: String },
+    /// The member isn't accepted by the predicate of a custom pattern.
+    Predicate,
+}
+
+impl fmt::Display for MismatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingClass => write!(f, "class not found"),
+            Self::MissingMember => write!(f, "member not found"),
+            Self::Renamed { expected, found } => write!(f, "expected name {expected}, found {found}"),
+            Self::Visibility { found } => write!(f, "visibility changed to {found:?}"),
+            Self::Static { expected: true } => {
+                write!(f, "expected a static member, found an instance member")
+            }
+            Self::Static { expected: false } => {
+                write!(f, "expected an instance member, found a static member")
+            }
+            Self::Flags { expected, found } => {
+                write!(f, "expected flags {expected:#06x}, found {found:#06x}")
+            }
+            Self::Descriptor { found } => write!(f, "descriptor changed to {found}"),
+            Self::Predicate => write!(f, "rejected by the predicate"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cafebabe::MethodAccessFlags;
+
+    use super::*;
+    use crate::testing::{TestClass, TestJar};
+    use crate::{Searcher, TypePat};
+
+    #[test]
+    fn verify_saved_matches() {
+        let pat = ClassPat::default()
+            .with(MethodPat::default().flags(MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC))
+            .with(MethodPat::default().param(TypePat::Any).returns(TypePat::Any));
+        let version = |flags, desc: &str| {
+            TestJar::default()
+                .class(TestClass::new("a").method(flags, "run", "()V").method(
+                    MethodAccessFlags::PUBLIC,
+                    "get",
+                    desc,
+                ))
+                .open()
+                .unwrap()
+        };
+        let searcher = Searcher::new([pat.clone()]);
+        let mut session = searcher.session();
+        let mut old = version(MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC, "(I)I");
+        searcher.search_in_session(&mut old, &mut session).unwrap();
+        let matches: Vec<_> = session.matches().map(|(_, mat)| mat.clone()).collect();
+        assert!(old
+            .verify_matches(std::slice::from_ref(&pat), &matches)
+            .unwrap()
+            .is_empty());
+
+        let mut new = version(MethodAccessFlags::PROTECTED, "()I");
+        let kinds: Vec<_> = new
+            .verify_matches(&[pat], &matches)
+            .unwrap()
+            .into_iter()
+            .map(|mismatch| (mismatch.member, mismatch.kind))
+            .collect();
+        assert_eq!(kinds, [
+            (Some(0), MismatchKind::Visibility {
+                found: Visibility::Protected
+            }),
+            (Some(0), MismatchKind::Static { expected: true }),
+            (Some(1), MismatchKind::Descriptor {
+                found: "()I".to_owned()
+            }),
+        ]);
+    }
+}
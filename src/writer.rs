@@ -0,0 +1,808 @@
+//! Writers for class files and JAR archives.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+
+use cafebabe::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+use thiserror::Error;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+
+use crate::jar::{Jar, JarEntry};
+/// A constant value of a field written by a [`FieldBuilder`].
+pub use crate::model::ConstantValue;
+use crate::result::Result;
+
+/// A constant pool of a class file being written.
+///
+/// Entries are deduplicated, adding an entry that already exists returns its index.
+/// Entries that don't fit in a class file, i.e. strings longer than 65535 bytes once encoded
+/// or entries past the 65535 slots of the pool, get the index 0 and make [`ClassBuilder::try_to_bytes`] fail.
+#[derive(Debug, Clone, Default)]
+pub struct PoolBuilder {
+    bytes: Vec<u8>,
+    count: u16,
+    entries: HashMap<Vec<u8>, u16>,
+    error: Option<WriteError>,
+}
+
+impl PoolBuilder {
+    /// Adds a `CONSTANT_Utf8` entry, encoded as the modified UTF-8 used by class files.
+    pub fn utf8(&mut self, str: &str) -> u16 {
+        let bytes = encode_utf8(str);
+        let Ok(len) = u16::try_from(bytes.len()) else {
+            self.error.get_or_insert(WriteError::StringTooLong(bytes.len()));
+            return 0;
+        };
+        let mut entry = vec![1];
+        entry.extend_from_slice(&len.to_be_bytes());
+        entry.extend_from_slice(&bytes);
+        self.add(entry, 1)
+    }
+
+    /// Adds a `CONSTANT_Class` entry for an internal class name.
+    pub fn class(&mut self, name: &str) -> u16 {
+        let name = self.utf8(name);
+        self.add_indices(7, &[name])
+    }
+
+    /// Adds a `CONSTANT_String` entry.
+    pub fn string(&mut self, str: &str) -> u16 {
+        let str = self.utf8(str);
+        self.add_indices(8, &[str])
+    }
+
+    /// Adds a `CONSTANT_Integer` entry.
+    pub fn integer(&mut self, value: i32) -> u16 {
+        let mut entry = vec![3];
+        entry.extend_from_slice(&value.to_be_bytes());
+        self.add(entry, 1)
+    }
+
+    /// Adds a `CONSTANT_Float` entry.
+    pub fn float(&mut self, value: f32) -> u16 {
+        let mut entry = vec![4];
+        entry.extend_from_slice(&value.to_bits().to_be_bytes());
+        self.add(entry, 1)
+    }
+
+    /// Adds a `CONSTANT_Long` entry, which takes up two slots in the pool.
+    pub fn long(&mut self, value: i64) -> u16 {
+        let mut entry = vec![5];
+        entry.extend_from_slice(&value.to_be_bytes());
+        self.add(entry, 2)
+    }
+
+    /// Adds a `CONSTANT_Double` entry, which takes up two slots in the pool.
+    pub fn double(&mut self, value: f64) -> u16 {
+        let mut entry = vec![6];
+        entry.extend_from_slice(&value.to_bits().to_be_bytes());
+        self.add(entry, 2)
+    }
+
+    /// Adds a `CONSTANT_NameAndType` entry.
+    pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        self.add_indices(12, &[name, descriptor])
+    }
+
+    /// Adds a `CONSTANT_Fieldref` entry.
+    pub fn field_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.add_indices(9, &[class, name_and_type])
+    }
+
+    /// Adds a `CONSTANT_Methodref` entry.
+    pub fn method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.add_indices(10, &[class, name_and_type])
+    }
+
+    /// Adds a `CONSTANT_InterfaceMethodref` entry.
+    pub fn interface_method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.add_indices(11, &[class, name_and_type])
+    }
+
+    /// Adds a `CONSTANT_MethodHandle` entry referring to a field or a method entry,
+    /// `kind` is one of the `REF_` constants of the JVM specification, e.g. 6 for `REF_invokeStatic`.
+    pub fn method_handle(&mut self, kind: u8, reference: u16) -> u16 {
+        let mut entry = vec![15, kind];
+        entry.extend_from_slice(&reference.to_be_bytes());
+        self.add(entry, 1)
+    }
+
+    /// Adds a `CONSTANT_MethodType` entry.
+    pub fn method_type(&mut self, descriptor: &str) -> u16 {
+        let descriptor = self.utf8(descriptor);
+        self.add_indices(16, &[descriptor])
+    }
+
+    /// Adds a `CONSTANT_InvokeDynamic` entry,
+    /// `bootstrap_method` is an index returned by [`ClassBuilder::bootstrap_method`].
+    pub fn invoke_dynamic(&mut self, bootstrap_method: u16, name: &str, descriptor: &str) -> u16 {
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.add_indices(18, &[bootstrap_method, name_and_type])
+    }
+
+    fn add_indices(&mut self, tag: u8, indices: &[u16]) -> u16 {
+        let mut entry = vec![tag];
+        for index in indices {
+            entry.extend_from_slice(&index.to_be_bytes());
+        }
+        self.add(entry, 1)
+    }
+
+    fn add(&mut self, entry: Vec<u8>, slots: u16) -> u16 {
+        if let Some(&index) = self.entries.get(&entry) {
+            return index;
+        }
+        // the count written to the class file is one more than the number of slots
+        let Some(count) = self.count.checked_add(slots).filter(|&count| count < u16::MAX) else {
+            self.error.get_or_insert(WriteError::PoolOverflow);
+            return 0;
+        };
+        let index = self.count + 1;
+        self.bytes.extend_from_slice(&entry);
+        self.count = count;
+        self.entries.insert(entry, index);
+        index
+    }
+}
+
+/// Encodes a string as modified UTF-8, which differs from UTF-8 in that the null character is written
+/// on two bytes and supplementary characters are written as surrogate pairs of three bytes each.
+fn encode_utf8(str: &str) -> Cow<'_, [u8]> {
+    if !str.chars().any(|char| char == '\0' || char > '\u{ffff}') {
+        return Cow::Borrowed(str.as_bytes());
+    }
+    let mut bytes = Vec::with_capacity(str.len() + 2);
+    for char in str.chars() {
+        match char {
+            '\0' => bytes.extend_from_slice(&[0xC0, 0x80]),
+            '\u{10000}'.. => {
+                for unit in char.encode_utf16(&mut [0; 2]) {
+                    bytes.extend_from_slice(&[
+                        0xE0 | (*unit >> 12) as u8,
+                        0x80 | (*unit >> 6 & 0x3F) as u8,
+                        0x80 | (*unit & 0x3F) as u8,
+                    ]);
+                }
+            }
+            _ => bytes.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+/// An error preventing a class from being written, see [`ClassBuilder::try_to_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WriteError {
+    #[error("string of {0} bytes is too long for the constant pool")]
+    StringTooLong(usize),
+    #[error("constant pool has more than 65534 slots")]
+    PoolOverflow,
+    #[error("class has {count} {kind}, at most 65535 are allowed")]
+    TooMany { kind: &'static str, count: usize },
+}
+
+/// A builder of a field of a class.
+#[derive(Debug, Clone)]
+pub struct FieldBuilder {
+    access_flags: FieldAccessFlags,
+    name: String,
+    descriptor: String,
+    constant: Option<ConstantValue>,
+    signature: Option<String>,
+    attributes: Vec<(String, Vec<u8>)>,
+}
+
+impl FieldBuilder {
+    pub fn new(
+        access_flags: FieldAccessFlags,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+    ) -> Self {
+        Self {
+            access_flags,
+            name: name.into(),
+            descriptor: descriptor.into(),
+            constant: None,
+            signature: None,
+            attributes: vec![],
+        }
+    }
+
+    /// Adds a `ConstantValue` attribute to the field.
+    #[inline]
+    pub fn constant(mut self, value: ConstantValue) -> Self {
+        self.constant = Some(value);
+        self
+    }
+
+    /// Adds a `Signature` attribute to the field.
+    #[inline]
+    pub fn signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+
+    /// Adds an attribute with arbitrary contents to the field.
+    #[inline]
+    pub fn attribute(mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        self.attributes.push((name.into(), data.into()));
+        self
+    }
+}
+
+/// The `Code` attribute of a method written by a [`MethodBuilder`].
+#[derive(Debug, Clone)]
+pub struct Code {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    /// The bytecode of the method, constant pool indices can be obtained from [`ClassBuilder::pool`].
+    pub bytecode: Vec<u8>,
+}
+
+/// A builder of a method of a class.
+#[derive(Debug, Clone)]
+pub struct MethodBuilder {
+    access_flags: MethodAccessFlags,
+    name: String,
+    descriptor: String,
+    code: Option<Code>,
+    exceptions: Vec<String>,
+    signature: Option<String>,
+    attributes: Vec<(String, Vec<u8>)>,
+}
+
+impl MethodBuilder {
+    pub fn new(
+        access_flags: MethodAccessFlags,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+    ) -> Self {
+        Self {
+            access_flags,
+            name: name.into(),
+            descriptor: descriptor.into(),
+            code: None,
+            exceptions: vec![],
+            signature: None,
+            attributes: vec![],
+        }
+    }
+
+    /// Adds a `Code` attribute to the method.
+    #[inline]
+    pub fn code(mut self, code: Code) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Adds an exception to the `Exceptions` attribute of the method.
+    #[inline]
+    pub fn throws(mut self, class: impl Into<String>) -> Self {
+        self.exceptions.push(class.into());
+        self
+    }
+
+    /// Adds a `Signature` attribute to the method.
+    #[inline]
+    pub fn signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+
+    /// Adds an attribute with arbitrary contents to the method.
+    #[inline]
+    pub fn attribute(mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        self.attributes.push((name.into(), data.into()));
+        self
+    }
+}
+
+/// A builder of class files.
+///
+/// # Examples
+/// ```
+/// use jars::cafebabe::MethodAccessFlags;
+/// use jars::writer::{ClassBuilder, Code, MethodBuilder};
+///
+/// let mut class = ClassBuilder::new("com/example/Foo");
+/// let greeting = class.pool().string("hello");
+/// let method = MethodBuilder::new(MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC, "greet", "()Ljava/lang/String;")
+///     .code(Code {
+///         max_stack: 1,
+///         max_locals: 0,
+///         // ldc_w greeting, areturn
+///         bytecode: [&[0x13][..], &greeting.to_be_bytes(), &[0xb0]].concat(),
+///     });
+/// let bytes = class.with_method(method).to_bytes();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClassBuilder {
+    pool: PoolBuilder,
+    major_version: u16,
+    minor_version: u16,
+    access_flags: ClassAccessFlags,
+    name: String,
+    this_class: u16,
+    super_class: u16,
+    interfaces: Vec<u16>,
+    fields: Vec<Member>,
+    methods: Vec<Member>,
+    attributes: Vec<(u16, Vec<u8>)>,
+    // name of the attribute in the pool and the handles and arguments of the methods
+    bootstrap_methods: (u16, Vec<(u16, Vec<u16>)>),
+    error: Option<WriteError>,
+}
+
+#[derive(Debug, Clone)]
+struct Member {
+    access_flags: u16,
+    name: u16,
+    descriptor: u16,
+    attributes: Vec<(u16, Vec<u8>)>,
+}
+
+impl ClassBuilder {
+    /// Creates a builder of a public class extending `java/lang/Object`, targeting Java 8.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let mut pool = PoolBuilder::default();
+        let this_class = pool.class(&name);
+        let super_class = pool.class("java/lang/Object");
+        Self {
+            pool,
+            major_version: 52,
+            minor_version: 0,
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            name,
+            this_class,
+            super_class,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+            bootstrap_methods: (0, vec![]),
+            error: None,
+        }
+    }
+
+    /// Returns the internal name of the class.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the constant pool of the class.
+    #[inline]
+    pub fn pool(&mut self) -> &mut PoolBuilder {
+        &mut self.pool
+    }
+
+    /// Sets the class file version.
+    #[inline]
+    pub fn version(mut self, major: u16, minor: u16) -> Self {
+        self.major_version = major;
+        self.minor_version = minor;
+        self
+    }
+
+    /// Sets the access flags of the class.
+    #[inline]
+    pub fn flags(mut self, flags: ClassAccessFlags) -> Self {
+        self.access_flags = flags;
+        self
+    }
+
+    /// Sets the base class of the class, [`None`] is only valid for `java/lang/Object`.
+    #[inline]
+    pub fn extends(mut self, name: Option<&str>) -> Self {
+        self.super_class = name.map_or(0, |name| self.pool.class(name));
+        self
+    }
+
+    /// Adds an implemented interface to the class.
+    #[inline]
+    pub fn implements(mut self, name: &str) -> Self {
+        let index = self.pool.class(name);
+        self.interfaces.push(index);
+        self
+    }
+
+    /// Adds a field to the class.
+    pub fn with_field(mut self, field: FieldBuilder) -> Self {
+        let mut attributes = vec![];
+        if let Some(constant) = &field.constant {
+            let index = match constant {
+                ConstantValue::Integer(i) => self.pool.integer(*i),
+                ConstantValue::Float(f) => self.pool.float(*f),
+                ConstantValue::Long(l) => self.pool.long(*l),
+                ConstantValue::Double(d) => self.pool.double(*d),
+                ConstantValue::String(str) => self.pool.string(str),
+            };
+            attributes.push(self.attribute_entry("ConstantValue", index.to_be_bytes().into()));
+        }
+        if let Some(signature) = &field.signature {
+            let index = self.pool.utf8(signature);
+            attributes.push(self.attribute_entry("Signature", index.to_be_bytes().into()));
+        }
+        for (name, data) in field.attributes {
+            attributes.push(self.attribute_entry(&name, data));
+        }
+
+        let member = Member {
+            access_flags: field.access_flags.bits(),
+            name: self.pool.utf8(&field.name),
+            descriptor: self.pool.utf8(&field.descriptor),
+            attributes,
+        };
+        self.fields.push(member);
+        self
+    }
+
+    /// Adds a method to the class.
+    pub fn with_method(mut self, method: MethodBuilder) -> Self {
+        let mut attributes = vec![];
+        if let Some(code) = method.code {
+            let mut data = vec![];
+            data.extend_from_slice(&code.max_stack.to_be_bytes());
+            data.extend_from_slice(&code.max_locals.to_be_bytes());
+            data.extend_from_slice(&(code.bytecode.len() as u32).to_be_bytes());
+            data.extend_from_slice(&code.bytecode);
+            // exception_table_length and attributes_count
+            data.extend_from_slice(&[0; 4]);
+            attributes.push(self.attribute_entry("Code", data));
+        }
+        if !method.exceptions.is_empty() {
+            let mut data = vec![];
+            data.extend_from_slice(&length(
+                method.exceptions.len(),
+                "exceptions in a method",
+                &mut self.error,
+            ));
+            for class in &method.exceptions {
+                data.extend_from_slice(&self.pool.class(class).to_be_bytes());
+            }
+            attributes.push(self.attribute_entry("Exceptions", data));
+        }
+        if let Some(signature) = &method.signature {
+            let index = self.pool.utf8(signature);
+            attributes.push(self.attribute_entry("Signature", index.to_be_bytes().into()));
+        }
+        for (name, data) in method.attributes {
+            attributes.push(self.attribute_entry(&name, data));
+        }
+
+        let member = Member {
+            access_flags: method.access_flags.bits(),
+            name: self.pool.utf8(&method.name),
+            descriptor: self.pool.utf8(&method.descriptor),
+            attributes,
+        };
+        self.methods.push(member);
+        self
+    }
+
+    /// Adds a `SourceFile` attribute to the class.
+    pub fn source_file(mut self, name: &str) -> Self {
+        let index = self.pool.utf8(name);
+        let attribute = self.attribute_entry("SourceFile", index.to_be_bytes().into());
+        self.attributes.push(attribute);
+        self
+    }
+
+    /// Adds an attribute with arbitrary contents to the class.
+    pub fn attribute(mut self, name: &str, data: impl Into<Vec<u8>>) -> Self {
+        let attribute = self.attribute_entry(name, data.into());
+        self.attributes.push(attribute);
+        self
+    }
+
+    /// Adds an entry to the `BootstrapMethods` attribute of the class and returns its index.
+    ///
+    /// `handle` is a method handle and `arguments` are loadable entries of the constant pool.
+    pub fn bootstrap_method(&mut self, handle: u16, arguments: &[u16]) -> u16 {
+        let (name, methods) = &mut self.bootstrap_methods;
+        *name = self.pool.utf8("BootstrapMethods");
+        methods.push((handle, arguments.to_vec()));
+        methods.len() as u16 - 1
+    }
+
+    /// Writes the class file.
+    ///
+    /// # Panics
+    /// Panics if the class doesn't fit in a class file, see [`try_to_bytes`](Self::try_to_bytes).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.try_to_bytes()
+            .unwrap_or_else(|err| panic!("class {} can't be written: {err}", self.name))
+    }
+
+    /// Writes the class file, or returns an error if it exceeds a limit of the format,
+    /// e.g. a string longer than 65535 bytes or more than 65535 methods.
+    pub fn try_to_bytes(&self) -> Result<Vec<u8>, WriteError> {
+        let mut error = self.pool.error.clone().or_else(|| self.error.clone());
+        let mut out = vec![];
+        out.extend_from_slice(&0xCAFE_BABEu32.to_be_bytes());
+        out.extend_from_slice(&self.minor_version.to_be_bytes());
+        out.extend_from_slice(&self.major_version.to_be_bytes());
+        out.extend_from_slice(&(self.pool.count + 1).to_be_bytes());
+        out.extend_from_slice(&self.pool.bytes);
+        out.extend_from_slice(&self.access_flags.bits().to_be_bytes());
+        out.extend_from_slice(&self.this_class.to_be_bytes());
+        out.extend_from_slice(&self.super_class.to_be_bytes());
+        out.extend_from_slice(&length(self.interfaces.len(), "interfaces", &mut error));
+        for index in &self.interfaces {
+            out.extend_from_slice(&index.to_be_bytes());
+        }
+        for (members, kind) in [(&self.fields, "fields"), (&self.methods, "methods")] {
+            out.extend_from_slice(&length(members.len(), kind, &mut error));
+            for member in members {
+                out.extend_from_slice(&member.access_flags.to_be_bytes());
+                out.extend_from_slice(&member.name.to_be_bytes());
+                out.extend_from_slice(&member.descriptor.to_be_bytes());
+                write_attributes(&mut out, &member.attributes, &mut error);
+            }
+        }
+        let (name, methods) = &self.bootstrap_methods;
+        if methods.is_empty() {
+            write_attributes(&mut out, &self.attributes, &mut error);
+        } else {
+            let mut data = length(methods.len(), "bootstrap methods", &mut error).to_vec();
+            for (handle, arguments) in methods {
+                data.extend_from_slice(&handle.to_be_bytes());
+                data.extend_from_slice(&length(arguments.len(), "bootstrap method arguments", &mut error));
+                for argument in arguments {
+                    data.extend_from_slice(&argument.to_be_bytes());
+                }
+            }
+            let mut attributes = self.attributes.clone();
+            attributes.push((*name, data));
+            write_attributes(&mut out, &attributes, &mut error);
+        }
+        match error {
+            Some(err) => Err(err),
+            None => Ok(out),
+        }
+    }
+
+    fn attribute_entry(&mut self, name: &str, data: Vec<u8>) -> (u16, Vec<u8>) {
+        (self.pool.utf8(name), data)
+    }
+}
+
+fn write_attributes(out: &mut Vec<u8>, attributes: &[(u16, Vec<u8>)], error: &mut Option<WriteError>) {
+    out.extend_from_slice(&length(attributes.len(), "attributes", error));
+    for (name, data) in attributes {
+        out.extend_from_slice(&name.to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        out.extend_from_slice(data);
+    }
+}
+
+/// Returns the bytes of a `u16` length, recording an error if it doesn't fit.
+fn length(len: usize, kind: &'static str, error: &mut Option<WriteError>) -> [u8; 2] {
+    let len = u16::try_from(len).unwrap_or_else(|_| {
+        error.get_or_insert(WriteError::TooMany { kind, count: len });
+        0
+    });
+    len.to_be_bytes()
+}
+
+/// A writer of JAR archives.
+pub struct JarWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    options: FileOptions,
+}
+
+impl<W: Write + Seek> JarWriter<W> {
+    /// Creates a writer storing entries compressed with deflate.
+    pub fn new(writer: W) -> Self {
+        Self {
+            zip: ZipWriter::new(writer),
+            options: FileOptions::default().compression_method(CompressionMethod::Deflated),
+        }
+    }
+
+    /// Sets the compression method used for subsequent entries.
+    #[inline]
+    pub fn compression(mut self, method: CompressionMethod) -> Self {
+        self.options = self.options.compression_method(method);
+        self
+    }
+
+    /// Writes an entry with arbitrary contents.
+    pub fn write_entry(&mut self, name: &str, contents: &[u8]) -> Result<()> {
+        self.zip.start_file(name, self.options)?;
+        self.zip.write_all(contents)?;
+        Ok(())
+    }
+
+    /// Writes a class, stored under a path derived from its name.
+    pub fn write_class(&mut self, class: &ClassBuilder) -> Result<()> {
+        self.write_entry(&format!("{}.class", class.name()), &class.try_to_bytes()?)
+    }
+
+    /// Copies an entry read from another archive.
+    pub fn copy_entry(&mut self, entry: &JarEntry) -> Result<()> {
+        self.write_entry(entry.name(), entry.bytes())
+    }
+
+    /// Finishes the archive and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        Ok(self.zip.finish()?)
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    /// Writes a canonical copy of the archive, so that archives with the same contents produce the same bytes,
+    /// e.g. before fingerprinting or diffing them.
+    ///
+    /// Entries are sorted by name, directories are dropped, timestamps are set to the earliest date
+    /// a ZIP archive can store and everything is recompressed with deflate.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut jar = jars::Jar::open("app.jar")?;
+    /// jar.normalize_to(std::fs::File::create("app-normalized.jar")?)?;
+    /// # Ok::<(), jars::Error>(())
+    /// ```
+    pub fn normalize_to<W: Write + Seek>(&mut self, writer: W) -> Result<W> {
+        let mut entries = self.classes().collect::<Result<Vec<_>>>()?;
+        for entry in self.resources() {
+            entries.push(entry?);
+        }
+        entries.sort_by(|a, b| a.raw_name().cmp(b.raw_name()));
+
+        let mut writer = JarWriter::new(writer);
+        writer.options = writer.options.last_modified_time(DateTime::default());
+        for entry in &entries {
+            writer.copy_entry(entry)?;
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use cafebabe::attributes::AttributeData;
+
+    use super::*;
+    use crate::jar::Jar;
+    use crate::model::{ClassModel, Constant};
+    use crate::result::Error;
+
+    #[test]
+    fn write_and_read_back() {
+        let mut class = ClassBuilder::new("com/example/Foo").implements("java/lang/Runnable");
+        let long = class.pool().long(1 << 40);
+        let field = FieldBuilder::new(
+            FieldAccessFlags::PRIVATE | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+            "ID",
+            "J",
+        )
+        .constant(ConstantValue::Long(1 << 40));
+        let method = MethodBuilder::new(MethodAccessFlags::PUBLIC, "run", "()V")
+            .code(Code {
+                max_stack: 2,
+                max_locals: 1,
+                // ldc2_w long, pop2, return
+                bytecode: [&[0x14][..], &long.to_be_bytes(), &[0x58, 0xb1]].concat(),
+            })
+            .throws("java/lang/Exception");
+        let class = class
+            .with_field(field)
+            .with_method(method)
+            .source_file("Foo.java");
+
+        let mut writer = JarWriter::new(Cursor::new(vec![]));
+        writer.write_class(&class).unwrap();
+        writer
+            .write_entry("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n")
+            .unwrap();
+        let mut jar = Jar::new(Cursor::new(writer.finish().unwrap().into_inner())).unwrap();
+
+        let entry = jar.classes().next().unwrap().unwrap();
+        assert_eq!(entry.name(), "com/example/Foo.class");
+        let parsed = entry.parse().unwrap();
+        assert_eq!(parsed.this_class(), "com/example/Foo");
+        assert_eq!(parsed.interface(0), "java/lang/Runnable");
+        assert_eq!(parsed.field_constant(0), Some(Constant::Long(1 << 40)));
+        assert_eq!(parsed.method(0).name, "run");
+        let code = parsed.methods[0]
+            .attributes
+            .iter()
+            .find_map(|attr| match &attr.data {
+                AttributeData::Code(code) => Some(code.code),
+                _ => None,
+            });
+        assert_eq!(code, Some(&[0x14, 0, long as u8, 0x58, 0xb1][..]));
+    }
+
+    #[test]
+    fn deduplicate_pool_entries() {
+        let mut pool = PoolBuilder::default();
+        let first = pool.method_ref("java/lang/Object", "<init>", "()V");
+        let long = pool.long(0);
+        // longs take up two slots
+        assert_eq!(pool.integer(0), long + 2);
+        assert_eq!(pool.method_ref("java/lang/Object", "<init>", "()V"), first);
+        assert_eq!(pool.class("java/lang/Object"), 2);
+    }
+
+    #[test]
+    fn reject_classes_exceeding_limits() {
+        let mut pool = PoolBuilder::default();
+        let index = pool.utf8("a\0\u{1F600}");
+        assert_eq!(&pool.bytes[..], [
+            1, 0, 9, b'a', 0xC0, 0x80, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80
+        ]);
+        assert_eq!(pool.utf8("a\0\u{1F600}"), index);
+
+        let mut class = ClassBuilder::new("a");
+        assert_eq!(class.pool().utf8(&"a".repeat(65536)), 0);
+        assert_eq!(class.try_to_bytes(), Err(WriteError::StringTooLong(65536)));
+
+        let mut class = ClassBuilder::new("a");
+        for i in 0..65534 {
+            class.pool().integer(i);
+        }
+        assert_eq!(class.try_to_bytes(), Err(WriteError::PoolOverflow));
+
+        let field = FieldBuilder::new(FieldAccessFlags::PRIVATE, "x", "I");
+        let class = (0..65536).fold(ClassBuilder::new("a"), |class, _| class.with_field(field.clone()));
+        assert_eq!(
+            class.try_to_bytes(),
+            Err(WriteError::TooMany {
+                kind: "fields",
+                count: 65536
+            })
+        );
+        let mut writer = JarWriter::new(Cursor::new(vec![]));
+        assert!(matches!(writer.write_class(&class), Err(Error::WriteError(_))));
+    }
+
+    #[test]
+    fn normalize_archives() {
+        let class = ClassBuilder::new("a").to_bytes();
+        let normalize = |entries: &[(&str, &[u8])], method| {
+            let mut writer = JarWriter::new(Cursor::new(vec![])).compression(method);
+            for (name, contents) in entries {
+                writer.write_entry(name, contents).unwrap();
+            }
+            let mut jar = Jar::new(writer.finish().unwrap()).unwrap();
+            jar.normalize_to(Cursor::new(vec![])).unwrap().into_inner()
+        };
+        let first = normalize(
+            &[
+                ("a.class", &class),
+                ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+            ],
+            CompressionMethod::Stored,
+        );
+        let second = normalize(
+            &[
+                ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+                ("a.class", &class),
+            ],
+            CompressionMethod::Deflated,
+        );
+        assert_eq!(first, second);
+
+        let mut jar = Jar::new(Cursor::new(first)).unwrap();
+        let names: Vec<_> = jar
+            .resources()
+            .map(|entry| entry.unwrap().name().to_owned())
+            .collect();
+        assert_eq!(names, ["META-INF/MANIFEST.MF"]);
+    }
+}